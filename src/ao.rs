@@ -0,0 +1,123 @@
+use rayon::prelude::*;
+use crate::aov::AovBuffers;
+use crate::camera::Camera;
+use crate::math::Vec3;
+use crate::sampling::{self, Rng};
+use crate::scene::Scene;
+
+// Oclusión ambiental, en dos variantes con los mismos ajustes: con rayos cortos en el hemisferio
+// de la normal (la que oscurece el ambiente de cast_ray) o en espacio de pantalla, solo con la
+// profundidad y la normal de los AOVs (más barata, pero no ve lo que queda fuera de cuadro)
+#[derive(Clone, Copy)]
+pub struct AoSettings {
+    pub enabled: bool,
+    pub samples: u32, // rayos por punto
+    pub radius: f32,  // distancia máxima a la que algo cuenta como oclusor
+}
+
+impl Default for AoSettings {
+    fn default() -> Self {
+        AoSettings {
+            enabled: true,
+            samples: 8,
+            radius: 1.0,
+        }
+    }
+}
+
+/// Fracción del hemisferio que NO está bloqueada (1.0 = totalmente abierto, 0.0 = encerrado).
+/// Los rayos siguen una distribución coseno, así que el resultado ya está ponderado por cos(theta).
+/// No mira `settings.enabled`: eso lo decide quien lo llama (cast_ray o el pase de AO).
+pub fn ambient_occlusion(scene: &Scene, settings: &AoSettings, point: &Vec3, normal: &Vec3) -> f32 {
+    if settings.samples == 0 || settings.radius <= 0.0 {
+        return 1.0;
+    }
+
+    let mut rng = Rng::from_point(point, 0xA0);
+    let origin = *point + *normal * 5e-3;
+    let mut open = 0;
+    for _ in 0..settings.samples {
+        let dir = sampling::cosine_hemisphere(normal, &mut rng);
+        if !scene.intersects_any(&origin, &dir, settings.radius) {
+            open += 1;
+        }
+    }
+    open as f32 / settings.samples as f32
+}
+
+/// Oclusión en espacio de pantalla, una por píxel (fila por fila; 1.0 = abierto, como
+/// ambient_occlusion). Cada muestra del hemisferio se proyecta a la imagen y cuenta como ocluida
+/// si lo que se ve en ese píxel está más cerca de la cámara que ella, pero a menos de `radius`
+/// (un objeto muy por delante no tapa la muestra). El cielo queda abierto.
+pub fn screen_space_occlusion(aovs: &AovBuffers, camera: &Camera, settings: &AoSettings) -> Vec<f32> {
+    let (width, height) = (aovs.width, aovs.height);
+    let (w, h) = (width as f32, height as f32);
+    (0..width * height)
+        .into_par_iter()
+        .map(|i| {
+            let depth = aovs.depth[i as usize];
+            if !depth.is_finite() || settings.samples == 0 || settings.radius <= 0.0 {
+                return 1.0;
+            }
+            let ray = camera.primary_ray((i % width) as f32, (i / width) as f32, w, h);
+            let point = ray.origin + ray.direction * depth;
+            let normal = aovs.normal[i as usize];
+
+            let mut rng = Rng::from_point(&point, 0x55A0);
+            let origin = point + normal * 5e-3;
+            let mut occluded = 0;
+            for _ in 0..settings.samples {
+                // muestras dentro de la semiesfera (no solo sobre su borde), a distancia al azar
+                let dir = sampling::cosine_hemisphere(&normal, &mut rng);
+                let sample = origin + dir * (settings.radius * rng.next_f32());
+                let Some((px, py)) = camera.project(&sample, w, h) else { continue; };
+                let (px, py) = (px.floor() as i32, py.floor() as i32);
+                if px < 0 || px >= width || py < 0 || py >= height {
+                    continue;
+                }
+                let seen = aovs.depth[(py * width + px) as usize];
+                let sample_depth = (sample - camera.eye).length();
+                if seen < sample_depth - 1e-3 && sample_depth - seen < settings.radius {
+                    occluded += 1;
+                }
+            }
+            1.0 - occluded as f32 / settings.samples as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::daycycle::DayCycle;
+    use crate::material::Material;
+    use crate::renderer::{trace_frame, PassSettings};
+    use crate::textures::TextureManager;
+
+    #[test]
+    fn screen_space_darkens_the_crease() {
+        // piso con una pared al fondo; la cámara mira el rincón desde arriba
+        let floor = Cube::new(Vec3::new(0.0, -0.5, 0.0), Vec3::new(5.0, 0.5, 5.0), 0.0, 0.0, Material::default());
+        let wall = Cube::new(Vec3::new(0.0, 1.0, -2.25), Vec3::new(5.0, 1.0, 0.25), 0.0, 0.0, Material::default());
+        let scene = Scene::new(vec![Box::new(floor), Box::new(wall)], Vec::new(), DayCycle::new(12.0));
+        let camera = Camera::new(Vec3::new(0.0, 2.0, 3.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0));
+        let (width, height) = (64, 48);
+        let mut aovs = AovBuffers::new(width, height);
+        let pass = PassSettings { want_aovs: true, ..PassSettings::full() };
+        for (x, y, _, aov) in trace_frame(width, height, &scene, &camera, &TextureManager::new(), pass) {
+            aovs.set(x, y, &aov.unwrap());
+        }
+        let settings = AoSettings { enabled: true, samples: 64, radius: 1.0 };
+        let occlusion = screen_space_occlusion(&aovs, &camera, &settings);
+
+        let at = |p: Vec3| {
+            let (x, y) = camera.project(&p, width as f32, height as f32).unwrap();
+            occlusion[(y as i32 * width + x as i32) as usize]
+        };
+        let crease = at(Vec3::new(0.0, 0.02, -1.9));
+        let open = at(Vec3::new(0.0, 0.0, 1.0));
+        assert!(open > 0.95, "piso abierto: {}", open);
+        assert!(crease < open - 0.1, "rincón {} vs abierto {}", crease, open);
+    }
+}
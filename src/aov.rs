@@ -0,0 +1,208 @@
+use crate::math::{Color, Rgba8, Vec2, Vec3};
+use std::fs::File;
+use std::io::{self, Write};
+
+// Pases auxiliares (AOVs) que render() llena a partir del hit primario de cada píxel
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AovPass {
+    Beauty,
+    Depth,
+    Normal,
+    Albedo,
+    MaterialId,
+    ObjectId,
+    Uv,
+}
+
+impl AovPass {
+    pub const ALL: [AovPass; 7] = [
+        AovPass::Beauty,
+        AovPass::Depth,
+        AovPass::Normal,
+        AovPass::Albedo,
+        AovPass::MaterialId,
+        AovPass::ObjectId,
+        AovPass::Uv,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AovPass::Beauty => "beauty",
+            AovPass::Depth => "depth",
+            AovPass::Normal => "normal",
+            AovPass::Albedo => "albedo",
+            AovPass::MaterialId => "material_id",
+            AovPass::ObjectId => "object_id",
+            AovPass::Uv => "uv",
+        }
+    }
+
+    /// Siguiente pase (para ciclar con una tecla)
+    pub fn next(&self) -> AovPass {
+        let i = AovPass::ALL.iter().position(|p| p == self).unwrap_or(0);
+        AovPass::ALL[(i + 1) % AovPass::ALL.len()]
+    }
+}
+
+// identificador para píxeles sin hit (cielo)
+pub const NO_ID: u32 = u32::MAX;
+
+// Valores de los pases para un píxel
+#[derive(Clone, Copy)]
+pub struct AovSample {
+    pub depth: f32,        // distancia lineal desde la cámara (INFINITY en el cielo)
+    pub normal: Vec3,   // normal en espacio mundo
+    pub albedo: Color,  // color base ya con textura aplicada
+    pub material_id: u32,
+    pub object_id: u32,    // índice del objeto en la escena
+    pub uv: Vec2,       // coordenadas de textura de la cara (sin repetición)
+}
+
+impl AovSample {
+    pub fn miss() -> Self {
+        AovSample {
+            depth: f32::INFINITY,
+            normal: Vec3::zero(),
+            albedo: Color::BLACK,
+            material_id: NO_ID,
+            object_id: NO_ID,
+            uv: Vec2::zero(),
+        }
+    }
+}
+
+pub struct AovBuffers {
+    pub width: i32,
+    pub height: i32,
+    pub depth: Vec<f32>,
+    pub normal: Vec<Vec3>,
+    pub albedo: Vec<Color>,
+    pub material_id: Vec<u32>,
+    pub object_id: Vec<u32>,
+    pub uv: Vec<Vec2>,
+}
+
+// color estable y bien separado para un identificador
+fn id_color(id: u32) -> Rgba8 {
+    if id == NO_ID {
+        return Rgba8::BLACK;
+    }
+    let mut h = id.wrapping_add(1).wrapping_mul(0x9E37_79B9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    Rgba8::new((h & 0xff) as u8 | 0x30, ((h >> 8) & 0xff) as u8 | 0x30, ((h >> 16) & 0xff) as u8 | 0x30, 255)
+}
+
+fn unit_to_color(v: Vec3) -> Rgba8 {
+    Rgba8::new(
+        (v.x.clamp(0.0, 1.0) * 255.0) as u8,
+        (v.y.clamp(0.0, 1.0) * 255.0) as u8,
+        (v.z.clamp(0.0, 1.0) * 255.0) as u8,
+        255,
+    )
+}
+
+impl AovBuffers {
+    pub fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        let miss = AovSample::miss();
+        AovBuffers {
+            width,
+            height,
+            depth: vec![miss.depth; size],
+            normal: vec![miss.normal; size],
+            albedo: vec![miss.albedo; size],
+            material_id: vec![miss.material_id; size],
+            object_id: vec![miss.object_id; size],
+            uv: vec![miss.uv; size],
+        }
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, sample: &AovSample) {
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            let index = (y * self.width + x) as usize;
+            self.depth[index] = sample.depth;
+            self.normal[index] = sample.normal;
+            self.albedo[index] = sample.albedo;
+            self.material_id[index] = sample.material_id;
+            self.object_id[index] = sample.object_id;
+            self.uv[index] = sample.uv;
+        }
+    }
+
+    // rango de profundidades finitas (para normalizar la visualización)
+    fn depth_range(&self) -> (f32, f32) {
+        let mut min = f32::INFINITY;
+        let mut max = 0.0_f32;
+        for d in self.depth.iter().filter(|d| d.is_finite()) {
+            min = min.min(*d);
+            max = max.max(*d);
+        }
+        if min > max { (0.0, 1.0) } else { (min, max.max(min + 1e-3)) }
+    }
+
+    /// Colores de visualización de un pase (Beauty no vive aquí: devuelve None)
+    pub fn visualize(&self, pass: AovPass) -> Option<Vec<Rgba8>> {
+        let colors = match pass {
+            AovPass::Beauty => return None,
+            AovPass::Depth => {
+                // cerca = blanco, lejos = negro; cielo negro
+                let (min, max) = self.depth_range();
+                self.depth
+                    .iter()
+                    .map(|d| {
+                        if d.is_finite() {
+                            let v = 1.0 - (d - min) / (max - min);
+                            unit_to_color(Vec3::new(v, v, v))
+                        } else {
+                            Rgba8::BLACK
+                        }
+                    })
+                    .collect()
+            }
+            AovPass::Normal => self
+                .normal
+                .iter()
+                .map(|n| if n.length() > 0.0 { unit_to_color(*n * 0.5 + 0.5) } else { Rgba8::BLACK })
+                .collect(),
+            AovPass::Albedo => self.albedo.iter().map(|a| a.to_rgba8()).collect(),
+            AovPass::MaterialId => self.material_id.iter().map(|id| id_color(*id)).collect(),
+            AovPass::ObjectId => self.object_id.iter().map(|id| id_color(*id)).collect(),
+            AovPass::Uv => self.uv.iter().map(|uv| unit_to_color(Vec3::new(uv.x, uv.y, 0.0))).collect(),
+        };
+        Some(colors)
+    }
+
+    /// Escribe la profundidad sin cuantizar como PFM (float, una componente); el cielo queda en 0.
+    pub fn write_depth_pfm(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        write!(file, "Pf\n{} {}\n-1.0\n", self.width, self.height)?;
+        // PFM guarda las filas de abajo hacia arriba
+        let mut bytes = Vec::with_capacity(self.depth.len() * 4);
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let d = self.depth[(y * self.width + x) as usize];
+                let d = if d.is_finite() { d } else { 0.0 };
+                bytes.extend_from_slice(&d.to_le_bytes());
+            }
+        }
+        file.write_all(&bytes)
+    }
+
+    /// Escribe las normales sin cuantizar como PFM de tres componentes.
+    pub fn write_normal_pfm(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        write!(file, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        let mut bytes = Vec::with_capacity(self.normal.len() * 12);
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let n = self.normal[(y * self.width + x) as usize];
+                for c in [n.x, n.y, n.z] {
+                    bytes.extend_from_slice(&c.to_le_bytes());
+                }
+            }
+        }
+        file.write_all(&bytes)
+    }
+}
@@ -0,0 +1,708 @@
+// Modelos de bloque al estilo de Minecraft: cada bloque es una lista de elementos (cajas, o quads
+// si tienen grosor cero) en píxeles de 0 a 16, con una textura y un rectángulo UV por cara. Se leen
+// del formato JSON del juego (`parent`, `textures`, `elements` con `from`/`to`/`rotation`/`faces`)
+// y hay modelos incluidos con los nombres del juego para losas, escaleras, cercas, paneles,
+// antorchas y plantas (`cross`, dos quads cruzados):
+//
+//     cube_all  slab  slab_top  stairs  fence_post  fence_side  template_glass_pane_post
+//     template_glass_pane_side  template_torch  cross
+//
+// Las caras de las plantas y las antorchas dependen de la transparencia de la textura: si se
+// cargó su AlphaMask (ModelLibrary::load_mask) los rayos atraviesan los texeles transparentes.
+// Las caras son de un solo lado como en el juego: si falta una cara el rayo no choca con ella.
+
+use crate::bvh::Aabb;
+use crate::json::{self, Json};
+use crate::material::Material;
+use crate::math::{Mat4, Vec2, Vec3};
+use crate::png::{self, RgbaImage};
+use crate::ray_intersect::{HitInfo, RayIntersect};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+// orden de las caras en ModelElement::faces (normales en face_normal)
+pub const FACES: [&str; 6] = ["down", "up", "north", "south", "west", "east"];
+
+fn face_normal(face: usize) -> Vec3 {
+    match face {
+        0 => Vec3::new(0.0, -1.0, 0.0),
+        1 => Vec3::new(0.0, 1.0, 0.0),
+        2 => Vec3::new(0.0, 0.0, -1.0),
+        3 => Vec3::new(0.0, 0.0, 1.0),
+        4 => Vec3::new(-1.0, 0.0, 0.0),
+        _ => Vec3::new(1.0, 0.0, 0.0),
+    }
+}
+
+// cara con normal hacia el lado `positive` del eje
+fn face_index(axis: usize, positive: bool) -> usize {
+    match (axis, positive) {
+        (0, false) => 4,
+        (0, true) => 5,
+        (1, false) => 0,
+        (1, true) => 1,
+        (2, false) => 2,
+        _ => 3,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelFace {
+    // rectángulo [u0, v0, u1, v1] en píxeles de la textura (v hacia abajo); None = el de Minecraft
+    // por defecto, que sigue la posición de la cara en el bloque
+    pub uv: Option<[f32; 4]>,
+    // referencia a una variable (`#side`) o una textura directa
+    pub texture: String,
+    // giro de la textura en grados (0, 90, 180 o 270, horario)
+    pub rotation: u32,
+}
+
+// giro del elemento alrededor de `origin`; `rescale` lo estira para que siga ocupando el bloque
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElementRotation {
+    pub origin: Vec3,
+    pub axis: usize,
+    pub angle: f32,
+    pub rescale: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelElement {
+    pub from: Vec3,
+    pub to: Vec3,
+    pub rotation: Option<ElementRotation>,
+    pub faces: [Option<ModelFace>; 6],
+}
+
+impl ModelElement {
+    // rectángulo UV por defecto de una cara (el que calcula Minecraft si no se da `uv`)
+    fn default_uv(&self, face: usize) -> [f32; 4] {
+        let (f, t) = (self.from, self.to);
+        match face {
+            0 => [f.x, 16.0 - t.z, t.x, 16.0 - f.z],
+            1 => [f.x, f.z, t.x, t.z],
+            2 => [16.0 - t.x, 16.0 - t.y, 16.0 - f.x, 16.0 - f.y],
+            3 => [f.x, 16.0 - t.y, t.x, 16.0 - f.y],
+            4 => [f.z, 16.0 - t.y, t.z, 16.0 - f.y],
+            _ => [16.0 - t.z, 16.0 - t.y, 16.0 - f.z, 16.0 - f.y],
+        }
+    }
+
+    // transformación del elemento (giro y estiramiento) en píxeles del modelo
+    fn transform(&self) -> Mat4 {
+        let Some(r) = &self.rotation else { return Mat4::identity() };
+        let angle = r.angle.to_radians();
+        let rotation = match r.axis {
+            0 => Mat4::rotation_x(angle),
+            1 => Mat4::rotation_y(angle),
+            _ => Mat4::rotation_z(angle),
+        };
+        let mut stretch = Vec3::new(1.0, 1.0, 1.0);
+        if r.rescale {
+            let s = 1.0 / angle.cos();
+            stretch = match r.axis {
+                0 => Vec3::new(1.0, s, s),
+                1 => Vec3::new(s, 1.0, s),
+                _ => Vec3::new(s, s, 1.0),
+            };
+        }
+        Mat4::translation(r.origin) * rotation * Mat4::scale(stretch) * Mat4::translation(-r.origin)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockModel {
+    pub parent: Option<String>,
+    // variables de textura; las primeras tienen prioridad
+    pub textures: Vec<(String, String)>,
+    pub elements: Vec<ModelElement>,
+}
+
+fn vec3(value: &Json, what: &str) -> Result<Vec3, String> {
+    let [x, y, z] = value.as_floats::<3>().ok_or_else(|| format!("'{}' espera tres números", what))?;
+    Ok(Vec3::new(x, y, z))
+}
+
+fn element(value: &Json) -> Result<ModelElement, String> {
+    let from = vec3(value.get("from").ok_or("a un elemento le falta 'from'")?, "from")?;
+    let to = vec3(value.get("to").ok_or("a un elemento le falta 'to'")?, "to")?;
+    let rotation = match value.get("rotation") {
+        Some(r) => Some(ElementRotation {
+            origin: r.get("origin").map(|o| vec3(o, "origin")).transpose()?.unwrap_or(Vec3::new(8.0, 8.0, 8.0)),
+            axis: match r.get("axis").and_then(Json::as_str) {
+                Some("x") => 0,
+                Some("y") => 1,
+                Some("z") => 2,
+                other => return Err(format!("eje de rotación inválido {:?}", other)),
+            },
+            angle: r.get("angle").and_then(Json::as_f64).unwrap_or(0.0) as f32,
+            rescale: r.get("rescale").and_then(Json::as_bool).unwrap_or(false),
+        }),
+        None => None,
+    };
+    let mut faces: [Option<ModelFace>; 6] = Default::default();
+    for (name, face) in value.get("faces").and_then(Json::as_object).unwrap_or_default() {
+        let index = FACES.iter().position(|f| f == name).ok_or_else(|| format!("cara desconocida '{}'", name))?;
+        faces[index] = Some(ModelFace {
+            uv: face.get("uv").map(|uv| uv.as_floats::<4>().ok_or("'uv' espera cuatro números")).transpose()?,
+            texture: face.get("texture").and_then(Json::as_str).ok_or("a una cara le falta 'texture'")?.to_string(),
+            rotation: match face.get("rotation").and_then(Json::as_f64).unwrap_or(0.0) as u32 {
+                r @ (0 | 90 | 180 | 270) => r,
+                r => return Err(format!("rotación de cara inválida {}", r)),
+            },
+        });
+    }
+    Ok(ModelElement { from, to, rotation, faces })
+}
+
+impl BlockModel {
+    pub fn from_json(value: &Json) -> Result<BlockModel, String> {
+        let mut model = BlockModel {
+            parent: value.get("parent").and_then(Json::as_str).map(str::to_string),
+            ..Default::default()
+        };
+        for (name, texture) in value.get("textures").and_then(Json::as_object).unwrap_or_default() {
+            if let Some(texture) = texture.as_str() {
+                model.textures.push((name.clone(), texture.to_string()));
+            }
+        }
+        for e in value.get("elements").and_then(Json::as_array).unwrap_or_default() {
+            model.elements.push(element(e)?);
+        }
+        Ok(model)
+    }
+
+    pub fn parse(text: &str) -> Result<BlockModel, String> {
+        BlockModel::from_json(&json::parse(text)?)
+    }
+
+    /// Lee un .json de Minecraft y sus padres. Los padres (`block/stairs`) se buscan como .json en
+    /// la misma carpeta y en la carpeta de modelos (la de arriba de `block/`), y si no están se
+    /// usan los incluidos con el mismo nombre.
+    pub fn load(path: &str) -> io::Result<BlockModel> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e));
+        let mut model = BlockModel::parse(&fs::read_to_string(path)?).map_err(invalid)?;
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        for _ in 0..16 {
+            let Some(parent) = model.parent.take() else { return Ok(model) };
+            let name = parent.trim_start_matches("minecraft:");
+            let short = name.rsplit('/').next().unwrap_or(name);
+            let files = [dir.join(format!("{}.json", short)), dir.parent().unwrap_or(dir).join(format!("{}.json", name))];
+            let base = match files.iter().find(|f| f.is_file()) {
+                Some(file) => BlockModel::parse(&fs::read_to_string(file)?).map_err(invalid)?,
+                None => match BlockModel::builtin(short) {
+                    Some(builtin) => builtin.clone(),
+                    // block/block y parecidos solo tienen datos de display: sin elementos
+                    None if model.elements.is_empty() => return Err(invalid(format!("modelo padre '{}' no encontrado", parent))),
+                    None => BlockModel::default(),
+                },
+            };
+            model = model.inherit(base);
+        }
+        Err(invalid("demasiados niveles de 'parent'".into()))
+    }
+
+    // el hijo reemplaza las variables de textura y, si tiene, los elementos del padre
+    fn inherit(mut self, base: BlockModel) -> BlockModel {
+        self.textures.extend(base.textures);
+        if self.elements.is_empty() {
+            self.elements = base.elements;
+        }
+        self.parent = base.parent;
+        self
+    }
+
+    /// Copia con variables de textura agregadas (con prioridad sobre las del modelo)
+    pub fn with_textures(&self, textures: &[(String, String)]) -> BlockModel {
+        let mut model = self.clone();
+        model.textures = textures.iter().cloned().chain(model.textures).collect();
+        model
+    }
+
+    /// Textura a la que lleva una referencia `#variable` (siguiendo cadenas de variables); None si
+    /// alguna variable no tiene valor
+    pub fn texture<'a>(&'a self, reference: &'a str) -> Option<&'a str> {
+        let mut current = reference;
+        for _ in 0..16 {
+            let Some(name) = current.strip_prefix('#') else { return Some(current) };
+            current = self.textures.iter().find(|(n, _)| n == name)?.1.as_str();
+        }
+        None
+    }
+
+    /// Modelo incluido por nombre (`stairs`, `minecraft:block/slab`, ...)
+    pub fn builtin(name: &str) -> Option<&'static BlockModel> {
+        let short = name.trim_start_matches("minecraft:").rsplit('/').next().unwrap_or(name);
+        builtins().iter().find(|(n, _)| *n == short).map(|(_, m)| m)
+    }
+}
+
+// definiciones incluidas en el formato del juego (con los rectángulos UV por defecto)
+const BUILTIN_JSON: &[(&str, &str)] = &[
+    (
+        "cube",
+        r##"{"elements": [{"from": [0, 0, 0], "to": [16, 16, 16], "faces": {
+            "down": {"texture": "#down"}, "up": {"texture": "#up"}, "north": {"texture": "#north"},
+            "south": {"texture": "#south"}, "west": {"texture": "#west"}, "east": {"texture": "#east"}}}]}"##,
+    ),
+    (
+        "cube_all",
+        r##"{"parent": "block/cube", "textures": {"down": "#all", "up": "#all", "north": "#all",
+            "south": "#all", "west": "#all", "east": "#all"}}"##,
+    ),
+    (
+        "slab",
+        r##"{"elements": [{"from": [0, 0, 0], "to": [16, 8, 16], "faces": {
+            "down": {"texture": "#bottom"}, "up": {"texture": "#top"}, "north": {"texture": "#side"},
+            "south": {"texture": "#side"}, "west": {"texture": "#side"}, "east": {"texture": "#side"}}}]}"##,
+    ),
+    (
+        "slab_top",
+        r##"{"elements": [{"from": [0, 8, 0], "to": [16, 16, 16], "faces": {
+            "down": {"texture": "#bottom"}, "up": {"texture": "#top"}, "north": {"texture": "#side"},
+            "south": {"texture": "#side"}, "west": {"texture": "#side"}, "east": {"texture": "#side"}}}]}"##,
+    ),
+    (
+        "stairs",
+        r##"{"elements": [
+            {"from": [0, 0, 0], "to": [16, 8, 16], "faces": {
+                "down": {"texture": "#bottom"}, "up": {"texture": "#top"}, "north": {"texture": "#side"},
+                "south": {"texture": "#side"}, "west": {"texture": "#side"}, "east": {"texture": "#side"}}},
+            {"from": [8, 8, 0], "to": [16, 16, 16], "faces": {
+                "up": {"texture": "#top"}, "north": {"texture": "#side"}, "south": {"texture": "#side"},
+                "west": {"texture": "#side"}, "east": {"texture": "#side"}}}]}"##,
+    ),
+    (
+        "fence_post",
+        r##"{"elements": [{"from": [6, 0, 6], "to": [10, 16, 10], "faces": {
+            "down": {"texture": "#texture"}, "up": {"texture": "#texture"}, "north": {"texture": "#texture"},
+            "south": {"texture": "#texture"}, "west": {"texture": "#texture"}, "east": {"texture": "#texture"}}}]}"##,
+    ),
+    (
+        "fence_side",
+        r##"{"elements": [
+            {"from": [7, 12, 0], "to": [9, 15, 9], "faces": {
+                "down": {"texture": "#texture"}, "up": {"texture": "#texture"}, "north": {"texture": "#texture"},
+                "west": {"texture": "#texture"}, "east": {"texture": "#texture"}}},
+            {"from": [7, 6, 0], "to": [9, 9, 9], "faces": {
+                "down": {"texture": "#texture"}, "up": {"texture": "#texture"}, "north": {"texture": "#texture"},
+                "west": {"texture": "#texture"}, "east": {"texture": "#texture"}}}]}"##,
+    ),
+    (
+        "template_glass_pane_post",
+        r##"{"elements": [{"from": [7, 0, 7], "to": [9, 16, 9], "faces": {
+            "down": {"texture": "#edge"}, "up": {"texture": "#edge"}, "north": {"texture": "#pane"},
+            "south": {"texture": "#pane"}, "west": {"texture": "#pane"}, "east": {"texture": "#pane"}}}]}"##,
+    ),
+    (
+        "template_glass_pane_side",
+        r##"{"elements": [{"from": [7, 0, 0], "to": [9, 16, 7], "faces": {
+            "down": {"texture": "#edge"}, "up": {"texture": "#edge"}, "north": {"texture": "#edge"},
+            "west": {"texture": "#pane"}, "east": {"texture": "#pane"}}}]}"##,
+    ),
+    (
+        "template_torch",
+        r##"{"elements": [{"from": [7, 0, 7], "to": [9, 10, 9], "faces": {
+            "down": {"uv": [7, 13, 9, 15], "texture": "#torch"}, "up": {"uv": [7, 6, 9, 8], "texture": "#torch"},
+            "north": {"uv": [7, 6, 9, 16], "texture": "#torch"}, "south": {"uv": [7, 6, 9, 16], "texture": "#torch"},
+            "west": {"uv": [7, 6, 9, 16], "texture": "#torch"}, "east": {"uv": [7, 6, 9, 16], "texture": "#torch"}}}]}"##,
+    ),
+    (
+        "cross",
+        r##"{"elements": [
+            {"from": [0.8, 0, 8], "to": [15.2, 16, 8],
+             "rotation": {"origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true},
+             "faces": {"north": {"uv": [0, 0, 16, 16], "texture": "#cross"}, "south": {"uv": [0, 0, 16, 16], "texture": "#cross"}}},
+            {"from": [8, 0, 0.8], "to": [8, 16, 15.2],
+             "rotation": {"origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true},
+             "faces": {"west": {"uv": [0, 0, 16, 16], "texture": "#cross"}, "east": {"uv": [0, 0, 16, 16], "texture": "#cross"}}}]}"##,
+    ),
+];
+
+fn builtins() -> &'static [(&'static str, BlockModel)] {
+    static BUILTINS: OnceLock<Vec<(&'static str, BlockModel)>> = OnceLock::new();
+    BUILTINS.get_or_init(|| {
+        let parsed: Vec<(&str, BlockModel)> =
+            BUILTIN_JSON.iter().map(|(name, text)| (*name, BlockModel::parse(text).expect("modelo incluido inválido"))).collect();
+        // los padres de los incluidos también son incluidos
+        parsed
+            .iter()
+            .map(|(name, model)| {
+                let mut model = model.clone();
+                while let Some(parent) = model.parent.as_deref() {
+                    let short = parent.rsplit('/').next().unwrap_or(parent);
+                    let base = parsed.iter().find(|(n, _)| *n == short).map(|(_, m)| m.clone()).unwrap_or_default();
+                    model = model.inherit(base);
+                }
+                (*name, model)
+            })
+            .collect()
+    })
+}
+
+/// Qué texeles de una textura son transparentes (alfa < 128), para recortar caras
+#[derive(Debug)]
+pub struct AlphaMask {
+    width: i32,
+    height: i32,
+    opaque: Vec<bool>,
+}
+
+impl AlphaMask {
+    /// None si la imagen es opaca en todos lados (no hace falta máscara)
+    pub fn from_image(image: &RgbaImage) -> Option<AlphaMask> {
+        let opaque: Vec<bool> = image.pixels.iter().map(|p| p.a >= 128).collect();
+        if opaque.iter().all(|&o| o) {
+            return None;
+        }
+        Some(AlphaMask { width: image.width, height: image.height, opaque })
+    }
+
+    /// Mismo mapeo (con repetición) que TextureManager::sample_uv
+    pub fn is_opaque(&self, u: f32, v: f32) -> bool {
+        let (u, v) = (u - u.floor(), v - v.floor());
+        let x = ((u * self.width as f32).floor() as i32).rem_euclid(self.width);
+        let y = (((1.0 - v) * self.height as f32).floor() as i32).rem_euclid(self.height);
+        self.opaque.get((y * self.width + x) as usize).copied().unwrap_or(true)
+    }
+}
+
+/// Modelos leídos de disco y máscaras de transparencia, por nombre/ruta
+#[derive(Clone, Default)]
+pub struct ModelLibrary {
+    pub models: Vec<(String, BlockModel)>,
+    pub masks: Vec<(String, Arc<AlphaMask>)>,
+}
+
+impl ModelLibrary {
+    /// Un .json cargado con load_model o un modelo incluido
+    pub fn model(&self, name: &str) -> Option<&BlockModel> {
+        self.models.iter().find(|(n, _)| n == name).map(|(_, m)| m).or_else(|| BlockModel::builtin(name))
+    }
+
+    /// Lee el modelo si es una ruta a un .json y no estaba cargado; los nombres de modelos
+    /// incluidos no tocan el disco
+    pub fn load_model(&mut self, name: &str) -> io::Result<()> {
+        if self.models.iter().any(|(n, _)| n == name) {
+            return Ok(());
+        }
+        if !name.ends_with(".json") {
+            return match BlockModel::builtin(name) {
+                Some(_) => Ok(()),
+                None => Err(io::Error::new(io::ErrorKind::NotFound, format!("modelo de bloque '{}' desconocido", name))),
+            };
+        }
+        let model = BlockModel::load(name)?;
+        self.models.push((name.to_string(), model));
+        Ok(())
+    }
+
+    /// Lee la transparencia de una textura PNG (solo se guarda si tiene texeles transparentes)
+    pub fn load_mask(&mut self, path: &str) -> io::Result<()> {
+        if self.masks.iter().any(|(p, _)| p == path) {
+            return Ok(());
+        }
+        if let Some(mask) = AlphaMask::from_image(&png::read(path)?) {
+            self.masks.push((path.to_string(), Arc::new(mask)));
+        }
+        Ok(())
+    }
+
+    pub fn mask(&self, path: &str) -> Option<Arc<AlphaMask>> {
+        self.masks.iter().find(|(p, _)| p == path).map(|(_, m)| m.clone())
+    }
+}
+
+struct PartFace {
+    uv: [f32; 4],
+    rotation: u32,
+    material: Material,
+    mask: Option<Arc<AlphaMask>>,
+}
+
+// un elemento ya ubicado en el mundo
+struct Part {
+    from: Vec3,
+    to: Vec3,
+    // mundo → píxeles del modelo; su transpuesta lleva las normales al mundo
+    to_local: Mat4,
+    faces: [Option<PartFace>; 6],
+}
+
+impl Part {
+    // (s, t) en [0, 1] sobre la cara: s hacia la derecha y t hacia abajo de la textura
+    fn face_coords(&self, face: usize, p: Vec3) -> (f32, f32) {
+        let (f, t) = (self.from, self.to);
+        let frac = |v: f32, a: f32, b: f32| (v - a) / (b - a).abs().max(1e-6);
+        match face {
+            0 => (frac(p.x, f.x, t.x), frac(t.z - p.z + f.z, f.z, t.z)),
+            1 => (frac(p.x, f.x, t.x), frac(p.z, f.z, t.z)),
+            2 => (frac(t.x - p.x + f.x, f.x, t.x), frac(t.y - p.y + f.y, f.y, t.y)),
+            3 => (frac(p.x, f.x, t.x), frac(t.y - p.y + f.y, f.y, t.y)),
+            4 => (frac(p.z, f.z, t.z), frac(t.y - p.y + f.y, f.y, t.y)),
+            _ => (frac(t.z - p.z + f.z, f.z, t.z), frac(t.y - p.y + f.y, f.y, t.y)),
+        }
+    }
+
+    fn intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        // la dirección local no es unitaria, pero el t del rayo es el mismo en los dos espacios
+        let o = self.to_local.transform_point(*ray_origin);
+        let d = self.to_local.transform_vector(*ray_direction);
+        let (o, d) = ([o.x, o.y, o.z], [d.x, d.y, d.z]);
+        let (min, max) = ([self.from.x, self.from.y, self.from.z], [self.to.x, self.to.y, self.to.z]);
+
+        let (mut t_near, mut t_far) = (f32::NEG_INFINITY, f32::INFINITY);
+        let (mut near_face, mut far_face) = (0, 0);
+        for axis in 0..3 {
+            if d[axis].abs() < 1e-9 {
+                if o[axis] < min[axis] || o[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((min[axis] - o[axis]) / d[axis], (max[axis] - o[axis]) / d[axis]);
+            let (t_in, t_out) = (t1.min(t2), t1.max(t2));
+            if t_in > t_near {
+                t_near = t_in;
+                near_face = face_index(axis, d[axis] < 0.0);
+            }
+            if t_out < t_far {
+                t_far = t_out;
+                far_face = face_index(axis, d[axis] > 0.0);
+            }
+        }
+        if t_near > t_far || t_far < 0.0 {
+            return None;
+        }
+        // desde adentro (rayos refractados) se usa la cara de salida, como en Cube
+        let (t, face_index) = if t_near >= 0.0 { (t_near, near_face) } else { (t_far, far_face) };
+        let face = self.faces[face_index].as_ref()?;
+
+        let p = Vec3::new(o[0] + d[0] * t, o[1] + d[1] * t, o[2] + d[2] * t);
+        let (s, tt) = self.face_coords(face_index, p);
+        let (s, tt) = match face.rotation {
+            90 => (tt, 1.0 - s),
+            180 => (1.0 - s, 1.0 - tt),
+            270 => (1.0 - tt, s),
+            _ => (s, tt),
+        };
+        let [u0, v0, u1, v1] = face.uv;
+        // píxeles del juego (v hacia abajo) → uv de sample_uv (v hacia arriba)
+        let uv = Vec2::new((u0 + s * (u1 - u0)) / 16.0, 1.0 - (v0 + tt * (v1 - v0)) / 16.0);
+        if let Some(mask) = &face.mask
+            && !mask.is_opaque(uv.x, uv.y)
+        {
+            return None;
+        }
+
+        let local_normal = face_normal(face_index);
+        Some(HitInfo {
+            hit: true,
+            point: *ray_origin + *ray_direction * t,
+            local_point: p * (1.0 / 16.0) - Vec3::new(0.5, 0.5, 0.5),
+            local_half_size: Vec3::new(0.5, 0.5, 0.5),
+            normal: self.to_local.transpose().transform_vector(local_normal).normalized(),
+            local_normal,
+            distance: t,
+            material: face.material.clone(),
+            texture_repeat: Vec2::new(1.0, 1.0),
+            uv: Some(uv),
+        })
+    }
+}
+
+/// Un modelo de bloque ubicado en la escena: ocupa el cubo de 1×1×1 alrededor de `center`, girado
+/// como Cube (rotation.x y después rotation.y, en grados)
+pub struct ModelShape {
+    parts: Vec<Part>,
+    bounds: Option<Aabb>,
+}
+
+impl ModelShape {
+    /// Cada cara usa `material` con la textura de su variable si es un .png; las variables sin
+    /// valor (o texturas del juego como `block/stone`) dejan la textura de `material`
+    pub fn new(model: &BlockModel, center: Vec3, rotation: Vec2, material: &Material, library: &ModelLibrary) -> ModelShape {
+        let block = Mat4::translation(center)
+            * Mat4::rotation_y(rotation.y.to_radians())
+            * Mat4::rotation_x(rotation.x.to_radians())
+            * Mat4::scale(Vec3::new(1.0 / 16.0, 1.0 / 16.0, 1.0 / 16.0))
+            * Mat4::translation(Vec3::new(-8.0, -8.0, -8.0));
+        let mut parts = Vec::new();
+        let mut bounds: Option<Aabb> = None;
+        for element in &model.elements {
+            let to_world = block * element.transform();
+            let Some(to_local) = to_world.inverse() else { continue };
+            let faces = std::array::from_fn(|i| {
+                let face = element.faces[i].as_ref()?;
+                let mut material = material.clone();
+                if let Some(texture) = model.texture(&face.texture).filter(|t| t.ends_with(".png")) {
+                    material.texture_path = Some(texture.to_string());
+                }
+                let mask = material.texture_path.as_deref().and_then(|path| library.mask(path));
+                Some(PartFace { uv: face.uv.unwrap_or_else(|| element.default_uv(i)), rotation: face.rotation, material, mask })
+            });
+            let (f, t) = (element.from, element.to);
+            let corners = (0..8).map(|i| {
+                to_world.transform_point(Vec3::new(
+                    if i & 1 == 0 { f.x } else { t.x },
+                    if i & 2 == 0 { f.y } else { t.y },
+                    if i & 4 == 0 { f.z } else { t.z },
+                ))
+            });
+            let aabb = Aabb::from_points(corners);
+            bounds = Some(bounds.map_or(aabb, |b| b.union(&aabb)));
+            parts.push(Part { from: f, to: t, to_local, faces });
+        }
+        ModelShape { parts, bounds }
+    }
+}
+
+impl RayIntersect for ModelShape {
+    fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        self.parts
+            .iter()
+            .filter_map(|part| part.intersect(ray_origin, ray_direction))
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rgba8;
+
+    fn place(name: &str, rotation: Vec2, library: &ModelLibrary) -> ModelShape {
+        let model = library.model(name).unwrap();
+        ModelShape::new(model, Vec3::new(0.5, 0.5, 0.5), rotation, &Material::default(), library)
+    }
+
+    fn down() -> Vec3 {
+        Vec3::new(0.0, -1.0, 0.0)
+    }
+
+    #[test]
+    fn builtins_parse_and_inherit() {
+        for (name, _) in BUILTIN_JSON {
+            assert!(BlockModel::builtin(name).is_some(), "{}", name);
+        }
+        let cube_all = BlockModel::builtin("minecraft:block/cube_all").unwrap();
+        assert_eq!(cube_all.elements.len(), 1);
+        let with_texture = cube_all.with_textures(&[("all".to_string(), "assets/brick.png".to_string())]);
+        assert_eq!(with_texture.texture("#east"), Some("assets/brick.png"));
+        assert_eq!(cube_all.texture("#east"), None);
+    }
+
+    #[test]
+    fn slab_and_stairs_have_the_right_height() {
+        let library = ModelLibrary::default();
+        let slab = place("slab", Vec2::zero(), &library);
+        let hit = slab.ray_intersect(&Vec3::new(0.5, 5.0, 0.5), &down()).unwrap();
+        assert!((hit.point.y - 0.5).abs() < 1e-5);
+        assert!((hit.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-5);
+
+        // la escalera sin girar tiene el escalón alto hacia +x; girada 180° hacia -x
+        let stairs = place("stairs", Vec2::zero(), &library);
+        let high = stairs.ray_intersect(&Vec3::new(0.75, 5.0, 0.5), &down()).unwrap();
+        let low = stairs.ray_intersect(&Vec3::new(0.25, 5.0, 0.5), &down()).unwrap();
+        assert!((high.point.y - 1.0).abs() < 1e-5 && (low.point.y - 0.5).abs() < 1e-5);
+        let turned = place("stairs", Vec2::new(0.0, 180.0), &library);
+        let high = turned.ray_intersect(&Vec3::new(0.25, 5.0, 0.5), &down()).unwrap();
+        assert!((high.point.y - 1.0).abs() < 1e-4);
+        let b = turned.bounds().unwrap();
+        assert!((b.min - Vec3::zero()).length() < 1e-4 && (b.max - Vec3::new(1.0, 1.0, 1.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn face_uvs_follow_the_element() {
+        let library = ModelLibrary::default();
+        // la cara de arriba de la losa usa el rectángulo completo: uv = posición en el bloque
+        let slab = place("slab", Vec2::zero(), &library);
+        let hit = slab.ray_intersect(&Vec3::new(0.25, 5.0, 0.75), &down()).unwrap();
+        let uv = hit.uv.unwrap();
+        assert!((uv.x - 0.25).abs() < 1e-5 && (uv.y - 0.25).abs() < 1e-5);
+        // el costado de la losa es la mitad de abajo de la textura
+        let side = slab.ray_intersect(&Vec3::new(0.5, 0.25, 5.0), &Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        let uv = side.uv.unwrap();
+        assert!((uv.y - 0.25).abs() < 1e-5 && (uv.x - 0.5).abs() < 1e-5);
+        // el palo de la antorcha usa las columnas 7 a 9 de su textura
+        let torch = place("template_torch", Vec2::zero(), &library);
+        let hit = torch.ray_intersect(&Vec3::new(0.5, 0.3, 5.0), &Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        let u = hit.uv.unwrap().x * 16.0;
+        assert!((7.0..=9.0).contains(&u));
+        assert!(torch.ray_intersect(&Vec3::new(0.2, 0.3, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn cross_quads_are_cut_by_the_alpha_mask() {
+        let mut library = ModelLibrary::default();
+        let cross = place("cross", Vec2::zero(), &library);
+        // los quads están girados 45°: el rayo por el eje x los encuentra en el centro
+        let hit = cross.ray_intersect(&Vec3::new(5.0, 0.5, 0.5), &Vec3::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.point.x - 0.5).abs() < 1e-4);
+        assert!(hit.normal.x > 0.5);
+        // rayo perpendicular al primer quad a `a` bloques del centro sobre el quad, a la altura y
+        let (along, normal) = (Vec3::new(1.0, 0.0, -1.0).normalized(), Vec3::new(1.0, 0.0, 1.0).normalized());
+        let shoot = |cross: &ModelShape, a: f32, y: f32| {
+            cross.ray_intersect(&(Vec3::new(0.5, y, 0.5) + along * a + normal * 5.0), &-normal)
+        };
+        // rescalado: el quad llega casi hasta las esquinas del bloque (0.8 a 15.2 píxeles)
+        assert!(shoot(&cross, 0.6, 0.5).is_some());
+        assert!(shoot(&cross, 0.66, 0.5).is_none());
+
+        // textura de 2×2 con solo el texel de arriba a la izquierda opaco
+        let clear = Rgba8::new(0, 0, 0, 0);
+        let image = RgbaImage::new(2, 2, vec![Rgba8::new(0, 200, 0, 255), clear, clear, clear]);
+        library.masks.push(("assets/flor.png".to_string(), Arc::new(AlphaMask::from_image(&image).unwrap())));
+        let flower = Material { texture_path: Some("assets/flor.png".to_string()), ..Material::default() };
+        let cross = ModelShape::new(library.model("cross").unwrap(), Vec3::new(0.5, 0.5, 0.5), Vec2::zero(), &flower, &library);
+        assert!(shoot(&cross, -0.25, 0.75).is_some());
+        assert!(shoot(&cross, 0.25, 0.75).is_none());
+        assert!(shoot(&cross, -0.25, 0.25).is_none());
+    }
+
+    #[test]
+    fn loads_json_with_parent() {
+        let dir = std::env::temp_dir().join(format!("animal_rt_models_{}", std::process::id()));
+        fs::create_dir_all(dir.join("block")).unwrap();
+        fs::write(
+            dir.join("block/oak_stairs.json"),
+            r##"{"parent": "minecraft:block/stairs", "textures": {"bottom": "assets/a.png", "top": "assets/a.png", "side": "assets/b.png"}}"##,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("block/lamp.json"),
+            r##"{"parent": "block/block", "textures": {"t": "assets/lamp.png"},
+                "elements": [{"from": [4, 0, 4], "to": [12, 8, 12], "faces": {"up": {"texture": "#t", "uv": [0, 0, 8, 8], "rotation": 90}}}]}"##,
+        )
+        .unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let stairs = BlockModel::load(&path("block/oak_stairs.json")).unwrap();
+        assert_eq!(stairs.elements.len(), 2);
+        assert_eq!(stairs.texture("#side"), Some("assets/b.png"));
+        let lamp = BlockModel::load(&path("block/lamp.json")).unwrap();
+        assert_eq!(lamp.elements[0].faces[1].as_ref().unwrap().rotation, 90);
+        let mut library = ModelLibrary::default();
+        library.load_model(&path("block/lamp.json")).unwrap();
+        assert!(library.load_model("no_existe").is_err());
+        let _ = fs::remove_dir_all(&dir);
+
+        let lamp = ModelShape::new(&library.models[0].1, Vec3::new(0.5, 0.5, 0.5), Vec2::zero(), &Material::default(), &library);
+        let hit = lamp.ray_intersect(&Vec3::new(0.5, 5.0, 0.5), &down()).unwrap();
+        assert_eq!(hit.material.texture_path.as_deref(), Some("assets/lamp.png"));
+        // sin cara de costado: un rayo horizontal no lo toca
+        assert!(lamp.ray_intersect(&Vec3::new(5.0, 0.25, 0.5), &Vec3::new(-1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_models() {
+        assert!(BlockModel::parse(r##"{"elements": [{"from": [0, 0], "to": [1, 1, 1]}]}"##).is_err());
+        assert!(BlockModel::parse(r##"{"elements": [{"from": [0, 0, 0], "to": [1, 1, 1], "faces": {"arriba": {"texture": "#a"}}}]}"##).is_err());
+        assert!(BlockModel::parse(r##"{"elements": [{"from": [0, 0, 0], "to": [1, 1, 1], "rotation": {"axis": "w"}}]}"##).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+// Jerarquía de volúmenes envolventes (BVH) sobre los objetos de la escena: cajas alineadas a los
+// ejes anidadas, con un objeto por hoja. Los rayos solo prueban los objetos cuyas cajas cruzan.
+//
+// Cuando un objeto se mueve no hace falta reconstruir todo: `update` cambia la caja de su hoja y
+// re-ajusta (refit) las cajas de sus ancestros. El árbol sigue siendo correcto aunque pierda algo
+// de calidad si los objetos se alejan mucho de donde estaban al construirlo; `build` lo rehace.
+
+use crate::math::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    };
+
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Aabb::EMPTY, |b, p| Aabb::new(b.min.min(p), b.max.max(p)))
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Caja agrandada `margin` en cada dirección
+    pub fn expanded(&self, margin: f32) -> Aabb {
+        Aabb::new(self.min - margin, self.max + margin)
+    }
+
+    /// Distancia (en unidades de `t`) a la que el rayo entra a la caja, 0 si el origen está adentro,
+    /// o None si no la cruza antes de `t_max`. `inv_direction` es 1/dirección por componente.
+    pub fn hit(&self, origin: &Vec3, inv_direction: &Vec3, t_max: f32) -> Option<f32> {
+        let mut t_near = 0.0f32;
+        let mut t_far = t_max;
+        for (o, inv, min, max) in [
+            (origin.x, inv_direction.x, self.min.x, self.max.x),
+            (origin.y, inv_direction.y, self.min.y, self.max.y),
+            (origin.z, inv_direction.z, self.min.z, self.max.z),
+        ] {
+            let t1 = (min - o) * inv;
+            let t2 = (max - o) * inv;
+            // con dirección 0 en este eje, t1/t2 son ±inf (o NaN si el origen está justo en el
+            // borde); max/min de f32 descartan los NaN
+            t_near = t_near.max(t1.min(t2));
+            t_far = t_far.min(t1.max(t2));
+        }
+        (t_near <= t_far).then_some(t_near)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum NodeKind {
+    Leaf(usize),
+    Inner(usize, usize),
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    bounds: Aabb,
+    parent: Option<usize>,
+    kind: NodeKind,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    root: Option<usize>,
+    // caja de cada objeto (None = sin límites, p.ej. un plano infinito) y la hoja que lo contiene
+    object_bounds: Vec<Option<Aabb>>,
+    leaf_of: Vec<Option<usize>>,
+    // objetos sin caja: se prueban siempre
+    unbounded: Vec<usize>,
+}
+
+// margen para que los rayos rasantes no se pierdan por redondeo en el borde de la caja
+const MARGIN: f32 = 1e-4;
+
+impl Bvh {
+    /// Árbol para objetos con estas cajas (el índice en el slice es el índice del objeto)
+    pub fn build(bounds: &[Option<Aabb>]) -> Self {
+        let mut bvh = Bvh {
+            object_bounds: bounds.to_vec(),
+            leaf_of: vec![None; bounds.len()],
+            ..Default::default()
+        };
+        let mut bounded = Vec::new();
+        for (index, b) in bounds.iter().enumerate() {
+            match b {
+                Some(_) => bounded.push(index),
+                None => bvh.unbounded.push(index),
+            }
+        }
+        if !bounded.is_empty() {
+            bvh.root = Some(bvh.build_node(&mut bounded, None));
+        }
+        bvh
+    }
+
+    // divide por la mediana de los centroides en el eje más largo
+    fn build_node(&mut self, objects: &mut [usize], parent: Option<usize>) -> usize {
+        let index = self.nodes.len();
+        if let [object] = objects {
+            let bounds = self.object_bounds[*object].unwrap().expanded(MARGIN);
+            self.nodes.push(Node { bounds, parent, kind: NodeKind::Leaf(*object) });
+            self.leaf_of[*object] = Some(index);
+            return index;
+        }
+
+        let centroids = Aabb::from_points(objects.iter().map(|&o| self.object_bounds[o].unwrap().centroid()));
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let key = |o: &usize| {
+            let c = self.object_bounds[*o].unwrap().centroid();
+            [c.x, c.y, c.z][axis]
+        };
+        objects.sort_by(|a, b| key(a).total_cmp(&key(b)));
+
+        // se reserva el nodo antes de los hijos para que la raíz quede primera
+        self.nodes.push(Node { bounds: Aabb::EMPTY, parent, kind: NodeKind::Inner(0, 0) });
+        let (left, right) = objects.split_at_mut(objects.len() / 2);
+        let left = self.build_node(left, Some(index));
+        let right = self.build_node(right, Some(index));
+        self.nodes[index].bounds = self.nodes[left].bounds.union(&self.nodes[right].bounds);
+        self.nodes[index].kind = NodeKind::Inner(left, right);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.object_bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.object_bounds.is_empty()
+    }
+
+    /// Cambia la caja de un objeto. Si sigue teniendo caja (y ya la tenía) solo se re-ajustan su
+    /// hoja y los ancestros; si pasa de tener caja a no tenerla (o al revés) se reconstruye.
+    pub fn update(&mut self, object: usize, bounds: Option<Aabb>) {
+        let had_bounds = self.object_bounds[object].is_some();
+        self.object_bounds[object] = bounds;
+        match (self.leaf_of[object], bounds) {
+            (Some(leaf), Some(bounds)) => {
+                self.nodes[leaf].bounds = bounds.expanded(MARGIN);
+                let mut node = self.nodes[leaf].parent;
+                while let Some(index) = node {
+                    if let NodeKind::Inner(left, right) = self.nodes[index].kind {
+                        self.nodes[index].bounds = self.nodes[left].bounds.union(&self.nodes[right].bounds);
+                    }
+                    node = self.nodes[index].parent;
+                }
+            }
+            (None, None) if !had_bounds => {}
+            _ => *self = Bvh::build(&self.object_bounds),
+        }
+    }
+
+    /// Recorre los objetos cuyas cajas cruza el rayo, del más cercano al más lejano (en orden
+    /// aproximado). `visit(objeto, &mut t_max)` puede achicar `t_max` para podar lo que queda
+    /// detrás de un impacto, y devuelve true para terminar el recorrido.
+    pub fn traverse(&self, origin: &Vec3, direction: &Vec3, t_max: f32, mut visit: impl FnMut(usize, &mut f32) -> bool) {
+        let mut t_max = t_max;
+        for &object in &self.unbounded {
+            if visit(object, &mut t_max) {
+                return;
+            }
+        }
+        let Some(root) = self.root else { return };
+        let inv_direction = Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+
+        let Some(t_root) = self.nodes[root].bounds.hit(origin, &inv_direction, t_max) else { return };
+        let mut stack = vec![(root, t_root)];
+        while let Some((index, t_enter)) = stack.pop() {
+            // la caja pudo quedar detrás de un impacto encontrado después de apilarla
+            if t_enter > t_max {
+                continue;
+            }
+            match self.nodes[index].kind {
+                NodeKind::Leaf(object) => {
+                    if visit(object, &mut t_max) {
+                        return;
+                    }
+                }
+                NodeKind::Inner(left, right) => {
+                    let hit_left = self.nodes[left].bounds.hit(origin, &inv_direction, t_max).map(|t| (left, t));
+                    let hit_right = self.nodes[right].bounds.hit(origin, &inv_direction, t_max).map(|t| (right, t));
+                    // el más cercano se apila último para visitarlo primero
+                    match (hit_left, hit_right) {
+                        (Some(a), Some(b)) if a.1 <= b.1 => stack.extend([b, a]),
+                        (Some(a), Some(b)) => stack.extend([a, b]),
+                        (Some(a), None) | (None, Some(a)) => stack.push(a),
+                        (None, None) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::Rng;
+
+    fn unit_box(center: Vec3) -> Option<Aabb> {
+        Some(Aabb::new(center - 0.5, center + 0.5))
+    }
+
+    // todos los objetos cuyas cajas cruza el rayo, sin podar
+    fn visited(bvh: &Bvh, origin: Vec3, direction: Vec3) -> Vec<usize> {
+        let mut objects = Vec::new();
+        bvh.traverse(&origin, &direction, f32::INFINITY, |o, _| {
+            objects.push(o);
+            false
+        });
+        objects.sort();
+        objects
+    }
+
+    #[test]
+    fn aabb_hit_reports_entry_distance() {
+        let b = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let inv = |d: Vec3| Vec3::new(1.0 / d.x, 1.0 / d.y, 1.0 / d.z);
+        let d = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(b.hit(&Vec3::new(0.0, 0.0, -5.0), &inv(d), f32::INFINITY), Some(4.0));
+        assert_eq!(b.hit(&Vec3::new(0.0, 0.0, 0.0), &inv(d), f32::INFINITY), Some(0.0));
+        assert_eq!(b.hit(&Vec3::new(0.0, 0.0, -5.0), &inv(d), 3.0), None);
+        assert_eq!(b.hit(&Vec3::new(2.0, 0.0, -5.0), &inv(d), f32::INFINITY), None);
+        assert_eq!(b.hit(&Vec3::new(0.0, 0.0, 5.0), &inv(d), f32::INFINITY), None);
+    }
+
+    #[test]
+    fn traversal_matches_brute_force() {
+        let mut rng = Rng::new(7);
+        let mut bounds: Vec<Option<Aabb>> = (0..40)
+            .map(|_| unit_box(Vec3::new(rng.next_f32() * 20.0 - 10.0, rng.next_f32() * 20.0 - 10.0, rng.next_f32() * 20.0 - 10.0)))
+            .collect();
+        bounds[3] = None;
+        let mut bvh = Bvh::build(&bounds);
+
+        for round in 0..2 {
+            for _ in 0..200 {
+                let origin = Vec3::new(rng.next_f32() * 30.0 - 15.0, rng.next_f32() * 30.0 - 15.0, rng.next_f32() * 30.0 - 15.0);
+                let direction = Vec3::new(rng.next_f32() - 0.5, rng.next_f32() - 0.5, rng.next_f32() - 0.5).normalized();
+                let inv = Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+                let expected: Vec<usize> = (0..bounds.len())
+                    .filter(|&o| bounds[o].is_none_or(|b| b.expanded(MARGIN).hit(&origin, &inv, f32::INFINITY).is_some()))
+                    .collect();
+                assert_eq!(visited(&bvh, origin, direction), expected, "ronda {}", round);
+            }
+            // mover la mitad de los objetos y re-ajustar sin reconstruir
+            for object in (0..bounds.len()).step_by(2) {
+                bounds[object] = unit_box(Vec3::new(rng.next_f32() * 20.0 - 10.0, 0.0, rng.next_f32() * 20.0 - 10.0));
+                bvh.update(object, bounds[object]);
+            }
+        }
+    }
+
+    #[test]
+    fn update_handles_objects_gaining_and_losing_bounds() {
+        let mut bvh = Bvh::build(&[unit_box(Vec3::zero()), None]);
+        let forward = Vec3::new(0.0, 0.0, 1.0);
+        let origin = Vec3::new(5.0, 0.0, -5.0);
+        assert_eq!(visited(&bvh, origin, forward), vec![1]);
+        bvh.update(1, unit_box(Vec3::new(5.0, 0.0, 0.0)));
+        assert_eq!(visited(&bvh, origin, forward), vec![1]);
+        bvh.update(1, unit_box(Vec3::new(-5.0, 0.0, 0.0)));
+        assert!(visited(&bvh, origin, forward).is_empty());
+        bvh.update(0, None);
+        assert_eq!(visited(&bvh, origin, forward), vec![0]);
+    }
+
+    #[test]
+    fn pruning_skips_boxes_behind_a_hit() {
+        let bounds: Vec<Option<Aabb>> = (0..8).map(|i| unit_box(Vec3::new(0.0, 0.0, i as f32 * 3.0))).collect();
+        let bvh = Bvh::build(&bounds);
+        let mut objects = Vec::new();
+        bvh.traverse(&Vec3::new(0.0, 0.0, -5.0), &Vec3::new(0.0, 0.0, 1.0), f32::INFINITY, |o, t_max| {
+            objects.push(o);
+            *t_max = 4.5;
+            false
+        });
+        assert_eq!(objects, vec![0]);
+    }
+}
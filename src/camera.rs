@@ -1,23 +1,27 @@
-use raylib::prelude::*;
+use crate::math::{Mat4, Ray, Vec3};
+use std::f32::consts::PI;
 
+// campo de visión vertical de los rayos primarios
+pub const FOV: f32 = PI / 3.0;
 
+#[derive(Clone)]
 pub struct Camera {
-    pub eye: Vector3,  // donde esta la camara en el mundo  7, 100, 10
-    pub center: Vector3,     // que mira la camara  7, 100, 5
-    pub up: Vector3,     // what is up? for the camera
+    pub eye: Vec3,  // donde esta la camara en el mundo  7, 100, 10
+    pub center: Vec3,     // que mira la camara  7, 100, 5
+    pub up: Vec3,     // what is up? for the camera
 
-    pub forward: Vector3,
-    pub right: Vector3,
+    pub forward: Vec3,
+    pub right: Vec3,
 }
 
 impl Camera {
-    pub fn new(eye: Vector3, center: Vector3, up: Vector3) -> Self {
+    pub fn new(eye: Vec3, center: Vec3, up: Vec3) -> Self {
         let mut camera = Camera {
             eye,
             center,
             up,
-            forward: Vector3::zero(),
-            right: Vector3::zero(),
+            forward: Vec3::zero(),
+            right: Vec3::zero(),
         };
 
         camera.update_basis();
@@ -47,7 +51,7 @@ impl Camera {
         // x = r * cos(a) * cos(b)
         // y = r * sin(a)
         // z = r * cos(a) * sin (b)
-        let new_relative_pos = Vector3::new(
+        let new_relative_pos = Vec3::new(
             radius * pitch_cos * new_yaw.cos(),
             radius * pitch_sin,
             radius * pitch_cos * new_yaw.sin(),
@@ -58,11 +62,47 @@ impl Camera {
         self.update_basis();
     }
 
-    pub fn basis_change(&self, p: &Vector3) -> Vector3 {
-        Vector3::new(
-            p.x * self.right.x + p.y * self.up.x - p.z * self.forward.x,
-            p.x * self.right.y + p.y * self.up.y - p.z * self.forward.y,
-            p.x * self.right.z + p.y * self.up.z - p.z * self.forward.z,
-        )
+    /// Rotación de espacio cámara (x derecha, y arriba, -z adelante) a espacio mundo
+    pub fn camera_to_world(&self) -> Mat4 {
+        Mat4::from_axes(self.right, self.up, -self.forward)
+    }
+
+    pub fn basis_change(&self, p: &Vec3) -> Vec3 {
+        self.camera_to_world().transform_vector(*p)
+    }
+
+    // Dirección (en mundo) del rayo primario que pasa por el píxel (x, y)
+    pub fn primary_ray_direction(&self, x: f32, y: f32, width: f32, height: f32) -> Vec3 {
+        let aspect_ratio = width / height;
+        let perspective_scale = (FOV * 0.5).tan();
+
+        let screen_x = (2.0 * x) / width - 1.0;
+        let screen_y = -(2.0 * y) / height + 1.0;
+        let sx = screen_x * aspect_ratio * perspective_scale;
+        let sy = screen_y * perspective_scale;
+        let ray_direction = Vec3::new(sx, sy, -1.0).normalized();
+        self.basis_change(&ray_direction)
+    }
+
+    /// Rayo primario (desde el ojo) que pasa por el píxel (x, y)
+    pub fn primary_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Ray {
+        Ray::new(self.eye, self.primary_ray_direction(x, y, width, height))
+    }
+
+    /// Inversa de primary_ray_direction: píxel (x, y) en el que se ve el punto `p`,
+    /// o None si queda detrás de la cámara.
+    pub fn project(&self, p: &Vec3, width: f32, height: f32) -> Option<(f32, f32)> {
+        let d = *p - self.eye;
+        let z = d.dot(self.forward);
+        if z <= 1e-4 {
+            return None;
+        }
+        let aspect_ratio = width / height;
+        let perspective_scale = (FOV * 0.5).tan();
+        let sx = d.dot(self.right) / z;
+        let sy = d.dot(self.up) / z;
+        let screen_x = sx / (aspect_ratio * perspective_scale);
+        let screen_y = sy / perspective_scale;
+        Some(((screen_x + 1.0) * width * 0.5, (1.0 - screen_y) * height * 0.5))
     }
 }
@@ -0,0 +1,197 @@
+// Geometría constructiva de sólidos (CSG): un nodo combina dos objetos por unión, intersección o
+// diferencia, p.ej. una pared menos un cubo para abrir una ventana o un cubo menos un cilindro
+// para un arco. Se recorren los tramos del rayo dentro de cada hijo (RayIntersect::ray_spans) y
+// se quedan los pedazos donde la operación da "adentro"; cada borde conserva la normal y el
+// material del hijo cuya superficie es. Los hijos tienen que ser sólidos cerrados (Cube, Sphere,
+// las primitivas cerradas de src/primitives.rs u otro Csg); los que no devuelven tramos cuentan
+// como vacíos.
+
+use crate::bvh::Aabb;
+use crate::math::Vec3;
+use crate::ray_intersect::{HitInfo, RayIntersect, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    // el primero menos el segundo
+    Difference,
+}
+
+impl CsgOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            CsgOp::Union => "union",
+            CsgOp::Intersection => "intersection",
+            CsgOp::Difference => "difference",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CsgOp> {
+        [CsgOp::Union, CsgOp::Intersection, CsgOp::Difference].into_iter().find(|op| op.name() == name)
+    }
+
+    fn inside(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+pub struct Csg {
+    pub op: CsgOp,
+    pub a: Box<dyn RayIntersect + Send + Sync>,
+    pub b: Box<dyn RayIntersect + Send + Sync>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, a: Box<dyn RayIntersect + Send + Sync>, b: Box<dyn RayIntersect + Send + Sync>) -> Self {
+        Csg { op, a, b }
+    }
+}
+
+// en la diferencia la superficie del segundo queda del lado de adentro: su normal se da vuelta
+fn flipped(mut hit: HitInfo) -> HitInfo {
+    hit.normal = -hit.normal;
+    hit.local_normal = -hit.local_normal;
+    hit
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        // el primer borde delante del origen, como los primitivos: la entrada o, si el origen
+        // está adentro, la salida
+        let span = self.ray_spans(ray_origin, ray_direction).into_iter().next()?;
+        Some(if span.enter.distance >= 0.0 { span.enter } else { span.exit })
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        // bordes de los dos hijos ordenados por distancia: (hijo b?, entra?, choque)
+        let mut events: Vec<(bool, bool, HitInfo)> = Vec::new();
+        for (is_b, child) in [(false, &self.a), (true, &self.b)] {
+            for span in child.ray_spans(ray_origin, ray_direction) {
+                events.push((is_b, true, span.enter));
+                events.push((is_b, false, span.exit));
+            }
+        }
+        events.sort_by(|x, y| x.2.distance.total_cmp(&y.2.distance));
+
+        let (mut in_a, mut in_b) = (false, false);
+        let mut enter: Option<HitInfo> = None;
+        let mut spans = Vec::new();
+        for (is_b, entering, hit) in events {
+            let was_inside = self.op.inside(in_a, in_b);
+            if is_b {
+                in_b = entering;
+            } else {
+                in_a = entering;
+            }
+            let inside = self.op.inside(in_a, in_b);
+            if was_inside == inside {
+                continue;
+            }
+            let hit = if is_b && self.op == CsgOp::Difference { flipped(hit) } else { hit };
+            if inside {
+                enter = Some(hit);
+            } else if let Some(enter) = enter.take()
+                && hit.distance >= 0.0
+            {
+                spans.push(Span { enter, exit: hit });
+            }
+        }
+        spans
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (a, b) = (self.a.bounds(), self.b.bounds());
+        match self.op {
+            CsgOp::Union => Some(a?.union(&b?)),
+            // lo que queda está dentro de los dos (o del primero en la diferencia)
+            CsgOp::Intersection => match (a, b) {
+                (Some(a), Some(b)) => Some(Aabb::new(a.min.max(b.min), a.max.min(b.max))),
+                (a, b) => a.or(b),
+            },
+            CsgOp::Difference => a,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::math::Color;
+    use crate::sphere::Sphere;
+
+    fn colored(r: f32) -> Material {
+        Material { diffuse: Color::new(r, 0.0, 0.0), ..Material::default() }
+    }
+
+    fn wall_with_window() -> Csg {
+        // pared de 4×4×0.5 con un hueco de 1×1 en el medio
+        let wall = Cube::new(Vec3::zero(), Vec3::new(2.0, 2.0, 0.25), 0.0, 0.0, colored(1.0));
+        let hole = Cube::new(Vec3::zero(), Vec3::new(0.5, 0.5, 1.0), 0.0, 0.0, colored(0.5));
+        Csg::new(CsgOp::Difference, Box::new(wall), Box::new(hole))
+    }
+
+    #[test]
+    fn difference_carves_a_window() {
+        let wall = wall_with_window();
+        let forward = Vec3::new(0.0, 0.0, -1.0);
+        // por el hueco el rayo pasa derecho
+        assert!(wall.ray_intersect(&Vec3::new(0.0, 0.0, 5.0), &forward).is_none());
+        // fuera del hueco choca con la cara de la pared
+        let hit = wall.ray_intersect(&Vec3::new(1.5, 0.0, 5.0), &forward).unwrap();
+        assert!((hit.distance - 4.75).abs() < 1e-5);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-5);
+        // desde el medio del hueco, el borde de la ventana es la cara del hueco dada vuelta y con su material
+        let hit = wall.ray_intersect(&Vec3::zero(), &Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.point.x - 0.5).abs() < 1e-5);
+        assert!((hit.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-5);
+        assert_eq!(hit.material.diffuse, Color::new(0.5, 0.0, 0.0));
+        let b = wall.bounds().unwrap();
+        assert_eq!((b.min, b.max), (Vec3::new(-2.0, -2.0, -0.25), Vec3::new(2.0, 2.0, 0.25)));
+    }
+
+    #[test]
+    fn union_and_intersection_of_spheres() {
+        let sphere = |x: f32, r: f32| Box::new(Sphere { center: Vec3::new(x, 0.0, 0.0), radius: 1.0, material: colored(r) });
+        let right = Vec3::new(1.0, 0.0, 0.0);
+        let origin = Vec3::new(-5.0, 0.0, 0.0);
+
+        let union = Csg::new(CsgOp::Union, sphere(-0.5, 1.0), sphere(0.5, 0.5));
+        let spans = union.ray_spans(&origin, &right);
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].enter.point.x + 1.5).abs() < 1e-5 && (spans[0].exit.point.x - 1.5).abs() < 1e-5);
+        assert_eq!(spans[0].exit.material.diffuse, Color::new(0.5, 0.0, 0.0));
+
+        let lens = Csg::new(CsgOp::Intersection, sphere(-0.5, 1.0), sphere(0.5, 0.5));
+        let hit = lens.ray_intersect(&origin, &right).unwrap();
+        // entra por la segunda esfera (en x = -0.5) con su normal hacia afuera
+        assert!((hit.point.x + 0.5).abs() < 1e-5);
+        assert!(hit.normal.x < -0.99);
+        assert_eq!(hit.material.diffuse, Color::new(0.5, 0.0, 0.0));
+        // desde adentro devuelve la salida
+        let hit = lens.ray_intersect(&Vec3::zero(), &right).unwrap();
+        assert!((hit.point.x - 0.5).abs() < 1e-5 && hit.normal.x > 0.99);
+        let b = lens.bounds().unwrap();
+        assert!((b.min.x + 0.5).abs() < 1e-6 && (b.max.x - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn nested_nodes_combine_spans() {
+        // la pared con ventana unida a un marco que tapa el hueco por la mitad de arriba
+        let frame = Cube::new(Vec3::new(0.0, 0.25, 0.0), Vec3::new(0.5, 0.25, 0.1), 0.0, 0.0, colored(0.25));
+        let node = Csg::new(CsgOp::Union, Box::new(wall_with_window()), Box::new(frame));
+        let forward = Vec3::new(0.0, 0.0, -1.0);
+        let hit = node.ray_intersect(&Vec3::new(0.0, 0.25, 5.0), &forward).unwrap();
+        assert!((hit.distance - 4.9).abs() < 1e-5);
+        assert_eq!(hit.material.diffuse, Color::new(0.25, 0.0, 0.0));
+        assert!(node.ray_intersect(&Vec3::new(0.0, -0.25, 5.0), &forward).is_none());
+        // detrás del rayo no cuenta
+        assert!(node.ray_intersect(&Vec3::new(1.5, 0.0, -5.0), &forward).is_none());
+    }
+}
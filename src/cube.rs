@@ -1,27 +1,31 @@
-use raylib::prelude::*;
-use crate::ray_intersect::{RayIntersect, HitInfo};
+use crate::bvh::Aabb;
+use crate::math::{Vec2, Vec3};
+use crate::ray_intersect::{RayIntersect, HitInfo, Span};
 use crate::material::Material;
 use std::f32;
 
+#[derive(Clone)]
 pub struct Cube {
     // Center en espacio mundo, half_size en cada eje (caja AABB en espacio local)
-    pub center: Vector3,
-    pub half_size: Vector3,
+    pub center: Vec3,
+    pub half_size: Vec3,
     // Rotación en radianes (rotar primero X, luego Y) — puedes ajustar rx, ry
     pub rot_x: f32,
     pub rot_y: f32,
     pub material: Material,
-    pub texture_repeat: Vector2,
+    pub texture_repeat: Vec2,
+    // si es true la repetición se calcula por cara (ver Cube::tiled) y texture_repeat no se usa
+    pub tile_per_face: bool,
 }
 
 impl Cube {
-    pub fn new(center: Vector3, half_size: Vector3, rot_x: f32, rot_y: f32, material: Material) -> Self {
+    pub fn new(center: Vec3, half_size: Vec3, rot_x: f32, rot_y: f32, material: Material) -> Self {
         // Factor base: decide cuántas repeticiones por unidad de tamaño
         // Por ejemplo, 1.0 -> una repetición por unidad; 2.0 -> 2 repeticiones por unidad
         let base_repeat_factor = 1.0;
 
         // usamos tamaño total (2 * half_size) para calcular repeticiones
-        let texture_repeat = Vector2::new(
+        let texture_repeat = Vec2::new(
             (half_size.x * 2.0 * base_repeat_factor).max(1.0),
             (half_size.y * 2.0 * base_repeat_factor).max(1.0),
         );
@@ -33,13 +37,37 @@ impl Cube {
             rot_y,
             material,
             texture_repeat,
+            tile_per_face: false,
+        }
+    }
+
+    /// Caja alineada con los ejes cuya textura se repite una vez por unidad de largo en los dos
+    /// ejes de cada cara, igual que una pared de cubos de 1×1×1 (lo que genera merge::merge_blocks).
+    /// `new` usa el ancho y el alto de la caja para todas las caras.
+    pub fn tiled(center: Vec3, half_size: Vec3, material: Material) -> Self {
+        Cube { tile_per_face: true, ..Cube::new(center, half_size, 0.0, 0.0, material) }
+    }
+
+    // repeticiones (u, v) para la cara con esa normal local, con el mismo mapeo de ejes que
+    // renderer::map_uv_for_cube
+    fn face_repeat(&self, local_normal: Vec3) -> Vec2 {
+        if !self.tile_per_face {
+            return self.texture_repeat;
+        }
+        let size = self.half_size * 2.0;
+        if local_normal.x.abs() > 0.9 {
+            Vec2::new(size.z, size.y)
+        } else if local_normal.y.abs() > 0.9 {
+            Vec2::new(size.x, size.z)
+        } else {
+            Vec2::new(size.x, size.y)
         }
     }
 
     // rota vector por X
-    fn rotate_x(v: Vector3, angle: f32) -> Vector3 {
+    fn rotate_x(v: Vec3, angle: f32) -> Vec3 {
         let (s, c) = angle.sin_cos();
-        Vector3::new(
+        Vec3::new(
             v.x,
             v.y * c - v.z * s,
             v.y * s + v.z * c,
@@ -47,9 +75,9 @@ impl Cube {
     }
 
     // rota vector por Y
-    fn rotate_y(v: Vector3, angle: f32) -> Vector3 {
+    fn rotate_y(v: Vec3, angle: f32) -> Vec3 {
         let (s, c) = angle.sin_cos();
-        Vector3::new(
+        Vec3::new(
             v.x * c + v.z * s,
             v.y,
             -v.x * s + v.z * c,
@@ -57,13 +85,13 @@ impl Cube {
     }
 
     // Aplicar rotación forward: Rx then Ry -> v' = Ry(Rx(v))
-    fn rotate_forward(&self, v: Vector3) -> Vector3 {
+    fn rotate_forward(&self, v: Vec3) -> Vec3 {
         let v = Cube::rotate_x(v, self.rot_x);
         Cube::rotate_y(v, self.rot_y)
     }
 
     // Aplicar rotación inversa: inverse(Ry*Rx) = Rx(-rx) * Ry(-ry) -> aplicar Ry(-ry) then Rx(-rx)
-    fn rotate_inverse(&self, v: Vector3) -> Vector3 {
+    fn rotate_inverse(&self, v: Vec3) -> Vec3 {
         let v = Cube::rotate_y(v, -self.rot_y);
         Cube::rotate_x(v, -self.rot_x)
     }
@@ -83,10 +111,42 @@ impl Cube {
             (t1.min(t2), t1.max(t2))
         }
     }
+
+    /// Intervalo [t_entrada, t_salida] del rayo dentro de la caja (t_entrada puede ser negativo
+    /// si el origen está adentro). Útil para volúmenes acotados por un cubo.
+    pub fn ray_interval(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let local_origin = self.rotate_inverse(*ray_origin - self.center);
+        let local_direction = self.rotate_inverse(*ray_direction);
+
+        let (txmin, txmax) = Cube::safe_slab(local_origin.x, local_direction.x, -self.half_size.x, self.half_size.x);
+        let (tymin, tymax) = Cube::safe_slab(local_origin.y, local_direction.y, -self.half_size.y, self.half_size.y);
+        let (tzmin, tzmax) = Cube::safe_slab(local_origin.z, local_direction.z, -self.half_size.z, self.half_size.z);
+
+        let t_near = txmin.max(tymin).max(tzmin);
+        let t_far = txmax.min(tymax).min(tzmax);
+
+        if t_near > t_far || t_far < 0.0 {
+            return None;
+        }
+        Some((t_near, t_far))
+    }
 }
 
 impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<HitInfo> {
+    fn bounds(&self) -> Option<Aabb> {
+        let h = self.half_size;
+        let corners = (0..8).map(|i| {
+            let corner = Vec3::new(
+                if i & 1 == 0 { -h.x } else { h.x },
+                if i & 2 == 0 { -h.y } else { h.y },
+                if i & 4 == 0 { -h.z } else { h.z },
+            );
+            self.center + self.rotate_forward(corner)
+        });
+        Some(Aabb::from_points(corners))
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
         // Transformar rayo al espacio local del cubo:
         // 1) trasladar por -center
         // 2) rotar por la inversa
@@ -94,8 +154,8 @@ impl RayIntersect for Cube {
         let local_direction = self.rotate_inverse(*ray_direction); // rotación sin traslación para direcciones
 
         // En espacio local la caja es AABB con bounds [-half, +half]
-        let min = Vector3::new(-self.half_size.x, -self.half_size.y, -self.half_size.z);
-        let max = Vector3::new( self.half_size.x,  self.half_size.y,  self.half_size.z);
+        let min = Vec3::new(-self.half_size.x, -self.half_size.y, -self.half_size.z);
+        let max = Vec3::new( self.half_size.x,  self.half_size.y,  self.half_size.z);
 
         // Slab test en espacio local
         let (txmin, txmax) = Cube::safe_slab(local_origin.x, local_direction.x, min.x, max.x);
@@ -121,33 +181,70 @@ impl RayIntersect for Cube {
 
         // Determinar normal en espacio local según el slab que produjo t_near
         let eps = 1e-5;
-        let mut local_normal = Vector3::new(0.0, 0.0, 0.0);
+        let local_normal;
 
         if (t - txmin).abs() < 1e-6 {
-            local_normal = if local_direction.x > 0.0 { Vector3::new(-1.0, 0.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+            local_normal = if local_direction.x > 0.0 { Vec3::new(-1.0, 0.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
         } else if (t - tymin).abs() < 1e-6 {
-            local_normal = if local_direction.y > 0.0 { Vector3::new(0.0, -1.0, 0.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+            local_normal = if local_direction.y > 0.0 { Vec3::new(0.0, -1.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
         } else if (t - tzmin).abs() < 1e-6 {
-            local_normal = if local_direction.z > 0.0 { Vector3::new(0.0, 0.0, -1.0) } else { Vector3::new(0.0, 0.0, 1.0) };
+            local_normal = if local_direction.z > 0.0 { Vec3::new(0.0, 0.0, -1.0) } else { Vec3::new(0.0, 0.0, 1.0) };
         } else {
             // fallback por proximidad a las caras
-            if (local_hit.x - min.x).abs() < eps { local_normal = Vector3::new(-1.0, 0.0, 0.0); }
-            else if (local_hit.x - max.x).abs() < eps { local_normal = Vector3::new(1.0, 0.0, 0.0); }
-            else if (local_hit.y - min.y).abs() < eps { local_normal = Vector3::new(0.0, -1.0, 0.0); }
-            else if (local_hit.y - max.y).abs() < eps { local_normal = Vector3::new(0.0, 1.0, 0.0); }
-            else if (local_hit.z - min.z).abs() < eps { local_normal = Vector3::new(0.0, 0.0, -1.0); }
-            else if (local_hit.z - max.z).abs() < eps { local_normal = Vector3::new(0.0, 0.0, 1.0); }
+            if (local_hit.x - min.x).abs() < eps { local_normal = Vec3::new(-1.0, 0.0, 0.0); }
+            else if (local_hit.x - max.x).abs() < eps { local_normal = Vec3::new(1.0, 0.0, 0.0); }
+            else if (local_hit.y - min.y).abs() < eps { local_normal = Vec3::new(0.0, -1.0, 0.0); }
+            else if (local_hit.y - max.y).abs() < eps { local_normal = Vec3::new(0.0, 1.0, 0.0); }
+            else if (local_hit.z - min.z).abs() < eps { local_normal = Vec3::new(0.0, 0.0, -1.0); }
+            else if (local_hit.z - max.z).abs() < eps { local_normal = Vec3::new(0.0, 0.0, 1.0); }
             else {
                 local_normal = (local_hit).normalized();
             }
         }
 
+        Some(self.hit_at(local_hit, local_normal, t))
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        let Some((t_near, t_far)) = self.ray_interval(ray_origin, ray_direction) else { return Vec::new() };
+        let local_origin = self.rotate_inverse(*ray_origin - self.center);
+        let local_direction = self.rotate_inverse(*ray_direction);
+
+        // la cara por la que entra es la del slab con la entrada más lejana, y la de salida la del
+        // slab con la salida más cercana; las dos normales miran hacia afuera
+        let axis = |v: Vec3, i: usize| [v.x, v.y, v.z][i];
+        let unit = |i: usize, sign: f32| {
+            let mut n = [0.0; 3];
+            n[i] = sign;
+            Vec3::new(n[0], n[1], n[2])
+        };
+        let slabs: Vec<(f32, f32)> = (0..3)
+            .map(|i| {
+                let h = axis(self.half_size, i);
+                Cube::safe_slab(axis(local_origin, i), axis(local_direction, i), -h, h)
+            })
+            .collect();
+        let enter_axis = (0..3).max_by(|&a, &b| slabs[a].0.total_cmp(&slabs[b].0)).unwrap_or(0);
+        let exit_axis = (0..3).min_by(|&a, &b| slabs[a].1.total_cmp(&slabs[b].1)).unwrap_or(0);
+        let enter_normal = unit(enter_axis, -axis(local_direction, enter_axis).signum());
+        let exit_normal = unit(exit_axis, axis(local_direction, exit_axis).signum());
+
+        vec![Span {
+            enter: self.hit_at(local_origin + local_direction * t_near, enter_normal, t_near),
+            exit: self.hit_at(local_origin + local_direction * t_far, exit_normal, t_far),
+        }]
+    }
+}
+
+impl Cube {
+    // choque en el punto y la normal locales dados, con punto y normal pasados a espacio mundo
+    fn hit_at(&self, local_hit: Vec3, local_normal: Vec3, t: f32) -> HitInfo {
         // Transformar punto y normal de vuelta a espacio mundo
         let world_point = self.rotate_forward(local_hit) + self.center;
         // Normales rotan con la rotación forward (rotación sin translación)
         let world_normal = self.rotate_forward(local_normal).normalized();
 
-       Some(HitInfo {
+        HitInfo {
             hit: true,
             point: world_point,
             local_point: local_hit,            // punto en espacio local del cubo
@@ -156,7 +253,8 @@ impl RayIntersect for Cube {
             local_normal,                      // normal en espacio local
             distance: t,
             material: self.material.clone(),
-            texture_repeat: self.texture_repeat,
-        })
+            texture_repeat: self.face_repeat(local_normal),
+            uv: None,
+        }
     }
 }
@@ -0,0 +1,182 @@
+use crate::math::{Color, Vec3};
+use std::f32::consts::PI;
+use crate::light::Light;
+
+// Ciclo día/noche: a partir de una hora del día (0..24) calcula la posición del sol y la luna,
+// los colores del cielo procedural y cuánto pesan los bloques emisivos (antorchas, glowstone).
+#[derive(Clone, Copy)]
+pub struct DayCycle {
+    pub time: f32,     // hora del día en [0, 24)
+    pub speed: f32,    // horas por segundo cuando `running == true`
+    pub running: bool, // avance automático
+}
+
+// Colores del gradiente del cielo para un momento dado
+pub struct SkyPalette {
+    pub ground: Color,
+    pub horizon: Color,
+    pub zenith: Color,
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// hash barato para las estrellas (determinista por celda)
+fn hash2(x: i32, y: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(374761393) ^ (y as u32).wrapping_mul(668265263);
+    h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    h ^= h >> 16;
+    (h & 0x00ff_ffff) as f32 / 16_777_215.0
+}
+
+impl DayCycle {
+    pub fn new(time: f32) -> Self {
+        DayCycle {
+            time: time.rem_euclid(24.0),
+            speed: 1.0,
+            running: false,
+        }
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time.rem_euclid(24.0);
+    }
+
+    /// Avanza el reloj `dt` segundos (solo si el ciclo automático está activo).
+    /// Devuelve true si la hora cambió.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        if !self.running || dt <= 0.0 {
+            return false;
+        }
+        self.set_time(self.time + self.speed * dt);
+        true
+    }
+
+    // ángulo del sol: 0 al amanecer (6h), PI/2 al mediodía, PI al atardecer (18h)
+    fn sun_angle(&self) -> f32 {
+        (self.time - 6.0) / 12.0 * PI
+    }
+
+    /// Vector unitario que apunta HACIA el sol. El sol recorre el cielo de este (+x) a oeste (-x),
+    /// inclinado hacia el frente del diorama (-z) para que entre por la abertura.
+    pub fn sun_direction(&self) -> Vec3 {
+        let a = self.sun_angle();
+        Vec3::new(a.cos(), a.sin(), -0.75).normalized()
+    }
+
+    /// La luna va siempre opuesta al sol (pero del mismo lado frontal).
+    pub fn moon_direction(&self) -> Vec3 {
+        let a = self.sun_angle() + PI;
+        Vec3::new(a.cos(), a.sin(), -0.75).normalized()
+    }
+
+    /// Altura del sol sobre el horizonte en [-1, 1] (seno de la elevación).
+    pub fn sun_elevation(&self) -> f32 {
+        self.sun_direction().y
+    }
+
+    /// 1.0 a pleno día, 0.0 de noche, transición suave alrededor del horizonte.
+    pub fn daylight(&self) -> f32 {
+        smoothstep(-0.1, 0.25, self.sun_elevation())
+    }
+
+    /// Cuánto "atardecer" hay: máximo cuando el sol está en el horizonte.
+    pub fn twilight(&self) -> f32 {
+        let e = self.sun_elevation();
+        (1.0 - (e.abs() / 0.3)).clamp(0.0, 1.0)
+    }
+
+    /// Luz direccional dominante: el sol de día, la luna (tenue y azulada) de noche.
+    pub fn sun_light(&self) -> Light {
+        let day = self.daylight();
+        if day > 0.0 {
+            let white = Color::new(1.0, 1.0, 1.0);
+            let orange = Color::new(1.0, 0.55, 0.25);
+            let color = white.lerp(orange, self.twilight());
+            Light::directional(-self.sun_direction(), color, 1.6 * day)
+        } else {
+            Light::directional(-self.moon_direction(), Color::new(0.55, 0.65, 1.0), 0.18)
+        }
+    }
+
+    /// Luz ambiente (sustituye a la constante 0.06): más oscura de noche.
+    pub fn ambient(&self) -> f32 {
+        0.02 + 0.04 * self.daylight()
+    }
+
+    /// Factor para los bloques emisivos y las luces puntuales (antorchas): pesan más de noche.
+    pub fn emissive_scale(&self) -> f32 {
+        1.0 + 1.0 * (1.0 - self.daylight())
+    }
+
+    pub fn palette(&self) -> SkyPalette {
+        let day = SkyPalette {
+            ground: Color::new(0.1, 0.6, 0.2),  // grass green
+            horizon: Color::new(1.0, 1.0, 1.0), // horizon haze
+            zenith: Color::new(0.3, 0.5, 1.0),  // sky blue
+        };
+        let sunset = SkyPalette {
+            ground: Color::new(0.12, 0.3, 0.12),
+            horizon: Color::new(1.0, 0.55, 0.3),
+            zenith: Color::new(0.35, 0.3, 0.6),
+        };
+        let night = SkyPalette {
+            ground: Color::new(0.02, 0.06, 0.03),
+            horizon: Color::new(0.05, 0.06, 0.12),
+            zenith: Color::new(0.01, 0.01, 0.05),
+        };
+
+        let d = self.daylight();
+        let tw = self.twilight();
+        let blend = |a: Color, b: Color, c: Color| c.lerp(a, d).lerp(b, tw);
+        SkyPalette {
+            ground: blend(day.ground, sunset.ground, night.ground),
+            horizon: blend(day.horizon, sunset.horizon, night.horizon),
+            zenith: blend(day.zenith, sunset.zenith, night.zenith),
+        }
+    }
+
+    /// Aporte de los astros en la dirección `d` (normalizada): disco del sol, luna y estrellas.
+    pub fn celestial(&self, d: Vec3) -> Color {
+        let mut color = Color::BLACK;
+        let night = 1.0 - self.daylight();
+
+        // disco solar
+        let sun_cos = d.dot(self.sun_direction());
+        if sun_cos > 0.9995 && self.sun_elevation() > -0.05 {
+            color += Color::new(1.0, 0.9, 0.7) * 2.0;
+        }
+
+        if night > 0.0 && d.y > 0.0 {
+            // luna: disco pálido con un poco de halo
+            let moon_cos = d.dot(self.moon_direction());
+            if moon_cos > 0.9990 {
+                color += Color::new(0.9, 0.92, 1.0) * night;
+            } else if moon_cos > 0.995 {
+                color += Color::new(0.15, 0.17, 0.25) * night * ((moon_cos - 0.995) / 0.004);
+            }
+
+            // estrellas: rejilla en coordenadas esféricas, una celda de cada ~300 brilla
+            let phi = d.z.atan2(d.x);
+            let theta = d.y.clamp(-1.0, 1.0).acos();
+            let cx = (phi * 120.0).floor() as i32;
+            let cy = (theta * 120.0).floor() as i32;
+            let h = hash2(cx, cy);
+            if h > 0.997 {
+                let brightness = (h - 0.997) / 0.003;
+                color += Color::new(1.0, 1.0, 1.0) * brightness * night * d.y.min(0.3) / 0.3;
+            }
+        }
+
+        color
+    }
+}
+
+impl Default for DayCycle {
+    fn default() -> Self {
+        // media mañana: el sol queda parecido a la antigua luz fija en (2.5, 6, -7.5)
+        DayCycle::new(10.5)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::math::{Color, Rgba8, Vec3};
+use rayon::prelude::*;
+use crate::aov::AovBuffers;
+use crate::camera::Camera;
+
+// kernel B3-spline 1D del filtro à-trous (5 taps)
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// Denoiser estilo SVGF simplificado: acumulación temporal reproyectando con la cámara anterior
+// y luego varias pasadas de à-trous con pesos que evitan bordes usando normal, profundidad y
+// luminancia. Se filtra la iluminación (color / albedo) para no borrar las texturas.
+pub struct Denoiser {
+    pub enabled: bool,
+    pub split_compare: bool, // mitad izquierda sin filtrar, mitad derecha filtrada
+    pub iterations: u32,
+    pub sigma_color: f32,
+    pub sigma_normal: f32,
+    pub sigma_depth: f32,
+    pub temporal_alpha: f32, // peso del frame nuevo al mezclar con la historia
+    history: Option<History>,
+}
+
+struct History {
+    width: i32,
+    height: i32,
+    irradiance: Vec<Color>,
+    depth: Vec<f32>,
+    normal: Vec<Vec3>,
+    camera: Camera,
+}
+
+// evita dividir por albedos casi negros
+fn safe_albedo(a: &Color) -> Color {
+    a.max(Color::splat(0.02))
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        Denoiser {
+            enabled: false,
+            split_compare: false,
+            iterations: 4,
+            sigma_color: 0.6,
+            sigma_normal: 64.0,
+            sigma_depth: 0.5,
+            temporal_alpha: 0.25,
+            history: None,
+        }
+    }
+
+    /// Olvida la historia (p.ej. cuando cambia la escena, no la cámara)
+    pub fn reset(&mut self) {
+        self.history = None;
+    }
+
+    /// Filtra `pixels` (el beauty) usando los AOVs del mismo frame. Devuelve los colores a mostrar.
+    pub fn apply(&mut self, pixels: &[Rgba8], aovs: &AovBuffers, camera: &Camera) -> Vec<Rgba8> {
+        let width = aovs.width;
+        let height = aovs.height;
+        let size = (width * height) as usize;
+
+        // 1) demodular: iluminación = color / albedo (el cielo no se filtra)
+        let mut irradiance: Vec<Color> = (0..size)
+            .into_par_iter()
+            .map(|i| {
+                let color = Color::from_rgba8(pixels[i]);
+                if aovs.depth[i].is_finite() { color / safe_albedo(&aovs.albedo[i]) } else { color }
+            })
+            .collect();
+
+        // 2) acumulación temporal con reproyección
+        if let Some(history) = &self.history
+            && history.width == width
+            && history.height == height
+        {
+            irradiance = self.reproject(&irradiance, aovs, camera, history);
+        }
+        self.history = Some(History {
+            width,
+            height,
+            irradiance: irradiance.clone(),
+            depth: aovs.depth.clone(),
+            normal: aovs.normal.clone(),
+            camera: camera.clone(),
+        });
+
+        // 3) à-trous con pasos 1, 2, 4, 8...
+        for iteration in 0..self.iterations {
+            irradiance = self.atrous_pass(&irradiance, aovs, 1 << iteration);
+        }
+
+        // 4) remodular y armar la salida (con comparación lado a lado si se pidió)
+        (0..size)
+            .into_par_iter()
+            .map(|i| {
+                let x = (i as i32) % width;
+                if self.split_compare && x < width / 2 {
+                    return pixels[i];
+                }
+                if aovs.depth[i].is_finite() {
+                    (irradiance[i] * safe_albedo(&aovs.albedo[i])).to_rgba8()
+                } else {
+                    pixels[i]
+                }
+            })
+            .collect()
+    }
+
+    fn reproject(&self, current: &[Color], aovs: &AovBuffers, camera: &Camera, history: &History) -> Vec<Color> {
+        let width = aovs.width;
+        let height = aovs.height;
+        let (w, h) = (width as f32, height as f32);
+        let alpha = self.temporal_alpha.clamp(0.0, 1.0);
+
+        (0..current.len())
+            .into_par_iter()
+            .map(|i| {
+                let depth = aovs.depth[i];
+                if !depth.is_finite() {
+                    return current[i];
+                }
+                let x = (i as i32) % width;
+                let y = (i as i32) / width;
+                let dir = camera.primary_ray_direction(x as f32, y as f32, w, h);
+                let world = camera.eye + dir * depth;
+
+                let Some((px, py)) = history.camera.project(&world, w, h) else { return current[i]; };
+                let (px, py) = (px.round() as i32, py.round() as i32);
+                if px < 0 || px >= width || py < 0 || py >= height {
+                    return current[i];
+                }
+                let j = (py * width + px) as usize;
+
+                // la historia solo sirve si ve la misma superficie
+                let expected = (world - history.camera.eye).length();
+                let prev_depth = history.depth[j];
+                if !prev_depth.is_finite() || (prev_depth - expected).abs() > 0.05 * expected.max(1.0) {
+                    return current[i];
+                }
+                if history.normal[j].dot(aovs.normal[i]) < 0.9 {
+                    return current[i];
+                }
+                history.irradiance[j] * (1.0 - alpha) + current[i] * alpha
+            })
+            .collect()
+    }
+
+    fn atrous_pass(&self, input: &[Color], aovs: &AovBuffers, step: i32) -> Vec<Color> {
+        let width = aovs.width;
+        let height = aovs.height;
+
+        (0..input.len())
+            .into_par_iter()
+            .map(|i| {
+                let depth = aovs.depth[i];
+                if !depth.is_finite() {
+                    return input[i];
+                }
+                let x = (i as i32) % width;
+                let y = (i as i32) / width;
+                let center = input[i];
+                let center_lum = center.luminance();
+                let normal = aovs.normal[i];
+
+                let mut sum = Color::BLACK;
+                let mut weight_sum = 0.0;
+                for (ky, wy) in KERNEL.iter().enumerate() {
+                    for (kx, wx) in KERNEL.iter().enumerate() {
+                        let qx = x + (kx as i32 - 2) * step;
+                        let qy = y + (ky as i32 - 2) * step;
+                        if qx < 0 || qx >= width || qy < 0 || qy >= height {
+                            continue;
+                        }
+                        let j = (qy * width + qx) as usize;
+                        let q_depth = aovs.depth[j];
+                        if !q_depth.is_finite() {
+                            continue;
+                        }
+
+                        let w_normal = normal.dot(aovs.normal[j]).max(0.0).powf(self.sigma_normal);
+                        let w_depth = (-(depth - q_depth).abs() / (self.sigma_depth * step as f32)).exp();
+                        let lum_diff = center_lum - input[j].luminance();
+                        let w_color = (-(lum_diff * lum_diff) / (self.sigma_color * self.sigma_color)).exp();
+
+                        let w = wx * wy * w_normal * w_depth * w_color;
+                        sum += input[j] * w;
+                        weight_sum += w;
+                    }
+                }
+                if weight_sum > 0.0 { sum / weight_sum } else { center }
+            })
+            .collect()
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser::new()
+    }
+}
@@ -0,0 +1,192 @@
+use crate::math::{Color, Vec3};
+use std::f32::consts::PI;
+use crate::cube::Cube;
+use crate::scene::Scene;
+
+// distancia máxima que se integra cuando el rayo no choca con nada (cielo)
+const MAX_FOG_DISTANCE: f32 = 40.0;
+
+#[derive(Clone)]
+pub enum FogShape {
+    // niebla uniforme en todo el espacio
+    Homogeneous,
+    // densidad que decae exponencialmente por encima de `base` (constante por debajo)
+    Height { base: f32, falloff: f32 },
+    // niebla uniforme solo dentro de una caja (puede estar rotada)
+    Bounded(Cube),
+}
+
+#[derive(Clone)]
+pub struct FogVolume {
+    pub shape: FogShape,
+    pub density: f32,
+    pub absorption: f32, // sigma_a (por unidad de densidad)
+    pub scattering: f32, // sigma_s (por unidad de densidad)
+    pub color: Color,  // tinte de la luz dispersada
+    pub g: f32,          // anisotropía Henyey–Greenstein: >0 hacia adelante, <0 hacia atrás
+}
+
+impl FogVolume {
+    pub fn homogeneous(density: f32, absorption: f32, scattering: f32, g: f32) -> Self {
+        FogVolume {
+            shape: FogShape::Homogeneous,
+            density,
+            absorption,
+            scattering,
+            color: Color::WHITE,
+            g,
+        }
+    }
+
+    pub fn height(base: f32, falloff: f32, density: f32, absorption: f32, scattering: f32, g: f32) -> Self {
+        FogVolume {
+            shape: FogShape::Height { base, falloff },
+            density,
+            absorption,
+            scattering,
+            color: Color::WHITE,
+            g,
+        }
+    }
+
+    pub fn bounded(bounds: Cube, density: f32, absorption: f32, scattering: f32, g: f32) -> Self {
+        FogVolume {
+            shape: FogShape::Bounded(bounds),
+            density,
+            absorption,
+            scattering,
+            color: Color::WHITE,
+            g,
+        }
+    }
+
+    /// Densidad del volumen en el punto `p`
+    pub fn density_at(&self, p: &Vec3) -> f32 {
+        match &self.shape {
+            FogShape::Homogeneous => self.density,
+            FogShape::Height { base, falloff } => {
+                let h = (p.y - base).max(0.0);
+                self.density * (-falloff * h).exp()
+            }
+            FogShape::Bounded(_) => self.density,
+        }
+    }
+
+    /// Tramo [t0, t1] del rayo en el que el volumen puede tener densidad
+    fn segment(&self, origin: &Vec3, direction: &Vec3, t_max: f32) -> Option<(f32, f32)> {
+        match &self.shape {
+            FogShape::Bounded(cube) => {
+                let (t0, t1) = cube.ray_interval(origin, direction)?;
+                let t0 = t0.max(0.0);
+                let t1 = t1.min(t_max);
+                if t0 < t1 { Some((t0, t1)) } else { None }
+            }
+            _ => Some((0.0, t_max)),
+        }
+    }
+}
+
+/// Función de fase de Henyey–Greenstein. `cos_theta` es el coseno entre la dirección de la luz
+/// incidente y la dirección de salida.
+pub fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let g2 = g * g;
+    let denom = (1.0 + g2 - 2.0 * g * cos_theta).max(1e-6);
+    (1.0 - g2) / (4.0 * PI * denom * denom.sqrt())
+}
+
+// desplazamiento pseudoaleatorio por rayo para que el ray marching no deje bandas
+fn jitter(direction: &Vec3) -> f32 {
+    let v = (direction.x * 12.9898 + direction.y * 78.233 + direction.z * 37.719).sin() * 43_758.547;
+    v - v.floor()
+}
+
+/// Integra los volúmenes de niebla de la escena a lo largo del rayo hasta `t_hit`
+/// (o MAX_FOG_DISTANCE si el rayo se fue al cielo). Atenúa `surface_color` por la
+/// transmitancia y suma la luz dispersada hacia la cámara (single scattering con rayos
+/// de sombra hacia cada luz).
+pub fn integrate(
+    scene: &Scene,
+    origin: &Vec3,
+    direction: &Vec3,
+    t_hit: Option<f32>,
+    surface_color: Color,
+) -> Color {
+    if !scene.fog_enabled || scene.fog.is_empty() || scene.fog_steps == 0 {
+        return surface_color;
+    }
+
+    let t_max = t_hit.unwrap_or(MAX_FOG_DISTANCE).min(MAX_FOG_DISTANCE);
+
+    // tramo que cubre todos los volúmenes (los de caja pueden estar lejos del origen)
+    let segments: Vec<Option<(f32, f32)>> = scene
+        .fog
+        .iter()
+        .map(|volume| volume.segment(origin, direction, t_max))
+        .collect();
+    let mut t_start = f32::INFINITY;
+    let mut t_end = 0.0_f32;
+    for (t0, t1) in segments.iter().flatten() {
+        t_start = t_start.min(*t0);
+        t_end = t_end.max(*t1);
+    }
+    if t_start >= t_end {
+        return surface_color;
+    }
+
+    let steps = scene.fog_steps;
+    let dt = (t_end - t_start) / steps as f32;
+    let offset = jitter(direction);
+
+    let mut transmittance = 1.0_f32;
+    let mut inscattered = Color::BLACK;
+
+    for i in 0..steps {
+        let t = t_start + (i as f32 + offset) * dt;
+        let p = *origin + *direction * t;
+
+        // coeficientes combinados de todos los volúmenes en este punto
+        let mut sigma_t = 0.0;
+        let mut scatter = Color::BLACK; // sigma_s * color
+        let mut sigma_s = 0.0;
+        let mut phase_g = 0.0;
+        for (volume, segment) in scene.fog.iter().zip(&segments) {
+            match segment {
+                Some((t0, t1)) if t >= *t0 && t <= *t1 => {}
+                _ => continue,
+            }
+            let d = volume.density_at(&p);
+            if d <= 0.0 { continue; }
+            sigma_t += d * (volume.absorption + volume.scattering);
+            sigma_s += d * volume.scattering;
+            scatter += volume.color * (d * volume.scattering);
+            phase_g += volume.g * d * volume.scattering;
+        }
+        if sigma_t <= 0.0 { continue; }
+        // g promedio ponderado por cuánto dispersa cada volumen
+        let g = if sigma_s > 0.0 { (phase_g / sigma_s).clamp(-0.99, 0.99) } else { 0.0 };
+
+        // luz que llega al punto desde cada fuente
+        let mut light_in = Color::BLACK;
+        for light in scene.lights.iter() {
+            let Some((light_dir, dist)) = light.incidence(&p) else { continue; };
+            let visibility = scene.shadow_transmittance(&p, &light_dir, dist - 1e-3);
+            if visibility <= 0.0 { continue; }
+            // atenuación aproximada dentro del propio medio (densidad local, tramo acotado)
+            let medium = (-sigma_t * dist.min(MAX_FOG_DISTANCE * 0.5)).exp();
+            let phase = henyey_greenstein(direction.dot(light_dir), g);
+            light_in += light.color * (light.attenuation(dist) * visibility * medium * phase);
+        }
+
+        // integración analítica del paso (con transmitancia constante dentro del paso)
+        let step_t = (-sigma_t * dt).exp();
+        let weight = transmittance * (1.0 - step_t) / sigma_t;
+        inscattered += light_in * scatter * weight;
+        transmittance *= step_t;
+
+        if transmittance < 1e-3 {
+            break;
+        }
+    }
+
+    surface_color * transmittance + inscattered
+}
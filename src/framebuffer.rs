@@ -1,100 +1,270 @@
-use raylib::prelude::*;
+use crate::aov::{AovBuffers, AovPass};
+use crate::math::Rgba8;
+#[cfg(feature = "raylib")]
+use raylib::prelude::{Image, RaylibDraw, RaylibDrawHandle, RaylibHandle, RaylibTexture2D, RaylibThread, Rectangle, Texture2D};
+#[cfg(feature = "raylib")]
+use std::time::Instant;
+
+// Tiempos de la última subida a la GPU (se muestran en el overlay)
+#[derive(Clone, Copy, Default)]
+pub struct UploadStats {
+    pub upload_ms: f32,        // duración de la última actualización de la textura
+    pub uploaded_pixels: usize, // píxeles subidos en esa actualización
+}
 
 pub struct Framebuffer {
     pub width: i32,
     pub height: i32,
-    pub color_buffer: Image,
-    background_color: Color,
-    current_color: Color,
-    pixel_data: Vec<Color>,
-    overlays: Vec<(String, i32, i32, i32, Color)>,
+    background_color: Rgba8,
+    current_color: Rgba8,
+    // beauty del último render (lo que exportan y filtran los demás módulos)
+    pixel_data: Vec<Rgba8>,
+    // lo que se ve en la ventana, RGBA8 contiguo fila por fila: se sube tal cual a la textura
+    display: Vec<u8>,
+    overlays: Vec<(String, i32, i32, i32, Rgba8)>,
+    // segmentos (x0, y0, x1, y1) dibujados encima de la imagen, p.ej. el contorno de la selección
+    line_overlays: Vec<(i32, i32, i32, i32, Rgba8)>,
 
+    // textura persistente: se crea una vez y después solo se actualiza su contenido
+    #[cfg(feature = "raylib")]
     gpu_texture: Option<Texture2D>,
-    pub dirty: bool,
+    // rectángulo [x0, x1) × [y0, y1) modificado desde la última subida
+    dirty_rect: Option<(i32, i32, i32, i32)>,
+    // buffer auxiliar para empaquetar el sub-rectángulo a subir
+    #[cfg(feature = "raylib")]
+    upload_buffer: Vec<u8>,
+    pub stats: UploadStats,
+
+    // pases auxiliares (profundidad, normal, albedo, ids, uv); None si no se piden
+    pub aovs: Option<AovBuffers>,
+    // pase que se está mostrando en la ventana
+    shown_pass: AovPass,
+    // progreso del frame en curso (0..1), se dibuja como barra al pie de la ventana
+    progress: Option<f32>,
 }
 
 impl Framebuffer {
-    pub fn new(width: i32, height: i32, background_color: Color) -> Self {
+    pub fn new(width: i32, height: i32, background_color: Rgba8) -> Self {
         let size = (width * height) as usize;
-        let pixel_data = vec![background_color; size];
-        let color_buffer = Image::gen_image_color(width, height, background_color);
-        Framebuffer {
+        let mut framebuffer = Framebuffer {
             width,
             height,
-            color_buffer,
             background_color,
-            current_color: Color::WHITE,
-            pixel_data,
+            current_color: Rgba8::WHITE,
+            pixel_data: vec![background_color; size],
+            display: vec![0; size * 4],
             overlays: Vec::new(),
+            line_overlays: Vec::new(),
+            #[cfg(feature = "raylib")]
             gpu_texture: None,
-            dirty: true, // la primera vez se debe crear la textura
-        }
+            dirty_rect: None,
+            #[cfg(feature = "raylib")]
+            upload_buffer: Vec::new(),
+            stats: UploadStats::default(),
+            aovs: None,
+            shown_pass: AovPass::Beauty,
+            progress: None,
+        };
+        framebuffer.clear();
+        framebuffer
+    }
+
+    // marca la región como pendiente de subir
+    fn mark_dirty(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        self.dirty_rect = Some(match self.dirty_rect {
+            Some((ax0, ay0, ax1, ay1)) => (ax0.min(x0), ay0.min(y0), ax1.max(x1), ay1.max(y1)),
+            None => (x0, y0, x1, y1),
+        });
+    }
+
+    fn write_display(&mut self, index: usize, color: Rgba8) {
+        self.display[index * 4..index * 4 + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
     }
 
     pub fn clear(&mut self) {
         self.pixel_data.fill(self.background_color);
-        self.color_buffer = Image::gen_image_color(self.width, self.height, self.background_color);
-        self.dirty = true;
+        let c = self.background_color;
+        for pixel in self.display.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[c.r, c.g, c.b, c.a]);
+        }
+        self.mark_dirty(0, 0, self.width, self.height);
     }
 
     pub fn set_pixel(&mut self, x: i32, y: i32) {
         if x >= 0 && x < self.width && y >= 0 && y < self.height {
             let index = (y * self.width + x) as usize;
             self.pixel_data[index] = self.current_color;
-            Image::draw_pixel(&mut self.color_buffer, x as i32, y as i32, self.current_color);
-            self.dirty = true; // marcamos que el contenido cambió y debemos recargar la textura antes de mostrar
+            self.write_display(index, self.current_color);
+            self.mark_dirty(x, y, x + 1, y + 1);
         }
     }
 
-    pub fn set_background_color(&mut self, color: Color) {
+    pub fn set_background_color(&mut self, color: Rgba8) {
         self.background_color = color;
         self.clear();
     }
 
-    pub fn set_current_color(&mut self, color: Color) {
+    pub fn set_current_color(&mut self, color: Rgba8) {
         self.current_color = color;
     }
 
-    // draw_text ahora reemplaza overlays (no acumular)
-    pub fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
-        self.overlays.clear();
+    // los textos se acumulan hasta el próximo swap_buffers (estado, error de recarga, modo del gizmo)
+    pub fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Rgba8) {
         self.overlays.push((text.to_string(), x, y, font_size, color));
     }
 
+    // segmento encima de la imagen hasta el próximo swap_buffers (contorno y gizmo de la selección)
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Rgba8) {
+        self.line_overlays.push((x0, y0, x1, y1, color));
+    }
+
+    /// Progreso del render en curso; None oculta la barra
+    pub fn set_progress(&mut self, progress: Option<f32>) {
+        self.progress = progress;
+    }
+
+    /// Lo que se ve en la ventana, RGBA8 fila por fila (mismo buffer que se sube a la GPU)
+    pub fn display_rgba(&self) -> &[u8] {
+        &self.display
+    }
+
+    /// Exporta lo que se ve en la ventana (mismo buffer que se sube a la GPU)
+    #[cfg(feature = "raylib")]
     pub fn render_to_file(&self, file_path: &str) {
-        Image::export_image(&self.color_buffer, file_path);
+        export_rgba(self.width, self.height, &self.display, file_path);
     }
 
-    // swap_buffers: crea/recarga la textura SOLO si `dirty == true`, si no reutiliza la misma GPU texture
-    pub fn swap_buffers(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
-        if self.gpu_texture.is_none() || self.dirty {
-            // (re)crear la textura desde la imagen actual
+    // Sube a la textura solo el rectángulo modificado (UpdateTexture / UpdateTextureRec),
+    // sin recrear la textura ni pasar por una Image intermedia.
+    #[cfg(feature = "raylib")]
+    fn upload(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+        if self.gpu_texture.is_none() {
+            // la textura se crea una sola vez, con el formato RGBA8 de gen_image_color
+            let image = Image::gen_image_color(self.width, self.height, raylib::color::Color::from(self.background_color));
             let texture = window
-                .load_texture_from_image(raylib_thread, &self.color_buffer)
+                .load_texture_from_image(raylib_thread, &image)
                 .unwrap_or_else(|e| panic!("Failed to create texture from framebuffer: {}", e));
-            // al asignar Some(texture) el Texture2D anterior (si existía) será droppeado y
-            // dependiendo del binding liberará la textura GPU (Drop suele llamar UnloadTexture).
             self.gpu_texture = Some(texture);
-            self.dirty = false;
+            self.dirty_rect = Some((0, 0, self.width, self.height));
+        }
+        let Some((x0, y0, x1, y1)) = self.dirty_rect.take() else { return; };
+        let Some(texture) = self.gpu_texture.as_mut() else { return; };
+        let start = Instant::now();
+
+        let result = if x0 == 0 && y0 == 0 && x1 == self.width && y1 == self.height {
+            texture.update_texture(&self.display)
+        } else {
+            // filas del rectángulo empaquetadas al inicio del buffer. El binding valida el largo
+            // contra la textura completa aunque UpdateTextureRec solo lea ancho × alto del
+            // rectángulo, así que el buffer auxiliar tiene el tamaño de la textura entera.
+            self.upload_buffer.resize(self.display.len(), 0);
+            let row_bytes = ((x1 - x0) * 4) as usize;
+            for (row, y) in (y0..y1).enumerate() {
+                let src = ((y * self.width + x0) * 4) as usize;
+                self.upload_buffer[row * row_bytes..(row + 1) * row_bytes]
+                    .copy_from_slice(&self.display[src..src + row_bytes]);
+            }
+            let rect = Rectangle::new(x0 as f32, y0 as f32, (x1 - x0) as f32, (y1 - y0) as f32);
+            texture.update_texture_rec(rect, &self.upload_buffer)
+        };
+        if let Err(e) = result {
+            eprintln!("no se pudo actualizar la textura: {}", e);
         }
 
+        self.stats = UploadStats {
+            upload_ms: start.elapsed().as_secs_f32() * 1000.0,
+            uploaded_pixels: ((x1 - x0) * (y1 - y0)) as usize,
+        };
+    }
+
+    // swap_buffers: sube a la GPU lo que cambió desde el último frame y dibuja textura + overlays
+    #[cfg(feature = "raylib")]
+    pub fn swap_buffers(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+        self.swap_buffers_with(window, raylib_thread, |_| {});
+    }
+
+    /// Igual que swap_buffers, pero `ui` dibuja controles (raygui) encima de todo en el mismo frame
+    #[cfg(feature = "raylib")]
+    pub fn swap_buffers_with(
+        &mut self,
+        window: &mut RaylibHandle,
+        raylib_thread: &RaylibThread,
+        ui: impl FnOnce(&mut RaylibDrawHandle),
+    ) {
+        self.upload(window, raylib_thread);
+
         // dibujar la textura cacheada + overlays
         {
             let mut d = window.begin_drawing(raylib_thread);
             if let Some(ref tex) = self.gpu_texture {
-                d.draw_texture(tex, 0, 0, Color::WHITE);
+                d.draw_texture(tex, 0, 0, raylib::color::Color::WHITE);
+            }
+            for (x0, y0, x1, y1, color) in &self.line_overlays {
+                d.draw_line(*x0, *y0, *x1, *y1, raylib::color::Color::from(*color));
             }
             for (text, x, y, font_size, color) in &self.overlays {
-                d.draw_text(text, *x, *y, *font_size, *color);
+                d.draw_text(text, *x, *y, *font_size, raylib::color::Color::from(*color));
             }
+            if let Some(progress) = self.progress {
+                let bar_width = (self.width as f32 * progress.clamp(0.0, 1.0)) as i32;
+                d.draw_rectangle(0, self.height - 4, self.width, 4, raylib::color::Color::new(0, 0, 0, 160));
+                d.draw_rectangle(0, self.height - 4, bar_width, 4, raylib::color::Color::new(90, 200, 255, 220));
+            }
+            ui(&mut d);
             // end drawing al salir del scope
         }
 
         // limpio overlays para siguiente frame (si quieres mantener, cambia esto)
+        // (textos y segmentos se piden de nuevo en cada frame)
         self.overlays.clear();
+        self.line_overlays.clear();
+    }
+
+    /// Activa (o reinicia) los buffers de AOVs; render() los llena si existen
+    pub fn enable_aovs(&mut self) {
+        self.aovs = Some(AovBuffers::new(self.width, self.height));
+    }
+
+    pub fn shown_pass(&self) -> AovPass {
+        self.shown_pass
+    }
+
+    /// Cambia el pase visible en la ventana. pixel_data siempre conserva el beauty,
+    /// así que volver a AovPass::Beauty no requiere re-renderizar.
+    pub fn show_pass(&mut self, pass: AovPass) {
+        let colors = match self.aovs.as_ref().and_then(|aovs| aovs.visualize(pass)) {
+            Some(colors) => colors,
+            None => self.pixel_data.clone(),
+        };
+        self.present(&colors);
+        self.shown_pass = if self.aovs.is_some() { pass } else { AovPass::Beauty };
+    }
+
+    /// Muestra `colors` en la ventana sin tocar pixel_data (p.ej. la salida del denoiser)
+    pub fn present(&mut self, colors: &[Rgba8]) {
+        for (index, color) in colors.iter().enumerate().take(self.pixel_data.len()) {
+            self.write_display(index, *color);
+        }
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    /// Colores del último render (beauty), fila por fila
+    pub fn pixels(&self) -> &[Rgba8] {
+        &self.pixel_data
     }
 
-    pub fn get_pixel_color(&self, x: i32, y: i32) -> Option<Color> {
+    /// Exporta un pase a PNG (el beauty sale de pixel_data, los demás de su visualización)
+    #[cfg(feature = "raylib")]
+    pub fn export_pass(&self, pass: AovPass, file_path: &str) {
+        let colors = match self.aovs.as_ref().and_then(|aovs| aovs.visualize(pass)) {
+            Some(colors) => colors,
+            None => self.pixel_data.clone(),
+        };
+        let rgba: Vec<u8> = colors.iter().flat_map(|c| [c.r, c.g, c.b, c.a]).collect();
+        export_rgba(self.width, self.height, &rgba, file_path);
+    }
+
+    pub fn get_pixel_color(&self, x: i32, y: i32) -> Option<Rgba8> {
         if x >= 0 && x < self.width && y >= 0 && y < self.height {
             let index = (y * self.width + x) as usize;
             Some(self.pixel_data[index])
@@ -103,3 +273,34 @@ impl Framebuffer {
         }
     }
 }
+
+// Escribe un buffer RGBA8 a disco copiándolo de una vez dentro de una Image de raylib
+#[cfg(feature = "raylib")]
+fn export_rgba(width: i32, height: i32, rgba: &[u8], file_path: &str) {
+    let image = Image::gen_image_color(width, height, raylib::color::Color::BLACK);
+    // gen_image_color reserva width × height píxeles RGBA8, el mismo layout que `rgba`
+    unsafe {
+        let data = image.data() as *mut u8;
+        std::ptr::copy_nonoverlapping(rgba.as_ptr(), data, rgba.len().min((width * height * 4) as usize));
+    }
+    image.export_image(file_path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_keeps_lines_and_other_texts() {
+        // lo que main pide en un frame: estado, error, contorno y gizmo, y el modo del gizmo al final
+        let mut framebuffer = Framebuffer::new(4, 4, Rgba8::BLACK);
+        framebuffer.draw_text("FPS", 8, 8, 20, Rgba8::BLACK);
+        framebuffer.draw_text("error", 8, 32, 20, Rgba8::WHITE);
+        framebuffer.draw_line(0, 0, 3, 3, Rgba8::WHITE);
+        framebuffer.draw_line(3, 0, 0, 3, Rgba8::WHITE);
+        framebuffer.draw_text("[mover]", 8, 56, 20, Rgba8::BLACK);
+        let texts: Vec<&str> = framebuffer.overlays.iter().map(|o| o.0.as_str()).collect();
+        assert_eq!(texts, ["FPS", "error", "[mover]"]);
+        assert_eq!(framebuffer.line_overlays.len(), 2);
+    }
+}
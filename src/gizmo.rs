@@ -0,0 +1,346 @@
+// Manipuladores en pantalla (gizmos) para mover, rotar y escalar el objeto seleccionado
+// arrastrando con el mouse. Trabajan sobre la descripción del objeto (ObjectDesc) igual que el
+// inspector; quien los usa reemplaza el objeto en la escena con el resultado de cada arrastre.
+//
+// - Mover: una flecha por eje del mundo; el objeto sigue la proyección del mouse sobre la flecha.
+// - Rotar (solo cubos): un anillo por eje de rotación (X local y Y del mundo, como Cube); el
+//   ángulo es el que gira el mouse alrededor del centro en pantalla.
+// - Escalar: una flecha por eje local del cubo que cambia esa componente de half_size (en la
+//   esfera, las tres cambian el radio).
+
+use crate::camera::Camera;
+use crate::math::{Mat4, Rgba8, Vec3};
+use crate::scene_file::{ObjectDesc, Shape};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GizmoMode::Translate => "mover",
+            GizmoMode::Rotate => "rotar",
+            GizmoMode::Scale => "escalar",
+        }
+    }
+}
+
+/// Color de cada eje (x rojo, y verde, z azul) y del que se está arrastrando
+pub const AXIS_COLORS: [Rgba8; 3] = [Rgba8::new(230, 60, 60, 255), Rgba8::new(70, 200, 70, 255), Rgba8::new(70, 110, 240, 255)];
+pub const ACTIVE_COLOR: Rgba8 = Rgba8::new(255, 220, 0, 255);
+
+// distancia máxima en píxeles entre el cursor y un manipulador para agarrarlo
+const PICK_RADIUS: f32 = 8.0;
+const RING_SEGMENTS: usize = 48;
+// pasos al arrastrar con Shift
+const TRANSLATE_SNAP: f32 = 0.5;
+const SCALE_SNAP: f32 = 0.25;
+const ROTATE_SNAP: f32 = 15.0;
+const MIN_SIZE: f32 = 0.05;
+
+/// Un manipulador: el eje que controla y los segmentos (en espacio mundo) con que se dibuja
+pub struct Handle {
+    pub axis: usize,
+    pub segments: Vec<(Vec3, Vec3)>,
+}
+
+struct Drag {
+    axis: usize,
+    start: ObjectDesc,
+    start_mouse: (f32, f32),
+    direction: Vec3,
+    // píxeles que recorre en pantalla una unidad de mundo a lo largo de `direction`
+    screen_axis: (f32, f32),
+    // centro del objeto en pantalla (para rotar)
+    pivot: (f32, f32),
+    // el eje de rotación apunta hacia la cámara (el giro se ve antihorario)
+    facing_camera: bool,
+}
+
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    drag: Option<Drag>,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Gizmo::new()
+    }
+}
+
+fn unit(axis: usize) -> Vec3 {
+    match axis {
+        0 => Vec3::new(1.0, 0.0, 0.0),
+        1 => Vec3::new(0.0, 1.0, 0.0),
+        _ => Vec3::new(0.0, 0.0, 1.0),
+    }
+}
+
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let len2 = abx * abx + aby * aby;
+    let t = if len2 > 0.0 { (((p.0 - a.0) * abx + (p.1 - a.1) * aby) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    let (dx, dy) = (a.0 + abx * t - p.0, a.1 + aby * t - p.1);
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn snap(value: f32, step: f32, enabled: bool) -> f32 {
+    if enabled { (value / step).round() * step } else { value }
+}
+
+// ángulo en grados llevado a (-180, 180]
+fn wrap_degrees(angle: f32) -> f32 {
+    let a = (angle + 180.0).rem_euclid(360.0) - 180.0;
+    if a == -180.0 { 180.0 } else { a }
+}
+
+impl Gizmo {
+    pub fn new() -> Self {
+        Gizmo { mode: GizmoMode::Translate, drag: None }
+    }
+
+    // largo de las flechas y radio de los anillos: un poco más que el objeto
+    fn size(shape: &Shape) -> f32 {
+        shape.extent() + 1.0
+    }
+
+    /// Ejes que ofrece el modo actual para esta forma (la esfera no rota; un modelo de bloque
+    /// ocupa siempre un bloque, así que no se escala, y las medidas de las primitivas y de los sdf
+    /// y las partes de un csg se editan en el inspector o en el archivo)
+    fn axes(&self, shape: &Shape) -> Vec<usize> {
+        match (self.mode, shape) {
+            (_, Shape::Csg { .. }) => Vec::new(),
+            (GizmoMode::Rotate, Shape::Sphere { .. }) | (GizmoMode::Scale, Shape::Model { .. } | Shape::Primitive { .. } | Shape::Sdf { .. }) => Vec::new(),
+            (GizmoMode::Rotate, Shape::Cube { .. } | Shape::Model { .. } | Shape::Primitive { .. } | Shape::Sdf { .. }) => vec![0, 1],
+            _ => vec![0, 1, 2],
+        }
+    }
+
+    /// Dirección en mundo del eje: la de la flecha, o la normal del anillo al rotar
+    fn direction(&self, shape: &Shape, axis: usize) -> Vec3 {
+        match self.mode {
+            GizmoMode::Translate => unit(axis),
+            GizmoMode::Scale => shape.rotation().transform_vector(unit(axis)),
+            // rotation.x gira alrededor del X local después de aplicar rotation.y; rotation.y, del Y del mundo
+            GizmoMode::Rotate => match shape {
+                Shape::Cube { rotation, .. } | Shape::Model { rotation, .. } | Shape::Primitive { rotation, .. } | Shape::Sdf { rotation, .. } if axis == 0 => Mat4::rotation_y(rotation.y.to_radians()).transform_vector(unit(0)),
+                _ => unit(1),
+            },
+        }
+    }
+
+    pub fn handles(&self, shape: &Shape) -> Vec<Handle> {
+        let center = shape.center();
+        let size = Gizmo::size(shape);
+        self.axes(shape)
+            .into_iter()
+            .map(|axis| {
+                let direction = self.direction(shape, axis);
+                let segments = if self.mode == GizmoMode::Rotate {
+                    // dos vectores perpendiculares al eje para trazar el anillo
+                    let helper = if direction.y.abs() < 0.9 { unit(1) } else { unit(0) };
+                    let u = direction.cross(helper).normalized();
+                    let v = direction.cross(u);
+                    let point = |i: usize| {
+                        let a = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        center + (u * a.cos() + v * a.sin()) * size
+                    };
+                    (0..RING_SEGMENTS).map(|i| (point(i), point(i + 1))).collect()
+                } else {
+                    vec![(center, center + direction * size)]
+                };
+                Handle { axis, segments }
+            })
+            .collect()
+    }
+
+    /// Manipulador bajo el cursor (el más cercano dentro de PICK_RADIUS píxeles)
+    pub fn pick(&self, shape: &Shape, camera: &Camera, width: f32, height: f32, mouse: (f32, f32)) -> Option<usize> {
+        let mut best: Option<(usize, f32)> = None;
+        for handle in self.handles(shape) {
+            for (a, b) in &handle.segments {
+                let (Some(a), Some(b)) = (camera.project(a, width, height), camera.project(b, width, height)) else { continue };
+                let d = distance_to_segment(mouse, a, b);
+                if d <= PICK_RADIUS && best.is_none_or(|(_, best_d)| d < best_d) {
+                    best = Some((handle.axis, d));
+                }
+            }
+        }
+        best.map(|(axis, _)| axis)
+    }
+
+    /// Empieza a arrastrar el eje `axis`. Devuelve false si el eje no se puede usar desde esta
+    /// vista (p.ej. una flecha que apunta directo a la cámara).
+    pub fn begin(&mut self, object: &ObjectDesc, axis: usize, camera: &Camera, width: f32, height: f32, mouse: (f32, f32)) -> bool {
+        let shape = &object.shape;
+        let center = shape.center();
+        let direction = self.direction(shape, axis);
+        let size = Gizmo::size(shape);
+        let Some(pivot) = camera.project(&center, width, height) else { return false };
+        let screen_axis = match camera.project(&(center + direction * size), width, height) {
+            Some(tip) => ((tip.0 - pivot.0) / size, (tip.1 - pivot.1) / size),
+            None => (0.0, 0.0),
+        };
+        if self.mode != GizmoMode::Rotate && screen_axis.0.hypot(screen_axis.1) < 1.0 {
+            return false;
+        }
+        self.drag = Some(Drag {
+            axis,
+            start: object.clone(),
+            start_mouse: mouse,
+            direction,
+            screen_axis,
+            pivot,
+            facing_camera: direction.dot(camera.forward) < 0.0,
+        });
+        true
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    pub fn active_axis(&self) -> Option<usize> {
+        self.drag.as_ref().map(|d| d.axis)
+    }
+
+    /// El objeto como queda con el mouse en `mouse` (relativo al inicio del arrastre, así no se
+    /// acumula error). `snap` redondea a pasos fijos (Shift).
+    pub fn drag_to(&self, mouse: (f32, f32), snap_enabled: bool) -> Option<ObjectDesc> {
+        let drag = self.drag.as_ref()?;
+        let mut object = drag.start.clone();
+        let (dx, dy) = (mouse.0 - drag.start_mouse.0, mouse.1 - drag.start_mouse.1);
+        // unidades de mundo a lo largo del eje que corresponden al movimiento del mouse
+        let (sx, sy) = drag.screen_axis;
+        let along = (dx * sx + dy * sy) / (sx * sx + sy * sy);
+
+        match (self.mode, &mut object.shape) {
+            (
+                GizmoMode::Translate,
+                Shape::Cube { center, .. } | Shape::Sphere { center, .. } | Shape::Model { center, .. } | Shape::Primitive { center, .. } | Shape::Sdf { center, .. },
+            ) => {
+                let start = drag.start.shape.center();
+                let component = |v: Vec3| [v.x, v.y, v.z][drag.axis];
+                let target = snap(component(start) + along, TRANSLATE_SNAP, snap_enabled);
+                *center = start + drag.direction * (target - component(start));
+            }
+            (GizmoMode::Scale, Shape::Cube { half_size, .. }) => {
+                let value = match drag.axis {
+                    0 => &mut half_size.x,
+                    1 => &mut half_size.y,
+                    _ => &mut half_size.z,
+                };
+                *value = snap(*value + along, SCALE_SNAP, snap_enabled).max(MIN_SIZE);
+            }
+            (GizmoMode::Scale, Shape::Sphere { radius, .. }) => {
+                *radius = snap(*radius + along, SCALE_SNAP, snap_enabled).max(MIN_SIZE);
+            }
+            (GizmoMode::Rotate, Shape::Cube { rotation, .. } | Shape::Model { rotation, .. } | Shape::Primitive { rotation, .. } | Shape::Sdf { rotation, .. }) => {
+                let angle = |p: (f32, f32)| (p.1 - drag.pivot.1).atan2(p.0 - drag.pivot.0);
+                // con y hacia abajo, un ángulo de pantalla creciente se ve horario
+                let screen_delta = (angle(mouse) - angle(drag.start_mouse)).to_degrees();
+                let delta = if drag.facing_camera { -screen_delta } else { screen_delta };
+                let value = if drag.axis == 0 { &mut rotation.x } else { &mut rotation.y };
+                *value = wrap_degrees(snap(*value + delta, ROTATE_SNAP, snap_enabled));
+            }
+            (GizmoMode::Rotate, Shape::Sphere { .. })
+            | (GizmoMode::Scale, Shape::Model { .. } | Shape::Primitive { .. } | Shape::Sdf { .. })
+            | (_, Shape::Csg { .. }) => {}
+        }
+        Some(object)
+    }
+
+    /// Termina el arrastre; devuelve el objeto como estaba al empezar (para el historial)
+    pub fn end(&mut self) -> Option<ObjectDesc> {
+        self.drag.take().map(|d| d.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    const W: f32 = 800.0;
+    const H: f32 = 600.0;
+
+    fn camera() -> Camera {
+        // mirando hacia +z: x del mundo va a la izquierda de la pantalla, y hacia arriba
+        Camera::new(Vec3::new(0.0, 0.0, -10.0), Vec3::zero(), Vec3::new(0.0, 1.0, 0.0))
+    }
+
+    fn cube() -> ObjectDesc {
+        ObjectDesc {
+            name: None,
+            shape: Shape::Cube { center: Vec3::zero(), half_size: Vec3::new(1.0, 1.0, 1.0), rotation: Vec2::zero() },
+            material: "m".to_string(),
+        }
+    }
+
+    fn screen(p: Vec3) -> (f32, f32) {
+        camera().project(&p, W, H).unwrap()
+    }
+
+    #[test]
+    fn translate_follows_the_mouse_along_the_axis() {
+        let mut gizmo = Gizmo::new();
+        let object = cube();
+        let tip = screen(Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(gizmo.pick(&object.shape, &camera(), W, H, tip), Some(1));
+
+        // agarrar la flecha a la altura del centro y llevar el mouse a donde se ve y = 1.5 (más un
+        // desvío perpendicular que se ignora)
+        assert!(gizmo.begin(&object, 1, &camera(), W, H, screen(Vec3::zero())));
+        let target = screen(Vec3::new(0.0, 1.5, 0.0));
+        let moved = gizmo.drag_to((target.0 + 30.0, target.1), false).unwrap();
+        let center = moved.shape.center();
+        assert!((center - Vec3::new(0.0, 1.5, 0.0)).length() < 1e-3, "{:?}", center);
+
+        let snapped = gizmo.drag_to(screen(Vec3::new(0.0, 1.3, 0.0)), true).unwrap();
+        assert!((snapped.shape.center().y - 1.5).abs() < 1e-4);
+        assert_eq!(gizmo.end(), Some(object));
+        assert!(!gizmo.is_dragging());
+    }
+
+    #[test]
+    fn scale_clamps_and_follows_local_axes() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Scale, drag: None };
+        let mut object = cube();
+        object.shape = Shape::Cube { center: Vec3::zero(), half_size: Vec3::new(1.0, 1.0, 1.0), rotation: Vec2::new(0.0, 90.0) };
+        // con 90° en y el eje x local apunta a -z del mundo: su flecha se ve de frente y no se puede usar
+        assert!(!gizmo.begin(&object, 0, &camera(), W, H, (400.0, 300.0)));
+
+        assert!(gizmo.begin(&object, 1, &camera(), W, H, screen(Vec3::new(0.0, 2.0, 0.0))));
+        let grown = gizmo.drag_to(screen(Vec3::new(0.0, 3.0, 0.0)), false).unwrap();
+        let Shape::Cube { half_size, .. } = grown.shape else { unreachable!() };
+        assert!((half_size.y - 2.0).abs() < 1e-3 && half_size.x == 1.0);
+        let shrunk = gizmo.drag_to(screen(Vec3::new(0.0, -5.0, 0.0)), false).unwrap();
+        let Shape::Cube { half_size, .. } = shrunk.shape else { unreachable!() };
+        assert_eq!(half_size.y, MIN_SIZE);
+    }
+
+    #[test]
+    fn rotate_turns_with_the_mouse_around_the_center() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Rotate, drag: None };
+        let object = cube();
+        assert_eq!(gizmo.handles(&object.shape).len(), 2);
+        let sphere = ObjectDesc { shape: Shape::Sphere { center: Vec3::zero(), radius: 1.0 }, ..cube() };
+        assert!(gizmo.handles(&sphere.shape).is_empty());
+
+        // el eje x se ve de costado: su anillo es una línea vertical; agarrarlo arriba y llevarlo
+        // un cuarto de vuelta alrededor del centro de la pantalla
+        let top = screen(Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(gizmo.pick(&object.shape, &camera(), W, H, top), Some(0));
+        assert!(gizmo.begin(&object, 0, &camera(), W, H, (400.0, 200.0)));
+        let turned = gizmo.drag_to((500.0, 300.0), true).unwrap();
+        let Shape::Cube { rotation, .. } = turned.shape else { unreachable!() };
+        assert_eq!(rotation.y, 0.0);
+        assert!((rotation.x.abs() - 90.0).abs() < 1e-3, "{:?}", rotation);
+        assert_eq!(wrap_degrees(190.0), -170.0);
+        assert_eq!(wrap_degrees(-180.0), 180.0);
+    }
+}
@@ -0,0 +1,123 @@
+// Historial de deshacer/rehacer de las ediciones de objetos del archivo de escena (gizmos y
+// demás). Cada entrada guarda el objeto completo antes y después, así deshacer no depende de
+// cómo se hizo el cambio.
+
+use crate::scene_file::ObjectDesc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectEdit {
+    // índice en SceneFile::objects
+    pub index: usize,
+    pub before: ObjectDesc,
+    pub after: ObjectDesc,
+}
+
+pub struct History {
+    undo: Vec<ObjectEdit>,
+    redo: Vec<ObjectEdit>,
+    // cantidad máxima de ediciones que se recuerdan
+    limit: usize,
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        History { undo: Vec::new(), redo: Vec::new(), limit: limit.max(1) }
+    }
+
+    /// Agrega una edición ya aplicada. Descarta lo que se podía rehacer.
+    pub fn record(&mut self, edit: ObjectEdit) {
+        if edit.before == edit.after {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Vuelve el último objeto editado a como estaba; devuelve su índice
+    pub fn undo(&mut self, objects: &mut [ObjectDesc]) -> Option<usize> {
+        let edit = self.undo.pop()?;
+        let index = edit.index;
+        *objects.get_mut(index)? = edit.before.clone();
+        self.redo.push(edit);
+        Some(index)
+    }
+
+    /// Rehace la última edición deshecha; devuelve el índice del objeto
+    pub fn redo(&mut self, objects: &mut [ObjectDesc]) -> Option<usize> {
+        let edit = self.redo.pop()?;
+        let index = edit.index;
+        *objects.get_mut(index)? = edit.after.clone();
+        self.undo.push(edit);
+        Some(index)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Olvida todo (p.ej. al recargar el archivo, los índices dejan de valer)
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+    use crate::scene_file::Shape;
+
+    fn sphere(x: f32) -> ObjectDesc {
+        ObjectDesc { name: None, shape: Shape::Sphere { center: Vec3::new(x, 0.0, 0.0), radius: 1.0 }, material: "m".to_string() }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_objects() {
+        let mut objects = vec![sphere(0.0), sphere(10.0)];
+        let mut history = History::new(10);
+        assert_eq!(history.undo(&mut objects), None);
+
+        objects[0] = sphere(1.0);
+        history.record(ObjectEdit { index: 0, before: sphere(0.0), after: sphere(1.0) });
+        objects[0] = sphere(2.0);
+        history.record(ObjectEdit { index: 0, before: sphere(1.0), after: sphere(2.0) });
+        // una edición que no cambia nada no se guarda
+        history.record(ObjectEdit { index: 1, before: sphere(10.0), after: sphere(10.0) });
+
+        assert_eq!(history.undo(&mut objects), Some(0));
+        assert_eq!(objects[0], sphere(1.0));
+        assert_eq!(history.undo(&mut objects), Some(0));
+        assert_eq!(objects[0], sphere(0.0));
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(&mut objects), Some(0));
+        assert_eq!(objects[0], sphere(1.0));
+
+        // una edición nueva descarta lo que quedaba por rehacer
+        objects[1] = sphere(11.0);
+        history.record(ObjectEdit { index: 1, before: sphere(10.0), after: sphere(11.0) });
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(&mut objects), Some(1));
+        assert_eq!(objects, vec![sphere(1.0), sphere(10.0)]);
+    }
+
+    #[test]
+    fn keeps_only_the_last_edits() {
+        let mut objects = vec![sphere(0.0)];
+        let mut history = History::new(2);
+        for x in 1..=3 {
+            history.record(ObjectEdit { index: 0, before: sphere(x as f32 - 1.0), after: sphere(x as f32) });
+        }
+        assert_eq!(history.undo(&mut objects), Some(0));
+        assert_eq!(history.undo(&mut objects), Some(0));
+        assert_eq!(history.undo(&mut objects), None);
+        assert_eq!(objects[0], sphere(1.0));
+    }
+}
@@ -0,0 +1,226 @@
+// Panel lateral para inspeccionar y editar el objeto seleccionado con el mouse. Edita el SceneFile
+// (la descripción de la escena), no los objetos ya construidos: quien lo usa reconstruye la escena
+// con `build_objects` cuando hay cambios, y puede guardar el archivo tal cual.
+
+use crate::material::Material;
+use crate::scene_file::{ObjectDesc, SceneFile, Shape};
+
+#[cfg(feature = "raylib")]
+use raylib::prelude::{Rectangle, RaylibDrawGui};
+
+/// Un valor editable con su rango (el del slider)
+pub struct Field<'a> {
+    pub label: &'static str,
+    pub value: &'a mut f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl<'a> Field<'a> {
+    fn new(label: &'static str, value: &'a mut f32, min: f32, max: f32) -> Self {
+        Field { label, value, min, max }
+    }
+}
+
+/// Geometría editable del objeto: centro, tamaño y rotación (cubo), radio (esfera), centro y
+/// rotación (modelo de bloque o sdf) o centro, medidas y rotación (primitivas)
+pub fn object_fields(object: &mut ObjectDesc) -> Vec<Field<'_>> {
+    match &mut object.shape {
+        Shape::Cube { center, half_size, rotation } => vec![
+            Field::new("centro x", &mut center.x, -20.0, 20.0),
+            Field::new("centro y", &mut center.y, -20.0, 20.0),
+            Field::new("centro z", &mut center.z, -20.0, 20.0),
+            Field::new("mitad x", &mut half_size.x, 0.05, 10.0),
+            Field::new("mitad y", &mut half_size.y, 0.05, 10.0),
+            Field::new("mitad z", &mut half_size.z, 0.05, 10.0),
+            Field::new("rot. x", &mut rotation.x, -180.0, 180.0),
+            Field::new("rot. y", &mut rotation.y, -180.0, 180.0),
+        ],
+        Shape::Sphere { center, radius } => vec![
+            Field::new("centro x", &mut center.x, -20.0, 20.0),
+            Field::new("centro y", &mut center.y, -20.0, 20.0),
+            Field::new("centro z", &mut center.z, -20.0, 20.0),
+            Field::new("radio", radius, 0.05, 10.0),
+        ],
+        Shape::Model { center, rotation, .. } | Shape::Sdf { center, rotation, .. } => vec![
+            Field::new("centro x", &mut center.x, -20.0, 20.0),
+            Field::new("centro y", &mut center.y, -20.0, 20.0),
+            Field::new("centro z", &mut center.z, -20.0, 20.0),
+            Field::new("rot. x", &mut rotation.x, -180.0, 180.0),
+            Field::new("rot. y", &mut rotation.y, -180.0, 180.0),
+        ],
+        Shape::Primitive { center, rotation, primitive } => {
+            let mut fields = vec![
+                Field::new("centro x", &mut center.x, -20.0, 20.0),
+                Field::new("centro y", &mut center.y, -20.0, 20.0),
+                Field::new("centro z", &mut center.z, -20.0, 20.0),
+            ];
+            fields.extend(primitive.params().into_iter().map(|(_, label, value)| Field::new(label, value, 0.05, 10.0)));
+            fields.push(Field::new("rot. x", &mut rotation.x, -180.0, 180.0));
+            fields.push(Field::new("rot. y", &mut rotation.y, -180.0, 180.0));
+            fields
+        }
+        // las partes de un csg se editan en el archivo de escena
+        Shape::Csg { .. } => Vec::new(),
+    }
+}
+
+/// Propiedades editables del material (compartido por todos los objetos que lo usan)
+pub fn material_fields(material: &mut Material) -> Vec<Field<'_>> {
+    vec![
+        Field::new("difuso r", &mut material.diffuse.r, 0.0, 1.0),
+        Field::new("difuso g", &mut material.diffuse.g, 0.0, 1.0),
+        Field::new("difuso b", &mut material.diffuse.b, 0.0, 1.0),
+        Field::new("especular", &mut material.specular, 1.0, 256.0),
+        Field::new("reflexión", &mut material.reflectivity, 0.0, 1.0),
+        Field::new("transpar.", &mut material.transparency, 0.0, 1.0),
+        Field::new("refracción", &mut material.refractive_index, 1.0, 2.5),
+        Field::new("emisión", &mut material.emission, 0.0, 5.0),
+    ]
+}
+
+/// Lo que pasó en el panel durante el frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PanelResult {
+    // algún valor cambió: hay que reconstruir los objetos de la escena
+    pub changed: bool,
+    // se pidió guardar el archivo
+    pub save: bool,
+}
+
+pub struct Inspector {
+    // índice en SceneFile::objects (el mismo que en Scene::objects)
+    pub selected: Option<usize>,
+    // hay cambios sin guardar
+    pub unsaved: bool,
+    screen_width: i32,
+    screen_height: i32,
+}
+
+const PANEL_WIDTH: i32 = 280;
+#[cfg(feature = "raylib")]
+const ROW_HEIGHT: i32 = 22;
+
+impl Inspector {
+    pub fn new(screen_width: i32, screen_height: i32) -> Self {
+        Inspector { selected: None, unsaved: false, screen_width, screen_height }
+    }
+
+    /// Rectángulo (x, y, ancho, alto) del panel, pegado al borde derecho
+    pub fn bounds(&self) -> (i32, i32, i32, i32) {
+        (self.screen_width - PANEL_WIDTH, 0, PANEL_WIDTH, self.screen_height)
+    }
+
+    /// Si el punto cae sobre el panel (solo visible con algo seleccionado); los clics ahí no hacen picking
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let (px, py, w, h) = self.bounds();
+        self.selected.is_some() && x >= px && x < px + w && y >= py && y < py + h
+    }
+
+    /// Quita la selección si el índice ya no existe (p.ej. tras recargar el archivo)
+    pub fn validate(&mut self, scene_file: &SceneFile) {
+        if self.selected.is_some_and(|i| i >= scene_file.objects.len()) {
+            self.selected = None;
+        }
+    }
+
+    /// Dibuja el panel del objeto seleccionado y aplica lo que el usuario mueva en los sliders
+    #[cfg(feature = "raylib")]
+    pub fn draw(&mut self, d: &mut impl RaylibDrawGui, scene_file: &mut SceneFile) -> PanelResult {
+        let mut result = PanelResult::default();
+        let Some(index) = self.selected else { return result };
+        let SceneFile { objects, materials, .. } = scene_file;
+        let Some(object) = objects.get_mut(index) else { return result };
+
+        let (px, py, w, h) = self.bounds();
+        let (x, w) = ((px + 8) as f32, (w - 16) as f32);
+        let mut y = (py + 32) as f32;
+        let title = format!("{}{}", object.name.as_deref().unwrap_or("(sin nombre)"), if self.unsaved { " *" } else { "" });
+        d.gui_panel(Rectangle::new(px as f32, py as f32, PANEL_WIDTH as f32, h as f32), &title);
+
+        result.changed |= slider_rows(d, object_fields(object), x, w, &mut y);
+        y += 8.0;
+        match materials.iter_mut().find(|(name, _)| *name == object.material) {
+            Some((name, material)) => {
+                // el material es por nombre: el cambio afecta a todos los objetos que lo usan
+                d.gui_label(Rectangle::new(x, y, w, ROW_HEIGHT as f32), &format!("material {} (compartido)", name));
+                y += ROW_HEIGHT as f32;
+                result.changed |= slider_rows(d, material_fields(material), x, w, &mut y);
+            }
+            None => {
+                d.gui_label(Rectangle::new(x, y, w, ROW_HEIGHT as f32), &format!("material {} no definido", object.material));
+                y += ROW_HEIGHT as f32;
+            }
+        }
+
+        y += 8.0;
+        result.save = d.gui_button(Rectangle::new(x, y, w, 28.0), "Guardar (Ctrl+S)");
+        self.unsaved |= result.changed;
+        result
+    }
+}
+
+// Un slider por campo, con la etiqueta a la izquierda y el valor a la derecha; true si alguno cambió
+#[cfg(feature = "raylib")]
+fn slider_rows(d: &mut impl RaylibDrawGui, fields: Vec<Field<'_>>, x: f32, w: f32, y: &mut f32) -> bool {
+    let mut changed = false;
+    for field in fields {
+        let before = *field.value;
+        let text = format!("{:.2}", before);
+        // los primeros 80 px son para la etiqueta y los últimos 48 para el valor
+        let bounds = Rectangle::new(x + 80.0, *y, w - 80.0 - 48.0, (ROW_HEIGHT - 4) as f32);
+        d.gui_slider_bar(bounds, field.label, &text, field.value, field.min, field.max);
+        changed |= *field.value != before;
+        *y += ROW_HEIGHT as f32;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Vec2, Vec3};
+
+    fn cube() -> ObjectDesc {
+        ObjectDesc {
+            name: Some("bloque".to_string()),
+            shape: Shape::Cube { center: Vec3::new(1.0, 2.0, 3.0), half_size: Vec3::new(1.0, 1.0, 1.0), rotation: Vec2::new(0.0, 45.0) },
+            material: "brick".to_string(),
+        }
+    }
+
+    #[test]
+    fn fields_edit_the_description() {
+        let mut object = cube();
+        let mut fields = object_fields(&mut object);
+        assert_eq!(fields.len(), 8);
+        *fields[1].value = -4.0;
+        *fields[7].value = 90.0;
+        assert_eq!(
+            object.shape,
+            Shape::Cube { center: Vec3::new(1.0, -4.0, 3.0), half_size: Vec3::new(1.0, 1.0, 1.0), rotation: Vec2::new(0.0, 90.0) }
+        );
+
+        let mut material = Material::default();
+        for field in material_fields(&mut material) {
+            assert!(field.min < field.max, "{}", field.label);
+            assert!((field.min..=field.max).contains(field.value), "{} fuera de rango", field.label);
+        }
+    }
+
+    #[test]
+    fn panel_only_blocks_clicks_with_a_selection() {
+        let mut scene_file = SceneFile { objects: vec![cube()], ..Default::default() };
+        let mut inspector = Inspector::new(900, 700);
+        assert!(!inspector.contains(850, 100));
+        inspector.selected = Some(0);
+        assert!(inspector.contains(850, 100));
+        assert!(!inspector.contains(100, 100));
+
+        inspector.validate(&scene_file);
+        assert_eq!(inspector.selected, Some(0));
+        scene_file.objects.clear();
+        inspector.validate(&scene_file);
+        assert_eq!(inspector.selected, None);
+    }
+}
@@ -0,0 +1,59 @@
+//! Núcleo del raytracer: escena, cámara, materiales, luces, primitivas y el renderer.
+//!
+//! Se puede usar sin ventana (`default-features = false`): `Renderer::render_to_buffer`
+//! devuelve la imagen en memoria. Con la feature `raylib` (activa por defecto) el
+//! framebuffer además sabe subirse a una textura y cargar texturas a la GPU; las texturas en CPU
+//! se pueden leer sin ventana con `TextureManager::load_cpu_texture`.
+
+pub mod math;
+pub mod bvh;
+pub mod zlib;
+pub mod nbt;
+pub mod json;
+pub mod png;
+pub mod framebuffer;
+pub mod ray_intersect;
+pub mod cube;
+pub mod sphere;
+pub mod block_model;
+pub mod csg;
+pub mod primitives;
+pub mod sdf;
+pub mod material;
+pub mod camera;
+pub mod light;
+pub mod textures;
+pub mod procedural;
+pub mod shader;
+pub mod daycycle;
+pub mod scene;
+pub mod scene_file;
+pub mod fog;
+pub mod sampling;
+pub mod subsurface;
+pub mod photon_map;
+pub mod ao;
+pub mod aov;
+pub mod denoise;
+pub mod progressive;
+pub mod tiles;
+pub mod renderer;
+pub mod watch;
+pub mod inspector;
+pub mod gizmo;
+pub mod history;
+pub mod minecraft;
+pub mod merge;
+
+pub use camera::Camera;
+pub use framebuffer::Framebuffer;
+pub use light::Light;
+pub use material::Material;
+pub use ray_intersect::{HitInfo, RayIntersect};
+pub use renderer::{
+    cast_ray, map_uv_for_cube, pick, procedural_sky, reflect, refract, render, render_ao, render_ssao, to_pixel_color,
+    trace_frame, trace_region, PassSettings, Renderer, TracedPixel,
+};
+pub use scene::Scene;
+pub use scene_file::SceneFile;
+pub use textures::TextureManager;
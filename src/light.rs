@@ -1,8 +1,68 @@
-use raylib::prelude::*;
+use crate::math::{Color, Vec3};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LightKind {
+    // luz puntual: ilumina desde `position` y se atenúa con la distancia
+    Point,
+    // luz direccional (sol/luna): todos los rayos llegan paralelos, sin atenuación
+    Directional,
+}
 
 #[derive(Clone, Copy)]
 pub struct Light {
-    pub position: Vector3,
-    pub color: Vector3,
+    pub position: Vec3,
+    pub color: Color,
     pub intensity: f32,
+    pub kind: LightKind,
+    // dirección hacia la que viaja la luz (solo se usa en luces direccionales)
+    pub direction: Vec3,
+}
+
+impl Light {
+    pub fn point(position: Vec3, color: Color, intensity: f32) -> Self {
+        Light {
+            position,
+            color,
+            intensity,
+            kind: LightKind::Point,
+            direction: Vec3::zero(),
+        }
+    }
+
+    pub fn directional(direction: Vec3, color: Color, intensity: f32) -> Self {
+        Light {
+            position: Vec3::zero(),
+            color,
+            intensity,
+            kind: LightKind::Directional,
+            direction: direction.normalized(),
+        }
+    }
+
+    /// Dirección (normalizada) desde `point` hacia la luz y distancia hasta ella.
+    /// Para luces direccionales la distancia es infinita.
+    pub fn incidence(&self, point: &Vec3) -> Option<(Vec3, f32)> {
+        match self.kind {
+            LightKind::Point => {
+                let lvec = self.position - *point;
+                let dist = lvec.length();
+                if dist <= 0.0 {
+                    return None;
+                }
+                Some((lvec / dist, dist))
+            }
+            LightKind::Directional => Some((-self.direction, f32::INFINITY)),
+        }
+    }
+
+    /// Intensidad que llega a una distancia `dist` (atenuación simple, k pequeño para que la luz alcance más).
+    pub fn attenuation(&self, dist: f32) -> f32 {
+        match self.kind {
+            LightKind::Point => {
+                let k = 0.02_f32;
+                self.intensity / (1.0 + k * dist * dist)
+            }
+            LightKind::Directional => self.intensity,
+        }
+    }
 }
@@ -1,344 +1,228 @@
 // main.rs
-#![allow(unused_imports)]
-#![allow(dead_code)]
 
-use raylib::prelude::*;
+use raylib::prelude::{KeyboardKey, MouseButton, TraceLogLevel};
 use std::f32::consts::PI;
-use rayon::prelude::*;
 use std::sync::Arc;
 
-mod framebuffer;
-mod ray_intersect;
-mod cube;
-mod material;
-mod camera;
-mod light;
-mod textures;
-
-use framebuffer::Framebuffer;
-use ray_intersect::{RayIntersect, HitInfo};
-use cube::Cube;
-use material::Material;
-use camera::Camera;
-use light::Light;
-use textures::TextureManager;
-
-// Util para comprobar si hay cualquier intersección entre origin y origin + dir*max_dist
-fn intersects_any(
-    origin: &Vector3,
-    direction: &Vector3,
-    objects: &[&(dyn RayIntersect + Sync)],
-    max_dist: f32,
-) -> bool {
-    for obj in objects {
-        if let Some(hit) = obj.ray_intersect(origin, direction) {
-            if hit.distance < max_dist {
-                return true;
+// Front-end con ventana: arma la escena del diorama y la muestra con raylib.
+// Todo el trazado vive en la biblioteca (animal_rt).
+use animal_rt::math::{Rgba8, Vec3};
+use animal_rt::{pick, render, render_ao, render_ssao, Camera, Framebuffer, Scene, SceneFile, TextureManager};
+use animal_rt::daycycle::DayCycle;
+use animal_rt::watch::FileWatcher;
+use animal_rt::inspector::{Inspector, PanelResult};
+use animal_rt::gizmo::{Gizmo, GizmoMode, ACTIVE_COLOR, AXIS_COLORS};
+use animal_rt::history::{History, ObjectEdit};
+use raylib::prelude::{RaylibHandle, RaylibThread};
+use std::time::Duration;
+use animal_rt::aov::AovPass;
+use animal_rt::denoise::Denoiser;
+use animal_rt::progressive::Progressive;
+
+// Opciones de línea de comandos:
+//   --scene <archivo>               escena a cargar (por defecto scenes/diorama.scene)
+//   --time <horas>                  hora inicial del día (0..24); si no, la del archivo
+//   --animate-day <frames> [dir]    renderiza un día completo a PNGs y termina
+//   --ao-samples <n> / --ao-radius <r>   rayos y radio de la oclusión ambiental (0 muestras la apaga)
+struct Options {
+    scene: String,
+    time: Option<f32>,
+    animate_day: Option<(u32, String)>,
+    ao_samples: Option<u32>,
+    ao_radius: Option<f32>,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        scene: "scenes/diorama.scene".to_string(),
+        time: None,
+        animate_day: None,
+        ao_samples: None,
+        ao_radius: None,
+    };
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--time" => {
+                if let Some(t) = args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    options.time = Some(t);
+                    i += 1;
+                } else {
+                    eprintln!("--time necesita un número de horas");
+                }
+            }
+            "--scene" => {
+                if let Some(path) = args.get(i + 1) {
+                    options.scene = path.clone();
+                    i += 1;
+                } else {
+                    eprintln!("--scene necesita la ruta de un archivo");
+                }
             }
+            "--animate-day" => {
+                if let Some(frames) = args.get(i + 1).and_then(|v| v.parse::<u32>().ok()) {
+                    i += 1;
+                    let dir = match args.get(i + 1) {
+                        Some(d) if !d.starts_with("--") => {
+                            i += 1;
+                            d.clone()
+                        }
+                        _ => "frames".to_string(),
+                    };
+                    options.animate_day = Some((frames.max(1), dir));
+                } else {
+                    eprintln!("--animate-day necesita la cantidad de frames");
+                }
+            }
+            "--ao-samples" => {
+                options.ao_samples = args.get(i + 1).and_then(|v| v.parse::<u32>().ok());
+                if options.ao_samples.is_none() {
+                    eprintln!("--ao-samples necesita un entero");
+                }
+                i += 1;
+            }
+            "--ao-radius" => {
+                options.ao_radius = args.get(i + 1).and_then(|v| v.parse::<f32>().ok());
+                if options.ao_radius.is_none() {
+                    eprintln!("--ao-radius necesita un número");
+                }
+                i += 1;
+            }
+            other => eprintln!("argumento desconocido: {}", other),
         }
+        i += 1;
     }
-    false
+    options
 }
 
-
-fn reflect(i: &Vector3, n: &Vector3) -> Vector3 {
-    *i - *n * 2.0 * i.dot(*n)
+fn format_time_of_day(time: f32) -> String {
+    let hours = time.floor() as u32;
+    let minutes = ((time - time.floor()) * 60.0).floor() as u32;
+    format!("{:02}:{:02}", hours, minutes)
 }
 
-pub fn refract(incident: &Vector3, normal: &Vector3, refractive_index: f32) -> Vector3 {
-    // Implementation of Snell's Law for refraction.
-    // It calculates the direction of a ray as it passes from one medium to another.
-
-    // `cosi` is the cosine of the angle between the incident ray and the normal.
-    // We clamp it to the [-1, 1] range to avoid floating point errors.
-    let mut cosi = incident.dot(*normal).max(-1.0).min(1.0);
-
-    // `etai` is the refractive index of the medium the ray is currently in.
-    // `etat` is the refractive index of the medium the ray is entering.
-    // `n` is the normal vector, which may be flipped depending on the ray's direction.
-    let mut etai = 1.0; // Assume we are in Air (or vacuum) initially
-    let mut etat = refractive_index;
-    let mut n = *normal;
-
-    if cosi > 0.0 {
-        // The ray is inside the medium (e.g., glass) and going out into the air.
-        // We need to swap the refractive indices.
-        std::mem::swap(&mut etai, &mut etat);
-        // We also flip the normal so it points away from the medium.
-        n = -n;
-    } else {
-        // The ray is outside the medium and going in.
-        // We need a positive cosine for the calculation, so we negate it.
-        cosi = -cosi;
+// Renderiza `frames` imágenes repartidas en 24 horas, empezando a la hora actual de la escena.
+fn render_day_animation(
+    framebuffer: &mut Framebuffer,
+    scene: &mut Scene,
+    camera: &Camera,
+    texture_manager: &TextureManager,
+    frames: u32,
+    out_dir: &str,
+) {
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        eprintln!("no se pudo crear {}: {}", out_dir, e);
+        return;
     }
-
-    // `eta` is the ratio of the refractive indices (n1 / n2).
-    let eta = etai / etat;
-    // `k` is a term derived from Snell's law that helps determine if total internal reflection occurs.
-    let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
-
-    if k < 0.0 {
-        // If k is negative, it means total internal reflection has occurred.
-        // There is no refracted ray, so we return None.
-        Vector3::zero()
-    } else {
-        // If k is non-negative, we can calculate the direction of the refracted ray.
-        *incident * eta + n * (eta * cosi - k.sqrt())
+    let start = scene.day.time;
+    for frame in 0..frames {
+        let time = start + 24.0 * frame as f32 / frames as f32;
+        scene.set_time_of_day(time);
+        scene.build_photon_map();
+        render(framebuffer, scene, camera, texture_manager);
+        let path = format!("{}/day_{:04}.png", out_dir, frame);
+        framebuffer.render_to_file(&path);
+        println!("{} ({})", path, format_time_of_day(scene.day.time));
     }
 }
 
-
-
-fn get_cube_uv(hit_point: Vector3, normal: Vector3) -> (f32, f32) {
-    let (u, v) = if normal.x.abs() > 0.5 {
-        // Cara derecha o izquierda
-        ((hit_point.z + 0.5), (hit_point.y + 0.5))
-    } else if normal.y.abs() > 0.5 {
-        // Cara superior o inferior
-        ((hit_point.x + 0.5), (hit_point.z + 0.5))
-    } else {
-        // Cara delantera o trasera
-        ((hit_point.x + 0.5), (hit_point.y + 0.5))
-    };
-
-    (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+// Carga (en GPU y CPU) las texturas que usa la escena; las que fallan se informan y se dibujan en blanco
+fn load_textures(
+    texture_manager: &mut TextureManager,
+    window: &mut RaylibHandle,
+    raylib_thread: &RaylibThread,
+    scene_file: &SceneFile,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for path in scene_file.texture_paths() {
+        if let Err(e) = texture_manager.load_texture(window, raylib_thread, &path) {
+            eprintln!("{}", e);
+            errors.push(e);
+        }
+    }
+    errors
 }
 
-fn map_uv_for_cube(local_point: &Vector3, local_normal: &Vector3, half_size: &Vector3) -> Option<(f32, f32)> {
-    // local_point está en coordenadas locales (ej: x in [-hx, +hx])
-    let p = *local_point;
-    let hx = half_size.x;
-    let hy = half_size.y;
-    let hz = half_size.z;
-
-    // evita dividir por cero
-    if hx.abs() < 1e-6 || hy.abs() < 1e-6 || hz.abs() < 1e-6 {
-        return None;
+// Avisa de los bloques importados que no están en la tabla (no se dibujan) y vigila los archivos
+// que lee la escena (importaciones y modelos .json) para recargarla cuando cambian
+fn watch_files(watcher: &mut FileWatcher, scene_file: &SceneFile) {
+    for import in &scene_file.imports {
+        if !import.unmapped.is_empty() {
+            eprintln!("{}: bloques sin material: {}", import.file, import.unmapped.join(", "));
+        }
     }
-
-    if local_normal.x.abs() > 0.9 {
-        // cara izquierda/derecha: u = z, v = y
-        let u = (p.z + hz) / (2.0 * hz); // map z from [-hz, hz] -> [0,1]
-        let v = (p.y + hy) / (2.0 * hy); // map y from [-hy, hy] -> [0,1]
-        Some((u, v))
-    } else if local_normal.y.abs() > 0.9 {
-        // cara top/bottom: u = x, v = z
-        let u = (p.x + hx) / (2.0 * hx);
-        let v = (p.z + hz) / (2.0 * hz);
-        Some((u, v))
-    } else if local_normal.z.abs() > 0.9 {
-        // cara front/back: u = x, v = y
-        let u = (p.x + hx) / (2.0 * hx);
-        let v = (p.y + hy) / (2.0 * hy);
-        Some((u, v))
-    } else {
-        None
+    for path in scene_file.file_paths() {
+        watcher.watch_file(&path);
     }
 }
 
-
-
-
-pub fn cast_ray(
-    ray_origin: &Vector3,
-    ray_direction: &Vector3,
-    objects: &[&(dyn RayIntersect + Sync)],
-    lights: &[Light],
-    depth: u32,
-    texture_manager: &TextureManager,
-) -> Vector3 {
-    if depth > 3 {
-        return Vector3::new(0.1, 0.1, 0.2); // sky
+// Escena lista para renderizar: la del archivo más los ajustes de la línea de comandos
+fn build_scene(scene_file: &SceneFile, day: DayCycle, options: &Options) -> Scene {
+    let mut scene = scene_file.build(day);
+    if let Some(samples) = options.ao_samples {
+        scene.ao.samples = samples;
+        scene.ao.enabled = samples > 0;
     }
-
-    // Buscar el hit más cercano
-    let mut closest_hit: Option<HitInfo> = None;
-    for object in objects {
-        if let Some(hit) = object.ray_intersect(ray_origin, ray_direction) {
-            if closest_hit.is_none() || hit.distance < closest_hit.as_ref().unwrap().distance {
-                closest_hit = Some(hit);
-            }
-        }
-    }
-
-    if let Some(hit) = closest_hit {
-        let m = hit.material;
-
-        // color base desde material (y/o textura)
-        let mut base_color = Vector3::new(
-            m.diffuse.r as f32 / 255.0,
-            m.diffuse.g as f32 / 255.0,
-            m.diffuse.b as f32 / 255.0,
-        );
-
-        if let Some(texture_path) = &m.texture_path {
-            if let Some((u_raw, v_raw)) = map_uv_for_cube(&hit.local_point, &hit.local_normal, &hit.local_half_size) {
-                let u_scaled = u_raw * hit.texture_repeat.x;
-                let v_scaled = v_raw * hit.texture_repeat.y;
-                base_color = texture_manager.sample_uv(texture_path, u_scaled, v_scaled);
-            }
-        }
-
-        // Ambient (luz suave general, evita que todo sea negro)
-        let ambient = Vector3::new(0.06, 0.06, 0.06);
-
-        // acumuladores de iluminación
-        let mut total_diffuse = ambient * base_color; // start with ambient * base color
-        let mut total_specular = Vector3::zero();
-
-        // vista (dirección del ojo)
-        let view_dir = (*ray_origin - hit.point).normalized();
-
-        // recorrer todas las luces
-        for light in lights.iter() {
-            // vector hacia la luz y distancia
-            let mut lvec = light.position - hit.point;
-            let dist = lvec.length();
-            if dist <= 0.0 { continue; }
-            let light_dir = lvec / dist; // normalizado
-
-            // test de sombra: si hay algo entre el punto y la luz, atenua
-            let shadow_origin = hit.point + hit.normal * 5e-3; // mejor epsilon
-            let in_shadow = intersects_any(&shadow_origin, &light_dir, objects, dist - 1e-3);
-
-            // atenuación simple (ajustable)
-            // usa k pequeño para que la luz alcance más
-            let k = 0.02_f32;
-            let attenuation = light.intensity / (1.0 + k * dist * dist);
-
-            // si está en sombra: ponemos una fracción residual (para evitar negro absoluto)
-            let shadow_factor = if in_shadow { 0.15 } else { 1.0 };
-
-            // difuso (Lambert)
-            let ndotl = hit.normal.dot(light_dir).max(0.0);
-            total_diffuse += base_color * ndotl * attenuation * light.color * shadow_factor;
-
-            // especular (Blinn-Phong)
-            let half = (view_dir + light_dir).normalized();
-            let ndoth = hit.normal.dot(half).max(0.0);
-            let spec = ndoth.powf(m.specular);
-            total_specular += light.color * spec * attenuation * shadow_factor;
-        }
-
-        // Reflection recursiva
-        let mut reflection_color = Vector3::new(0.1, 0.1, 0.2);
-        if m.reflectivity > 0.0 {
-            let rdir = reflect(ray_direction, &hit.normal).normalized();
-            let rorigin = hit.point + hit.normal * 1e-3;
-            reflection_color = cast_ray(&rorigin, &rdir, objects, lights, depth + 1, texture_manager);
-        }
-
-        // Refraction recursiva
-        let mut refraction_color = Vector3::zero();
-        if m.transparency > 0.0 {
-            let refr_dir = refract(ray_direction, &hit.normal, m.refractive_index);
-            let refr_dir = refr_dir.normalized();
-            let rorigin = hit.point - hit.normal * 1e-3;
-            refraction_color = cast_ray(&rorigin, &refr_dir, objects, lights, depth + 1, texture_manager);
-        }
-
-        // Emisión del material (si tiene)
-        let emitted = m.emissive * m.emission;
-
-        // Composición final (clamp implícito en conversión a color)
-        let color = total_diffuse * m.albedo[0]
-            + total_specular * m.albedo[1]
-            + reflection_color * m.reflectivity
-            + refraction_color * m.transparency
-            + emitted;
-
-        color
-    } else {
-        procedural_sky(*ray_direction)
+    if let Some(radius) = options.ao_radius {
+        scene.ao.radius = radius;
     }
+    scene
 }
 
+// Acceso mutable a la escena compartida: cancela el frame en curso y reinicia el refinamiento,
+// porque la imagen deja de ser válida. El job de Progressive es el único otro dueño del Arc y
+// restart espera a que sus workers terminen, así que después la escena es exclusiva.
+fn edit_scene<'a>(scene: &'a mut Arc<Scene>, progressive: &mut Progressive) -> &'a mut Scene {
+    progressive.restart();
+    Arc::get_mut(scene).expect("la escena solo se comparte con el render en curso")
+}
 
+// Igual que edit_scene, para recargar texturas
+fn edit_textures<'a>(texture_manager: &'a mut Arc<TextureManager>, progressive: &mut Progressive) -> &'a mut TextureManager {
+    progressive.restart();
+    Arc::get_mut(texture_manager).expect("las texturas solo se comparten con el render en curso")
+}
 
-fn procedural_sky(dir: Vector3) -> Vector3 {
-    let d = dir.normalized();
-    let t = (d.y + 1.0) * 0.5; // map y [-1,1] → [0,1]
-
-    let green = Vector3::new(0.1, 0.6, 0.2); // grass green
-    let white = Vector3::new(1.0, 1.0, 1.0); // horizon haze
-    let blue = Vector3::new(0.3, 0.5, 1.0);  // sky blue
-
-    if t < 0.54 {
-        // Bottom → fade green to white
-        let k = t / 0.55;
-        green * (1.0 - k) + white * k
-    } else if t < 0.55 {
-        // Around horizon → mostly white
-        white
-    } else if t < 0.8 {
-        // Fade white to blue
-        let k = (t - 0.55) / (0.25);
-        white * (1.0 - k) + blue * k
-    } else {
-        // Upper sky → solid blue
-        blue
+// Después de renderizar: muestra el AOV elegido o, en el beauty, la salida del denoiser si está activo
+fn refresh_display(framebuffer: &mut Framebuffer, denoiser: &mut Denoiser, camera: &Camera) {
+    if framebuffer.shown_pass() != AovPass::Beauty {
+        framebuffer.show_pass(framebuffer.shown_pass());
+        return;
     }
+    if !denoiser.enabled {
+        return;
+    }
+    let filtered = match &framebuffer.aovs {
+        Some(aovs) => denoiser.apply(framebuffer.pixels(), aovs, camera),
+        None => return,
+    };
+    framebuffer.present(&filtered);
 }
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[&(dyn RayIntersect + Sync)], camera: &Camera, texture_manager: &TextureManager) {
-    let width_i = framebuffer.width;
-    let height_i = framebuffer.height;
-    let width = width_i as f32;
-    let height = height_i as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
-
-    let lights_vec = vec![
-        Light { // exterior / sol
-            position: Vector3::new(2.5, 6.0, -7.5),
-            color: Vector3::new(1.0, 1.0, 1.0),
-            intensity: 4.5,
-        },
-        Light { // antorcha: posición justo encima del bloque visible
-            position: Vector3::new(-3.0, -2.0, 2.0), 
-            color: Vector3::new(1.0, 0.72, 0.35),
-            intensity: 6.0,
-        },
-    ];
-
-    let lights_arc = Arc::new(lights_vec);
-
-    // Iterador paralelo: para cada fila (y) en paralelo
-    let pixels: Vec<(i32, i32, Color)> = (0..height_i)
-        .into_par_iter()
-        .flat_map(|y| {
-            let lights_for_row = lights_arc.clone();
-            (0..width_i).into_par_iter().map(move |x| {
-                let screen_x = (2.0 * x as f32) / width - 1.0;
-                let screen_y = -(2.0 * y as f32) / height + 1.0;
-                let sx = screen_x * aspect_ratio * perspective_scale;
-                let sy = screen_y * perspective_scale;
-                let ray_direction = Vector3::new(sx, sy, -1.0).normalized();
-                let rotated_direction = camera.basis_change(&ray_direction);
-
-                // calculamos color pasando todas las luces (slice desde Arc)
-                let ray_color = cast_ray(&camera.eye, &rotated_direction, objects, &*lights_for_row, 0, texture_manager);
-
-                let pixel_color = Color::new(
-                    (ray_color.x.clamp(0.0, 1.0) * 255.0) as u8,
-                    (ray_color.y.clamp(0.0, 1.0) * 255.0) as u8,
-                    (ray_color.z.clamp(0.0, 1.0) * 255.0) as u8,
-                    255,
-                );
-
-                (x, y, pixel_color)
-            })
-        })
-        .collect();
-
-    for (x, y, color) in pixels {
-        framebuffer.set_current_color(color);
-        framebuffer.set_pixel(x, y);
+// Escribe cada pase en `out_dir` (beauty.png, depth.png, ...) más depth.pfm y normal.pfm
+fn export_aovs(framebuffer: &Framebuffer, out_dir: &str) {
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        eprintln!("no se pudo crear {}: {}", out_dir, e);
+        return;
     }
+    for pass in AovPass::ALL {
+        framebuffer.export_pass(pass, &format!("{}/{}.png", out_dir, pass.name()));
+    }
+    if let Some(aovs) = &framebuffer.aovs {
+        if let Err(e) = aovs.write_depth_pfm(&format!("{}/depth.pfm", out_dir)) {
+            eprintln!("no se pudo escribir depth.pfm: {}", e);
+        }
+        if let Err(e) = aovs.write_normal_pfm(&format!("{}/normal.pfm", out_dir)) {
+            eprintln!("no se pudo escribir normal.pfm: {}", e);
+        }
+    }
+    println!("pases exportados en {}/", out_dir);
 }
 
 fn main() {
+    let options = parse_options();
+
     let window_width = 900;
     let window_height = 700;
 
@@ -349,190 +233,203 @@ fn main() {
         .build();
 
     window.set_target_fps(60);
-    let mut framebuffer = Framebuffer::new(window_width as i32, window_height as i32, Color::BLACK);
-    framebuffer.set_background_color(Color::new(201, 201, 201, 255));
-
-    let mut texture_manager = TextureManager::new();
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/blackstone.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/brick.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/glass.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/log_spruce.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/glowstone.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/water_flow.png");
-
-
-    let brick = Material {
-        diffuse: Color::new(180, 80, 60, 255),  
-        specular: 16.0,                         
-        reflectivity: 0.03,                    
-        transparency: 0.0,                      
-        refractive_index: 1.0,                 
-        albedo: [0.9, 0.1],                     
-        texture_path: Some("assets/brick.png".to_string()),
-        emissive:Vector3::zero(),
-        emission:0.0
-    };
-
-    let blackstone = Material {
-        diffuse: Color::new(160, 110, 230, 255),
-        specular: 32.0,
-        reflectivity: 0.1,
-        transparency: 0.0,
-        refractive_index: 1.0,
-        albedo: [0.8, 0.2],
-        texture_path: Some("assets/blackstone.png".to_string()),
-        emissive:Vector3::zero(),
-        emission:0.0
-    };
-
-
-    // let mirror = Material {
-    //     diffuse: Color::WHITE,
-    //     specular: 1000.0,
-    //     reflectivity: 1.0,
-    //     transparency: 0.0,
-    //     refractive_index: 1.0,
-    //     albedo: [0.0, 1.0],
-    //     texture_path: Some("algo".to_string())
-    // };
-
-    let glass = Material {
-        diffuse: Color::WHITE,
-        specular: 90.0,
-        reflectivity: 0.15,
-        transparency: 0.9,
-        refractive_index: 1.5,
-        albedo: [0.05, 0.95],
-        texture_path: Some("assets/glass.png".to_string()),  
-        emissive:Vector3::zero(),
-        emission:0.0
-    };
-
-
-    let wood = Material {
-        diffuse: Color::new(100, 70, 50, 255),  
-        specular: 8.0,                          
-        reflectivity: 0.02,                      
-        transparency: 0.0,                       
-        refractive_index: 1.0,                
-        albedo: [0.9, 0.1],                     
-        texture_path: Some("assets/log_spruce.png".to_string()),
-        emissive:Vector3::zero(),
-        emission:0.0
-    };
-
-    let water = Material {
-        diffuse: Color::new(60, 130, 200, 255),
-        specular: 80.0,                        
-        reflectivity: 0.08,                   
-        transparency: 0.75,                     
-        refractive_index: 1.333,                
-        albedo: [0.05, 0.95],                 
-        texture_path: Some("assets/water_flow.png".to_string()),
-        emissive:Vector3::zero(),
-        emission:0.0
-    };
-
-    let glowstone = Material {
-        diffuse: Color::WHITE,
-        specular: 12.0,
-        reflectivity: 0.0,
-        transparency: 0.0,
-        refractive_index: 1.0,
-        albedo: [0.6, 0.4],
-        texture_path: Some("assets/glowstone.png".to_string()),
-        emissive: Vector3::new(1.0, 0.6, 0.2),
-        emission: 1.5,
+    let mut framebuffer = Framebuffer::new(window_width, window_height, Rgba8::BLACK);
+    framebuffer.set_background_color(Rgba8::new(201, 201, 201, 255));
+    framebuffer.enable_aovs();
+
+    let mut scene_file = match SceneFile::load(&options.scene) {
+        Ok(scene_file) => scene_file,
+        Err(e) => {
+            eprintln!("no se pudo cargar la escena: {}", e);
+            return;
+        }
     };
 
+    let mut texture_manager = TextureManager::new();
+    load_textures(&mut texture_manager, &mut window, &raylib_thread, &scene_file);
+    // compartido (solo lectura) con los hilos que renderizan los tiles
+    let mut texture_manager = Arc::new(texture_manager);
 
+    let mut day = scene_file.day();
+    if let Some(time) = options.time {
+        day.set_time(time);
+    }
+    let mut scene = build_scene(&scene_file, day, &options);
 
-    let cube = Cube::new(
-        Vector3::new(0.0, 4.1, 0.0),
-        Vector3::new(4.0, 1.0, 4.0),
-        0f32.to_radians(),
-        (0f32).to_radians(),
-        brick,
-    );
-    
-    let cube2 = Cube::new(
-        Vector3::new(0.0, 0.0, 4.0),
-        Vector3::new(4.0, 3.2, 0.5), 
-        0f32.to_radians(),
-        (0f32).to_radians(),
-        glass,
-    );
-
-    // let cube3 = Cube::new(
-    //     Vector3::new(5.0, 2.0, -5.0),
-    //     Vector3::new(1.0, 1.0, 1.0),
-    //     20f32.to_radians(),
-    //     (-30f32).to_radians(),
-    //     mirror,
-    // );
-
-    let cube4 = Cube::new(
-        Vector3::new(0.0, -4.0, 0.0),
-        Vector3::new(4.0, 1.0, 4.0),
-        0f32.to_radians(),
-        (0f32).to_radians(),
-        blackstone,
-    );
-
-    let water1 = Cube::new(
-        Vector3::new(3.0 ,  -2.5, -1.0),
-        Vector3::new(1.0, 0.5,3.8), 
-        0.0, 0.0,
-        water,
-    );
-
-
-    let cube5 = Cube::new(
-        Vector3::new(-5.0, 0.0, 0.0),
-        Vector3::new(1.0, 4.0, 4.0),
-        0f32.to_radians(),
-        (0f32).to_radians(),
-        wood.clone(),
-    );
-
-    let cube6 = Cube::new(
-        Vector3::new(5.0, 0.0, 0.0),
-        Vector3::new(1.0, 4.0, 4.0),
-        0f32.to_radians(),
-        (0f32).to_radians(),
-        wood.clone(),
-    );
- 
-    let torch_obj = Cube::new(
-        Vector3::new(-3.0, -2.2, 2.0),
-        Vector3::new(0.8, 0.8,0.8),
-        0.0, 
-        0.0,
-        glowstone,
-    );
-
-    let objects_vec: Vec<&(dyn RayIntersect + Sync)> = vec![
-        &cube as &(dyn RayIntersect + Sync),
-        &cube2 as &(dyn RayIntersect + Sync),
-        // &cube3 as &(dyn RayIntersect + Sync),
-        &cube4 as &(dyn RayIntersect + Sync),
-        &cube5 as &(dyn RayIntersect + Sync),
-        &cube6 as &(dyn RayIntersect + Sync),
-        &water1 as &(dyn RayIntersect + Sync),
-        &torch_obj as &(dyn RayIntersect + Sync),
-    ];
-    let objects_slice: &[&(dyn RayIntersect + Sync)] = &objects_vec;
-
-    let mut camera = Camera::new(
-        Vector3::new(0.0, 0.0, -15.0),  // eye
-        Vector3::new(0.0, 0.0, 0.0),  // center
-        Vector3::new(0.0, 1.0, 0.0),  // up
-    );
+    let mut camera = scene_file.camera.clone().unwrap_or_else(|| Camera::new(
+        Vec3::new(0.0, 0.0, -15.0),  // eye
+        Vec3::new(0.0, 0.0, 0.0),  // center
+        Vec3::new(0.0, 1.0, 0.0),  // up
+    ));
     let rotation_speed = PI / 50.0;
 
+    // modo animación: renderiza un día completo a disco y termina
+    if let Some((frames, out_dir)) = &options.animate_day {
+        render_day_animation(&mut framebuffer, &mut scene, &camera, &texture_manager, *frames, out_dir);
+        return;
+    }
+
+    // la escena se comparte con el render en segundo plano; para modificarla se usa edit_scene
+    let mut scene = Arc::new(scene);
+    let mut denoiser = Denoiser::new();
+    // ~20 fps mientras se mueve la cámara
+    let mut progressive = Progressive::new(1.0 / 20.0);
     let mut camera_moved = true;
+    // las cáusticas dependen de las luces: se reconstruyen cuando cambia la hora
+    let mut caustics_enabled = true;
+    let mut lights_changed = true;
+    let mut photons_pending = false; // hay un mapa de vista previa esperando el completo
+
+    // recarga en caliente: el archivo de escena y las texturas de assets/ se revisan dos veces por
+    // segundo; si el archivo nuevo no se puede leer se sigue mostrando la última escena buena
+    let mut watcher = FileWatcher::new(Duration::from_millis(500));
+    watcher.watch_file(&options.scene);
+    watcher.watch_dir("assets", "png");
+    watch_files(&mut watcher, &scene_file);
+    let mut reload_error: Option<String> = None;
+    // clic izquierdo selecciona el objeto bajo el cursor; el panel de la derecha lo edita
+    let mut inspector = Inspector::new(window_width, window_height);
+    let mut panel = PanelResult::default();
+    // arrastrar los manipuladores mueve/rota/escala el objeto seleccionado; Ctrl+Z / Ctrl+Y deshacen y rehacen
+    let mut gizmo = Gizmo::new();
+    let mut history = History::new(100);
 
     while !window.window_should_close() {
+        let dt = window.get_frame_time();
+
+        for path in watcher.poll() {
+            if path == options.scene || scene_file.file_paths().contains(&path) {
+                match SceneFile::load(&options.scene) {
+                    Ok(loaded) => {
+                        scene_file = loaded;
+                        watch_files(&mut watcher, &scene_file);
+                        let textures = edit_textures(&mut texture_manager, &mut progressive);
+                        let errors = load_textures(textures, &mut window, &raylib_thread, &scene_file);
+                        // se conservan la hora y la niebla elegidas en la ventana
+                        let scene = edit_scene(&mut scene, &mut progressive);
+                        let fog_enabled = scene.fog_enabled;
+                        *scene = build_scene(&scene_file, scene.day, &options);
+                        scene.fog_enabled = fog_enabled;
+                        // el archivo en disco pasa a ser la versión vigente (se pierden las ediciones sin guardar)
+                        inspector.validate(&scene_file);
+                        inspector.unsaved = false;
+                        history.clear();
+                        gizmo.end();
+                        reload_error = errors.into_iter().next();
+                        println!("escena recargada: {}", path);
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        reload_error = Some(e.to_string());
+                    }
+                }
+            } else if texture_manager.contains(&path) {
+                let textures = edit_textures(&mut texture_manager, &mut progressive);
+                match textures.reload_texture(&mut window, &raylib_thread, &path) {
+                    Ok(()) => {
+                        reload_error = None;
+                        println!("textura recargada: {}", path);
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        reload_error = Some(e);
+                    }
+                }
+            } else {
+                continue;
+            }
+            camera_moved = true;
+            lights_changed = true;
+        }
+
+        // selección: el rayo del cursor es el mismo que genera render() para ese píxel; un clic en
+        // el cielo quita la selección
+        // el clic sobre un manipulador empieza un arrastre en lugar de elegir otro objeto
+        let mouse = window.get_mouse_position();
+        let (screen_w, screen_h) = (framebuffer.width as f32, framebuffer.height as f32);
+        if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let (x, y) = (mouse.x as i32, mouse.y as i32);
+            if !inspector.contains(x, y) {
+                let selected = inspector.selected.and_then(|i| scene_file.objects.get(i).map(|o| (i, o)));
+                let grabbed = selected.is_some_and(|(_, object)| {
+                    gizmo
+                        .pick(&object.shape, &camera, screen_w, screen_h, (mouse.x, mouse.y))
+                        .is_some_and(|axis| gizmo.begin(object, axis, &camera, screen_w, screen_h, (mouse.x, mouse.y)))
+                });
+                if !grabbed {
+                    // los bloques importados (después de los objetos del archivo) no se editan
+                    inspector.selected = pick(&scene, &camera, x, y, framebuffer.width, framebuffer.height)
+                        .map(|(index, _)| index)
+                        .filter(|&index| index < scene_file.objects.len());
+                }
+            }
+        }
+        if let Some(index) = inspector.selected.filter(|_| gizmo.is_dragging()) {
+            let snap = window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT);
+            if let Some(object) = gizmo.drag_to((mouse.x, mouse.y), snap)
+                && object != scene_file.objects[index]
+            {
+                // solo cambia la caja de este objeto en el BVH; las cáusticas se rehacen al soltar
+                scene_file.objects[index] = object;
+                edit_scene(&mut scene, &mut progressive).replace_object(index, scene_file.build_object(index));
+                inspector.unsaved = true;
+                camera_moved = true;
+            }
+            if window.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT)
+                && let Some(before) = gizmo.end()
+            {
+                history.record(ObjectEdit { index, before, after: scene_file.objects[index].clone() });
+                lights_changed = true;
+            }
+        }
+        // 1 / 2 / 3: mover, rotar o escalar con los manipuladores
+        for (key, mode) in [
+            (KeyboardKey::KEY_ONE, GizmoMode::Translate),
+            (KeyboardKey::KEY_TWO, GizmoMode::Rotate),
+            (KeyboardKey::KEY_THREE, GizmoMode::Scale),
+        ] {
+            if window.is_key_pressed(key) && !gizmo.is_dragging() {
+                gizmo.mode = mode;
+            }
+        }
+        let ctrl = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL);
+        if ctrl && !gizmo.is_dragging() {
+            let restored = if window.is_key_pressed(KeyboardKey::KEY_Z) {
+                history.undo(&mut scene_file.objects)
+            } else if window.is_key_pressed(KeyboardKey::KEY_Y) {
+                history.redo(&mut scene_file.objects)
+            } else {
+                None
+            };
+            if let Some(index) = restored {
+                edit_scene(&mut scene, &mut progressive).replace_object(index, scene_file.build_object(index));
+                inspector.selected = Some(index);
+                inspector.unsaved = true;
+                camera_moved = true;
+                lights_changed = true;
+            }
+        }
+        // cambios hechos en el panel durante el frame anterior
+        if panel.changed {
+            edit_scene(&mut scene, &mut progressive).set_objects(scene_file.build_objects());
+            camera_moved = true;
+            lights_changed = true;
+        }
+        let ctrl_s = ctrl && window.is_key_pressed(KeyboardKey::KEY_S);
+        if panel.save || ctrl_s {
+            // se reescribe el archivo completo (sin los comentarios del original)
+            match scene_file.save(&options.scene) {
+                Ok(()) => {
+                    watcher.acknowledge(&options.scene);
+                    inspector.unsaved = false;
+                    println!("escena guardada: {}", options.scene);
+                }
+                Err(e) => {
+                    eprintln!("no se pudo guardar {}: {}", options.scene, e);
+                    reload_error = Some(e.to_string());
+                }
+            }
+        }
 
         // detectar entrada y mover cámara
         if window.is_key_down(KeyboardKey::KEY_LEFT) {
@@ -552,21 +449,149 @@ fn main() {
             camera_moved = true;
         }
 
-        // Si la cámara se movió, re-renderiza (pesado).
+        // hora del día: Q/E retroceden/avanzan (2 h por segundo), T activa el ciclo automático
+        if window.is_key_down(KeyboardKey::KEY_Q) {
+            let time = scene.day.time - 2.0 * dt;
+            edit_scene(&mut scene, &mut progressive).set_time_of_day(time);
+            camera_moved = true;
+            lights_changed = true;
+        }
+        if window.is_key_down(KeyboardKey::KEY_E) {
+            let time = scene.day.time + 2.0 * dt;
+            edit_scene(&mut scene, &mut progressive).set_time_of_day(time);
+            camera_moved = true;
+            lights_changed = true;
+        }
+        // T prende o apaga el ciclo del día
+        if window.is_key_pressed(KeyboardKey::KEY_T) {
+            let scene = edit_scene(&mut scene, &mut progressive);
+            scene.day.running = !scene.day.running;
+        }
+        // F activa/desactiva la niebla
+        if window.is_key_pressed(KeyboardKey::KEY_F) {
+            let scene = edit_scene(&mut scene, &mut progressive);
+            scene.fog_enabled = !scene.fog_enabled;
+            camera_moved = true;
+        }
+        // el reloj avanza sobre una copia: la escena (y el refinamiento) solo se tocan si el sol
+        // se movió
+        let mut day = scene.day;
+        if day.advance(dt) {
+            edit_scene(&mut scene, &mut progressive).set_time_of_day(day.time);
+            camera_moved = true;
+            lights_changed = true;
+        }
+        // C activa/desactiva las cáusticas (photon mapping)
+        if window.is_key_pressed(KeyboardKey::KEY_C) {
+            caustics_enabled = !caustics_enabled;
+            lights_changed = true;
+            camera_moved = true;
+        }
+        // V cicla el pase visible (beauty, profundidad, normal, albedo, material, objeto, uv)
+        if window.is_key_pressed(KeyboardKey::KEY_V) {
+            let next = framebuffer.shown_pass().next();
+            framebuffer.show_pass(next);
+            refresh_display(&mut framebuffer, &mut denoiser, &camera);
+        }
+        // N activa/desactiva el denoiser, M compara (izquierda sin filtrar / derecha filtrada)
+        if window.is_key_pressed(KeyboardKey::KEY_N) {
+            denoiser.enabled = !denoiser.enabled;
+            denoiser.reset();
+            framebuffer.show_pass(framebuffer.shown_pass());
+            refresh_display(&mut framebuffer, &mut denoiser, &camera);
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_M) {
+            denoiser.split_compare = !denoiser.split_compare;
+            refresh_display(&mut framebuffer, &mut denoiser, &camera);
+        }
+        // X exporta todos los pases a aov/ (PNG + PFM sin cuantizar para profundidad y normal)
+        if window.is_key_pressed(KeyboardKey::KEY_X) {
+            export_aovs(&framebuffer, "aov");
+        }
+        // O exporta los pases de oclusión ambiental como imágenes aparte: con rayos (ao.png) y en
+        // espacio de pantalla desde los AOVs del último frame (ssao.png)
+        if window.is_key_pressed(KeyboardKey::KEY_O) {
+            let mut ao_buffer = Framebuffer::new(framebuffer.width, framebuffer.height, Rgba8::WHITE);
+            render_ao(&mut ao_buffer, &scene, &camera);
+            ao_buffer.render_to_file("ao.png");
+            println!("ao.png exportado");
+            if let Some(aovs) = &framebuffer.aovs {
+                render_ssao(&mut ao_buffer, aovs, &camera, &scene.ao);
+                ao_buffer.render_to_file("ssao.png");
+                println!("ssao.png exportado");
+            }
+        }
+        // Mientras las luces cambian en cada frame (Q/E, ciclo del día) el mapa de fotones se
+        // arma con pocos fotones; el completo espera al primer frame sin cambios
+        if lights_changed {
+            let scene = edit_scene(&mut scene, &mut progressive);
+            if caustics_enabled {
+                scene.build_preview_photon_map();
+            } else {
+                scene.clear_photon_map();
+            }
+            photons_pending = caustics_enabled;
+            lights_changed = false;
+        } else if photons_pending {
+            edit_scene(&mut scene, &mut progressive).build_photon_map();
+            photons_pending = false;
+        }
+
+        // Si la cámara se movió, la imagen se rehace empezando por una vista previa de baja
+        // resolución (la que esté en curso termina primero); sin input se refina hasta resolución
+        // completa. Los tiles se van copiando a medida que terminan (desde el centro).
         if camera_moved {
-            // render pinta en framebuffer.color_buffer / pixel_data y marca framebuffer.dirty via set_pixel o al final explicitamente
-            render(&mut framebuffer, objects_slice, &camera, &texture_manager);
-            // aseguramos que framebuffer se marque sucio (por si render no llamó a set_pixel internamente)
-            framebuffer.dirty = true;
+            progressive.camera_moved();
             camera_moved = false;
         }
+        if progressive.update(&mut framebuffer, &scene, &camera, &texture_manager) && progressive.is_full_resolution() {
+            refresh_display(&mut framebuffer, &mut denoiser, &camera);
+        }
+        framebuffer.set_progress(progressive.progress());
 
         // dibujar FPS — simple y rápido: lo ponemos como overlay para que swap_buffers lo pinte.
         let fps = window.get_fps();
-        let text = format!("FPS: {}", fps);
-        framebuffer.draw_text(&text, 8, 8, 20, Color::BLACK);
+        let text = format!(
+            "FPS: {}  {}  [{}]{}  {}  subida {:.2} ms ({} px)",
+            fps,
+            format_time_of_day(scene.day.time),
+            framebuffer.shown_pass().name(),
+            if denoiser.enabled { "  denoise" } else { "" },
+            if progressive.is_full_resolution() {
+                format!("{} spp", progressive.samples())
+            } else {
+                format!("1/{} res {:.0} ms", progressive.current_block(), progressive.last_frame_time * 1000.0)
+            },
+            framebuffer.stats.upload_ms,
+            framebuffer.stats.uploaded_pixels,
+        );
+        framebuffer.draw_text(&text, 8, 8, 20, Rgba8::BLACK);
+        if let Some(error) = &reload_error {
+            framebuffer.draw_text(error, 8, 32, 20, Rgba8::new(200, 30, 30, 255));
+        }
+
+        // contorno del objeto seleccionado, proyectado con la cámara actual
+        if let Some(object) = inspector.selected.and_then(|i| scene_file.objects.get(i)) {
+            let (w, h) = (framebuffer.width as f32, framebuffer.height as f32);
+            for (a, b) in object.shape.outline() {
+                if let (Some(a), Some(b)) = (camera.project(&a, w, h), camera.project(&b, w, h)) {
+                    framebuffer.draw_line(a.0 as i32, a.1 as i32, b.0 as i32, b.1 as i32, Rgba8::new(255, 200, 0, 255));
+                }
+            }
+            for handle in gizmo.handles(&object.shape) {
+                let color = if gizmo.active_axis() == Some(handle.axis) { ACTIVE_COLOR } else { AXIS_COLORS[handle.axis] };
+                for (a, b) in handle.segments {
+                    if let (Some(a), Some(b)) = (camera.project(&a, w, h), camera.project(&b, w, h)) {
+                        framebuffer.draw_line(a.0 as i32, a.1 as i32, b.0 as i32, b.1 as i32, color);
+                    }
+                }
+            }
+            framebuffer.draw_text(&format!("[{}]", gizmo.mode.name()), 8, 56, 20, Rgba8::BLACK);
+        }
 
-        // swap_buffers dibuja la textura cacheada (rápido si dirty == false)
-        framebuffer.swap_buffers(&mut window, &raylib_thread);
+        // swap_buffers dibuja la textura cacheada (rápido si dirty == false) y encima el panel
+        framebuffer.swap_buffers_with(&mut window, &raylib_thread, |d| {
+            panel = inspector.draw(d, &mut scene_file);
+        });
     }
 }
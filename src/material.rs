@@ -1,4 +1,9 @@
-use raylib::prelude::*;
+use crate::math::Color;
+use crate::procedural::TextureNode;
+use crate::shader::ShaderGraph;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Material {
@@ -9,6 +14,102 @@ pub struct Material {
     pub refractive_index: f32,
     pub albedo: [f32; 2],
     pub texture_path: Option<String>,
-    pub emissive: Vector3,
-    pub emission: f32
+    // textura procedural (ver src/procedural.rs); si está, da el color base en lugar de diffuse
+    // y texture_path (las imágenes entran al árbol como nodos Image). Arc: el material se copia
+    // en cada impacto
+    pub pattern: Option<Arc<TextureNode>>,
+    // grafo de nodos (ver src/shader.rs); sin él se usa ShaderGraph::legacy
+    pub shader: Option<Arc<ShaderGraph>>,
+    pub emissive: Color,
+    pub emission: f32,
+    pub subsurface: Option<Subsurface>,
+}
+
+// Material mate blanco, sin textura ni emisión (lo que asume un archivo de escena que no dice nada)
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            diffuse: Color::WHITE,
+            specular: 16.0,
+            reflectivity: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            albedo: [0.9, 0.1],
+            texture_path: None,
+            pattern: None,
+            shader: None,
+            emissive: Color::BLACK,
+            emission: 0.0,
+            subsurface: None,
+        }
+    }
+}
+
+impl Material {
+    /// Grafo con el que se sombrea: el propio o el que reproduce los campos de siempre
+    pub fn shader(&self) -> &ShaderGraph {
+        self.shader.as_deref().unwrap_or_else(|| ShaderGraph::legacy())
+    }
+
+    /// Identificador estable del material (mismo material => mismo id), usado por el pase de
+    /// AOV de material: se calcula a partir de sus propiedades, sin registrar nada, así que no
+    /// cambia entre corridas ni al recargar la escena. El patrón y el grafo cuentan por su
+    /// contenido (recorrerlos no reserva memoria).
+    pub fn id(&self) -> u32 {
+        let mut hasher = DefaultHasher::new();
+        let subsurface = self.subsurface.map_or([0.0; 5], |s| [s.color.r, s.color.g, s.color.b, s.mean_free_path, s.weight]);
+        for v in [
+            self.diffuse.r,
+            self.diffuse.g,
+            self.diffuse.b,
+            self.specular,
+            self.reflectivity,
+            self.transparency,
+            self.refractive_index,
+            self.albedo[0],
+            self.albedo[1],
+            self.emissive.r,
+            self.emissive.g,
+            self.emissive.b,
+            self.emission,
+        ]
+        .into_iter()
+        .chain(subsurface)
+        {
+            v.to_bits().hash(&mut hasher);
+        }
+        self.subsurface.is_some().hash(&mut hasher);
+        self.texture_path.hash(&mut hasher);
+        self.pattern.hash(&mut hasher);
+        self.shader.hash(&mut hasher);
+        (hasher.finish() & 0x7fff_ffff) as u32
+    }
+}
+
+// Translucidez (subsurface scattering aproximado con random walk dentro del volumen)
+#[derive(Clone, Copy)]
+pub struct Subsurface {
+    pub color: Color,      // albedo de dispersión por evento (qué tanto de cada canal sobrevive)
+    pub mean_free_path: f32,  // distancia media entre eventos de dispersión
+    pub weight: f32,          // cuánto del término difuso se reemplaza por la luz que viaja por dentro
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_covers_emission_and_subsurface() {
+        let base = Material::default();
+        assert_eq!(base.id(), Material::default().id());
+        let glowing = Material { emissive: Color::new(1.0, 0.5, 0.0), ..Material::default() };
+        assert_ne!(base.id(), glowing.id());
+        let waxy = Material { subsurface: Some(Subsurface { color: Color::WHITE, mean_free_path: 0.2, weight: 0.5 }), ..Material::default() };
+        assert_ne!(base.id(), waxy.id());
+        // el grafo cuenta por su contenido, no por dónde quedó en memoria
+        let graph = Material { shader: Some(Arc::new(ShaderGraph::default())), ..Material::default() };
+        let same = Material { shader: Some(Arc::new(ShaderGraph::default())), ..Material::default() };
+        assert_eq!(graph.id(), same.id());
+        assert_ne!(graph.id(), base.id());
+    }
 }
@@ -0,0 +1,658 @@
+// Tipos matemáticos del renderer: vectores y puntos (Vec2, Vec3, Point3), rayos (Ray), color
+// lineal (Color, f32 por canal), color de pantalla (Rgba8) y transformaciones (Mat4).
+// El núcleo no usa tipos de raylib; las conversiones viven al final de este archivo y solo se
+// compilan con la feature "raylib" (front-end con ventana).
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Vec2 { x, y }
+    }
+
+    pub const fn zero() -> Self {
+        Vec2 { x: 0.0, y: 0.0 }
+    }
+
+    pub fn dot(&self, v: Vec2) -> f32 {
+        self.x * v.x + self.y * v.y
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+// Un punto usa la misma representación que un vector; el alias documenta la intención
+pub type Point3 = Vec3;
+
+impl Vec3 {
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Vec3 { x, y, z }
+    }
+
+    pub const fn zero() -> Self {
+        Vec3 { x: 0.0, y: 0.0, z: 0.0 }
+    }
+
+    pub const fn one() -> Self {
+        Vec3 { x: 1.0, y: 1.0, z: 1.0 }
+    }
+
+    pub fn dot(&self, v: Vec3) -> f32 {
+        self.x * v.x + self.y * v.y + self.z * v.z
+    }
+
+    pub fn cross(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * v.z - self.z * v.y,
+            self.z * v.x - self.x * v.z,
+            self.x * v.y - self.y * v.x,
+        )
+    }
+
+    pub fn length_sqr(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_sqr().sqrt()
+    }
+
+    /// Vector unitario en la misma dirección (el vector nulo queda igual)
+    pub fn normalized(&self) -> Vec3 {
+        let length = self.length();
+        if length == 0.0 { *self } else { *self / length }
+    }
+
+    pub fn normalize(&mut self) {
+        *self = self.normalized();
+    }
+
+    /// Mínimo componente a componente
+    pub fn min(&self, v: Vec3) -> Vec3 {
+        Vec3::new(self.x.min(v.x), self.y.min(v.y), self.z.min(v.z))
+    }
+
+    /// Máximo componente a componente
+    pub fn max(&self, v: Vec3) -> Vec3 {
+        Vec3::new(self.x.max(v.x), self.y.max(v.y), self.z.max(v.z))
+    }
+
+    /// Interpolación lineal: self en t = 0, v en t = 1
+    pub fn lerp(&self, v: Vec3, t: f32) -> Vec3 {
+        *self + (v - *self) * t
+    }
+}
+
+// Rayo origin + t * direction (la dirección no se normaliza sola)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: Point3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub const fn new(origin: Point3, direction: Vec3) -> Self {
+        Ray { origin, direction }
+    }
+
+    /// Punto a distancia paramétrica t
+    pub fn at(&self, t: f32) -> Point3 {
+        self.origin + self.direction * t
+    }
+}
+
+// Color RGB lineal en f32. Puede pasar de 1 (luz emitida, acumulación); se recorta solo al
+// convertirlo a Rgba8 para mostrarlo.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Color { r, g, b }
+    }
+
+    /// Gris de intensidad v en los tres canales
+    pub const fn splat(v: f32) -> Self {
+        Color { r: v, g: v, b: v }
+    }
+
+    /// Color de 8 bits (0..255) a lineal (0..1), sin curva gamma: así se guardaban los colores
+    pub fn from_rgba8(c: Rgba8) -> Self {
+        Color::new(c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0)
+    }
+
+    /// Recorta a [0, 1] y cuantiza a 8 bits (alfa opaco)
+    pub fn to_rgba8(&self) -> Rgba8 {
+        Rgba8::new(
+            (self.r.clamp(0.0, 1.0) * 255.0) as u8,
+            (self.g.clamp(0.0, 1.0) * 255.0) as u8,
+            (self.b.clamp(0.0, 1.0) * 255.0) as u8,
+            255,
+        )
+    }
+
+    /// Luminancia relativa (Rec. 709)
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn max_component(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    /// Máximo canal a canal
+    pub fn max(&self, c: Color) -> Color {
+        Color::new(self.r.max(c.r), self.g.max(c.g), self.b.max(c.b))
+    }
+
+    /// Interpolación lineal: self en t = 0, c en t = 1
+    pub fn lerp(&self, c: Color, t: f32) -> Color {
+        *self + (c - *self) * t
+    }
+}
+
+// operadores componente a componente, con el mismo tipo y con escalares
+macro_rules! componentwise_ops {
+    ($t:ident { $($f:ident),+ }) => {
+        impl Add for $t { type Output = $t; fn add(self, v: $t) -> $t { $t { $($f: self.$f + v.$f),+ } } }
+        impl Sub for $t { type Output = $t; fn sub(self, v: $t) -> $t { $t { $($f: self.$f - v.$f),+ } } }
+        impl Mul for $t { type Output = $t; fn mul(self, v: $t) -> $t { $t { $($f: self.$f * v.$f),+ } } }
+        impl Div for $t { type Output = $t; fn div(self, v: $t) -> $t { $t { $($f: self.$f / v.$f),+ } } }
+        impl Add<f32> for $t { type Output = $t; fn add(self, s: f32) -> $t { $t { $($f: self.$f + s),+ } } }
+        impl Sub<f32> for $t { type Output = $t; fn sub(self, s: f32) -> $t { $t { $($f: self.$f - s),+ } } }
+        impl Mul<f32> for $t { type Output = $t; fn mul(self, s: f32) -> $t { $t { $($f: self.$f * s),+ } } }
+        impl Div<f32> for $t { type Output = $t; fn div(self, s: f32) -> $t { $t { $($f: self.$f / s),+ } } }
+        impl Mul<$t> for f32 { type Output = $t; fn mul(self, v: $t) -> $t { v * self } }
+        impl Neg for $t { type Output = $t; fn neg(self) -> $t { $t { $($f: -self.$f),+ } } }
+        impl AddAssign for $t { fn add_assign(&mut self, v: $t) { *self = *self + v; } }
+        impl SubAssign for $t { fn sub_assign(&mut self, v: $t) { *self = *self - v; } }
+        impl MulAssign for $t { fn mul_assign(&mut self, v: $t) { *self = *self * v; } }
+        impl DivAssign for $t { fn div_assign(&mut self, v: $t) { *self = *self / v; } }
+        impl AddAssign<f32> for $t { fn add_assign(&mut self, s: f32) { *self = *self + s; } }
+        impl SubAssign<f32> for $t { fn sub_assign(&mut self, s: f32) { *self = *self - s; } }
+        impl MulAssign<f32> for $t { fn mul_assign(&mut self, s: f32) { *self = *self * s; } }
+        impl DivAssign<f32> for $t { fn div_assign(&mut self, s: f32) { *self = *self / s; } }
+    };
+}
+
+componentwise_ops!(Vec2 { x, y });
+componentwise_ops!(Vec3 { x, y, z });
+componentwise_ops!(Color { r, g, b });
+
+// hash por los bits de cada componente: sirve para identificar por contenido (p. ej. el id de
+// material), no para agrupar valores "casi iguales"
+macro_rules! bitwise_hash {
+    ($t:ident { $($f:ident),* }) => {
+        impl Hash for $t {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                $(self.$f.to_bits().hash(state);)*
+            }
+        }
+    };
+}
+
+bitwise_hash!(Vec2 { x, y });
+bitwise_hash!(Vec3 { x, y, z });
+bitwise_hash!(Color { r, g, b });
+
+// Color de pantalla: RGBA de 8 bits por canal (mismo layout que el Color de raylib)
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Rgba8 {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba8 {
+    pub const BLACK: Rgba8 = Rgba8::new(0, 0, 0, 255);
+    pub const WHITE: Rgba8 = Rgba8::new(255, 255, 255, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Rgba8 { r, g, b, a }
+    }
+}
+
+// Matriz 4×4 en filas (m[fila][columna]) que transforma vectores columna: p' = M * p
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const fn identity() -> Self {
+        Mat4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn translation(t: Vec3) -> Self {
+        let mut r = Mat4::identity();
+        r.m[0][3] = t.x;
+        r.m[1][3] = t.y;
+        r.m[2][3] = t.z;
+        r
+    }
+
+    pub fn scale(s: Vec3) -> Self {
+        let mut r = Mat4::identity();
+        r.m[0][0] = s.x;
+        r.m[1][1] = s.y;
+        r.m[2][2] = s.z;
+        r
+    }
+
+    /// Rotación de `angle` radianes alrededor de X (regla de la mano derecha)
+    pub fn rotation_x(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        let mut r = Mat4::identity();
+        r.m[1][1] = c;
+        r.m[1][2] = -s;
+        r.m[2][1] = s;
+        r.m[2][2] = c;
+        r
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        let mut r = Mat4::identity();
+        r.m[0][0] = c;
+        r.m[0][2] = s;
+        r.m[2][0] = -s;
+        r.m[2][2] = c;
+        r
+    }
+
+    pub fn rotation_z(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        let mut r = Mat4::identity();
+        r.m[0][0] = c;
+        r.m[0][1] = -s;
+        r.m[1][0] = s;
+        r.m[1][1] = c;
+        r
+    }
+
+    /// Matriz cuyas columnas son los ejes dados (cambio de base de local a mundo)
+    pub fn from_axes(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Mat4 {
+            m: [
+                [x.x, y.x, z.x, 0.0],
+                [x.y, y.y, z.y, 0.0],
+                [x.z, y.z, z.z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut r = Mat4::identity();
+        for (i, row) in r.m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        r
+    }
+
+    /// Transforma un punto (w = 1, incluye la traslación)
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w != 0.0 && w != 1.0 { Vec3::new(x / w, y / w, z / w) } else { Vec3::new(x, y, z) }
+    }
+
+    /// Transforma una dirección (w = 0, sin traslación)
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    /// Inversa por eliminación de Gauss-Jordan con pivoteo parcial; None si es singular
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-8 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let p = a[col][col];
+            for k in 0..4 {
+                a[col][k] /= p;
+                inv[col][k] /= p;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let f = a[row][col];
+                    for k in 0..4 {
+                        a[row][k] -= f * a[col][k];
+                        inv[row][k] -= f * inv[col][k];
+                    }
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::identity()
+    }
+}
+
+impl Index<(usize, usize)> for Mat4 {
+    type Output = f32;
+    fn index(&self, (row, col): (usize, usize)) -> &f32 {
+        &self.m[row][col]
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+    fn mul(self, o: Mat4) -> Mat4 {
+        let mut r = Mat4 { m: [[0.0; 4]; 4] };
+        for i in 0..4 {
+            for j in 0..4 {
+                r.m[i][j] = (0..4).map(|k| self.m[i][k] * o.m[k][j]).sum();
+            }
+        }
+        r
+    }
+}
+
+// Conversiones hacia/desde raylib, solo en el borde con la ventana
+#[cfg(feature = "raylib")]
+mod raylib_conversions {
+    use super::{Color, Rgba8, Vec2, Vec3};
+
+    impl From<Vec2> for raylib::math::Vector2 {
+        fn from(v: Vec2) -> Self {
+            raylib::math::Vector2::new(v.x, v.y)
+        }
+    }
+
+    impl From<raylib::math::Vector2> for Vec2 {
+        fn from(v: raylib::math::Vector2) -> Self {
+            Vec2::new(v.x, v.y)
+        }
+    }
+
+    impl From<Vec3> for raylib::math::Vector3 {
+        fn from(v: Vec3) -> Self {
+            raylib::math::Vector3::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl From<raylib::math::Vector3> for Vec3 {
+        fn from(v: raylib::math::Vector3) -> Self {
+            Vec3::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl From<Rgba8> for raylib::color::Color {
+        fn from(c: Rgba8) -> Self {
+            raylib::color::Color::new(c.r, c.g, c.b, c.a)
+        }
+    }
+
+    impl From<raylib::color::Color> for Rgba8 {
+        fn from(c: raylib::color::Color) -> Self {
+            Rgba8::new(c.r, c.g, c.b, c.a)
+        }
+    }
+
+    impl From<Color> for raylib::color::Color {
+        fn from(c: Color) -> Self {
+            c.to_rgba8().into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    const EPS: f32 = 1e-5;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < EPS
+    }
+
+    fn close_vec(a: Vec3, b: Vec3) -> bool {
+        close(a.x, b.x) && close(a.y, b.y) && close(a.z, b.z)
+    }
+
+    fn close_mat(a: &Mat4, b: &Mat4) -> bool {
+        (0..4).all(|i| (0..4).all(|j| close(a[(i, j)], b[(i, j)])))
+    }
+
+    #[test]
+    fn vec2_dot_and_length() {
+        let v = Vec2::new(3.0, 4.0);
+        assert_eq!(v.dot(Vec2::new(1.0, 2.0)), 11.0);
+        assert_eq!(v.length(), 5.0);
+        assert_eq!(Vec2::zero(), Vec2::new(0.0, 0.0));
+        assert_eq!(v + Vec2::new(1.0, 1.0), Vec2::new(4.0, 5.0));
+        assert_eq!(v * 2.0, Vec2::new(6.0, 8.0));
+    }
+
+    #[test]
+    fn vec3_constructors() {
+        assert_eq!(Vec3::zero(), Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(Vec3::one(), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(Vec3::default(), Vec3::zero());
+    }
+
+    #[test]
+    fn vec3_arithmetic() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, 5.0, 6.0);
+        assert_eq!(a + b, Vec3::new(5.0, 7.0, 9.0));
+        assert_eq!(b - a, Vec3::new(3.0, 3.0, 3.0));
+        assert_eq!(a * b, Vec3::new(4.0, 10.0, 18.0));
+        assert_eq!(b / a, Vec3::new(4.0, 2.5, 2.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(2.0 * a, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(a / 2.0, Vec3::new(0.5, 1.0, 1.5));
+        assert_eq!(a + 1.0, Vec3::new(2.0, 3.0, 4.0));
+        assert_eq!(a - 1.0, Vec3::new(0.0, 1.0, 2.0));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+    }
+
+    #[test]
+    fn vec3_assign_operators() {
+        let mut v = Vec3::new(1.0, 2.0, 3.0);
+        v += Vec3::one();
+        assert_eq!(v, Vec3::new(2.0, 3.0, 4.0));
+        v -= Vec3::one();
+        assert_eq!(v, Vec3::new(1.0, 2.0, 3.0));
+        v *= 2.0;
+        assert_eq!(v, Vec3::new(2.0, 4.0, 6.0));
+        v /= 2.0;
+        assert_eq!(v, Vec3::new(1.0, 2.0, 3.0));
+        v *= Vec3::new(2.0, 1.0, 0.5);
+        assert_eq!(v, Vec3::new(2.0, 2.0, 1.5));
+        v /= Vec3::new(2.0, 2.0, 1.5);
+        assert_eq!(v, Vec3::one());
+        v += 1.0;
+        v -= 0.5;
+        assert_eq!(v, Vec3::new(1.5, 1.5, 1.5));
+    }
+
+    #[test]
+    fn vec3_dot_cross() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(x.dot(y), 0.0);
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0).dot(Vec3::new(4.0, 5.0, 6.0)), 32.0);
+        assert_eq!(x.cross(y), z);
+        assert_eq!(y.cross(z), x);
+        assert_eq!(y.cross(x), -z);
+    }
+
+    #[test]
+    fn vec3_length_and_normalize() {
+        let v = Vec3::new(2.0, 3.0, 6.0);
+        assert_eq!(v.length_sqr(), 49.0);
+        assert_eq!(v.length(), 7.0);
+        assert!(close(v.normalized().length(), 1.0));
+        assert!(close_vec(v.normalized(), Vec3::new(2.0 / 7.0, 3.0 / 7.0, 6.0 / 7.0)));
+        assert_eq!(Vec3::zero().normalized(), Vec3::zero());
+        let mut w = v;
+        w.normalize();
+        assert_eq!(w, v.normalized());
+    }
+
+    #[test]
+    fn vec3_min_max_lerp() {
+        let a = Vec3::new(1.0, 5.0, -2.0);
+        let b = Vec3::new(3.0, 0.0, -4.0);
+        assert_eq!(a.min(b), Vec3::new(1.0, 0.0, -4.0));
+        assert_eq!(a.max(b), Vec3::new(3.0, 5.0, -2.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Vec3::new(2.0, 2.5, -3.0));
+    }
+
+    #[test]
+    fn ray_at() {
+        let ray = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(ray.at(0.0), ray.origin);
+        assert_eq!(ray.at(1.5), Vec3::new(1.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn color_arithmetic() {
+        let a = Color::new(0.2, 0.4, 0.6);
+        let b = Color::new(0.5, 0.5, 0.5);
+        assert!(close_vec(
+            {
+                let c = a + b;
+                Vec3::new(c.r, c.g, c.b)
+            },
+            Vec3::new(0.7, 0.9, 1.1)
+        ));
+        assert_eq!(a * b, Color::new(0.1, 0.2, 0.3));
+        assert_eq!(a * 2.0, Color::new(0.4, 0.8, 1.2));
+        assert_eq!(a / 2.0, Color::new(0.1, 0.2, 0.3));
+        assert_eq!(a / b, Color::new(0.4, 0.8, 1.2));
+        let mut c = Color::BLACK;
+        c += a;
+        c *= 2.0;
+        assert_eq!(c, Color::new(0.4, 0.8, 1.2));
+        c *= Color::splat(0.5);
+        assert_eq!(c, a);
+    }
+
+    #[test]
+    fn color_helpers() {
+        assert_eq!(Color::splat(0.25), Color::new(0.25, 0.25, 0.25));
+        assert!(close(Color::WHITE.luminance(), 1.0));
+        assert_eq!(Color::new(0.1, 0.7, 0.3).max_component(), 0.7);
+        assert_eq!(Color::new(0.1, 0.7, 0.3).max(Color::splat(0.5)), Color::new(0.5, 0.7, 0.5));
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 0.5), Color::splat(0.5));
+    }
+
+    #[test]
+    fn color_rgba8_round_trip() {
+        assert_eq!(Color::WHITE.to_rgba8(), Rgba8::WHITE);
+        assert_eq!(Color::BLACK.to_rgba8(), Rgba8::BLACK);
+        // fuera de rango se recorta
+        assert_eq!(Color::new(2.0, -1.0, 0.5).to_rgba8(), Rgba8::new(255, 0, 127, 255));
+        let c = Rgba8::new(10, 128, 255, 255);
+        assert_eq!(Color::from_rgba8(c).to_rgba8(), c);
+    }
+
+    #[test]
+    fn mat4_identity_and_multiply() {
+        let t = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(Mat4::identity() * t, t);
+        assert_eq!(t * Mat4::identity(), t);
+        assert_eq!(Mat4::default(), Mat4::identity());
+        let s = Mat4::scale(Vec3::new(2.0, 2.0, 2.0));
+        // primero escala, después traslada
+        let p = (t * s).transform_point(Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(p, Vec3::new(3.0, 4.0, 5.0));
+    }
+
+    #[test]
+    fn mat4_point_vs_vector() {
+        let t = Mat4::translation(Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(t.transform_point(Vec3::zero()), Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(t.transform_vector(Vec3::one()), Vec3::one());
+        let s = Mat4::scale(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(s.transform_vector(Vec3::one()), Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn mat4_rotations() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        assert!(close_vec(Mat4::rotation_x(FRAC_PI_2).transform_vector(y), z));
+        assert!(close_vec(Mat4::rotation_y(FRAC_PI_2).transform_vector(z), x));
+        assert!(close_vec(Mat4::rotation_z(FRAC_PI_2).transform_vector(x), y));
+    }
+
+    #[test]
+    fn mat4_from_axes_transpose() {
+        let m = Mat4::from_axes(Vec3::new(0.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(m.transform_vector(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(m.transpose().transpose(), m);
+        // para una rotación la transpuesta es la inversa
+        assert!(close_mat(&(m * m.transpose()), &Mat4::identity()));
+        assert_eq!(m.transpose()[(0, 1)], m[(1, 0)]);
+    }
+
+    #[test]
+    fn mat4_inverse() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0)) * Mat4::rotation_y(0.7) * Mat4::scale(Vec3::new(2.0, 3.0, 4.0));
+        let inv = m.inverse().expect("invertible");
+        assert!(close_mat(&(m * inv), &Mat4::identity()));
+        assert!(close_mat(&(inv * m), &Mat4::identity()));
+        let p = Vec3::new(0.3, 0.5, -1.0);
+        assert!(close_vec(inv.transform_point(m.transform_point(p)), p));
+        assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+}
@@ -0,0 +1,312 @@
+// Fusión de bloques: junta cubos de 1×1×1 vecinos con el mismo material opaco en cajas más grandes
+// (greedy meshing por volúmenes) y descarta las cajas que quedan tapadas por todos lados. Una pared
+// de 16×16 bloques pasa a ser un solo Cube en lugar de 256.
+//
+// Las cajas se construyen con Cube::tiled, que repite la textura una vez por unidad en cada cara,
+// así que se ven igual que los bloques sueltos. Los bloques transparentes (vidrio, agua) no se
+// fusionan: las caras internas entre dos de ellos sí se ven.
+
+use crate::math::{Vec2, Vec3};
+use crate::scene_file::{ObjectDesc, Shape};
+use std::collections::HashMap;
+
+const EPS: f32 = 1e-4;
+
+// celdas como máximo en una grilla densa (la de merge_blocks o una VoxelGrid): 64 Mi, unos
+// 512 MB de celdas de 8 bytes
+pub const MAX_GRID_CELLS: usize = 64 * 1024 * 1024;
+
+/// Cantidad de celdas de una grilla de `size`, o None si algún lado no es positivo o la grilla
+/// pasa de MAX_GRID_CELLS (o el producto no entra en usize)
+pub fn grid_cells(size: [i32; 3]) -> Option<usize> {
+    if size.iter().any(|&s| s <= 0) {
+        return None;
+    }
+    let cells = (size[0] as usize).checked_mul(size[1] as usize)?.checked_mul(size[2] as usize)?;
+    (cells <= MAX_GRID_CELLS).then_some(cells)
+}
+
+// esquina mínima del cubo si es un bloque de 1×1×1 sin rotar
+fn unit_block(object: &ObjectDesc) -> Option<Vec3> {
+    match object.shape {
+        Shape::Cube { center, half_size, rotation }
+            if rotation == Vec2::zero() && [half_size.x, half_size.y, half_size.z].iter().all(|h| (h - 0.5).abs() < EPS) =>
+        {
+            Some(center - Vec3::new(0.5, 0.5, 0.5))
+        }
+        _ => None,
+    }
+}
+
+// celda de la grilla si `corner` está a una cantidad entera de bloques de `origin`
+fn cell(corner: Vec3, origin: Vec3) -> Option<[i32; 3]> {
+    let d = corner - origin;
+    let rounded = [d.x.round(), d.y.round(), d.z.round()];
+    let aligned = (d.x - rounded[0]).abs() < EPS && (d.y - rounded[1]).abs() < EPS && (d.z - rounded[2]).abs() < EPS;
+    aligned.then(|| rounded.map(|v| v as i32))
+}
+
+/// Fusiona los bloques de 1×1×1 (sin rotar y alineados entre sí) cuyo material es opaco según
+/// `opaque`. El resto de los objetos se devuelve primero, sin cambios y en el mismo orden; después
+/// van las cajas fusionadas, que hay que construir con Cube::tiled. Si los bloques están tan
+/// dispersos que la grilla que los cubre pasa de MAX_GRID_CELLS, se devuelven sin fusionar.
+pub fn merge_blocks(objects: Vec<ObjectDesc>, opaque: impl Fn(&str) -> bool) -> Vec<ObjectDesc> {
+    let mut result = Vec::new();
+    // los bloques tal cual, por si no se pueden fusionar
+    let mut blocks = Vec::new();
+    let mut origin = None;
+    let mut materials: Vec<String> = Vec::new();
+    let mut cells: HashMap<[i32; 3], usize> = HashMap::new();
+    for object in objects {
+        let block = unit_block(&object).filter(|_| opaque(&object.material));
+        let Some(key) = block.and_then(|corner| cell(corner, *origin.get_or_insert(corner))) else {
+            result.push(object);
+            continue;
+        };
+        let material = match materials.iter().position(|m| *m == object.material) {
+            Some(i) => i,
+            None => {
+                materials.push(object.material.clone());
+                materials.len() - 1
+            }
+        };
+        // si hay dos bloques en la misma celda queda el último, como al dibujarlos
+        cells.insert(key, material);
+        blocks.push(object);
+    }
+    let Some(origin) = origin else { return result };
+    if cells.is_empty() {
+        return result;
+    }
+
+    // grilla densa sobre la caja que ocupan los bloques
+    let mut min = [i32::MAX; 3];
+    let mut max = [i32::MIN; 3];
+    for key in cells.keys() {
+        for a in 0..3 {
+            min[a] = min[a].min(key[a]);
+            max[a] = max[a].max(key[a]);
+        }
+    }
+    let size = [0, 1, 2].map(|a| max[a].checked_sub(min[a]).and_then(|s| s.checked_add(1)).unwrap_or(0));
+    let Some(cell_count) = grid_cells(size) else {
+        result.extend(blocks);
+        return result;
+    };
+    let index = |x: i32, y: i32, z: i32| ((y * size[2] + z) * size[0] + x) as usize;
+    let mut grid: Vec<Option<usize>> = vec![None; cell_count];
+    for (key, &material) in &cells {
+        grid[index(key[0] - min[0], key[1] - min[1], key[2] - min[2])] = Some(material);
+    }
+    let occupied = |x: i32, y: i32, z: i32| {
+        (0..size[0]).contains(&x) && (0..size[1]).contains(&y) && (0..size[2]).contains(&z) && grid[index(x, y, z)].is_some()
+    };
+
+    let mut used = vec![false; grid.len()];
+    for y in 0..size[1] {
+        for z in 0..size[2] {
+            for x in 0..size[0] {
+                let Some(material) = grid[index(x, y, z)].filter(|_| !used[index(x, y, z)]) else { continue };
+                let free = |used: &[bool], x: i32, y: i32, z: i32| {
+                    (0..size[0]).contains(&x)
+                        && (0..size[1]).contains(&y)
+                        && (0..size[2]).contains(&z)
+                        && grid[index(x, y, z)] == Some(material)
+                        && !used[index(x, y, z)]
+                };
+                // crecer en x, después filas enteras en z y después capas enteras en y
+                let mut w = 1;
+                while free(&used, x + w, y, z) {
+                    w += 1;
+                }
+                let mut d = 1;
+                while (x..x + w).all(|xi| free(&used, xi, y, z + d)) {
+                    d += 1;
+                }
+                let mut h = 1;
+                while (z..z + d).all(|zi| (x..x + w).all(|xi| free(&used, xi, y + h, zi))) {
+                    h += 1;
+                }
+                for yi in y..y + h {
+                    for zi in z..z + d {
+                        for xi in x..x + w {
+                            used[index(xi, yi, zi)] = true;
+                        }
+                    }
+                }
+
+                // tapada si todas las celdas que la rodean tienen un bloque opaco
+                let covered = (y..y + h).all(|yi| (z..z + d).all(|zi| occupied(x - 1, yi, zi) && occupied(x + w, yi, zi)))
+                    && (y..y + h).all(|yi| (x..x + w).all(|xi| occupied(xi, yi, z - 1) && occupied(xi, yi, z + d)))
+                    && (z..z + d).all(|zi| (x..x + w).all(|xi| occupied(xi, y - 1, zi) && occupied(xi, y + h, zi)));
+                if covered {
+                    continue;
+                }
+
+                let corner = origin + Vec3::new((min[0] + x) as f32, (min[1] + y) as f32, (min[2] + z) as f32);
+                let half_size = Vec3::new(w as f32, h as f32, d as f32) * 0.5;
+                result.push(ObjectDesc {
+                    name: None,
+                    shape: Shape::Cube { center: corner + half_size, half_size, rotation: Vec2::zero() },
+                    material: materials[material].clone(),
+                });
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_model::ModelLibrary;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::ray_intersect::{HitInfo, RayIntersect};
+    use crate::renderer::map_uv_for_cube;
+    use crate::sampling::{uniform_sphere, Rng};
+
+    fn block(x: i32, y: i32, z: i32, material: &str) -> ObjectDesc {
+        ObjectDesc {
+            name: None,
+            shape: Shape::Cube {
+                center: Vec3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 - 0.5),
+                half_size: Vec3::new(0.5, 0.5, 0.5),
+                rotation: Vec2::zero(),
+            },
+            material: material.to_string(),
+        }
+    }
+
+    fn sizes(objects: &[ObjectDesc]) -> Vec<Vec3> {
+        objects.iter().map(|o| if let Shape::Cube { half_size, .. } = o.shape { half_size * 2.0 } else { Vec3::zero() }).collect()
+    }
+
+    #[test]
+    fn merges_wall_and_keeps_other_objects() {
+        let mut objects = vec![ObjectDesc {
+            name: Some("esfera".to_string()),
+            shape: Shape::Sphere { center: Vec3::zero(), radius: 1.0 },
+            material: "vidrio".to_string(),
+        }];
+        for y in 0..3 {
+            for x in 0..4 {
+                objects.push(block(x, y, 0, "piedra"));
+            }
+        }
+        objects.push(block(4, 0, 0, "ladrillo"));
+        objects.push(block(5, 0, 0, "vidrio"));
+        objects.push(block(6, 0, 0, "vidrio"));
+        let merged = merge_blocks(objects, |m| m != "vidrio");
+        assert_eq!(merged[0].name.as_deref(), Some("esfera"));
+        assert_eq!(merged.iter().filter(|o| o.material == "vidrio").count(), 3);
+        let stone: Vec<_> = merged.iter().filter(|o| o.material == "piedra").cloned().collect();
+        assert_eq!(sizes(&stone), vec![Vec3::new(4.0, 3.0, 1.0)]);
+        assert_eq!(stone[0].shape.center(), Vec3::new(2.0, 1.5, -0.5));
+        assert_eq!(merged.len(), 5);
+    }
+
+    #[test]
+    fn drops_boxes_hidden_on_every_side() {
+        // 3×3×3 de piedra con el centro de otro material: el del centro no se ve
+        let mut objects = Vec::new();
+        for y in 0..3 {
+            for z in 0..3 {
+                for x in 0..3 {
+                    objects.push(block(x, y, z, if (x, y, z) == (1, 1, 1) { "tierra" } else { "piedra" }));
+                }
+            }
+        }
+        let merged = merge_blocks(objects, |_| true);
+        assert!(merged.iter().all(|o| o.material == "piedra"));
+        let volume: f32 = sizes(&merged).iter().map(|s| s.x * s.y * s.z).sum();
+        assert_eq!(volume, 26.0);
+        // un bloque que no está alineado con la grilla de los demás queda suelto
+        let mut objects = vec![block(0, 0, 0, "piedra"), block(1, 0, 0, "piedra")];
+        if let Shape::Cube { center, .. } = &mut objects[1].shape {
+            center.x += 0.25;
+        }
+        assert_eq!(merge_blocks(objects, |_| true).len(), 2);
+    }
+
+    // impacto más cercano contra una lista de objetos
+    fn closest(objects: &[Box<dyn RayIntersect + Send + Sync>], origin: &Vec3, dir: &Vec3) -> Option<HitInfo> {
+        objects
+            .iter()
+            .filter_map(|o| o.ray_intersect(origin, dir))
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    // (u, v) de la textura tal como la muestrea el renderer, sin la parte entera
+    fn texel(hit: &HitInfo) -> (f32, f32) {
+        let (u, v) = map_uv_for_cube(&hit.local_point, &hit.local_normal, &hit.local_half_size).unwrap();
+        ((u * hit.texture_repeat.x).rem_euclid(1.0), (v * hit.texture_repeat.y).rem_euclid(1.0))
+    }
+
+    #[test]
+    fn merged_boxes_look_like_the_blocks() {
+        // escalón en L de 5×3×4 con un hueco: los rayos ven lo mismo antes y después
+        let mut objects = Vec::new();
+        for y in 0..3 {
+            for z in 0..4 {
+                for x in 0..5 {
+                    if (y > 0 && z > 1) || (x, y, z) == (2, 1, 0) {
+                        continue;
+                    }
+                    objects.push(block(x, y, z, if x == 4 { "ladrillo" } else { "piedra" }));
+                }
+            }
+        }
+        let build = |objects: &[ObjectDesc], tiled: bool| -> Vec<Box<dyn RayIntersect + Send + Sync>> {
+            objects
+                .iter()
+                .map(|o| match o.shape {
+                    Shape::Cube { center, half_size, .. } if tiled => {
+                        Box::new(Cube::tiled(center, half_size, Material::default())) as Box<dyn RayIntersect + Send + Sync>
+                    }
+                    _ => o.build(Material::default(), &ModelLibrary::default()),
+                })
+                .collect()
+        };
+        let blocks = build(&objects, false);
+        let merged_desc = merge_blocks(objects.clone(), |_| true);
+        assert!(merged_desc.len() < objects.len() / 4);
+        let merged = build(&merged_desc, true);
+
+        let mut rng = Rng::new(7);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let target = Vec3::new(rng.next_f32() * 5.0, rng.next_f32() * 3.0, -rng.next_f32() * 4.0);
+            // desde afuera de los bloques: con el origen adentro la salida de una caja grande es otra
+            let origin = Vec3::new(2.5, 1.5, -2.0) + uniform_sphere(&mut rng) * 12.0;
+            let dir = (target - origin).normalized();
+            match (closest(&blocks, &origin, &dir), closest(&merged, &origin, &dir)) {
+                (None, None) => {}
+                (Some(a), Some(b)) => {
+                    assert!((a.distance - b.distance).abs() < 1e-3, "{} vs {}", a.distance, b.distance);
+                    // en las aristas entre bloques la normal puede ser la de cualquiera de las dos caras
+                    if (a.normal - b.normal).length() > 1e-3 {
+                        continue;
+                    }
+                    let ((ua, va), (ub, vb)) = (texel(&a), texel(&b));
+                    let wrap = |d: f32| d.abs().min(1.0 - d.abs());
+                    assert!(wrap(ua - ub) < 1e-3 && wrap(va - vb) < 1e-3, "uv ({ua}, {va}) vs ({ub}, {vb})");
+                    hits += 1;
+                }
+                (a, b) => panic!("impacto distinto: {:?} vs {:?}", a.map(|h| h.distance), b.map(|h| h.distance)),
+            }
+        }
+        assert!(hits > 500);
+    }
+
+    #[test]
+    fn scattered_blocks_stay_unmerged() {
+        // dos bloques tan lejos que la grilla densa entre ellos sería enorme
+        let objects = vec![block(0, 0, 0, "piedra"), block(1, 0, 0, "piedra"), block(100_000, 0, 100_000, "piedra")];
+        let merged = merge_blocks(objects.clone(), |_| true);
+        assert_eq!(merged, objects);
+        assert_eq!(grid_cells([4, 4, 4]), Some(64));
+        assert_eq!(grid_cells([4, 0, 4]), None);
+        assert_eq!(grid_cells([i32::MAX, i32::MAX, 2]), None);
+    }
+}
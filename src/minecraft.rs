@@ -0,0 +1,737 @@
+// Importador de mundos de Minecraft: lee estructuras (.nbt, las que guarda el bloque de
+// estructuras) o una caja de una región Anvil (.mca, formato 1.13 en adelante) a una grilla de
+// bloques, y la convierte en cubos de 1×1×1 con materiales según una tabla bloque → textura.
+//
+// Tabla de bloques (archivo de texto, una entrada por línea, `#` comenta):
+//
+//     # bloque            material de la escena o textura (el primero que exista)
+//     bricks              assets/brick.png
+//     *_log               assets/log_spruce.png
+//     glass               vidrio assets/glass.png
+//
+// El prefijo `minecraft:` es opcional y `*` reemplaza cualquier texto. Si el valor es un
+// material definido en la escena se usa ese; si termina en .png se crea un material mate con esa
+// textura. Con varias entradas para el mismo bloque gana la última. Los bloques sin entrada se
+// omiten (y se informan en `WorldImport::unmapped`).
+//
+// Por defecto los bloques opacos vecinos con el mismo material se fusionan en cajas más grandes
+// (src/merge.rs); `merge off` en el bloque `minecraft` deja un cubo por bloque.
+
+use crate::material::Material;
+use crate::merge;
+use crate::math::{Vec2, Vec3};
+use crate::nbt::{self, Tag};
+use crate::scene_file::{ObjectDesc, Shape};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockState {
+    // con espacio de nombres, p.ej. "minecraft:oak_stairs"
+    pub name: String,
+    // propiedades del estado, p.ej. [("facing", "north"), ("half", "bottom")]
+    pub properties: Vec<(String, String)>,
+}
+
+impl BlockState {
+    fn from_tag(tag: &Tag) -> io::Result<BlockState> {
+        let name = tag.get("Name").and_then(Tag::as_str).ok_or_else(|| invalid("estado de bloque sin 'Name'".into()))?;
+        let properties = tag
+            .get("Properties")
+            .and_then(Tag::as_compound)
+            .map(|entries| entries.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+            .unwrap_or_default();
+        Ok(BlockState { name: name.to_string(), properties })
+    }
+
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_air(&self) -> bool {
+        matches!(short_name(&self.name), "air" | "cave_air" | "void_air" | "structure_void")
+    }
+}
+
+fn short_name(name: &str) -> &str {
+    name.strip_prefix("minecraft:").unwrap_or(name)
+}
+
+/// Grilla de bloques: índice en `palette` por celda (None = aire)
+#[derive(Clone, Debug, Default)]
+pub struct VoxelGrid {
+    pub size: [i32; 3],
+    pub palette: Vec<BlockState>,
+    cells: Vec<Option<u32>>,
+}
+
+impl VoxelGrid {
+    /// Grilla vacía; los lados tienen que ser positivos y el total no pasar de
+    /// merge::MAX_GRID_CELLS (el tamaño sale de archivos que pueden estar rotos)
+    pub fn new(size: [i32; 3]) -> io::Result<Self> {
+        let cells = merge::grid_cells(size).ok_or_else(|| {
+            invalid(format!("tamaño {}×{}×{} inválido o demasiado grande (máximo {} bloques)", size[0], size[1], size[2], merge::MAX_GRID_CELLS))
+        })?;
+        Ok(VoxelGrid { size, palette: Vec::new(), cells: vec![None; cells] })
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> Option<usize> {
+        let [sx, sy, sz] = self.size;
+        ((0..sx).contains(&x) && (0..sy).contains(&y) && (0..sz).contains(&z)).then(|| ((y * sz + z) * sx + x) as usize)
+    }
+
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<&BlockState> {
+        let cell = self.cells[self.index(x, y, z)?]?;
+        Some(&self.palette[cell as usize])
+    }
+
+    /// Índice en la paleta del bloque en (x, y, z)
+    pub fn get_index(&self, x: i32, y: i32, z: i32) -> Option<u32> {
+        self.cells[self.index(x, y, z)?]
+    }
+
+    /// Pone un bloque (el aire vacía la celda); fuera de la grilla no hace nada
+    pub fn set(&mut self, x: i32, y: i32, z: i32, state: &BlockState) {
+        let Some(index) = self.index(x, y, z) else { return };
+        self.cells[index] = if state.is_air() {
+            None
+        } else {
+            let palette_index = match self.palette.iter().position(|s| s == state) {
+                Some(i) => i,
+                None => {
+                    self.palette.push(state.clone());
+                    self.palette.len() - 1
+                }
+            };
+            Some(palette_index as u32)
+        };
+    }
+
+    /// Cantidad de celdas que no son aire
+    pub fn block_count(&self) -> usize {
+        self.cells.iter().filter(|c| c.is_some()).count()
+    }
+
+    /// Estructura guardada por el bloque de estructuras: `size`, `palette` (o `palettes`) y
+    /// `blocks` con `pos` y `state`
+    pub fn from_structure(root: &Tag) -> io::Result<VoxelGrid> {
+        let size = root.get("size").and_then(Tag::as_ints).filter(|s| s.len() == 3).ok_or_else(|| invalid("estructura sin 'size'".into()))?;
+        let palette = root
+            .get("palette")
+            .or_else(|| root.get("palettes").and_then(Tag::as_list).and_then(|p| p.first()))
+            .and_then(Tag::as_list)
+            .ok_or_else(|| invalid("estructura sin 'palette'".into()))?
+            .iter()
+            .map(BlockState::from_tag)
+            .collect::<io::Result<Vec<_>>>()?;
+        let blocks = root.get("blocks").and_then(Tag::as_list).ok_or_else(|| invalid("estructura sin 'blocks'".into()))?;
+
+        // un lado que no entra en i32 queda en 0 y lo rechaza VoxelGrid::new
+        let mut grid = VoxelGrid::new([0, 1, 2].map(|i| i32::try_from(size[i]).unwrap_or(0)))?;
+        for block in blocks {
+            let pos = block.get("pos").and_then(Tag::as_ints).filter(|p| p.len() == 3).ok_or_else(|| invalid("bloque sin 'pos'".into()))?;
+            let state = block.get("state").and_then(Tag::as_i64).ok_or_else(|| invalid("bloque sin 'state'".into()))?;
+            let state = palette.get(state as usize).ok_or_else(|| invalid(format!("estado {} fuera de la paleta", state)))?;
+            grid.set(pos[0] as i32, pos[1] as i32, pos[2] as i32, state);
+        }
+        Ok(grid)
+    }
+
+    pub fn read_structure(path: &str) -> io::Result<VoxelGrid> {
+        let (_, root) = nbt::read(path)?;
+        VoxelGrid::from_structure(&root).map_err(|e| invalid(format!("{}: {}", path, e)))
+    }
+
+    /// Bloques de la caja [min, max] (inclusive, coordenadas del mundo) de una región .mca. La
+    /// caja tiene que caer dentro de la región del archivo (r.X.Z.mca cubre 512×512 bloques).
+    pub fn read_region(path: &str, min: [i32; 3], max: [i32; 3]) -> io::Result<VoxelGrid> {
+        let data = fs::read(path)?;
+        read_region_data(&data, region_coords(path), min, max).map_err(|e| invalid(format!("{}: {}", path, e)))
+    }
+}
+
+// coordenadas de región a partir del nombre r.X.Z.mca (si el archivo se renombró, no se validan)
+fn region_coords(path: &str) -> Option<(i32, i32)> {
+    let name = Path::new(path).file_name()?.to_str()?;
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((x, z))
+}
+
+const SECTOR: usize = 4096;
+// desde esta versión (20w17a, 1.16) los índices de la paleta no cruzan de un long al siguiente
+const DATA_VERSION_NO_SPANNING: i64 = 2529;
+
+fn read_region_data(data: &[u8], region: Option<(i32, i32)>, min: [i32; 3], max: [i32; 3]) -> io::Result<VoxelGrid> {
+    let (min, max) = ([0, 1, 2].map(|i| min[i].min(max[i])), [0, 1, 2].map(|i| min[i].max(max[i])));
+    if data.len() < 2 * SECTOR {
+        return Err(invalid("región sin cabecera".into()));
+    }
+    let mut grid = VoxelGrid::new([0, 1, 2].map(|i| max[i].checked_sub(min[i]).and_then(|s| s.checked_add(1)).unwrap_or(0)))?;
+
+    for cz in min[2] >> 4..=max[2] >> 4 {
+        for cx in min[0] >> 4..=max[0] >> 4 {
+            if let Some((rx, rz)) = region
+                && (cx >> 5 != rx || cz >> 5 != rz)
+            {
+                return Err(invalid(format!("el chunk ({}, {}) no está en la región ({}, {})", cx, cz, rx, rz)));
+            }
+            let entry = 4 * ((cx & 31) + (cz & 31) * 32) as usize;
+            let location = u32::from_be_bytes(data[entry..entry + 4].try_into().unwrap());
+            let offset = (location >> 8) as usize * SECTOR;
+            if offset == 0 {
+                // chunk nunca generado: todo aire
+                continue;
+            }
+            let header = data.get(offset..offset + 5).ok_or_else(|| invalid(format!("chunk ({}, {}) fuera del archivo", cx, cz)))?;
+            let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+            let body = data
+                .get(offset + 5..offset + 4 + length)
+                .ok_or_else(|| invalid(format!("chunk ({}, {}) truncado", cx, cz)))?;
+            let bytes = match header[4] {
+                1 => crate::zlib::gunzip(body)?,
+                2 => crate::zlib::decompress(body)?,
+                3 => body.to_vec(),
+                other => return Err(invalid(format!("compresión de chunk {} no soportada", other))),
+            };
+            let (_, chunk) = nbt::parse(&bytes)?;
+            copy_chunk(&chunk, cx, cz, min, &mut grid)?;
+        }
+    }
+    Ok(grid)
+}
+
+// Copia a la grilla los bloques del chunk (cx, cz) que caen dentro de ella
+fn copy_chunk(chunk: &Tag, cx: i32, cz: i32, min: [i32; 3], grid: &mut VoxelGrid) -> io::Result<()> {
+    let data_version = chunk.get("DataVersion").and_then(Tag::as_i64).unwrap_or(0);
+    // 1.18+: `sections` en la raíz con `block_states`; 1.13–1.17: `Level.Sections` con `Palette` y `BlockStates`
+    let (sections, modern) = match chunk.get("sections") {
+        Some(sections) => (sections, true),
+        None => (
+            chunk.get("Level").and_then(|l| l.get("Sections")).ok_or_else(|| invalid("chunk sin secciones".into()))?,
+            false,
+        ),
+    };
+    for section in sections.as_list().unwrap_or_default() {
+        let Some(sy) = section.get("Y").and_then(Tag::as_i64) else { continue };
+        let sy = sy as i32;
+        if sy * 16 + 15 < min[1] || sy * 16 >= min[1] + grid.size[1] {
+            continue;
+        }
+        let (palette, states) = if modern {
+            let Some(block_states) = section.get("block_states") else { continue };
+            (block_states.get("palette"), block_states.get("data"))
+        } else {
+            (section.get("Palette"), section.get("BlockStates"))
+        };
+        let Some(palette) = palette.and_then(Tag::as_list) else {
+            if section.get("Blocks").is_some() {
+                return Err(invalid("formato de chunk anterior a 1.13 (ids numéricos) no soportado".into()));
+            }
+            continue;
+        };
+        let palette = palette.iter().map(BlockState::from_tag).collect::<io::Result<Vec<_>>>()?;
+        let states = states.and_then(Tag::as_long_array).unwrap_or_default();
+        let bits = (usize::BITS - (palette.len().max(1) - 1).leading_zeros()).max(4) as usize;
+        let spanning = data_version < DATA_VERSION_NO_SPANNING && !modern;
+
+        for i in 0..4096 {
+            let (x, y, z) = (cx * 16 + (i & 15) as i32, sy * 16 + (i >> 8) as i32, cz * 16 + ((i >> 4) & 15) as i32);
+            let (gx, gy, gz) = (x - min[0], y - min[1], z - min[2]);
+            if grid.index(gx, gy, gz).is_none() {
+                continue;
+            }
+            // con un solo estado en la paleta el juego no guarda `data`
+            let state = if states.is_empty() { 0 } else { packed_index(states, i, bits, spanning) };
+            let state = palette.get(state).ok_or_else(|| invalid(format!("estado {} fuera de la paleta", state)))?;
+            grid.set(gx, gy, gz, state);
+        }
+    }
+    Ok(())
+}
+
+// Índice `i` de un arreglo de enteros de `bits` bits empaquetados en longs
+fn packed_index(longs: &[i64], i: usize, bits: usize, spanning: bool) -> usize {
+    let mask = (1u64 << bits) - 1;
+    if spanning {
+        let bit = i * bits;
+        let (word, shift) = (bit / 64, bit % 64);
+        let low = longs.get(word).map_or(0, |&l| l as u64) >> shift;
+        let high = if shift + bits > 64 { longs.get(word + 1).map_or(0, |&l| l as u64) << (64 - shift) } else { 0 };
+        ((low | high) & mask) as usize
+    } else {
+        let per_long = 64 / bits;
+        let long = longs.get(i / per_long).map_or(0, |&l| l as u64);
+        ((long >> ((i % per_long) * bits)) & mask) as usize
+    }
+}
+
+/// Tabla bloque → material o textura (ver el formato al inicio del archivo)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockTable {
+    entries: Vec<(String, Vec<String>)>,
+}
+
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix),
+        None => pattern == name,
+    }
+}
+
+impl BlockTable {
+    pub fn parse(text: &str) -> Result<BlockTable, String> {
+        let mut table = BlockTable::default();
+        for (i, raw) in text.lines().enumerate() {
+            let words: Vec<&str> = raw.split('#').next().unwrap_or("").split_whitespace().collect();
+            match words[..] {
+                [] => {}
+                [block] => return Err(format!("línea {}: el bloque '{}' no tiene material ni textura", i + 1, block)),
+                [block, ref values @ ..] => table.insert(block, values.iter().map(|v| v.to_string()).collect()),
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn load(path: &str) -> io::Result<BlockTable> {
+        BlockTable::parse(&fs::read_to_string(path)?).map_err(|e| invalid(format!("{}: {}", path, e)))
+    }
+
+    /// Agrega una entrada; tiene prioridad sobre las anteriores que coincidan con el mismo bloque
+    pub fn insert(&mut self, block: &str, values: Vec<String>) {
+        self.entries.push((short_name(block).to_string(), values));
+    }
+
+    /// Candidatos (material o textura) para el bloque, en orden de preferencia
+    pub fn lookup(&self, block: &str) -> Option<&[String]> {
+        let name = short_name(block);
+        self.entries.iter().rev().find(|(pattern, _)| matches_pattern(pattern, name)).map(|(_, v)| v.as_slice())
+    }
+}
+
+/// Bloque `minecraft` del archivo de escena: de dónde leer, dónde ponerlo y con qué tabla.
+/// `objects`, `materials` y `unmapped` se llenan con `resolve`.
+#[derive(Clone, Default)]
+pub struct WorldImport {
+    pub name: Option<String>,
+    // estructura .nbt o región .mca
+    pub file: String,
+    // para regiones: caja a leer (mínimo y máximo inclusive, coordenadas del mundo)
+    pub region_box: Option<([i32; 3], [i32; 3])>,
+    // posición en la escena de la esquina mínima de la grilla
+    pub offset: Vec3,
+    pub table: Option<String>,
+    // entradas sueltas, con prioridad sobre las de `table`
+    pub blocks: Vec<(String, Vec<String>)>,
+    // fusionar bloques vecinos (ver merge::merge_blocks)
+    pub merge: bool,
+
+    pub objects: Vec<ObjectDesc>,
+    // materiales creados para las texturas de la tabla (nombrados con la ruta de la textura)
+    pub materials: Vec<(String, Material)>,
+    // bloques sin entrada en la tabla (o cuyo material/textura no existe)
+    pub unmapped: Vec<String>,
+}
+
+impl WorldImport {
+    pub fn read_grid(&self) -> io::Result<VoxelGrid> {
+        match self.region_box {
+            Some((min, max)) => VoxelGrid::read_region(&self.file, min, max),
+            None => VoxelGrid::read_structure(&self.file),
+        }
+    }
+
+    pub fn block_table(&self) -> io::Result<BlockTable> {
+        let mut table = match &self.table {
+            Some(path) => BlockTable::load(path)?,
+            None => BlockTable::default(),
+        };
+        for (block, values) in &self.blocks {
+            table.insert(block, values.clone());
+        }
+        Ok(table)
+    }
+
+    /// Lee el archivo y genera los cubos. `scene_materials` son los materiales de la escena a los
+    /// que puede referirse la tabla.
+    pub fn resolve(&mut self, scene_materials: &[(String, Material)]) -> io::Result<()> {
+        let grid = self.read_grid()?;
+        let table = self.block_table()?;
+        let imported = voxelize(&grid, &table, self.offset, scene_materials, self.merge);
+        self.objects = imported.objects;
+        self.materials = imported.materials;
+        self.unmapped = imported.unmapped;
+        Ok(())
+    }
+}
+
+pub struct Voxelized {
+    pub objects: Vec<ObjectDesc>,
+    pub materials: Vec<(String, Material)>,
+    pub unmapped: Vec<String>,
+}
+
+// plantas que se dibujan con dos quads cruzados
+const CROSS_PLANTS: &[&str] = &[
+    "grass", "short_grass", "fern", "dead_bush", "dandelion", "poppy", "blue_orchid", "allium", "azure_bluet",
+    "oxeye_daisy", "cornflower", "lily_of_the_valley", "wither_rose",
+];
+
+/// Modelos de bloque incluidos (con su rotación en grados) que dibujan un estado que no ocupa el
+/// bloque entero: losas, escaleras, vallas, paneles, antorchas y plantas. None = bloque completo.
+/// Las rotaciones siguen las de los blockstates del juego con el giro en y invertido, porque allí
+/// y = 90 gira de este a sur y acá de este a norte.
+pub fn block_models(state: &BlockState) -> Option<Vec<(&'static str, Vec2)>> {
+    let name = short_name(&state.name);
+    let facing = |key: &str| match state.property(key) {
+        Some("north") => 90.0,
+        Some("west") => 180.0,
+        Some("south") => -90.0,
+        _ => 0.0,
+    };
+    // lados conectados de vallas y paneles (los modelos de lado apuntan al norte)
+    let sides = |side: &'static str| {
+        [("north", 0.0), ("east", -90.0), ("south", 180.0), ("west", 90.0)]
+            .iter()
+            .filter(|(key, _)| state.property(key) == Some("true"))
+            .map(|&(_, angle)| (side, Vec2::new(0.0, angle)))
+            .collect::<Vec<_>>()
+    };
+    if name.ends_with("_slab") {
+        return match state.property("type") {
+            Some("double") => None,
+            Some("top") => Some(vec![("slab_top", Vec2::zero())]),
+            _ => Some(vec![("slab", Vec2::zero())]),
+        };
+    }
+    if name.ends_with("_stairs") {
+        // el modelo sin girar sube hacia el este; la mitad de arriba está dada vuelta
+        let x = if state.property("half") == Some("top") { 180.0 } else { 0.0 };
+        return Some(vec![("stairs", Vec2::new(x, facing("facing")))]);
+    }
+    if name.ends_with("_fence") {
+        let mut models = vec![("fence_post", Vec2::zero())];
+        models.extend(sides("fence_side"));
+        return Some(models);
+    }
+    if name.ends_with("_pane") || name == "iron_bars" {
+        let mut models = vec![("template_glass_pane_post", Vec2::zero())];
+        models.extend(sides("template_glass_pane_side"));
+        return Some(models);
+    }
+    // la antorcha de pared se dibuja parada en el centro del bloque
+    if matches!(name, "torch" | "wall_torch" | "soul_torch" | "soul_wall_torch") {
+        return Some(vec![("template_torch", Vec2::zero())]);
+    }
+    if name.ends_with("_sapling") || name.ends_with("_tulip") || CROSS_PLANTS.contains(&name) {
+        return Some(vec![("cross", Vec2::zero())]);
+    }
+    None
+}
+
+/// Un cubo de 1×1×1 por bloque con material (o las partes de su modelo si no es un bloque
+/// completo, ver block_models), omitiendo los que quedan tapados por bloques opacos en las seis
+/// direcciones (no se ven desde ningún lado). Con `merge` los opacos se fusionan en
+/// cajas que hay que construir con Cube::tiled; los tapados se descartan después de fusionar, así
+/// el relleno ayuda a formar cajas más grandes.
+pub fn voxelize(
+    grid: &VoxelGrid,
+    table: &BlockTable,
+    offset: Vec3,
+    scene_materials: &[(String, Material)],
+    merge: bool,
+) -> Voxelized {
+    let mut result = Voxelized { objects: Vec::new(), materials: Vec::new(), unmapped: Vec::new() };
+
+    // modelos de las entradas de la paleta que no son bloques completos
+    let palette_models: Vec<Option<Vec<(&str, Vec2)>>> = grid.palette.iter().map(block_models).collect();
+    // material de cada entrada de la paleta (None = sin entrada en la tabla) y si tapa a los vecinos
+    let palette_materials: Vec<Option<(String, bool)>> = grid
+        .palette
+        .iter()
+        .zip(&palette_models)
+        .map(|(state, models)| {
+            let full = models.is_none();
+            let candidates = table.lookup(&state.name).unwrap_or_default();
+            let found = candidates.iter().find_map(|value| {
+                if let Some((_, m)) = scene_materials.iter().find(|(n, _)| n == value) {
+                    return Some((value.clone(), full && m.transparency == 0.0));
+                }
+                if !value.ends_with(".png") {
+                    return None;
+                }
+                if !result.materials.iter().any(|(n, _)| n == value) {
+                    let material = Material { texture_path: Some(value.clone()), ..Material::default() };
+                    result.materials.push((value.clone(), material));
+                }
+                Some((value.clone(), full))
+            });
+            if found.is_none() && !result.unmapped.contains(&state.name) {
+                result.unmapped.push(state.name.clone());
+            }
+            found
+        })
+        .collect();
+
+    let opaque = |x: i32, y: i32, z: i32| {
+        grid.get_index(x, y, z).and_then(|i| palette_materials[i as usize].as_ref()).is_some_and(|(_, opaque)| *opaque)
+    };
+    let [sx, sy, sz] = grid.size;
+    for y in 0..sy {
+        for z in 0..sz {
+            for x in 0..sx {
+                let Some(index) = grid.get_index(x, y, z) else { continue };
+                let Some((material, _)) = &palette_materials[index as usize] else { continue };
+                let hidden = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)]
+                    .iter()
+                    .all(|(dx, dy, dz)| opaque(x + dx, y + dy, z + dz));
+                let center = offset + Vec3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5);
+                // las partes de un modelo no se fusionan: si está tapado no se ve nunca
+                if let Some(models) = &palette_models[index as usize] {
+                    if !hidden {
+                        result.objects.extend(models.iter().map(|&(model, rotation)| ObjectDesc {
+                            name: None,
+                            shape: Shape::Model { center, rotation, model: model.to_string(), textures: Vec::new() },
+                            material: material.clone(),
+                        }));
+                    }
+                    continue;
+                }
+                if hidden && !merge {
+                    continue;
+                }
+                result.objects.push(ObjectDesc {
+                    name: None,
+                    shape: Shape::Cube {
+                        center,
+                        half_size: Vec3::new(0.5, 0.5, 0.5),
+                        rotation: Vec2::zero(),
+                    },
+                    material: material.clone(),
+                });
+            }
+        }
+    }
+    if merge {
+        let opaque: Vec<&String> = palette_materials.iter().flatten().filter(|(_, opaque)| *opaque).map(|(m, _)| m).collect();
+        result.objects = merge::merge_blocks(result.objects, |m| opaque.iter().any(|o| *o == m));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str) -> Tag {
+        Tag::Compound(vec![("Name".to_string(), Tag::String(name.to_string()))])
+    }
+
+    fn ints(values: &[i32]) -> Tag {
+        Tag::List(values.iter().map(|&v| Tag::Int(v)).collect())
+    }
+
+    #[test]
+    fn reads_structure_blocks() {
+        let root = Tag::Compound(vec![
+            ("size".to_string(), ints(&[2, 2, 1])),
+            ("palette".to_string(), Tag::List(vec![state("minecraft:bricks"), state("minecraft:air"), state("minecraft:glass")])),
+            (
+                "blocks".to_string(),
+                Tag::List(
+                    [([0, 0, 0], 0), ([1, 0, 0], 2), ([0, 1, 0], 1), ([1, 1, 0], 0)]
+                        .iter()
+                        .map(|(pos, s)| Tag::Compound(vec![("pos".to_string(), ints(pos)), ("state".to_string(), Tag::Int(*s))]))
+                        .collect(),
+                ),
+            ),
+        ]);
+        let grid = VoxelGrid::from_structure(&root).unwrap();
+        assert_eq!(grid.size, [2, 2, 1]);
+        assert_eq!(grid.block_count(), 3);
+        assert_eq!(grid.get(1, 0, 0).unwrap().name, "minecraft:glass");
+        assert!(grid.get(0, 1, 0).is_none());
+        assert_eq!(grid.get_index(0, 0, 0), grid.get_index(1, 1, 0));
+        assert!(grid.get(5, 0, 0).is_none());
+    }
+
+    #[test]
+    fn rejects_bad_structure_sizes() {
+        for size in [[0, 2, 2], [-3, 2, 2], [65536, 65536, 65536], [1 << 30, 1 << 30, 4]] {
+            let root = Tag::Compound(vec![
+                ("size".to_string(), ints(&size)),
+                ("palette".to_string(), Tag::List(vec![state("minecraft:stone")])),
+                ("blocks".to_string(), Tag::List(Vec::new())),
+            ]);
+            let err = VoxelGrid::from_structure(&root).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        assert!(VoxelGrid::new([i32::MAX, i32::MAX, i32::MAX]).is_err());
+    }
+
+    #[test]
+    fn unpacks_palette_indices() {
+        // 5 bits por índice: 12 por long sin cruzar, o de corrido cruzando longs
+        let values: Vec<u64> = (0..40).map(|i| (i * 7) % 32).collect();
+        let mut packed = vec![0u64; 4];
+        for (i, v) in values.iter().enumerate() {
+            packed[i / 12] |= v << ((i % 12) * 5);
+        }
+        let packed: Vec<i64> = packed.into_iter().map(|l| l as i64).collect();
+        let mut spanning = vec![0u64; 4];
+        for (i, v) in values.iter().enumerate() {
+            let bit = i * 5;
+            spanning[bit / 64] |= v << (bit % 64);
+            if bit % 64 + 5 > 64 {
+                spanning[bit / 64 + 1] |= v >> (64 - bit % 64);
+            }
+        }
+        let spanning: Vec<i64> = spanning.into_iter().map(|l| l as i64).collect();
+        for (i, &v) in values.iter().enumerate() {
+            assert_eq!(packed_index(&packed, i, 5, false), v as usize);
+            assert_eq!(packed_index(&spanning, i, 5, true), v as usize);
+        }
+    }
+
+    // región con un chunk 1.18+ en (0, 0): una sección Y=0 con piedra abajo y un ladrillo arriba
+    fn region_bytes() -> Vec<u8> {
+        let mut states = vec![0u64; 256];
+        let set = |states: &mut Vec<u64>, x: usize, y: usize, z: usize, v: u64| {
+            let i = (y * 16 + z) * 16 + x;
+            states[i / 16] |= v << ((i % 16) * 4);
+        };
+        for z in 0..16 {
+            for x in 0..16 {
+                set(&mut states, x, 0, z, 1);
+            }
+        }
+        set(&mut states, 3, 1, 2, 2);
+        let section = Tag::Compound(vec![
+            ("Y".to_string(), Tag::Byte(0)),
+            (
+                "block_states".to_string(),
+                Tag::Compound(vec![
+                    ("palette".to_string(), Tag::List(vec![state("minecraft:air"), state("minecraft:stone"), state("minecraft:bricks")])),
+                    ("data".to_string(), Tag::LongArray(states.into_iter().map(|l| l as i64).collect())),
+                ]),
+            ),
+        ]);
+        let chunk = Tag::Compound(vec![
+            ("DataVersion".to_string(), Tag::Int(3465)),
+            ("sections".to_string(), Tag::List(vec![section])),
+        ]);
+        let compressed = crate::zlib::compress(&nbt::write("", &chunk));
+
+        let mut data = vec![0u8; 2 * SECTOR];
+        let sectors = (compressed.len() + 5).div_ceil(SECTOR);
+        data[0..4].copy_from_slice(&(2u32 << 8 | sectors as u32).to_be_bytes());
+        data.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+        data.push(2);
+        data.extend_from_slice(&compressed);
+        data.resize(2 * SECTOR + sectors * SECTOR, 0);
+        data
+    }
+
+    #[test]
+    fn reads_region_box() {
+        let data = region_bytes();
+        let grid = read_region_data(&data, Some((0, 0)), [2, 0, 1], [4, 3, 3]).unwrap();
+        assert_eq!(grid.size, [3, 4, 3]);
+        assert_eq!(grid.get(0, 0, 0).unwrap().name, "minecraft:stone");
+        assert_eq!(grid.get(1, 1, 1).unwrap().name, "minecraft:bricks");
+        assert!(grid.get(0, 1, 0).is_none());
+        // 9 de piedra + 1 ladrillo
+        assert_eq!(grid.block_count(), 10);
+        // una caja que se sale de la región r.0.0
+        assert!(read_region_data(&data, Some((0, 0)), [-1, 0, 0], [2, 2, 2]).is_err());
+        assert_eq!(region_coords("mundo/region/r.-1.2.mca"), Some((-1, 2)));
+    }
+
+    #[test]
+    fn table_lookup_uses_patterns_and_priority() {
+        let mut table = BlockTable::parse("bricks assets/brick.png\n*_log madera  # troncos\nminecraft:glass vidrio assets/glass.png\n").unwrap();
+        assert_eq!(table.lookup("minecraft:bricks"), Some(&["assets/brick.png".to_string()][..]));
+        assert_eq!(table.lookup("spruce_log").map(|v| v.len()), Some(1));
+        assert_eq!(table.lookup("minecraft:glass").map(|v| v.len()), Some(2));
+        assert!(table.lookup("stone").is_none());
+        table.insert("spruce_log", vec!["otra".to_string()]);
+        assert_eq!(table.lookup("minecraft:spruce_log"), Some(&["otra".to_string()][..]));
+        assert!(BlockTable::parse("piedra\n").is_err());
+
+        let bundled = BlockTable::load(&format!("{}/assets/blocks.table", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(bundled.lookup("minecraft:oak_log").map(|v| v[0].as_str()), Some("wood"));
+        assert_eq!(bundled.lookup("minecraft:glass").map(|v| v[1].as_str()), Some("assets/glass.png"));
+    }
+
+    #[test]
+    fn voxelize_culls_hidden_blocks_and_reports_unmapped() {
+        let mut grid = VoxelGrid::new([3, 3, 3]).unwrap();
+        let stone = BlockState { name: "minecraft:stone".to_string(), properties: Vec::new() };
+        for y in 0..3 {
+            for z in 0..3 {
+                for x in 0..3 {
+                    grid.set(x, y, z, &stone);
+                }
+            }
+        }
+        grid.set(0, 0, 0, &BlockState { name: "minecraft:dirt".to_string(), properties: Vec::new() });
+        let mut table = BlockTable::default();
+        table.insert("stone", vec!["piedra".to_string()]);
+        let materials = vec![("piedra".to_string(), Material::default())];
+
+        let result = voxelize(&grid, &table, Vec3::new(10.0, 0.0, 0.0), &materials, false);
+        // 26 de piedra menos el del centro, que está tapado
+        assert_eq!(result.objects.len(), 25);
+        assert_eq!(result.unmapped, vec!["minecraft:dirt".to_string()]);
+        assert!(result.objects.iter().all(|o| o.material == "piedra"));
+        assert!(result.objects.iter().all(|o| o.shape.center() != Vec3::new(11.5, 1.5, 1.5)));
+        assert!(result.objects.iter().any(|o| o.shape.center() == Vec3::new(12.5, 2.5, 2.5)));
+
+        // si la piedra es transparente el del centro se ve
+        let glassy = vec![("piedra".to_string(), Material { transparency: 0.5, ..Material::default() })];
+        assert_eq!(voxelize(&grid, &table, Vec3::zero(), &glassy, false).objects.len(), 26);
+        // los transparentes no se fusionan
+        assert_eq!(voxelize(&grid, &table, Vec3::zero(), &glassy, true).objects.len(), 26);
+
+        let merged = voxelize(&grid, &table, Vec3::new(10.0, 0.0, 0.0), &materials, true);
+        assert!(merged.objects.len() < 6);
+        assert!(merged.objects.iter().all(|o| o.material == "piedra"));
+        assert_eq!(merged.unmapped, result.unmapped);
+    }
+
+    #[test]
+    fn partial_blocks_use_block_models() {
+        let with = |name: &str, properties: &[(&str, &str)]| BlockState {
+            name: format!("minecraft:{}", name),
+            properties: properties.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        };
+        assert!(block_models(&with("stone", &[])).is_none());
+        assert!(block_models(&with("oak_slab", &[("type", "double")])).is_none());
+        assert_eq!(block_models(&with("oak_slab", &[("type", "top")])), Some(vec![("slab_top", Vec2::zero())]));
+        assert_eq!(
+            block_models(&with("brick_stairs", &[("facing", "north"), ("half", "top")])),
+            Some(vec![("stairs", Vec2::new(180.0, 90.0))])
+        );
+        let fence = block_models(&with("oak_fence", &[("east", "true"), ("west", "false"), ("south", "true")])).unwrap();
+        assert_eq!(fence, vec![("fence_post", Vec2::zero()), ("fence_side", Vec2::new(0.0, -90.0)), ("fence_side", Vec2::new(0.0, 180.0))]);
+        assert_eq!(block_models(&with("poppy", &[])), Some(vec![("cross", Vec2::zero())]));
+
+        // la losa no tapa al vecino y se emite como modelo con el material de la tabla
+        let mut grid = VoxelGrid::new([2, 1, 1]).unwrap();
+        grid.set(0, 0, 0, &with("stone", &[]));
+        grid.set(1, 0, 0, &with("stone_slab", &[("type", "bottom")]));
+        let mut table = BlockTable::default();
+        table.insert("stone*", vec!["piedra".to_string()]);
+        let materials = vec![("piedra".to_string(), Material::default())];
+        let result = voxelize(&grid, &table, Vec3::zero(), &materials, true);
+        assert_eq!(result.objects.len(), 2);
+        assert!(result.objects.iter().any(|o| matches!(&o.shape, Shape::Model { model, .. } if model == "slab")));
+        assert!(result.objects.iter().all(|o| o.material == "piedra"));
+    }
+}
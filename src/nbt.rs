@@ -0,0 +1,319 @@
+// NBT (Named Binary Tag), el formato binario de Minecraft: un árbol de valores con tipo, en
+// big-endian. Las estructuras (.nbt) vienen comprimidas con gzip y los chunks de las regiones
+// (.mca) con zlib; `parse_compressed` detecta cuál es.
+
+use crate::zlib;
+use std::fs;
+use std::io;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    // se conserva el orden de las claves para poder reescribir igual
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// Valor de la clave `name` si es un compound
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(k, _)| k == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Cualquier entero (byte, short, int o long) como i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&[(String, Tag)]> {
+        match self {
+            Tag::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Lista de enteros o IntArray (p.ej. `size` y `pos` de una estructura)
+    pub fn as_ints(&self) -> Option<Vec<i64>> {
+        match self {
+            Tag::IntArray(values) => Some(values.iter().map(|&v| v as i64).collect()),
+            Tag::List(items) => items.iter().map(Tag::as_i64).collect(),
+            _ => None,
+        }
+    }
+
+    pub fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + n).ok_or_else(|| invalid("nbt: datos truncados".into()))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> io::Result<usize> {
+        let n = i32::from_be_bytes(self.array()?);
+        usize::try_from(n).map_err(|_| invalid(format!("nbt: longitud negativa {}", n)))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let n = u16::from_be_bytes(self.array()?) as usize;
+        // Minecraft usa "Modified UTF-8"; para nombres de bloques es UTF-8 común
+        Ok(String::from_utf8_lossy(self.take(n)?).into_owned())
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> io::Result<Tag> {
+        if depth > 512 {
+            return Err(invalid("nbt: anidamiento demasiado profundo".into()));
+        }
+        Ok(match id {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let n = self.len()?;
+                Tag::ByteArray(self.take(n)?.iter().map(|&b| b as i8).collect())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_id = self.u8()?;
+                let n = self.len()?;
+                let mut items = Vec::with_capacity(n.min(4096));
+                for _ in 0..n {
+                    items.push(self.payload(item_id, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let id = self.u8()?;
+                    if id == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(id, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let n = self.len()?;
+                let bytes = self.take(n.checked_mul(4).ok_or_else(|| invalid("nbt: arreglo enorme".into()))?)?;
+                Tag::IntArray(bytes.chunks_exact(4).map(|c| i32::from_be_bytes(c.try_into().unwrap())).collect())
+            }
+            12 => {
+                let n = self.len()?;
+                let bytes = self.take(n.checked_mul(8).ok_or_else(|| invalid("nbt: arreglo enorme".into()))?)?;
+                Tag::LongArray(bytes.chunks_exact(8).map(|c| i64::from_be_bytes(c.try_into().unwrap())).collect())
+            }
+            other => return Err(invalid(format!("nbt: tipo de tag desconocido {}", other))),
+        })
+    }
+}
+
+/// Lee un NBT sin comprimir: devuelve el nombre y el compound raíz
+pub fn parse(data: &[u8]) -> io::Result<(String, Tag)> {
+    let mut reader = Reader { data, pos: 0 };
+    let id = reader.u8()?;
+    if id != 10 {
+        return Err(invalid(format!("nbt: la raíz debe ser un compound, no el tipo {}", id)));
+    }
+    let name = reader.string()?;
+    Ok((name, reader.payload(id, 0)?))
+}
+
+/// Igual que parse pero acepta datos comprimidos con gzip o zlib
+pub fn parse_compressed(data: &[u8]) -> io::Result<(String, Tag)> {
+    match data {
+        [0x1f, 0x8b, ..] => parse(&zlib::gunzip(data)?),
+        [0x78, ..] => parse(&zlib::decompress(data)?),
+        _ => parse(data),
+    }
+}
+
+pub fn read(path: &str) -> io::Result<(String, Tag)> {
+    parse_compressed(&fs::read(path)?).map_err(|e| invalid(format!("{}: {}", path, e)))
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_payload(out: &mut Vec<u8>, tag: &Tag) {
+    match tag {
+        Tag::Byte(v) => out.push(*v as u8),
+        Tag::Short(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Int(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Long(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Float(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Double(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::ByteArray(values) => {
+            out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+            out.extend(values.iter().map(|&b| b as u8));
+        }
+        Tag::String(s) => write_string(out, s),
+        Tag::List(items) => {
+            // una lista vacía se escribe con tipo End, como hace el juego
+            out.push(items.first().map_or(0, Tag::id));
+            out.extend_from_slice(&(items.len() as i32).to_be_bytes());
+            for item in items {
+                write_payload(out, item);
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, value) in entries {
+                out.push(value.id());
+                write_string(out, name);
+                write_payload(out, value);
+            }
+            out.push(0);
+        }
+        Tag::IntArray(values) => {
+            out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+            for v in values {
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        Tag::LongArray(values) => {
+            out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+            for v in values {
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+    }
+}
+
+/// Serializa un compound raíz sin comprimir (para comprimir con zlib::gzip o zlib::compress)
+pub fn write(name: &str, root: &Tag) -> Vec<u8> {
+    let mut out = vec![root.id()];
+    write_string(&mut out, name);
+    write_payload(&mut out, root);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Tag {
+        Tag::Compound(vec![
+            ("DataVersion".to_string(), Tag::Int(3465)),
+            ("size".to_string(), Tag::List(vec![Tag::Int(2), Tag::Int(1), Tag::Int(3)])),
+            ("name".to_string(), Tag::String("casa ñandú".to_string())),
+            ("empty".to_string(), Tag::List(Vec::new())),
+            (
+                "misc".to_string(),
+                Tag::Compound(vec![
+                    ("b".to_string(), Tag::Byte(-3)),
+                    ("s".to_string(), Tag::Short(-300)),
+                    ("l".to_string(), Tag::Long(-1)),
+                    ("f".to_string(), Tag::Float(0.5)),
+                    ("d".to_string(), Tag::Double(-2.25)),
+                    ("bytes".to_string(), Tag::ByteArray(vec![1, -1])),
+                    ("ints".to_string(), Tag::IntArray(vec![7, -7])),
+                    ("longs".to_string(), Tag::LongArray(vec![i64::MIN, 42])),
+                ]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn roundtrips_all_tag_types() {
+        let bytes = write("root", &sample());
+        assert_eq!(parse(&bytes).unwrap(), ("root".to_string(), sample()));
+        assert_eq!(parse_compressed(&zlib::gzip(&bytes)).unwrap().1, sample());
+        assert_eq!(parse_compressed(&zlib::compress(&bytes)).unwrap().1, sample());
+    }
+
+    #[test]
+    fn accessors() {
+        let root = sample();
+        assert_eq!(root.get("DataVersion").and_then(Tag::as_i64), Some(3465));
+        assert_eq!(root.get("size").and_then(Tag::as_ints), Some(vec![2, 1, 3]));
+        assert_eq!(root.get("misc").and_then(|m| m.get("ints")).and_then(Tag::as_ints), Some(vec![7, -7]));
+        assert_eq!(root.get("name").and_then(Tag::as_str), Some("casa ñandú"));
+        assert!(root.get("nada").is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = write("", &sample());
+        assert!(parse(&bytes[..bytes.len() - 3]).is_err());
+        assert!(parse(&[8, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::math::{Color, Vec3};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f32::consts::PI;
+use crate::light::{Light, LightKind};
+use crate::sampling::{self, Rng};
+use crate::scene::Scene;
+use crate::{reflect, refract};
+
+// Parámetros del pre-pase de photon mapping (solo cáusticas: caminos luz → especular(es) → difuso)
+#[derive(Clone, Copy)]
+pub struct PhotonSettings {
+    pub photons_per_light: u32,
+    pub max_bounces: u32,
+    pub gather_count: usize, // fotones más cercanos usados en la estimación
+    pub gather_radius: f32,  // radio máximo de búsqueda
+    pub sun_radius: f32,     // radio del disco desde el que se emiten los fotones de luces direccionales
+    pub strength: f32,       // escala artística del aporte de cáusticas
+}
+
+impl Default for PhotonSettings {
+    fn default() -> Self {
+        PhotonSettings {
+            photons_per_light: 200_000,
+            max_bounces: 6,
+            gather_count: 64,
+            gather_radius: 0.35,
+            sun_radius: 8.0,
+            strength: 1.0,
+        }
+    }
+}
+
+impl PhotonSettings {
+    /// Versión rápida para la ventana mientras las luces cambian en cada frame: 1/16 de los
+    /// fotones y el radio de búsqueda ×4 para juntar aproximadamente la misma cantidad
+    pub fn preview(&self) -> Self {
+        PhotonSettings {
+            photons_per_light: (self.photons_per_light / 16).max(1),
+            gather_radius: self.gather_radius * 4.0,
+            ..*self
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Photon {
+    pub position: Vec3,
+    pub direction: Vec3, // dirección en la que viajaba al llegar
+    pub power: Color,
+}
+
+pub struct PhotonMap {
+    // kd-tree implícito: cada sub-slice tiene su nodo en la mediana
+    photons: Vec<Photon>,
+    axes: Vec<u8>,
+    settings: PhotonSettings,
+}
+
+// entrada del heap de k vecinos (max-heap por distancia²)
+struct Neighbor {
+    dist2: f32,
+    index: usize,
+}
+
+impl PartialEq for Neighbor {
+    fn eq(&self, other: &Self) -> bool { self.dist2 == other.dist2 }
+}
+impl Eq for Neighbor {}
+impl PartialOrd for Neighbor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for Neighbor {
+    fn cmp(&self, other: &Self) -> Ordering { self.dist2.total_cmp(&other.dist2) }
+}
+
+fn axis_value(v: &Vec3, axis: u8) -> f32 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+impl PhotonMap {
+    /// Emite fotones desde cada luz de la escena, los sigue por superficies especulares y
+    /// refractivas (vidrio, agua) y guarda los que terminan en una superficie difusa después
+    /// de al menos un rebote especular.
+    pub fn build(scene: &Scene, settings: PhotonSettings) -> Self {
+        let mut photons: Vec<Photon> = Vec::new();
+
+        for (light_index, light) in scene.lights.iter().enumerate() {
+            let n = settings.photons_per_light;
+            if n == 0 || light.intensity <= 0.0 { continue; }
+
+            // potencia total: la que da `intensity` de irradiancia a distancia 1 (puntual) o en
+            // todo el disco (sol); la caída con la distancia se corrige al guardar el fotón
+            let (total_power, sun_frame) = match light.kind {
+                LightKind::Point => (4.0 * PI * light.intensity, None),
+                LightKind::Directional => {
+                    let r = settings.sun_radius;
+                    let (t, b) = sampling::orthonormal_basis(&light.direction);
+                    (light.intensity * PI * r * r, Some((t, b)))
+                }
+            };
+            let photon_power = light.color * (total_power / n as f32);
+
+            let stored: Vec<Photon> = (0..n)
+                .into_par_iter()
+                .flat_map_iter(|i| {
+                    let mut rng = Rng::new(((light_index as u64) << 32) | i as u64);
+                    let (origin, direction) = match sun_frame {
+                        None => (light.position, sampling::uniform_sphere(&mut rng)),
+                        Some((t, b)) => {
+                            // punto uniforme en un disco perpendicular a la luz, bien "atrás"
+                            let r = settings.sun_radius * rng.next_f32().sqrt();
+                            let phi = 2.0 * PI * rng.next_f32();
+                            let offset = t * (r * phi.cos()) + b * (r * phi.sin());
+                            (offset - light.direction * 30.0, light.direction)
+                        }
+                    };
+                    trace_photon(scene, light, origin, direction, photon_power, &settings, &mut rng)
+                })
+                .collect();
+            photons.extend(stored);
+        }
+
+        let mut axes = vec![0u8; photons.len()];
+        build_kdtree(&mut photons, &mut axes);
+        PhotonMap { photons, axes, settings }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// Estimación de irradiancia por cáusticas en `point` (superficie con normal `normal`):
+    /// suma la potencia de los k fotones más cercanos y la divide por el área del disco que los contiene.
+    pub fn irradiance(&self, point: &Vec3, normal: &Vec3) -> Color {
+        if self.photons.is_empty() {
+            return Color::BLACK;
+        }
+        let k = self.settings.gather_count.max(1);
+        let max_dist2 = self.settings.gather_radius * self.settings.gather_radius;
+        let mut heap: BinaryHeap<Neighbor> = BinaryHeap::with_capacity(k + 1);
+        self.knn(0, self.photons.len(), point, k, max_dist2, &mut heap);
+
+        if heap.is_empty() {
+            return Color::BLACK;
+        }
+        let r2 = if heap.len() == k { heap.peek().map(|n| n.dist2).unwrap_or(max_dist2) } else { max_dist2 };
+        let r2 = r2.max(1e-6);
+
+        let mut sum = Color::BLACK;
+        for neighbor in heap.iter() {
+            let photon = &self.photons[neighbor.index];
+            // solo cuentan los fotones que llegan por el lado visible de la superficie
+            if photon.direction.dot(*normal) < 0.0 {
+                // filtro cónico: pesa más a los fotones cercanos
+                let w = 1.0 - (neighbor.dist2 / r2).sqrt();
+                sum += photon.power * w;
+            }
+        }
+        // normalización del filtro cónico (k = 1): 1 - 2/(3k)
+        sum * (self.settings.strength / ((1.0 - 2.0 / 3.0) * PI * r2))
+    }
+
+    fn knn(&self, start: usize, end: usize, point: &Vec3, k: usize, max_dist2: f32, heap: &mut BinaryHeap<Neighbor>) {
+        if start >= end { return; }
+        let mid = start + (end - start) / 2;
+        let photon = &self.photons[mid];
+        let axis = self.axes[mid];
+
+        let diff = axis_value(point, axis) - axis_value(&photon.position, axis);
+        let (near, far) = if diff < 0.0 { ((start, mid), (mid + 1, end)) } else { ((mid + 1, end), (start, mid)) };
+
+        self.knn(near.0, near.1, point, k, max_dist2, heap);
+
+        let d = photon.position - *point;
+        let dist2 = d.dot(d);
+        if dist2 < max_dist2 {
+            if heap.len() < k {
+                heap.push(Neighbor { dist2, index: mid });
+            } else if dist2 < heap.peek().unwrap().dist2 {
+                heap.pop();
+                heap.push(Neighbor { dist2, index: mid });
+            }
+        }
+
+        let bound = if heap.len() == k { heap.peek().unwrap().dist2.min(max_dist2) } else { max_dist2 };
+        if diff * diff < bound {
+            self.knn(far.0, far.1, point, k, max_dist2, heap);
+        }
+    }
+}
+
+// ordena `photons` como kd-tree implícito: mediana en el eje de mayor extensión
+fn build_kdtree(photons: &mut [Photon], axes: &mut [u8]) {
+    if photons.len() <= 1 {
+        if let Some(a) = axes.first_mut() { *a = 0; }
+        return;
+    }
+    let mut min = photons[0].position;
+    let mut max = photons[0].position;
+    for p in photons.iter() {
+        min = min.min(p.position);
+        max = max.max(p.position);
+    }
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| {
+        axis_value(&a.position, axis).total_cmp(&axis_value(&b.position, axis))
+    });
+    axes[mid] = axis;
+
+    let (left, rest) = photons.split_at_mut(mid);
+    let (left_axes, rest_axes) = axes.split_at_mut(mid);
+    build_kdtree(left, left_axes);
+    build_kdtree(&mut rest[1..], &mut rest_axes[1..]);
+}
+
+// Los fotones de una luz puntual se reparten como 1/d², pero la luz directa de cast_ray cae
+// según Light::attenuation (más lento). Con este factor, sobre la distancia recorrida, la
+// irradiancia de los fotones da lo mismo que la luz directa sin nada enfocándola.
+fn falloff_correction(light: &Light, traveled: f32) -> f32 {
+    match light.kind {
+        LightKind::Point => light.attenuation(traveled) * traveled * traveled / light.intensity,
+        LightKind::Directional => 1.0,
+    }
+}
+
+// sigue un fotón; devuelve el fotón almacenado (si llegó a una superficie difusa tras un rebote especular)
+fn trace_photon(
+    scene: &Scene,
+    light: &Light,
+    mut origin: Vec3,
+    mut direction: Vec3,
+    mut power: Color,
+    settings: &PhotonSettings,
+    rng: &mut Rng,
+) -> Option<Photon> {
+    let mut specular_path = false;
+    let mut traveled = 0.0;
+
+    for _ in 0..settings.max_bounces {
+        let hit = scene.closest_hit(&origin, &direction)?;
+        let m = &hit.material;
+        traveled += hit.distance;
+
+        let xi = rng.next_f32();
+        if xi < m.transparency {
+            // refracción (con reflexión total interna si no hay rayo refractado)
+            let refr = refract(&direction, &hit.normal, m.refractive_index);
+            if refr.length() > 0.0 {
+                power *= m.diffuse;
+                direction = refr.normalized();
+                let side = if direction.dot(hit.normal) < 0.0 { -1.0 } else { 1.0 };
+                origin = hit.point + hit.normal * (side * 1e-3);
+            } else {
+                direction = reflect(&direction, &hit.normal).normalized();
+                let side = if direction.dot(hit.normal) < 0.0 { -1.0 } else { 1.0 };
+                origin = hit.point + hit.normal * (side * 1e-3);
+            }
+            specular_path = true;
+        } else if xi < m.transparency + m.reflectivity {
+            direction = reflect(&direction, &hit.normal).normalized();
+            origin = hit.point + hit.normal * 1e-3;
+            specular_path = true;
+        } else {
+            // superficie difusa: solo interesan los caminos que pasaron por algo especular
+            return if specular_path {
+                Some(Photon { position: hit.point, direction, power: power * falloff_correction(light, traveled) })
+            } else {
+                None
+            };
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::daycycle::DayCycle;
+    use crate::material::Material;
+
+    #[test]
+    fn unfocused_photons_match_direct_light() {
+        // vidrio con índice 1 (no desvía): los fotones lo cruzan y llegan al piso como la luz directa
+        let glass = Material { transparency: 1.0, refractive_index: 1.0, ..Material::default() };
+        let floor = Cube::new(Vec3::new(0.0, -0.5, 0.0), Vec3::new(6.0, 0.5, 6.0), 0.0, 0.0, Material::default());
+        let slab = Cube::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(6.0, 0.05, 6.0), 0.0, 0.0, glass);
+        let mut scene = Scene::new(vec![Box::new(floor), Box::new(slab)], Vec::new(), DayCycle::new(12.0));
+        let light = Light::point(Vec3::new(0.0, 3.0, 0.0), Color::WHITE, 2.0);
+        scene.lights = vec![light];
+
+        let map = PhotonMap::build(&scene, PhotonSettings::default());
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        for x in [0.7, 1.3, 2.0] {
+            let point = Vec3::new(x, 0.0, 0.4);
+            let to_light = light.position - point;
+            let direct = light.attenuation(to_light.length()) * to_light.normalized().dot(up);
+            let caustic = map.irradiance(&point, &up).g;
+            assert!((caustic - direct).abs() < direct * 0.15, "x = {}: fotones {} vs directa {}", x, caustic, direct);
+        }
+    }
+}
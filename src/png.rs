@@ -0,0 +1,293 @@
+// Lectura y escritura de PNG sin ventana (y sin raylib), para cargar texturas en modo headless
+// y guardar/comparar imágenes en los tests. Lee PNG no entrelazados de cualquier tipo de color
+// (gris, RGB, paleta, con o sin alfa; 1 a 16 bits) y escribe siempre RGBA de 8 bits.
+
+use crate::math::Rgba8;
+use crate::zlib;
+use std::fs;
+use std::io;
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// Imagen RGBA8 en memoria, fila por fila y empezando arriba
+#[derive(Clone)]
+pub struct RgbaImage {
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<Rgba8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: i32, height: i32, pixels: Vec<Rgba8>) -> Self {
+        assert_eq!(pixels.len(), (width * height).max(0) as usize, "tamaño de imagen inconsistente");
+        RgbaImage { width, height, pixels }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Rgba8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// Lee un PNG desde disco
+pub fn read(path: &str) -> io::Result<RgbaImage> {
+    decode(&fs::read(path)?)
+}
+
+/// Escribe la imagen como PNG RGBA de 8 bits
+pub fn write(path: &str, image: &RgbaImage) -> io::Result<()> {
+    fs::write(path, encode(image))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Aplica el filtro `kind` a una fila; `bpp` = bytes por píxel (mínimo 1)
+fn filter_row(kind: u8, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predicted = match kind {
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => 0,
+        };
+        out.push(row[i].wrapping_sub(predicted));
+    }
+}
+
+fn unfilter_row(kind: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> io::Result<()> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predicted = match kind {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return Err(invalid("png: filtro desconocido")),
+        };
+        row[i] = row[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = zlib::crc32_update(zlib::crc32(kind), data);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Codifica la imagen como PNG RGBA de 8 bits. Cada fila usa el filtro que minimiza la suma de
+/// residuos (la heurística habitual de libpng).
+pub fn encode(image: &RgbaImage) -> Vec<u8> {
+    let stride = image.width.max(0) as usize * 4;
+    let raw: Vec<u8> = image.pixels.iter().flat_map(|p| [p.r, p.g, p.b, p.a]).collect();
+
+    let mut filtered = Vec::with_capacity((stride + 1) * image.height.max(0) as usize);
+    let zero_row = vec![0u8; stride];
+    let mut candidate = Vec::with_capacity(stride);
+    let mut best = Vec::with_capacity(stride);
+    for y in 0..image.height.max(0) as usize {
+        let row = &raw[y * stride..(y + 1) * stride];
+        let prev = if y == 0 { &zero_row[..] } else { &raw[(y - 1) * stride..y * stride] };
+        let mut best_kind = 0;
+        let mut best_cost = u64::MAX;
+        for kind in 0..5u8 {
+            candidate.clear();
+            filter_row(kind, row, prev, 4, &mut candidate);
+            let cost = candidate.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_kind = kind;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        filtered.push(best_kind);
+        filtered.extend_from_slice(&best);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(image.width as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bits, RGBA, deflate, filtro adaptativo, sin entrelazado
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib::compress(&filtered));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+/// Decodifica un PNG a RGBA8
+pub fn decode(data: &[u8]) -> io::Result<RgbaImage> {
+    if data.len() < 8 || data[..8] != SIGNATURE {
+        return Err(invalid("png: firma inválida"));
+    }
+
+    let mut header: Option<&[u8]> = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data.get(pos + 8..pos + 8 + len).ok_or_else(|| invalid("png: chunk truncado"))?;
+        match kind {
+            b"IHDR" => header = Some(body),
+            b"PLTE" => palette = body,
+            b"tRNS" => transparency = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += len + 12; // longitud + tipo + datos + CRC
+    }
+
+    let header = header.filter(|h| h.len() == 13).ok_or_else(|| invalid("png: falta IHDR"))?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let depth = header[8] as usize;
+    let color_type = header[9];
+    if header[12] != 0 {
+        return Err(invalid("png: imágenes entrelazadas no soportadas"));
+    }
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(invalid("png: tipo de color desconocido")),
+    };
+    if !matches!(depth, 1 | 2 | 4 | 8 | 16) || (depth > 8 && color_type == 3) || (depth < 8 && !matches!(color_type, 0 | 3)) {
+        return Err(invalid("png: profundidad de bits no soportada"));
+    }
+
+    let bits_per_pixel = channels * depth;
+    let stride = (width * bits_per_pixel).div_ceil(8);
+    let bpp = bits_per_pixel.div_ceil(8).max(1);
+    let mut raw = zlib::decompress(&compressed)?;
+    if raw.len() < (stride + 1) * height {
+        return Err(invalid("png: datos de imagen incompletos"));
+    }
+
+    // deshace los filtros en el sitio; cada fila va precedida por su tipo de filtro
+    let mut prev = vec![0u8; stride];
+    let mut rows = Vec::with_capacity(stride * height);
+    for y in 0..height {
+        let start = y * (stride + 1);
+        let kind = raw[start];
+        let row = &mut raw[start + 1..start + 1 + stride];
+        unfilter_row(kind, row, &prev, bpp)?;
+        prev.copy_from_slice(row);
+        rows.extend_from_slice(row);
+    }
+
+    // muestra `index` de una fila (para profundidades < 8 hay varias por byte)
+    let sample = |row: &[u8], index: usize| -> u16 {
+        match depth {
+            8 => row[index] as u16,
+            16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+            _ => {
+                let bit = index * depth;
+                let shift = 8 - depth - bit % 8;
+                ((row[bit / 8] >> shift) & ((1u8 << depth) - 1)) as u16
+            }
+        }
+    };
+    let max = (1u32 << depth) - 1;
+    let to8 = |v: u16| ((v as u32 * 255 + max / 2) / max) as u8;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = &rows[y * stride..(y + 1) * stride];
+        for x in 0..width {
+            let s = |c: usize| sample(row, x * channels + c);
+            let pixel = match color_type {
+                0 => {
+                    let v = to8(s(0));
+                    Rgba8::new(v, v, v, 255)
+                }
+                2 => Rgba8::new(to8(s(0)), to8(s(1)), to8(s(2)), 255),
+                3 => {
+                    let i = s(0) as usize;
+                    let rgb = palette.get(i * 3..i * 3 + 3).ok_or_else(|| invalid("png: índice de paleta fuera de rango"))?;
+                    let a = transparency.get(i).copied().unwrap_or(255);
+                    Rgba8::new(rgb[0], rgb[1], rgb[2], a)
+                }
+                4 => {
+                    let v = to8(s(0));
+                    Rgba8::new(v, v, v, to8(s(1)))
+                }
+                _ => Rgba8::new(to8(s(0)), to8(s(1)), to8(s(2)), to8(s(3))),
+            };
+            pixels.push(pixel);
+        }
+    }
+
+    Ok(RgbaImage::new(width as i32, height as i32, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: i32, height: i32) -> RgbaImage {
+        let pixels = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                Rgba8::new((x * 7) as u8, (y * 11) as u8, ((x ^ y) * 3) as u8, (255 - x) as u8)
+            })
+            .collect();
+        RgbaImage::new(width, height, pixels)
+    }
+
+    #[test]
+    fn roundtrip_rgba() {
+        let image = gradient(37, 19);
+        let decoded = decode(&encode(&image)).unwrap();
+        assert_eq!((decoded.width, decoded.height), (37, 19));
+        assert_eq!(decoded.pixels, image.pixels);
+    }
+
+    #[test]
+    fn decodes_palette_assets() {
+        // brick.png es de paleta (tipo 3), blackstone.png es RGBA
+        let dir = env!("CARGO_MANIFEST_DIR");
+        for name in ["brick.png", "blackstone.png"] {
+            let image = read(&format!("{}/assets/{}", dir, name)).unwrap();
+            assert_eq!((image.width, image.height), (16, 16));
+            assert!(image.pixels.iter().any(|p| *p != image.pixels[0]));
+        }
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(decode(b"no es un png").is_err());
+        let mut bytes = encode(&gradient(4, 4));
+        bytes.truncate(bytes.len() / 2);
+        assert!(decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,786 @@
+// Primitivas analíticas además de Sphere y Cube: plano infinito (suelo), quad y disco finitos,
+// cilindro y cono con tapas, toro y cápsula. Todas se definen en espacio local con el eje y como
+// eje de simetría (el plano, el quad y el disco miran hacia +y) y se ubican con un centro y una
+// rotación en grados con el mismo orden que Cube: x primero, luego y.
+//
+// Cada una calcula los cruces del rayo (en espacio local) con su superficie; el choque es el
+// primero delante del origen. Las cerradas (cilindro, cono, cápsula y toro) también devuelven sus
+// tramos para CSG. Todas dan su propia UV: (x, z) en unidades de mundo para el plano, [0,1]² para
+// el quad y el disco, y (ángulo, altura) para las de revolución.
+
+use crate::bvh::Aabb;
+use crate::material::Material;
+use crate::math::{Mat4, Vec2, Vec3};
+use crate::ray_intersect::{HitInfo, RayIntersect, Span};
+use std::f32::consts::TAU;
+
+/// Dónde está una primitiva: centro en mundo y rotación (sin escala, así t es el mismo en local)
+#[derive(Clone)]
+pub struct Placement {
+    pub center: Vec3,
+    rotation: Mat4,
+    inverse: Mat4,
+}
+
+impl Placement {
+    /// `rotation` en grados alrededor de x y de y, como Shape::Cube
+    pub fn new(center: Vec3, rotation: Vec2) -> Self {
+        let rotation = Mat4::rotation_y(rotation.y.to_radians()) * Mat4::rotation_x(rotation.x.to_radians());
+        Placement { center, rotation, inverse: rotation.transpose() }
+    }
+
+    /// Rayo en espacio local (la dirección sigue siendo unitaria)
+    pub fn to_local(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> (Vec3, Vec3) {
+        (self.inverse.transform_vector(*ray_origin - self.center), self.inverse.transform_vector(*ray_direction))
+    }
+
+    pub fn point_to_world(&self, p: Vec3) -> Vec3 {
+        self.center + self.rotation.transform_vector(p)
+    }
+
+    pub fn vector_to_world(&self, v: Vec3) -> Vec3 {
+        self.rotation.transform_vector(v)
+    }
+
+    /// Caja en mundo de la caja local [-half, half]
+    pub fn bounds(&self, half: Vec3) -> Aabb {
+        Aabb::from_points((0..8).map(|i| {
+            let sign = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+            self.point_to_world(Vec3::new(sign(1) * half.x, sign(2) * half.y, sign(4) * half.z))
+        }))
+    }
+}
+
+// cruce del rayo con la superficie, en espacio local
+#[derive(Clone, Copy)]
+struct Crossing {
+    t: f32,
+    point: Vec3,
+    normal: Vec3,
+    uv: Vec2,
+}
+
+trait Analytic {
+    // si es un sólido cerrado (los cruces alternan entrada y salida)
+    const SOLID: bool;
+    fn placed(&self) -> (&Placement, &Material);
+    // mitad de la caja local que la contiene
+    fn half_extent(&self) -> Vec3;
+    fn texture_repeat(&self) -> Vec2 {
+        Vec2::new(1.0, 1.0)
+    }
+    // cruces del rayo local con la superficie, en cualquier orden
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing>;
+}
+
+fn sorted_crossings<T: Analytic>(shape: &T, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Crossing> {
+    let (o, d) = shape.placed().0.to_local(ray_origin, ray_direction);
+    let mut crossings = shape.crossings(o, d);
+    crossings.sort_by(|a, b| a.t.total_cmp(&b.t));
+    crossings
+}
+
+fn to_hit<T: Analytic>(shape: &T, c: &Crossing) -> HitInfo {
+    let (placement, material) = shape.placed();
+    HitInfo {
+        hit: true,
+        point: placement.point_to_world(c.point),
+        local_point: c.point,
+        local_half_size: shape.half_extent(),
+        normal: placement.vector_to_world(c.normal).normalized(),
+        local_normal: c.normal,
+        distance: c.t,
+        material: material.clone(),
+        texture_repeat: shape.texture_repeat(),
+        uv: Some(c.uv),
+    }
+}
+
+fn nearest<T: Analytic>(shape: &T, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+    let crossings = sorted_crossings(shape, ray_origin, ray_direction);
+    crossings.iter().find(|c| c.t >= 0.0).map(|c| to_hit(shape, c))
+}
+
+fn spans<T: Analytic>(shape: &T, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+    let crossings = sorted_crossings(shape, ray_origin, ray_direction);
+    // con un número impar de cruces el rayo rozó la superficie: no se puede armar tramos
+    if !T::SOLID || !crossings.len().is_multiple_of(2) {
+        return Vec::new();
+    }
+    crossings
+        .chunks(2)
+        .filter(|pair| pair[1].t >= 0.0)
+        .map(|pair| Span { enter: to_hit(shape, &pair[0]), exit: to_hit(shape, &pair[1]) })
+        .collect()
+}
+
+// u = ángulo alrededor del eje y en [0,1]
+fn angle_u(p: Vec3) -> f32 {
+    p.z.atan2(p.x) / TAU + 0.5
+}
+
+// raíces de a t² + 2 b t + c (la forma con b a medias evita factores 2 y 4)
+fn half_quadratic(a: f32, b: f32, c: f32) -> Vec<f32> {
+    if a.abs() < 1e-12 {
+        return if b.abs() < 1e-12 { Vec::new() } else { vec![-c / (2.0 * b)] };
+    }
+    let disc = b * b - a * c;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+    let root = disc.sqrt();
+    vec![(-b - root) / a, (-b + root) / a]
+}
+
+// cruce con el disco de radio r en la altura y, mirando hacia `up` (±1)
+fn cap(o: Vec3, d: Vec3, y: f32, r: f32, up: f32) -> Option<Crossing> {
+    if d.y.abs() < 1e-12 {
+        return None;
+    }
+    let t = (y - o.y) / d.y;
+    let point = o + d * t;
+    (point.x * point.x + point.z * point.z <= r * r).then(|| Crossing {
+        t,
+        point,
+        normal: Vec3::new(0.0, up, 0.0),
+        uv: Vec2::new(point.x / (2.0 * r) + 0.5, point.z / (2.0 * r) + 0.5),
+    })
+}
+
+// cruces con la esfera de centro (0, y, 0) y radio r
+fn sphere_crossings(o: Vec3, d: Vec3, y: f32, r: f32) -> Vec<(f32, Vec3, Vec3)> {
+    let oc = o - Vec3::new(0.0, y, 0.0);
+    half_quadratic(d.dot(d), oc.dot(d), oc.dot(oc) - r * r)
+        .into_iter()
+        .map(|t| {
+            let point = o + d * t;
+            (t, point, (point - Vec3::new(0.0, y, 0.0)) * (1.0 / r))
+        })
+        .collect()
+}
+
+// cruces con el cilindro infinito de radio r alrededor del eje y
+fn side_crossings(o: Vec3, d: Vec3, r: f32) -> Vec<(f32, Vec3)> {
+    half_quadratic(d.x * d.x + d.z * d.z, o.x * d.x + o.z * d.z, o.x * o.x + o.z * o.z - r * r)
+        .into_iter()
+        .map(|t| (t, o + d * t))
+        .collect()
+}
+
+/// Raíces reales de c[0] + c[1] t + c[2] t² + ... en [lo, hi]. Entre dos extremos seguidos el
+/// polinomio es monótono, así que alcanza con bisección; los extremos son las raíces de la
+/// derivada, que se buscan igual (recursivamente hasta llegar a grado 1).
+fn poly_roots(c: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    match c {
+        [] | [_] => Vec::new(),
+        [c0, c1] => {
+            let t = -c0 / c1;
+            if *c1 != 0.0 && (lo..=hi).contains(&t) { vec![t] } else { Vec::new() }
+        }
+        _ => {
+            let eval = |t: f64| c.iter().rev().fold(0.0, |acc, &k| acc * t + k);
+            let derivative: Vec<f64> = c.iter().enumerate().skip(1).map(|(i, &k)| k * i as f64).collect();
+            let mut points = vec![lo];
+            points.extend(poly_roots(&derivative, lo, hi));
+            points.push(hi);
+            let mut roots = Vec::new();
+            for w in points.windows(2) {
+                let (mut a, mut b) = (w[0], w[1]);
+                let (fa, fb) = (eval(a), eval(b));
+                // una raíz justo en un extremo se cuenta en el intervalo que termina ahí
+                if fb == 0.0 {
+                    roots.push(b);
+                    continue;
+                }
+                if fa * fb >= 0.0 {
+                    continue;
+                }
+                for _ in 0..64 {
+                    let m = 0.5 * (a + b);
+                    if (eval(m) < 0.0) == (fa < 0.0) {
+                        a = m;
+                    } else {
+                        b = m;
+                    }
+                }
+                roots.push(0.5 * (a + b));
+            }
+            roots
+        }
+    }
+}
+
+/// Plano infinito (y = 0 en local, normal +y), p.ej. el suelo
+#[derive(Clone)]
+pub struct Plane {
+    pub placement: Placement,
+    pub material: Material,
+}
+
+impl Plane {
+    pub fn new(center: Vec3, rotation: Vec2, material: Material) -> Self {
+        Plane { placement: Placement::new(center, rotation), material }
+    }
+}
+
+impl Analytic for Plane {
+    const SOLID: bool = false;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        Vec3::new(1.0, 0.0, 1.0)
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        cap(o, d, 0.0, f32::INFINITY, 1.0)
+            .map(|c| Crossing { uv: Vec2::new(c.point.x, c.point.z), ..c })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Disco de radio `radius` en el plano y = 0 local, normal +y
+#[derive(Clone)]
+pub struct Disk {
+    pub placement: Placement,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, rotation: Vec2, radius: f32, material: Material) -> Self {
+        Disk { placement: Placement::new(center, rotation), radius, material }
+    }
+}
+
+impl Analytic for Disk {
+    const SOLID: bool = false;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        Vec3::new(self.radius, 0.0, self.radius)
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        cap(o, d, 0.0, self.radius, 1.0).into_iter().collect()
+    }
+}
+
+/// Rectángulo de 2·half_width × 2·half_depth (x por z) en el plano y = 0 local, normal +y.
+/// La textura se repite una vez por unidad de largo, como en Cube.
+#[derive(Clone)]
+pub struct Quad {
+    pub placement: Placement,
+    pub half_width: f32,
+    pub half_depth: f32,
+    pub material: Material,
+}
+
+impl Quad {
+    pub fn new(center: Vec3, rotation: Vec2, half_width: f32, half_depth: f32, material: Material) -> Self {
+        Quad { placement: Placement::new(center, rotation), half_width, half_depth, material }
+    }
+}
+
+impl Analytic for Quad {
+    const SOLID: bool = false;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        Vec3::new(self.half_width, 0.0, self.half_depth)
+    }
+
+    fn texture_repeat(&self) -> Vec2 {
+        Vec2::new((self.half_width * 2.0).max(1.0), (self.half_depth * 2.0).max(1.0))
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        let (w, h) = (self.half_width, self.half_depth);
+        cap(o, d, 0.0, f32::INFINITY, 1.0)
+            .filter(|c| c.point.x.abs() <= w && c.point.z.abs() <= h)
+            .map(|c| Crossing { uv: Vec2::new((c.point.x + w) / (2.0 * w), (c.point.z + h) / (2.0 * h)), ..c })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Cilindro con tapas alrededor del eje y local, de y = -half_height a y = half_height
+#[derive(Clone)]
+pub struct Cylinder {
+    pub placement: Placement,
+    pub radius: f32,
+    pub half_height: f32,
+    pub material: Material,
+}
+
+impl Cylinder {
+    pub fn new(center: Vec3, rotation: Vec2, radius: f32, half_height: f32, material: Material) -> Self {
+        Cylinder { placement: Placement::new(center, rotation), radius, half_height, material }
+    }
+}
+
+impl Analytic for Cylinder {
+    const SOLID: bool = true;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        Vec3::new(self.radius, self.half_height, self.radius)
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        let (r, h) = (self.radius, self.half_height);
+        let mut crossings: Vec<Crossing> = side_crossings(o, d, r)
+            .into_iter()
+            .filter(|(_, p)| p.y.abs() <= h)
+            .map(|(t, p)| Crossing {
+                t,
+                point: p,
+                normal: Vec3::new(p.x / r, 0.0, p.z / r),
+                uv: Vec2::new(angle_u(p), (p.y + h) / (2.0 * h)),
+            })
+            .collect();
+        crossings.extend(cap(o, d, h, r, 1.0));
+        crossings.extend(cap(o, d, -h, r, -1.0));
+        crossings
+    }
+}
+
+/// Cono con la base (radio `radius`) en y = -half_height y la punta en y = half_height
+#[derive(Clone)]
+pub struct Cone {
+    pub placement: Placement,
+    pub radius: f32,
+    pub half_height: f32,
+    pub material: Material,
+}
+
+impl Cone {
+    pub fn new(center: Vec3, rotation: Vec2, radius: f32, half_height: f32, material: Material) -> Self {
+        Cone { placement: Placement::new(center, rotation), radius, half_height, material }
+    }
+}
+
+impl Analytic for Cone {
+    const SOLID: bool = true;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        Vec3::new(self.radius, self.half_height, self.radius)
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        let (r, h) = (self.radius, self.half_height);
+        // x² + z² = k² q², con q = h - y la distancia a la punta en altura
+        let k2 = (r / (2.0 * h)).powi(2);
+        let (qo, qd) = (h - o.y, -d.y);
+        let a = d.x * d.x + d.z * d.z - k2 * qd * qd;
+        let b = o.x * d.x + o.z * d.z - k2 * qo * qd;
+        let c = o.x * o.x + o.z * o.z - k2 * qo * qo;
+        // la cuádrica es un cono doble: solo vale la parte entre la base y la punta
+        let mut crossings: Vec<Crossing> = half_quadratic(a, b, c)
+            .into_iter()
+            .map(|t| (t, o + d * t))
+            .filter(|(_, p)| p.y.abs() <= h)
+            .map(|(t, p)| {
+                let normal = Vec3::new(p.x, k2 * (h - p.y), p.z);
+                let normal = if normal.length() > 1e-6 { normal.normalized() } else { Vec3::new(0.0, 1.0, 0.0) };
+                Crossing { t, point: p, normal, uv: Vec2::new(angle_u(p), (p.y + h) / (2.0 * h)) }
+            })
+            .collect();
+        crossings.extend(cap(o, d, -h, r, -1.0));
+        crossings
+    }
+}
+
+/// Toro alrededor del eje y local: el centro del tubo (radio `minor_radius`) es un círculo de
+/// radio `major_radius` en el plano y = 0
+#[derive(Clone)]
+pub struct Torus {
+    pub placement: Placement,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Material,
+}
+
+impl Torus {
+    pub fn new(center: Vec3, rotation: Vec2, major_radius: f32, minor_radius: f32, material: Material) -> Self {
+        Torus { placement: Placement::new(center, rotation), major_radius, minor_radius, material }
+    }
+}
+
+impl Analytic for Torus {
+    const SOLID: bool = true;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        let outer = self.major_radius + self.minor_radius;
+        Vec3::new(outer, self.minor_radius, outer)
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        // el polinomio se arma desde donde el rayo entra a la caja del toro: con el origen lejos
+        // los coeficientes pierden precisión. La caja se agranda un poco para que ningún cruce
+        // quede justo en el borde del intervalo
+        let half = self.half_extent() + 1e-3;
+        let mut range = (f32::NEG_INFINITY, f32::INFINITY);
+        for (o, d, h) in [(o.x, d.x, half.x), (o.y, d.y, half.y), (o.z, d.z, half.z)] {
+            if d.abs() < 1e-12 {
+                if o.abs() > h {
+                    return Vec::new();
+                }
+                continue;
+            }
+            let (t0, t1) = ((-h - o) / d, (h - o) / d);
+            range = (range.0.max(t0.min(t1)), range.1.min(t0.max(t1)));
+        }
+        if range.0 > range.1 {
+            return Vec::new();
+        }
+        let start = o + d * range.0;
+
+        let (major, minor) = (self.major_radius as f64, self.minor_radius as f64);
+        let (ox, oy, oz) = (start.x as f64, start.y as f64, start.z as f64);
+        let (dx, dy, dz) = (d.x as f64, d.y as f64, d.z as f64);
+        // (|p|² + R² - r²)² = 4 R² (x² + z²) con p = start + d t y |d| = 1
+        let g = ox * dx + oy * dy + oz * dz;
+        let h = ox * ox + oy * oy + oz * oz + major * major - minor * minor;
+        let (a, b, c) = (dx * dx + dz * dz, ox * dx + oz * dz, ox * ox + oz * oz);
+        let r2 = 4.0 * major * major;
+        let coeffs = [h * h - r2 * c, 4.0 * g * h - 2.0 * r2 * b, 4.0 * g * g + 2.0 * h - r2 * a, 4.0 * g, 1.0];
+
+        poly_roots(&coeffs, 0.0, (range.1 - range.0) as f64)
+            .into_iter()
+            .map(|s| {
+                let t = range.0 + s as f32;
+                let p = o + d * t;
+                let ring = Vec3::new(p.x, 0.0, p.z);
+                let ring = if ring.length() > 1e-6 { ring.normalized() } else { Vec3::new(1.0, 0.0, 0.0) };
+                let normal = (p - ring * self.major_radius).normalized();
+                let tube = p.y.atan2(Vec3::new(p.x, 0.0, p.z).length() - self.major_radius);
+                Crossing { t, point: p, normal, uv: Vec2::new(angle_u(p), tube / TAU + 0.5) }
+            })
+            .collect()
+    }
+}
+
+/// Cápsula: cilindro de radio `radius` entre y = ±half_height cerrado con dos semiesferas
+#[derive(Clone)]
+pub struct Capsule {
+    pub placement: Placement,
+    pub radius: f32,
+    pub half_height: f32,
+    pub material: Material,
+}
+
+impl Capsule {
+    pub fn new(center: Vec3, rotation: Vec2, radius: f32, half_height: f32, material: Material) -> Self {
+        Capsule { placement: Placement::new(center, rotation), radius, half_height, material }
+    }
+}
+
+impl Analytic for Capsule {
+    const SOLID: bool = true;
+
+    fn placed(&self) -> (&Placement, &Material) {
+        (&self.placement, &self.material)
+    }
+
+    fn half_extent(&self) -> Vec3 {
+        Vec3::new(self.radius, self.half_height + self.radius, self.radius)
+    }
+
+    fn crossings(&self, o: Vec3, d: Vec3) -> Vec<Crossing> {
+        let (r, h) = (self.radius, self.half_height);
+        // v recorre toda la altura, de la punta de abajo a la de arriba
+        let uv = |p: Vec3| Vec2::new(angle_u(p), (p.y + h + r) / (2.0 * (h + r)));
+        let mut crossings: Vec<Crossing> = side_crossings(o, d, r)
+            .into_iter()
+            .filter(|(_, p)| p.y.abs() <= h)
+            .map(|(t, p)| Crossing { t, point: p, normal: Vec3::new(p.x / r, 0.0, p.z / r), uv: uv(p) })
+            .collect();
+        for up in [1.0, -1.0] {
+            // de cada esfera solo la mitad que queda afuera del cilindro
+            crossings.extend(
+                sphere_crossings(o, d, up * h, r)
+                    .into_iter()
+                    .filter(|(_, p, _)| up * p.y > h)
+                    .map(|(t, point, normal)| Crossing { t, point, normal, uv: uv(point) }),
+            );
+        }
+        crossings
+    }
+}
+
+macro_rules! analytic_intersect {
+    ($($shape:ty),*) => {$(
+        impl RayIntersect for $shape {
+            fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+                nearest(self, ray_origin, ray_direction)
+            }
+
+            fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+                spans(self, ray_origin, ray_direction)
+            }
+
+            fn bounds(&self) -> Option<Aabb> {
+                Some(self.placement.bounds(self.half_extent()).expanded(1e-4))
+            }
+        }
+    )*};
+}
+
+analytic_intersect!(Disk, Quad, Cylinder, Cone, Torus, Capsule);
+
+// el plano no tiene caja: se prueba con todos los rayos
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        nearest(self, ray_origin, ray_direction)
+    }
+}
+
+/// Primitiva del archivo de escena con sus medidas (el centro y la rotación van en Shape)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Primitive {
+    Plane,
+    Disk { radius: f32 },
+    Quad { half_width: f32, half_depth: f32 },
+    Cylinder { radius: f32, half_height: f32 },
+    Cone { radius: f32, half_height: f32 },
+    Torus { major_radius: f32, minor_radius: f32 },
+    Capsule { radius: f32, half_height: f32 },
+}
+
+impl Primitive {
+    /// Tipo de bloque del archivo de escena
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Primitive::Plane => "plane",
+            Primitive::Disk { .. } => "disk",
+            Primitive::Quad { .. } => "quad",
+            Primitive::Cylinder { .. } => "cylinder",
+            Primitive::Cone { .. } => "cone",
+            Primitive::Torus { .. } => "torus",
+            Primitive::Capsule { .. } => "capsule",
+        }
+    }
+
+    /// Primitiva del tipo dado con las medidas en cero (para completarlas al leer el archivo)
+    pub fn from_kind(kind: &str) -> Option<Primitive> {
+        Some(match kind {
+            "plane" => Primitive::Plane,
+            "disk" => Primitive::Disk { radius: 0.0 },
+            "quad" => Primitive::Quad { half_width: 0.0, half_depth: 0.0 },
+            "cylinder" => Primitive::Cylinder { radius: 0.0, half_height: 0.0 },
+            "cone" => Primitive::Cone { radius: 0.0, half_height: 0.0 },
+            "torus" => Primitive::Torus { major_radius: 0.0, minor_radius: 0.0 },
+            "capsule" => Primitive::Capsule { radius: 0.0, half_height: 0.0 },
+            _ => return None,
+        })
+    }
+
+    /// Medidas editables: (clave en el archivo, etiqueta del inspector, valor)
+    pub fn params(&mut self) -> Vec<(&'static str, &'static str, &mut f32)> {
+        match self {
+            Primitive::Plane => Vec::new(),
+            Primitive::Disk { radius } => vec![("radius", "radio", radius)],
+            Primitive::Quad { half_width, half_depth } => {
+                vec![("half_width", "mitad x", half_width), ("half_depth", "mitad z", half_depth)]
+            }
+            Primitive::Cylinder { radius, half_height }
+            | Primitive::Cone { radius, half_height }
+            | Primitive::Capsule { radius, half_height } => {
+                vec![("radius", "radio", radius), ("half_height", "mitad alto", half_height)]
+            }
+            Primitive::Torus { major_radius, minor_radius } => {
+                vec![("major_radius", "radio mayor", major_radius), ("minor_radius", "radio tubo", minor_radius)]
+            }
+        }
+    }
+
+    /// Valores de las medidas, en el orden de `params`
+    pub fn values(&self) -> Vec<(&'static str, f32)> {
+        let mut copy = *self;
+        copy.params().into_iter().map(|(key, _, value)| (key, *value)).collect()
+    }
+
+    pub fn build(&self, center: Vec3, rotation: Vec2, material: Material) -> Box<dyn RayIntersect + Send + Sync> {
+        match *self {
+            Primitive::Plane => Box::new(Plane::new(center, rotation, material)),
+            Primitive::Disk { radius } => Box::new(Disk::new(center, rotation, radius, material)),
+            Primitive::Quad { half_width, half_depth } => Box::new(Quad::new(center, rotation, half_width, half_depth, material)),
+            Primitive::Cylinder { radius, half_height } => Box::new(Cylinder::new(center, rotation, radius, half_height, material)),
+            Primitive::Cone { radius, half_height } => Box::new(Cone::new(center, rotation, radius, half_height, material)),
+            Primitive::Torus { major_radius, minor_radius } => {
+                Box::new(Torus::new(center, rotation, major_radius, minor_radius, material))
+            }
+            Primitive::Capsule { radius, half_height } => Box::new(Capsule::new(center, rotation, radius, half_height, material)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down() -> Vec3 {
+        Vec3::new(0.0, -1.0, 0.0)
+    }
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-4
+    }
+
+    #[test]
+    fn flat_surfaces_hit_within_their_limits() {
+        let m = Material::default;
+        let plane = Plane::new(Vec3::new(0.0, -1.0, 0.0), Vec2::zero(), m());
+        let hit = plane.ray_intersect(&Vec3::new(3.5, 2.0, -7.25), &down()).unwrap();
+        assert!((hit.distance - 3.0).abs() < 1e-5 && close(hit.normal, Vec3::new(0.0, 1.0, 0.0)));
+        assert_eq!(hit.uv, Some(Vec2::new(3.5, -7.25)));
+        assert!(plane.ray_intersect(&Vec3::new(0.0, 2.0, 0.0), &Vec3::new(1.0, 0.0, 0.0)).is_none());
+        assert!(plane.bounds().is_none());
+
+        // disco parado (girado 90° en x): mira hacia +z
+        let disk = Disk::new(Vec3::zero(), Vec2::new(90.0, 0.0), 1.0, m());
+        let hit = disk.ray_intersect(&Vec3::new(0.5, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        assert!((hit.distance - 5.0).abs() < 1e-5 && close(hit.normal, Vec3::new(0.0, 0.0, 1.0)));
+        assert!(disk.ray_intersect(&Vec3::new(0.8, 0.8, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_none());
+
+        let quad = Quad::new(Vec3::zero(), Vec2::zero(), 2.0, 1.0, m());
+        let hit = quad.ray_intersect(&Vec3::new(1.0, 1.0, 0.5), &down()).unwrap();
+        assert!((hit.uv.unwrap() - Vec2::new(0.75, 0.75)).length() < 1e-5);
+        assert_eq!(hit.texture_repeat, Vec2::new(4.0, 2.0));
+        assert!(quad.ray_intersect(&Vec3::new(1.0, 1.0, 1.5), &down()).is_none());
+        let b = quad.bounds().unwrap();
+        assert!(b.min.x < -1.99 && b.max.z > 0.99 && b.max.y < 0.01);
+    }
+
+    #[test]
+    fn cylinder_side_and_caps() {
+        let cylinder = Cylinder::new(Vec3::new(0.0, 1.0, 0.0), Vec2::zero(), 0.5, 1.0, Material::default());
+        let side = cylinder.ray_intersect(&Vec3::new(5.0, 1.5, 0.0), &Vec3::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!((side.distance - 4.5).abs() < 1e-5 && close(side.normal, Vec3::new(1.0, 0.0, 0.0)));
+        assert!((side.uv.unwrap().y - 0.75).abs() < 1e-5);
+        let top = cylinder.ray_intersect(&Vec3::new(0.2, 5.0, 0.0), &down()).unwrap();
+        assert!((top.point.y - 2.0).abs() < 1e-5 && close(top.normal, Vec3::new(0.0, 1.0, 0.0)));
+        // acostado (90° en x) el eje queda sobre z
+        let lying = Cylinder::new(Vec3::zero(), Vec2::new(90.0, 0.0), 0.5, 2.0, Material::default());
+        let hit = lying.ray_intersect(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        assert!((hit.distance - 3.0).abs() < 1e-5);
+        let spans = lying.ray_spans(&Vec3::new(0.0, 5.0, 1.0), &down());
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].enter.point.y - 0.5).abs() < 1e-5 && (spans[0].exit.point.y + 0.5).abs() < 1e-5);
+        assert!(spans[0].exit.normal.y < -0.99);
+    }
+
+    #[test]
+    fn cone_narrows_to_the_tip() {
+        let cone = Cone::new(Vec3::zero(), Vec2::zero(), 1.0, 1.0, Material::default());
+        // a media altura el radio es 0.5
+        let hit = cone.ray_intersect(&Vec3::new(5.0, 0.0, 0.0), &Vec3::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.point.x - 0.5).abs() < 1e-5);
+        let slope = Vec3::new(2.0, 1.0, 0.0).normalized();
+        assert!(close(hit.normal, slope));
+        assert!(cone.ray_intersect(&Vec3::new(5.0, 1.1, 0.0), &Vec3::new(-1.0, 0.0, 0.0)).is_none());
+        let base = cone.ray_intersect(&Vec3::new(0.9, -5.0, 0.0), &Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((base.point.y + 1.0).abs() < 1e-5 && close(base.normal, down()));
+        assert_eq!(cone.ray_spans(&Vec3::new(0.0, 5.0, 0.2), &down()).len(), 1);
+    }
+
+    #[test]
+    fn torus_has_a_hole() {
+        let torus = Torus::new(Vec3::zero(), Vec2::zero(), 2.0, 0.5, Material::default());
+        // por el agujero del medio pasa derecho
+        assert!(torus.ray_intersect(&Vec3::new(0.0, 5.0, 0.0), &down()).is_none());
+        let hit = torus.ray_intersect(&Vec3::new(2.0, 5.0, 0.0), &down()).unwrap();
+        assert!((hit.distance - 4.5).abs() < 1e-4 && close(hit.normal, Vec3::new(0.0, 1.0, 0.0)));
+        // a lo largo del eje x cruza el tubo dos veces
+        let spans = torus.ray_spans(&Vec3::new(-10.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(spans.len(), 2);
+        let xs: Vec<f32> = spans.iter().flat_map(|s| [s.enter.point.x, s.exit.point.x]).collect();
+        for (x, expected) in xs.iter().zip([-2.5, -1.5, 1.5, 2.5]) {
+            assert!((x - expected).abs() < 1e-4, "{:?}", xs);
+        }
+        assert!(close(spans[0].enter.normal, Vec3::new(-1.0, 0.0, 0.0)));
+        assert!(close(spans[0].exit.normal, Vec3::new(1.0, 0.0, 0.0)));
+        // de lado, girado para que el agujero mire hacia z
+        let standing = Torus::new(Vec3::zero(), Vec2::new(90.0, 0.0), 2.0, 0.5, Material::default());
+        assert!(standing.ray_intersect(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_none());
+        assert!(standing.ray_intersect(&Vec3::new(0.0, 2.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_some());
+    }
+
+    #[test]
+    fn capsule_has_round_ends() {
+        let capsule = Capsule::new(Vec3::zero(), Vec2::zero(), 0.5, 1.0, Material::default());
+        let top = capsule.ray_intersect(&Vec3::new(0.0, 5.0, 0.0), &down()).unwrap();
+        assert!((top.point.y - 1.5).abs() < 1e-5 && close(top.normal, Vec3::new(0.0, 1.0, 0.0)));
+        let diagonal = Vec3::new(0.3, 5.0, 0.0);
+        let hit = capsule.ray_intersect(&diagonal, &down()).unwrap();
+        assert!((hit.point.y - (1.0 + 0.4)).abs() < 1e-5);
+        let side = capsule.ray_intersect(&Vec3::new(5.0, 0.9, 0.0), &Vec3::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!((side.point.x - 0.5).abs() < 1e-5 && close(side.normal, Vec3::new(1.0, 0.0, 0.0)));
+        let spans = capsule.ray_spans(&Vec3::new(0.0, 5.0, 0.0), &down());
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].exit.point.y + 1.5).abs() < 1e-5);
+        let b = capsule.bounds().unwrap();
+        assert!((b.max.y - 1.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn origin_inside_hits_the_exit() {
+        let solids: [Box<dyn RayIntersect>; 4] = [
+            Box::new(Cylinder::new(Vec3::zero(), Vec2::zero(), 1.0, 1.0, Material::default())),
+            Box::new(Cone::new(Vec3::zero(), Vec2::zero(), 1.0, 1.0, Material::default())),
+            Box::new(Capsule::new(Vec3::zero(), Vec2::zero(), 1.0, 1.0, Material::default())),
+            Box::new(Torus::new(Vec3::new(-2.0, 0.0, 0.0), Vec2::zero(), 2.0, 0.5, Material::default())),
+        ];
+        for solid in &solids {
+            let hit = solid.ray_intersect(&Vec3::new(0.0, -0.2, 0.0), &Vec3::new(0.0, 0.0, 1.0)).unwrap();
+            // la normal de la salida apunta en la misma dirección que el rayo
+            assert!(hit.distance > 0.0 && hit.normal.z > 0.0);
+        }
+    }
+
+    #[test]
+    fn polynomial_roots() {
+        // (t - 1)(t - 2)(t - 3)(t - 4) = t⁴ - 10t³ + 35t² - 50t + 24
+        let roots = poly_roots(&[24.0, -50.0, 35.0, -10.0, 1.0], 0.0, 10.0);
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+        assert_eq!(poly_roots(&[24.0, -50.0, 35.0, -10.0, 1.0], 1.5, 3.5).len(), 2);
+        assert!(poly_roots(&[1.0, 0.0, 1.0], -5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn primitive_params_roundtrip() {
+        let mut torus = Primitive::from_kind("torus").unwrap();
+        for (_, _, value) in torus.params() {
+            *value = 1.5;
+        }
+        assert_eq!(torus, Primitive::Torus { major_radius: 1.5, minor_radius: 1.5 });
+        assert_eq!(torus.values(), vec![("major_radius", 1.5), ("minor_radius", 1.5)]);
+        assert_eq!(Primitive::from_kind(torus.kind()).map(|p| p.kind()), Some("torus"));
+        assert!(Primitive::Plane.build(Vec3::zero(), Vec2::zero(), Material::default()).bounds().is_none());
+        assert!(Primitive::from_kind("cube").is_none());
+    }
+}
@@ -0,0 +1,311 @@
+// Texturas procedurales: patrones calculados en el punto del impacto (ruido Perlin con fBm,
+// celdas de Worley, damero, anillos de madera, vetas de mármol y degradé) en lugar de leídos de un
+// PNG. Un TextureNode es un árbol chico que combina patrones, colores fijos y texturas de imagen
+// con rampas de color, multiplicaciones y mezclas; Material::pattern lo guarda y
+// renderer::surface_albedo lo evalúa donde antes solo muestreaba la textura.
+//
+// Los patrones devuelven un valor en [0, 1] (un gris); `Ramp` lo pasa a un par de colores.
+
+use crate::math::{Color, Vec2, Vec3};
+use crate::textures::TextureManager;
+use std::hash::{Hash, Hasher};
+
+// De qué punto del impacto sale la posición del patrón
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Coords {
+    // fijo en la escena: el objeto se mueve "a través" del patrón
+    World,
+    // relativo al objeto (HitInfo::local_point): se mueve y gira con él
+    Local,
+    // la UV de la superficie (ya multiplicada por texture_repeat), con z = 0
+    Uv,
+}
+
+impl Coords {
+    pub fn name(self) -> &'static str {
+        match self {
+            Coords::World => "world",
+            Coords::Local => "local",
+            Coords::Uv => "uv",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Coords> {
+        [Coords::World, Coords::Local, Coords::Uv].into_iter().find(|c| c.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    // fBm: `octaves` capas de ruido Perlin, cada una al doble de frecuencia y la mitad de fuerza
+    Noise { scale: f32, octaves: u32 },
+    // distancia al punto de celda más cercano (piedras, escamas, células)
+    Worley { scale: f32 },
+    // cubos alternados de lado 1/scale
+    Checker { scale: f32 },
+    // anillos alrededor del eje y, desordenados por el ruido
+    Wood { scale: f32, turbulence: f32 },
+    // bandas a lo largo de x retorcidas por el ruido
+    Marble { scale: f32, turbulence: f32 },
+    // 0 en el origen y 1 a lo largo de `direction` (su largo es la distancia que tarda en llegar)
+    Gradient { direction: Vec3 },
+}
+
+// a mano por los f32 (por bits, como Vec3)
+impl Hash for Pattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match *self {
+            Pattern::Noise { scale, octaves } => (scale.to_bits(), octaves).hash(state),
+            Pattern::Worley { scale } | Pattern::Checker { scale } => scale.to_bits().hash(state),
+            Pattern::Wood { scale, turbulence } | Pattern::Marble { scale, turbulence } => {
+                (scale.to_bits(), turbulence.to_bits()).hash(state)
+            }
+            Pattern::Gradient { direction } => direction.hash(state),
+        }
+    }
+}
+
+/// Punto de la superficie donde se evalúan los patrones
+pub struct SurfacePoint {
+    pub world: Vec3,
+    pub local: Vec3,
+    // None si la superficie no tiene UV (las texturas de imagen quedan en blanco)
+    pub uv: Option<Vec2>,
+}
+
+impl SurfacePoint {
+    fn coords(&self, coords: Coords) -> Vec3 {
+        match coords {
+            Coords::World => self.world,
+            Coords::Local => self.local,
+            Coords::Uv => self.uv.map_or(Vec3::zero(), |uv| Vec3::new(uv.x, uv.y, 0.0)),
+        }
+    }
+}
+
+// hash entero de una celda de la grilla (mismo valor para la misma celda en cualquier máquina)
+fn hash(x: i32, y: i32, z: i32, salt: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841) ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= salt.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+fn unit_float(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+// uno de los 12 gradientes de Perlin (las aristas del cubo)
+fn gradient_dot(h: u32, x: f32, y: f32, z: f32) -> f32 {
+    match h % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Ruido Perlin (mejorado) en `p`, aproximadamente en [-1, 1] y 0 en los puntos enteros
+pub fn perlin(p: Vec3) -> f32 {
+    let cell = [p.x.floor(), p.y.floor(), p.z.floor()];
+    let (x, y, z) = (p.x - cell[0], p.y - cell[1], p.z - cell[2]);
+    let (i, j, k) = (cell[0] as i32, cell[1] as i32, cell[2] as i32);
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient_dot(hash(i + dx, j + dy, k + dz, 0), x - dx as f32, y - dy as f32, z - dz as f32)
+    };
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+/// Suma de `octaves` capas de perlin, normalizada a [-1, 1] aproximadamente
+pub fn fbm(p: Vec3, octaves: u32) -> f32 {
+    let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 1.0, 1.0, 0.0);
+    for _ in 0..octaves.max(1) {
+        sum += perlin(p * frequency) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+/// Distancia de `p` al punto de celda más cercano (uno al azar por celda unitaria)
+pub fn worley(p: Vec3) -> f32 {
+    let cell = [p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32];
+    let mut nearest = f32::INFINITY;
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                let (i, j, k) = (cell[0] + dx, cell[1] + dy, cell[2] + dz);
+                let feature = Vec3::new(
+                    i as f32 + unit_float(hash(i, j, k, 1)),
+                    j as f32 + unit_float(hash(i, j, k, 2)),
+                    k as f32 + unit_float(hash(i, j, k, 3)),
+                );
+                nearest = nearest.min((feature - p).length());
+            }
+        }
+    }
+    nearest
+}
+
+impl Pattern {
+    /// Valor del patrón en `p`, en [0, 1]
+    pub fn value(&self, p: Vec3) -> f32 {
+        let value = match *self {
+            Pattern::Noise { scale, octaves } => 0.5 + 0.5 * fbm(p * scale, octaves),
+            Pattern::Worley { scale } => worley(p * scale),
+            Pattern::Checker { scale } => {
+                let q = p * scale;
+                let sum = q.x.floor() as i64 + q.y.floor() as i64 + q.z.floor() as i64;
+                if sum.rem_euclid(2) == 0 { 0.0 } else { 1.0 }
+            }
+            Pattern::Wood { scale, turbulence } => {
+                let rings = Vec2::new(p.x, p.z).length() * scale + turbulence * fbm(p * 2.0, 3);
+                rings - rings.floor()
+            }
+            Pattern::Marble { scale, turbulence } => 0.5 + 0.5 * (p.x * scale + turbulence * fbm(p, 5) * 4.0).sin(),
+            Pattern::Gradient { direction } => {
+                let length2 = direction.dot(direction);
+                if length2 <= 0.0 { 0.0 } else { p.dot(direction) / length2 }
+            }
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub enum TextureNode {
+    Color(Color),
+    // PNG cargado en el TextureManager, muestreado en la UV
+    Image(String),
+    Pattern { pattern: Pattern, coords: Coords },
+    // pasa el gris de la entrada (su luminancia) de `low` a `high`
+    Ramp { input: Box<TextureNode>, low: Color, high: Color },
+    Multiply(Box<TextureNode>, Box<TextureNode>),
+    // `a` donde el factor es 0, `b` donde es 1 (se usa su luminancia)
+    Mix { a: Box<TextureNode>, b: Box<TextureNode>, factor: Box<TextureNode> },
+}
+
+impl TextureNode {
+    pub fn evaluate(&self, at: &SurfacePoint, textures: &TextureManager) -> Color {
+        match self {
+            TextureNode::Color(color) => *color,
+            TextureNode::Image(path) => at.uv.map_or(Color::WHITE, |uv| textures.sample_uv(path, uv.x, uv.y)),
+            TextureNode::Pattern { pattern, coords } => Color::splat(pattern.value(at.coords(*coords))),
+            TextureNode::Ramp { input, low, high } => low.lerp(*high, input.evaluate(at, textures).luminance().clamp(0.0, 1.0)),
+            TextureNode::Multiply(a, b) => a.evaluate(at, textures) * b.evaluate(at, textures),
+            TextureNode::Mix { a, b, factor } => {
+                let t = factor.evaluate(at, textures).luminance().clamp(0.0, 1.0);
+                a.evaluate(at, textures).lerp(b.evaluate(at, textures), t)
+            }
+        }
+    }
+
+    /// Rutas de las texturas de imagen que usa el árbol (para cargarlas y vigilarlas)
+    pub fn image_paths(&self) -> Vec<&str> {
+        match self {
+            TextureNode::Image(path) => vec![path.as_str()],
+            TextureNode::Color(_) | TextureNode::Pattern { .. } => Vec::new(),
+            TextureNode::Ramp { input, .. } => input.image_paths(),
+            TextureNode::Multiply(a, b) => [a.image_paths(), b.image_paths()].concat(),
+            TextureNode::Mix { a, b, factor } => [a.image_paths(), b.image_paths(), factor.image_paths()].concat(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::RgbaImage;
+    use crate::math::Rgba8;
+    use crate::textures::CpuTexture;
+
+    fn at(world: Vec3) -> SurfacePoint {
+        SurfacePoint { world, local: world - Vec3::new(10.0, 0.0, 0.0), uv: Some(Vec2::new(world.x, world.y)) }
+    }
+
+    #[test]
+    fn noise_is_smooth_and_repeatable() {
+        let p = Vec3::new(1.3, -2.7, 0.45);
+        assert_eq!(perlin(p), perlin(p));
+        assert_eq!(perlin(Vec3::new(2.0, -3.0, 7.0)), 0.0);
+        // cerca da valores cercanos
+        assert!((perlin(p) - perlin(p + Vec3::new(1e-3, 0.0, 0.0))).abs() < 1e-2);
+        let samples: Vec<f32> = (0..200).map(|i| fbm(Vec3::new(i as f32 * 0.37, i as f32 * 0.11, 0.5), 4)).collect();
+        assert!(samples.iter().all(|v| v.abs() <= 1.0));
+        assert!(samples.iter().any(|v| *v > 0.1) && samples.iter().any(|v| *v < -0.1));
+        // cada celda tiene su punto: nunca más lejos que la diagonal
+        assert!((0..100).all(|i| worley(Vec3::new(i as f32 * 0.29, 0.3, i as f32 * 0.13)) < 3f32.sqrt()));
+    }
+
+    #[test]
+    fn patterns_stay_in_range() {
+        let checker = Pattern::Checker { scale: 1.0 };
+        assert_eq!(checker.value(Vec3::new(0.5, 0.5, 0.5)), 0.0);
+        assert_eq!(checker.value(Vec3::new(1.5, 0.5, 0.5)), 1.0);
+        assert_eq!(checker.value(Vec3::new(-0.5, 0.5, 0.5)), 1.0);
+        let gradient = Pattern::Gradient { direction: Vec3::new(0.0, 4.0, 0.0) };
+        assert_eq!(gradient.value(Vec3::new(3.0, 2.0, 0.0)), 0.5);
+        assert_eq!(gradient.value(Vec3::new(0.0, 9.0, 0.0)), 1.0);
+        // sin turbulencia los anillos de madera son la distancia al eje
+        let wood = Pattern::Wood { scale: 2.0, turbulence: 0.0 };
+        assert!((wood.value(Vec3::new(0.3, 5.0, 0.4)) - 0.0).abs() < 1e-5);
+        assert!((wood.value(Vec3::new(0.0, 5.0, 0.6)) - 0.2).abs() < 1e-5);
+        for pattern in [
+            Pattern::Noise { scale: 3.0, octaves: 5 },
+            Pattern::Worley { scale: 2.0 },
+            Pattern::Wood { scale: 4.0, turbulence: 2.0 },
+            Pattern::Marble { scale: 2.0, turbulence: 3.0 },
+        ] {
+            assert!((0..100).map(|i| pattern.value(Vec3::new(i as f32 * 0.17, i as f32 * 0.05, -1.3))).all(|v| (0.0..=1.0).contains(&v)));
+        }
+    }
+
+    #[test]
+    fn nodes_combine_patterns_and_images() {
+        let mut textures = TextureManager::new();
+        let image = RgbaImage { width: 1, height: 1, pixels: vec![Rgba8::new(255, 0, 0, 255)] };
+        textures.insert("rojo.png", CpuTexture::from_rgba(&image));
+
+        let checker = TextureNode::Pattern { pattern: Pattern::Checker { scale: 1.0 }, coords: Coords::Local };
+        let ramp = TextureNode::Ramp { input: Box::new(checker), low: Color::splat(0.2), high: Color::WHITE };
+        let node = TextureNode::Multiply(Box::new(ramp), Box::new(TextureNode::Image("rojo.png".into())));
+        // en local (x - 10) el damero cambia en el mismo lugar que en el mundo
+        assert_eq!(node.evaluate(&at(Vec3::new(10.5, 0.5, 0.5)), &textures), Color::new(0.2, 0.0, 0.0));
+        assert_eq!(node.evaluate(&at(Vec3::new(11.5, 0.5, 0.5)), &textures), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(node.image_paths(), vec!["rojo.png"]);
+
+        let mix = TextureNode::Mix {
+            a: Box::new(TextureNode::Color(Color::BLACK)),
+            b: Box::new(TextureNode::Color(Color::WHITE)),
+            factor: Box::new(TextureNode::Pattern { pattern: Pattern::Gradient { direction: Vec3::new(0.0, 2.0, 0.0) }, coords: Coords::Uv }),
+        };
+        let color = mix.evaluate(&at(Vec3::new(0.0, 1.0, 0.0)), &textures);
+        assert!((color.r - 0.5).abs() < 1e-5);
+        // sin UV las imágenes quedan en blanco
+        let no_uv = SurfacePoint { uv: None, ..at(Vec3::zero()) };
+        assert_eq!(TextureNode::Image("rojo.png".into()).evaluate(&no_uv, &textures), Color::WHITE);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::math::Color;
+use std::sync::Arc;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+use crate::textures::TextureManager;
+use crate::tiles::{RenderJob, TileOrder};
+use crate::{to_pixel_color, PassSettings, TracedPixel};
+
+// Etapas del refinamiento progresivo
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Stage {
+    // hay input: vista previa en bloques grandes, tamaño adaptado al tiempo objetivo
+    Preview,
+    // sin input: bajar el tamaño de bloque a la mitad en cada frame
+    Refine(i32),
+    // resolución completa: primera muestra (con AOVs)
+    Full,
+    // sumar muestras con desplazamiento subpíxel
+    Accumulate,
+    Idle,
+}
+
+// Render adaptativo: mientras se mueve la cámara dibuja a baja resolución para no congelar la
+// ventana; cuando el input se detiene refina hasta resolución completa y luego acumula muestras.
+// Cada etapa es un RenderJob por tiles en segundo plano; los tiles se copian al framebuffer
+// a medida que terminan. Mover la cámara no cancela una vista previa a medio hacer (si no, con
+// input continuo ninguna terminaría): se la deja acabar y recién ahí se lanza la siguiente.
+pub struct Progressive {
+    pub target_frame_time: f32, // segundos por frame de vista previa
+    pub max_samples: u32,
+    pub max_block: i32,
+    pub preview_block: i32,     // tamaño de bloque actual (se adapta solo)
+    pub last_frame_time: f32,   // duración del último frame completo, para mostrar estadísticas
+    pub tile_order: TileOrder,
+    stage: Stage,
+    job: Option<RenderJob>,
+    stale: bool, // la vista previa en curso es de una cámara vieja: al terminar se repite
+
+    accumulation: Vec<Color>,
+    samples: u32,
+}
+
+// secuencia de Halton (base b) para repartir las muestras dentro del píxel
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut f = 1.0;
+    let mut r = 0.0;
+    while index > 0 {
+        f /= base as f32;
+        r += f * (index % base) as f32;
+        index /= base;
+    }
+    r
+}
+
+impl Progressive {
+    pub fn new(target_frame_time: f32) -> Self {
+        Progressive {
+            target_frame_time,
+            max_samples: 8,
+            max_block: 8,
+            preview_block: 4,
+            last_frame_time: 0.0,
+            tile_order: TileOrder::Spiral,
+            stage: Stage::Preview,
+            job: None,
+            stale: false,
+            accumulation: Vec::new(),
+            samples: 0,
+        }
+    }
+
+    /// Cambió la escena: cancela el frame en curso y vuelve a la vista previa. Al volver, ningún
+    /// worker tiene referencias a la escena.
+    pub fn restart(&mut self) {
+        if let Some(mut job) = self.job.take() {
+            if self.stage == Stage::Preview {
+                // una vista previa cortada también sirve para adaptar el bloque: con lo que llegó
+                // a hacer se estima cuánto habría tardado entera
+                let (elapsed, progress) = (job.elapsed(), job.progress());
+                if progress > 0.0 {
+                    self.adapt_preview(elapsed / progress);
+                } else if elapsed > self.target_frame_time * 1.1 {
+                    self.adapt_preview(f32::INFINITY);
+                }
+            }
+            job.cancel();
+        }
+        self.stage = Stage::Preview;
+        self.stale = false;
+        self.samples = 0;
+    }
+
+    /// Se movió la cámara: si hay una vista previa en curso se la deja terminar y se repite con
+    /// la cámara nueva; cualquier otra etapa se cancela como en restart.
+    pub fn camera_moved(&mut self) {
+        if self.stage == Stage::Preview && self.job.is_some() {
+            self.stale = true;
+        } else {
+            self.restart();
+        }
+    }
+
+    // acerca el bloque de la vista previa al tiempo objetivo según lo que tardó (o tardaría) un frame
+    fn adapt_preview(&mut self, frame_time: f32) {
+        if frame_time > self.target_frame_time * 1.1 && self.preview_block < self.max_block {
+            self.preview_block += 1;
+        } else if frame_time < self.target_frame_time * 0.5 && self.preview_block > 1 {
+            self.preview_block -= 1;
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.stage == Stage::Idle
+    }
+
+    /// true cuando el framebuffer tiene una imagen a resolución completa (con AOVs al día)
+    pub fn is_full_resolution(&self) -> bool {
+        matches!(self.stage, Stage::Accumulate | Stage::Idle)
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Progreso del frame en curso (None si no hay nada renderizándose)
+    pub fn progress(&self) -> Option<f32> {
+        self.job.as_ref().map(|job| job.progress())
+    }
+
+    /// Bloque del frame en curso (1 = resolución completa)
+    pub fn current_block(&self) -> i32 {
+        match self.stage {
+            Stage::Preview => self.preview_block,
+            Stage::Refine(block) => block,
+            _ => 1,
+        }
+    }
+
+    fn pass_for_stage(&self, want_aovs: bool) -> Option<PassSettings> {
+        match self.stage {
+            Stage::Idle => None,
+            Stage::Preview => Some(PassSettings { block: self.preview_block.max(1), ..PassSettings::full() }),
+            Stage::Refine(block) => Some(PassSettings { block, ..PassSettings::full() }),
+            Stage::Full => Some(PassSettings { want_aovs, ..PassSettings::full() }),
+            Stage::Accumulate => {
+                let jitter = (halton(self.samples, 2), halton(self.samples, 3));
+                Some(PassSettings { jitter, ..PassSettings::full() })
+            }
+        }
+    }
+
+    /// Lanza el job de la etapa actual si hace falta y copia al framebuffer los tiles que ya
+    /// terminaron. Devuelve true cuando acaba de completarse una imagen a resolución completa.
+    pub fn update(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        scene: &Arc<Scene>,
+        camera: &Camera,
+        texture_manager: &Arc<TextureManager>,
+    ) -> bool {
+        if self.job.is_none() {
+            // la vista previa con bloque 1 ya es el frame completo
+            if self.stage == Stage::Preview && self.preview_block <= 1 {
+                self.stage = Stage::Full;
+            }
+            let Some(pass) = self.pass_for_stage(framebuffer.aovs.is_some()) else { return false; };
+            if self.stage == Stage::Full {
+                self.accumulation = vec![Color::BLACK; (framebuffer.width * framebuffer.height) as usize];
+            }
+            self.job = Some(RenderJob::spawn(
+                scene.clone(),
+                texture_manager.clone(),
+                camera.clone(),
+                (framebuffer.width, framebuffer.height),
+                pass,
+                self.tile_order,
+            ));
+        }
+
+        let Some(job) = self.job.as_mut() else { return false; };
+        let pass = job.pass;
+        let tiles = job.take_finished();
+        let finished = job.is_finished().then(|| job.elapsed());
+        for pixels in &tiles {
+            self.apply_tile(framebuffer, pixels, pass);
+        }
+        let Some(elapsed) = finished else { return false; };
+
+        // frame terminado: pasar a la siguiente etapa
+        self.last_frame_time = elapsed;
+        self.job = None;
+        match self.stage {
+            Stage::Preview => {
+                self.adapt_preview(elapsed);
+                if self.stale {
+                    // la cámara se movió mientras tanto: otra vista previa desde la posición nueva
+                    self.stale = false;
+                    return false;
+                }
+                let block = pass.block / 2;
+                self.stage = if block > 1 { Stage::Refine(block) } else { Stage::Full };
+                false
+            }
+            Stage::Refine(block) => {
+                self.stage = if block / 2 > 1 { Stage::Refine(block / 2) } else { Stage::Full };
+                false
+            }
+            Stage::Full => {
+                self.samples = 1;
+                self.stage = if self.max_samples > 1 { Stage::Accumulate } else { Stage::Idle };
+                true
+            }
+            Stage::Accumulate => {
+                self.samples += 1;
+                if self.samples >= self.max_samples {
+                    self.stage = Stage::Idle;
+                }
+                true
+            }
+            Stage::Idle => false,
+        }
+    }
+
+    fn apply_tile(&mut self, framebuffer: &mut Framebuffer, pixels: &[TracedPixel], pass: PassSettings) {
+        let width = framebuffer.width;
+        match self.stage {
+            Stage::Preview | Stage::Refine(_) => fill_blocks(framebuffer, pixels, pass.block),
+            Stage::Full => {
+                for (x, y, color, aov) in pixels {
+                    self.accumulation[(y * width + x) as usize] = *color;
+                    framebuffer.set_current_color(to_pixel_color(*color));
+                    framebuffer.set_pixel(*x, *y);
+                    if let (Some(aovs), Some(sample)) = (framebuffer.aovs.as_mut(), aov) {
+                        aovs.set(*x, *y, sample);
+                    }
+                }
+            }
+            Stage::Accumulate => {
+                // esta muestra es la número samples + 1
+                let inv = 1.0 / (self.samples + 1) as f32;
+                for (x, y, color, _) in pixels {
+                    let index = (y * width + x) as usize;
+                    self.accumulation[index] += *color;
+                    framebuffer.set_current_color(to_pixel_color(self.accumulation[index] * inv));
+                    framebuffer.set_pixel(*x, *y);
+                }
+            }
+            Stage::Idle => {}
+        }
+    }
+}
+
+// pinta cada muestra en todo su bloque (recortando en los bordes)
+fn fill_blocks(framebuffer: &mut Framebuffer, pixels: &[TracedPixel], block: i32) {
+    for (x, y, color, _) in pixels {
+        framebuffer.set_current_color(to_pixel_color(*color));
+        for by in *y..(*y + block).min(framebuffer.height) {
+            for bx in *x..(*x + block).min(framebuffer.width) {
+                framebuffer.set_pixel(bx, by);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daycycle::DayCycle;
+    use crate::material::Material;
+    use crate::math::{Rgba8, Vec3};
+    use crate::sphere::Sphere;
+
+    fn setup() -> (Framebuffer, Arc<Scene>, Camera, Arc<TextureManager>) {
+        let sphere = Sphere { center: Vec3::new(0.0, 0.0, 0.0), radius: 1.0, material: Material::default() };
+        let scene = Scene::new(vec![Box::new(sphere)], Vec::new(), DayCycle::new(12.0));
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        (Framebuffer::new(64, 48, Rgba8::BLACK), Arc::new(scene), camera, Arc::new(TextureManager::new()))
+    }
+
+    #[test]
+    fn cancelled_previews_still_adapt_the_block() {
+        // con un tiempo objetivo de 0 cualquier vista previa es lenta: aunque ninguna llegue a
+        // terminar, el bloque tiene que crecer hasta el máximo
+        let (mut framebuffer, scene, camera, textures) = setup();
+        let mut progressive = Progressive::new(0.0);
+        for _ in 0..8 {
+            progressive.update(&mut framebuffer, &scene, &camera, &textures);
+            progressive.restart();
+        }
+        assert_eq!(progressive.preview_block, progressive.max_block);
+    }
+
+    #[test]
+    fn camera_motion_lets_the_preview_finish() {
+        let (mut framebuffer, scene, camera, textures) = setup();
+        let mut progressive = Progressive::new(0.0);
+        progressive.update(&mut framebuffer, &scene, &camera, &textures);
+        progressive.camera_moved();
+        assert!(progressive.progress().is_some(), "la vista previa no debería cancelarse");
+        while progressive.progress().is_some() {
+            progressive.update(&mut framebuffer, &scene, &camera, &textures);
+        }
+        // terminó, adaptó el bloque y sigue en vista previa (no refina una imagen vieja)
+        assert_eq!(progressive.preview_block, 5);
+        assert_eq!(progressive.current_block(), progressive.preview_block);
+    }
+}
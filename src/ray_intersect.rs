@@ -1,18 +1,42 @@
-use raylib::prelude::*;
+use crate::bvh::Aabb;
+use crate::math::{Vec2, Vec3};
 use crate::material::Material;
 
+#[derive(Clone)]
 pub struct HitInfo {
     pub hit: bool,
-    pub point: Vector3,        // punto en espacio mundo
-    pub local_point: Vector3,  // punto en espacio local del objeto (útil para UVs)
-    pub local_half_size: Vector3, // <-- nuevo: half_size del objeto en local (útil para mapeo)
-    pub normal: Vector3,       // normal en espacio mundo
-    pub local_normal: Vector3, // normal en espacio local (útil para decidir cara)
+    pub point: Vec3,        // punto en espacio mundo
+    pub local_point: Vec3,  // punto en espacio local del objeto (útil para UVs)
+    pub local_half_size: Vec3, // <-- nuevo: half_size del objeto en local (útil para mapeo)
+    pub normal: Vec3,       // normal en espacio mundo
+    pub local_normal: Vec3, // normal en espacio local (útil para decidir cara)
     pub distance: f32,
     pub material: Material,
-    pub texture_repeat: Vector2, // cuantas repeticiones aplicar (x: u, y: v)
+    pub texture_repeat: Vec2, // cuantas repeticiones aplicar (x: u, y: v)
+    pub uv: Option<Vec2>,     // uv ya calculada por el objeto (modelos de bloque); None = map_uv_for_cube
 }
 
 pub trait RayIntersect {
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<HitInfo>;
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo>;
+
+    /// Caja en espacio mundo que contiene al objeto, para el BVH de la escena.
+    /// None = sin límites (se prueba con todos los rayos).
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// Tramos del rayo dentro del objeto, ordenados y sin solaparse, para combinarlo en un nodo
+    /// CSG (ver src/csg.rs). Solo se devuelven los que terminan delante del origen; la entrada
+    /// puede tener t negativo si el origen está adentro. Las normales apuntan hacia afuera del
+    /// objeto también en la salida. Vacío = el objeto no es un sólido cerrado.
+    fn ray_spans(&self, _ray_origin: &Vec3, _ray_direction: &Vec3) -> Vec<Span> {
+        Vec::new()
+    }
+}
+
+/// Tramo de un rayo dentro de un sólido: el choque donde entra y donde sale
+#[derive(Clone)]
+pub struct Span {
+    pub enter: HitInfo,
+    pub exit: HitInfo,
 }
@@ -0,0 +1,521 @@
+use rayon::prelude::*;
+use std::sync::Arc;
+use crate::ao::{self, AoSettings};
+use crate::aov::{AovBuffers, AovSample};
+use crate::camera::Camera;
+use crate::daycycle::DayCycle;
+use crate::fog;
+use crate::framebuffer::Framebuffer;
+use crate::math::{Color, Rgba8, Vec2, Vec3};
+use crate::procedural::SurfacePoint;
+use crate::ray_intersect::HitInfo;
+use crate::scene::Scene;
+use crate::subsurface;
+use crate::textures::TextureManager;
+use crate::tiles::Tile;
+
+pub fn reflect(i: &Vec3, n: &Vec3) -> Vec3 {
+    *i - *n * 2.0 * i.dot(*n)
+}
+
+pub fn refract(incident: &Vec3, normal: &Vec3, refractive_index: f32) -> Vec3 {
+    // Implementation of Snell's Law for refraction.
+    // It calculates the direction of a ray as it passes from one medium to another.
+
+    // `cosi` is the cosine of the angle between the incident ray and the normal.
+    // We clamp it to the [-1, 1] range to avoid floating point errors.
+    let mut cosi = incident.dot(*normal).clamp(-1.0, 1.0);
+
+    // `etai` is the refractive index of the medium the ray is currently in.
+    // `etat` is the refractive index of the medium the ray is entering.
+    // `n` is the normal vector, which may be flipped depending on the ray's direction.
+    let mut etai = 1.0; // Assume we are in Air (or vacuum) initially
+    let mut etat = refractive_index;
+    let mut n = *normal;
+
+    if cosi > 0.0 {
+        // The ray is inside the medium (e.g., glass) and going out into the air.
+        // We need to swap the refractive indices.
+        std::mem::swap(&mut etai, &mut etat);
+        // We also flip the normal so it points away from the medium.
+        n = -n;
+    } else {
+        // The ray is outside the medium and going in.
+        // We need a positive cosine for the calculation, so we negate it.
+        cosi = -cosi;
+    }
+
+    // `eta` is the ratio of the refractive indices (n1 / n2).
+    let eta = etai / etat;
+    // `k` is a term derived from Snell's law that helps determine if total internal reflection occurs.
+    let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
+
+    if k < 0.0 {
+        // If k is negative, it means total internal reflection has occurred.
+        // There is no refracted ray, so we return None.
+        Vec3::zero()
+    } else {
+        // If k is non-negative, we can calculate the direction of the refracted ray.
+        *incident * eta + n * (eta * cosi - k.sqrt())
+    }
+}
+
+
+
+pub fn get_cube_uv(hit_point: Vec3, normal: Vec3) -> (f32, f32) {
+    let (u, v) = if normal.x.abs() > 0.5 {
+        // Cara derecha o izquierda
+        ((hit_point.z + 0.5), (hit_point.y + 0.5))
+    } else if normal.y.abs() > 0.5 {
+        // Cara superior o inferior
+        ((hit_point.x + 0.5), (hit_point.z + 0.5))
+    } else {
+        // Cara delantera o trasera
+        ((hit_point.x + 0.5), (hit_point.y + 0.5))
+    };
+
+    (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+}
+
+pub fn map_uv_for_cube(local_point: &Vec3, local_normal: &Vec3, half_size: &Vec3) -> Option<(f32, f32)> {
+    // local_point está en coordenadas locales (ej: x in [-hx, +hx])
+    let p = *local_point;
+    let hx = half_size.x;
+    let hy = half_size.y;
+    let hz = half_size.z;
+
+    // evita dividir por cero
+    if hx.abs() < 1e-6 || hy.abs() < 1e-6 || hz.abs() < 1e-6 {
+        return None;
+    }
+
+    if local_normal.x.abs() > 0.9 {
+        // cara izquierda/derecha: u = z, v = y
+        let u = (p.z + hz) / (2.0 * hz); // map z from [-hz, hz] -> [0,1]
+        let v = (p.y + hy) / (2.0 * hy); // map y from [-hy, hy] -> [0,1]
+        Some((u, v))
+    } else if local_normal.y.abs() > 0.9 {
+        // cara top/bottom: u = x, v = z
+        let u = (p.x + hx) / (2.0 * hx);
+        let v = (p.z + hz) / (2.0 * hz);
+        Some((u, v))
+    } else if local_normal.z.abs() > 0.9 {
+        // cara front/back: u = x, v = y
+        let u = (p.x + hx) / (2.0 * hx);
+        let v = (p.y + hy) / (2.0 * hy);
+        Some((u, v))
+    } else {
+        None
+    }
+}
+
+
+
+
+// UV del impacto sin repetir: la que da el objeto (modelos de bloque) o la del mapeo de cubo
+pub fn hit_uv(hit: &HitInfo) -> Option<(f32, f32)> {
+    match hit.uv {
+        Some(uv) => Some((uv.x, uv.y)),
+        None => map_uv_for_cube(&hit.local_point, &hit.local_normal, &hit.local_half_size),
+    }
+}
+
+// Punto donde se evalúan los patrones y los nodos de material (UV ya con la repetición)
+pub fn surface_point(hit: &HitInfo) -> SurfacePoint {
+    let uv = hit_uv(hit).map(|(u, v)| Vec2::new(u * hit.texture_repeat.x, v * hit.texture_repeat.y));
+    SurfacePoint { world: hit.point, local: hit.local_point, uv }
+}
+
+// Color base de la superficie: el patrón procedural del material, o su difuso o, si tiene
+// textura, el texel en su UV
+pub fn surface_albedo(hit: &HitInfo, texture_manager: &TextureManager) -> Color {
+    let m = &hit.material;
+    if let Some(pattern) = &m.pattern {
+        return pattern.evaluate(&surface_point(hit), texture_manager);
+    }
+    let mut base_color = m.diffuse;
+
+    if let Some(texture_path) = &m.texture_path
+        && let Some((u_raw, v_raw)) = hit_uv(hit)
+    {
+        let u_scaled = u_raw * hit.texture_repeat.x;
+        let v_scaled = v_raw * hit.texture_repeat.y;
+        base_color = texture_manager.sample_uv(texture_path, u_scaled, v_scaled);
+    }
+    base_color
+}
+
+// Hit primario tal como lo encontró cast_ray (para llenar los AOVs sin volver a trazar)
+pub struct PrimaryHit {
+    pub object: usize, // índice del objeto en scene.objects
+    pub hit: HitInfo,
+    pub albedo: Color, // color base que dio el grafo del material al sombrear
+}
+
+// Valores de los AOVs para el rayo primario (origen en la cámara)
+pub fn primary_aov(primary: Option<&PrimaryHit>) -> AovSample {
+    match primary {
+        Some(PrimaryHit { object, hit, albedo }) => {
+            let (u, v) = hit_uv(hit).unwrap_or((0.0, 0.0));
+            AovSample {
+                depth: hit.distance,
+                normal: hit.normal,
+                albedo: *albedo,
+                material_id: hit.material.id(),
+                object_id: *object as u32,
+                uv: Vec2::new(u, v),
+            }
+        }
+        None => AovSample::miss(),
+    }
+}
+
+pub fn cast_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    depth: u32,
+    texture_manager: &TextureManager,
+) -> Color {
+    shade_ray(ray_origin, ray_direction, scene, depth, texture_manager).0
+}
+
+// Igual que cast_ray, pero también devuelve el hit más cercano (None en el cielo o si se pasó
+// de profundidad)
+pub fn shade_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    depth: u32,
+    texture_manager: &TextureManager,
+) -> (Color, Option<PrimaryHit>) {
+    if depth > 3 {
+        return (Color::new(0.1, 0.1, 0.2), None); // sky
+    }
+
+    // Buscar el hit más cercano
+    let closest_hit = scene.closest_hit_indexed(ray_origin, ray_direction);
+    let t_hit = closest_hit.as_ref().map(|(_, hit)| hit.distance);
+
+    let (color, primary) = if let Some((object, hit)) = closest_hit {
+        let m = &hit.material;
+
+        // vista (dirección del ojo)
+        let view_dir = (*ray_origin - hit.point).normalized();
+
+        // color base, brillo, emisión y normal de sombreado desde el grafo del material (el de
+        // siempre si no tiene uno: difuso y/o textura)
+        let shading = m.shader().evaluate(&hit, &view_dir, texture_manager);
+        let base_color = shading.base_color;
+        let normal = shading.normal;
+
+        // Ambient (luz suave general, evita que todo sea negro); depende de la hora del día
+        // y se oscurece en esquinas y rendijas según la oclusión ambiental
+        let occlusion = if scene.ao.enabled {
+            ao::ambient_occlusion(scene, &scene.ao, &hit.point, &hit.normal)
+        } else {
+            1.0
+        };
+        let a = scene.day.ambient() * occlusion;
+        let ambient = Color::splat(a);
+
+        // acumuladores de iluminación
+        let mut total_diffuse = ambient * base_color; // start with ambient * base color
+        let mut total_specular = Color::BLACK;
+
+        // recorrer todas las luces
+        for light in scene.lights.iter() {
+            // dirección hacia la luz (normalizada) y distancia (infinita para el sol)
+            let Some((light_dir, dist)) = light.incidence(&hit.point) else { continue; };
+
+            // test de sombra: si hay algo entre el punto y la luz, atenua
+            let shadow_origin = hit.point + hit.normal * 5e-3; // mejor epsilon
+            let in_shadow = scene.intersects_any(&shadow_origin, &light_dir, dist - 1e-3);
+
+            let attenuation = light.attenuation(dist);
+
+            // si está en sombra: ponemos una fracción residual (para evitar negro absoluto)
+            let shadow_factor = if in_shadow { 0.15 } else { 1.0 };
+
+            // difuso (Lambert)
+            let ndotl = normal.dot(light_dir).max(0.0);
+            total_diffuse += base_color * ndotl * attenuation * light.color * shadow_factor;
+
+            // especular (Blinn-Phong)
+            let half = (view_dir + light_dir).normalized();
+            let ndoth = normal.dot(half).max(0.0);
+            let spec = ndoth.powf(shading.specular);
+            total_specular += light.color * spec * attenuation * shadow_factor;
+        }
+
+        // Cáusticas: irradiancia estimada desde el mapa de fotones (luz enfocada por vidrio/agua)
+        if let Some(photon_map) = &scene.photon_map
+            && m.transparency < 1.0
+        {
+            total_diffuse += base_color * photon_map.irradiance(&hit.point, &hit.normal);
+        }
+
+        // Translucidez: parte del difuso es luz que entró, viajó dentro del volumen y salió
+        if let Some(sss) = &m.subsurface {
+            let inner = subsurface::random_walk(scene, &hit, sss) * base_color;
+            total_diffuse = total_diffuse * (1.0 - sss.weight) + inner * sss.weight;
+        }
+
+        // Reflection recursiva
+        let mut reflection_color = Color::new(0.1, 0.1, 0.2);
+        if m.reflectivity > 0.0 {
+            let rdir = reflect(ray_direction, &normal).normalized();
+            let rorigin = hit.point + hit.normal * 1e-3;
+            reflection_color = cast_ray(&rorigin, &rdir, scene, depth + 1, texture_manager);
+        }
+
+        // Refraction recursiva
+        let mut refraction_color = Color::BLACK;
+        if m.transparency > 0.0 {
+            let refr_dir = refract(ray_direction, &hit.normal, m.refractive_index);
+            let refr_dir = refr_dir.normalized();
+            let rorigin = hit.point - hit.normal * 1e-3;
+            refraction_color = cast_ray(&rorigin, &refr_dir, scene, depth + 1, texture_manager);
+        }
+
+        // Emisión del material (si tiene); de noche los emisivos pesan más
+        let emitted = shading.emission * scene.day.emissive_scale();
+
+        // Composición final (clamp implícito en conversión a color)
+        let color = total_diffuse * m.albedo[0]
+            + total_specular * m.albedo[1]
+            + reflection_color * m.reflectivity
+            + refraction_color * m.transparency
+            + emitted;
+        (color, Some(PrimaryHit { object, hit, albedo: base_color }))
+    } else {
+        (procedural_sky(*ray_direction, &scene.day), None)
+    };
+
+    // niebla / medios participativos entre el origen y el hit (solo rayos primarios y primer rebote)
+    let color = if depth <= 1 {
+        fog::integrate(scene, ray_origin, ray_direction, t_hit, color)
+    } else {
+        color
+    };
+    (color, primary)
+}
+
+
+
+pub fn procedural_sky(dir: Vec3, day: &DayCycle) -> Color {
+    let d = dir.normalized();
+    let t = (d.y + 1.0) * 0.5; // map y [-1,1] → [0,1]
+
+    // colores del gradiente según la hora (día, atardecer, noche)
+    let palette = day.palette();
+    let green = palette.ground;  // grass green
+    let white = palette.horizon; // horizon haze
+    let blue = palette.zenith;   // sky blue
+
+    let gradient = if t < 0.54 {
+        // Bottom → fade green to white
+        let k = t / 0.55;
+        green * (1.0 - k) + white * k
+    } else if t < 0.55 {
+        // Around horizon → mostly white
+        white
+    } else if t < 0.8 {
+        // Fade white to blue
+        let k = (t - 0.55) / (0.25);
+        white * (1.0 - k) + blue * k
+    } else {
+        // Upper sky → solid blue
+        blue
+    };
+
+    // sol, luna y estrellas encima del gradiente
+    gradient + day.celestial(d)
+}
+
+// Color final de un píxel a partir del color lineal del rayo
+pub fn to_pixel_color(ray_color: Color) -> Rgba8 {
+    ray_color.to_rgba8()
+}
+
+// Un píxel trazado: coordenadas, color lineal y (opcionalmente) sus AOVs
+pub type TracedPixel = (i32, i32, Color, Option<AovSample>);
+
+// Cómo se traza un frame
+#[derive(Clone, Copy)]
+pub struct PassSettings {
+    pub block: i32,          // un rayo por bloque de block×block píxeles (1 = resolución completa)
+    pub jitter: (f32, f32),  // desplazamiento subpíxel (para acumular muestras)
+    pub want_aovs: bool,
+}
+
+impl PassSettings {
+    pub fn full() -> Self {
+        PassSettings { block: 1, jitter: (0.0, 0.0), want_aovs: false }
+    }
+}
+
+/// Traza un rayo por cada bloque (ver PassSettings). Devuelve la esquina de cada bloque.
+pub fn trace_frame(
+    width_i: i32,
+    height_i: i32,
+    scene: &Scene,
+    camera: &Camera,
+    texture_manager: &TextureManager,
+    pass: PassSettings,
+) -> Vec<TracedPixel> {
+    let full = Tile { x0: 0, y0: 0, x1: width_i, y1: height_i };
+    trace_region(width_i, height_i, &full, scene, camera, texture_manager, pass)
+}
+
+/// Igual que trace_frame pero solo para los bloques cuya esquina cae dentro de `tile`
+/// (las esquinas están alineadas a múltiplos de `block` en toda la imagen).
+pub fn trace_region(
+    width_i: i32,
+    height_i: i32,
+    tile: &Tile,
+    scene: &Scene,
+    camera: &Camera,
+    texture_manager: &TextureManager,
+    pass: PassSettings,
+) -> Vec<TracedPixel> {
+    let width = width_i as f32;
+    let height = height_i as f32;
+    let block = pass.block.max(1);
+    let jitter = pass.jitter;
+    let want_aovs = pass.want_aovs;
+    let align = |v: i32| (v + block - 1) / block * block;
+
+    // Iterador paralelo: para cada fila (y) en paralelo
+    (align(tile.y0)..tile.y1)
+        .into_par_iter()
+        .step_by(block as usize)
+        .flat_map(|y| {
+            (align(tile.x0)..tile.x1).into_par_iter().step_by(block as usize).map(move |x| {
+                // en modo bloque el rayo pasa por el centro del bloque
+                let offset = if block > 1 { (block as f32 * 0.5, block as f32 * 0.5) } else { jitter };
+                let ray = camera.primary_ray(x as f32 + offset.0, y as f32 + offset.1, width, height);
+
+                // las luces (sol/luna + antorchas) vienen de la escena
+                let (ray_color, primary) = shade_ray(&ray.origin, &ray.direction, scene, 0, texture_manager);
+
+                // pases auxiliares del hit primario (solo si el framebuffer los pidió)
+                let aov = if want_aovs {
+                    Some(primary_aov(primary.as_ref()))
+                } else {
+                    None
+                };
+
+                (x, y, ray_color, aov)
+            })
+        })
+        .collect()
+}
+
+/// Objeto visible en el píxel (x, y) (índice en scene.objects) y su hit, con el mismo rayo
+/// primario que usa render() para ese píxel
+pub fn pick(scene: &Scene, camera: &Camera, x: i32, y: i32, width: i32, height: i32) -> Option<(usize, HitInfo)> {
+    let ray = camera.primary_ray(x as f32, y as f32, width as f32, height as f32);
+    scene.closest_hit_indexed(&ray.origin, &ray.direction)
+}
+
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, texture_manager: &TextureManager) {
+    let pass = PassSettings { want_aovs: framebuffer.aovs.is_some(), ..PassSettings::full() };
+    let pixels = trace_frame(framebuffer.width, framebuffer.height, scene, camera, texture_manager, pass);
+
+    for (x, y, color, aov) in pixels {
+        framebuffer.set_current_color(to_pixel_color(color));
+        framebuffer.set_pixel(x, y);
+        if let (Some(aovs), Some(sample)) = (framebuffer.aovs.as_mut(), aov) {
+            aovs.set(x, y, &sample);
+        }
+    }
+}
+
+// Pase de oclusión ambiental: escala de grises (blanco = abierto, negro = ocluido) de los hits
+// primarios, para exportarlo y componer aparte. El cielo queda en blanco.
+pub fn render_ao(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
+    let width_i = framebuffer.width;
+    let height_i = framebuffer.height;
+    let width = width_i as f32;
+    let height = height_i as f32;
+
+    let pixels: Vec<(i32, i32, Rgba8)> = (0..height_i)
+        .into_par_iter()
+        .flat_map(|y| {
+            (0..width_i).into_par_iter().map(move |x| {
+                // el mismo rayo primario que trace_region, para que el pase calce con el beauty
+                let ray = camera.primary_ray(x as f32, y as f32, width, height);
+                let occlusion = match scene.closest_hit(&ray.origin, &ray.direction) {
+                    Some(hit) => ao::ambient_occlusion(scene, &scene.ao, &hit.point, &hit.normal),
+                    None => 1.0,
+                };
+                let v = (occlusion.clamp(0.0, 1.0) * 255.0) as u8;
+                (x, y, Rgba8::new(v, v, v, 255))
+            })
+        })
+        .collect();
+
+    for (x, y, color) in pixels {
+        framebuffer.set_current_color(color);
+        framebuffer.set_pixel(x, y);
+    }
+}
+
+// Igual que render_ao pero en espacio de pantalla (ver ao::screen_space_occlusion): sale de
+// la profundidad y la normal de un frame ya trazado con AOVs, sin rayos nuevos.
+pub fn render_ssao(framebuffer: &mut Framebuffer, aovs: &AovBuffers, camera: &Camera, settings: &AoSettings) {
+    let occlusion = ao::screen_space_occlusion(aovs, camera, settings);
+    for (i, open) in occlusion.into_iter().enumerate() {
+        let v = (open.clamp(0.0, 1.0) * 255.0) as u8;
+        framebuffer.set_current_color(Rgba8::new(v, v, v, 255));
+        framebuffer.set_pixel(i as i32 % aovs.width, i as i32 / aovs.width);
+    }
+}
+
+
+// Punto de entrada para quien embebe el renderer: junta las texturas con las funciones de
+// trazado y devuelve imágenes en memoria, sin ventana.
+pub struct Renderer {
+    pub texture_manager: Arc<TextureManager>,
+}
+
+impl Renderer {
+    pub fn new(texture_manager: Arc<TextureManager>) -> Self {
+        Renderer { texture_manager }
+    }
+
+    /// Color lineal (sin recortar) de un rayo cualquiera
+    pub fn trace(&self, origin: &Vec3, direction: &Vec3, scene: &Scene) -> Color {
+        cast_ray(origin, direction, scene, 0, &self.texture_manager)
+    }
+
+    /// Imagen de `width`×`height` en color lineal, fila por fila
+    pub fn render_linear(&self, scene: &Scene, camera: &Camera, width: i32, height: i32) -> Vec<Color> {
+        let mut buffer = vec![Color::BLACK; (width * height).max(0) as usize];
+        for (x, y, color, _) in trace_frame(width, height, scene, camera, &self.texture_manager, PassSettings::full()) {
+            buffer[(y * width + x) as usize] = color;
+        }
+        buffer
+    }
+
+    /// Imagen de `width`×`height` lista para mostrar o guardar (RGBA8), fila por fila
+    pub fn render_to_buffer(&self, scene: &Scene, camera: &Camera, width: i32, height: i32) -> Vec<Rgba8> {
+        self.render_linear(scene, camera, width, height).into_iter().map(to_pixel_color).collect()
+    }
+
+    /// Renderiza en un framebuffer (con sus AOVs si están activos)
+    pub fn render(&self, framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
+        render(framebuffer, scene, camera, &self.texture_manager);
+    }
+
+    /// Pase de oclusión ambiental en un framebuffer (ver render_ao)
+    pub fn render_ao(&self, framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
+        render_ao(framebuffer, scene, camera);
+    }
+
+    /// Pase de oclusión en espacio de pantalla a partir de AOVs ya trazados (ver render_ssao)
+    pub fn render_ssao(&self, framebuffer: &mut Framebuffer, aovs: &AovBuffers, camera: &Camera, settings: &AoSettings) {
+        render_ssao(framebuffer, aovs, camera, settings);
+    }
+}
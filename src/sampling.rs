@@ -0,0 +1,69 @@
+use crate::math::Vec3;
+use std::f32::consts::PI;
+
+// Generador pseudoaleatorio pequeño (PCG32). Se siembra de forma determinista (por píxel,
+// por punto de impacto, etc.) para que el render sea reproducible entre ejecuciones.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Semilla a partir de un punto (p.ej. el punto de impacto) y un valor extra.
+    pub fn from_point(p: &Vec3, salt: u32) -> Self {
+        let h = (p.x.to_bits() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (p.y.to_bits() as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (p.z.to_bits() as u64).wrapping_mul(0x1656_67B1_9E37_79F9)
+            ^ (salt as u64).wrapping_mul(0x27D4_EB2F_1656_67C5);
+        Rng::new(h)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    /// Número uniforme en [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / 16_777_216.0
+    }
+}
+
+/// Dirección uniforme en la esfera unitaria
+pub fn uniform_sphere(rng: &mut Rng) -> Vec3 {
+    let z = 1.0 - 2.0 * rng.next_f32();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.next_f32();
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Base ortonormal (t, b) alrededor de `n`
+pub fn orthonormal_basis(n: &Vec3) -> (Vec3, Vec3) {
+    let helper = if n.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let t = helper.cross(*n).normalized();
+    let b = n.cross(t);
+    (t, b)
+}
+
+/// Dirección en el hemisferio de `n` con densidad proporcional a cos(theta)
+pub fn cosine_hemisphere(n: &Vec3, rng: &mut Rng) -> Vec3 {
+    let r1 = rng.next_f32();
+    let r2 = rng.next_f32();
+    let r = r1.sqrt();
+    let phi = 2.0 * PI * r2;
+    let (t, b) = orthonormal_basis(n);
+    (t * (r * phi.cos()) + b * (r * phi.sin()) + *n * (1.0 - r1).max(0.0).sqrt()).normalized()
+}
@@ -0,0 +1,154 @@
+use crate::math::Vec3;
+use crate::ray_intersect::{RayIntersect, HitInfo};
+use crate::light::{Light, LightKind};
+use crate::daycycle::DayCycle;
+use crate::fog::FogVolume;
+use crate::photon_map::{PhotonMap, PhotonSettings};
+use crate::ao::AoSettings;
+use crate::bvh::Bvh;
+
+pub struct Scene {
+    // si se modifican directamente hay que llamar a rebuild_bvh (o usar set_objects / replace_object)
+    pub objects: Vec<Box<dyn RayIntersect + Send + Sync>>,
+    // luces fijas de la escena (antorchas, etc.); el sol/luna se agrega según la hora
+    pub static_lights: Vec<Light>,
+    // luces activas para el frame actual (se recalculan con update_lights)
+    pub lights: Vec<Light>,
+    pub day: DayCycle,
+    // medios participativos (niebla); se integran con ray marching en cast_ray
+    pub fog: Vec<FogVolume>,
+    pub fog_enabled: bool,
+    pub fog_steps: u32,
+    // mapa de fotones para cáusticas (None = desactivadas)
+    pub photon_map: Option<PhotonMap>,
+    pub photon_settings: PhotonSettings,
+    pub ao: AoSettings,
+    // aceleración de los rayos sobre `objects`
+    bvh: Bvh,
+}
+
+impl Scene {
+    pub fn new(objects: Vec<Box<dyn RayIntersect + Send + Sync>>, static_lights: Vec<Light>, day: DayCycle) -> Self {
+        let mut scene = Scene {
+            objects,
+            static_lights,
+            lights: Vec::new(),
+            day,
+            fog: Vec::new(),
+            fog_enabled: true,
+            fog_steps: 24,
+            photon_map: None,
+            photon_settings: PhotonSettings::default(),
+            ao: AoSettings::default(),
+            bvh: Bvh::default(),
+        };
+        scene.rebuild_bvh();
+        scene.update_lights();
+        scene
+    }
+
+    pub fn set_time_of_day(&mut self, time: f32) {
+        self.day.set_time(time);
+        self.update_lights();
+    }
+
+    /// Recalcula las luces activas: sol/luna según la hora + luces puntuales escaladas
+    /// por el peso de los emisivos (de noche las antorchas destacan más).
+    pub fn update_lights(&mut self) {
+        let emissive_scale = self.day.emissive_scale();
+        self.lights.clear();
+        self.lights.push(self.day.sun_light());
+        for light in &self.static_lights {
+            let mut l = *light;
+            if l.kind == LightKind::Point {
+                l.intensity *= emissive_scale;
+            }
+            self.lights.push(l);
+        }
+    }
+
+    /// (Re)construye el mapa de fotones con las luces actuales. Hay que llamarlo cuando
+    /// cambian las luces o la geometría si las cáusticas están activas.
+    pub fn build_photon_map(&mut self) {
+        let map = PhotonMap::build(self, self.photon_settings);
+        self.photon_map = Some(map);
+    }
+
+    /// Como build_photon_map pero con PhotonSettings::preview: cáusticas aproximadas mientras
+    /// la hora o las luces se siguen moviendo
+    pub fn build_preview_photon_map(&mut self) {
+        let map = PhotonMap::build(self, self.photon_settings.preview());
+        self.photon_map = Some(map);
+    }
+
+    pub fn clear_photon_map(&mut self) {
+        self.photon_map = None;
+    }
+
+    /// Reconstruye el BVH desde cero con las cajas actuales de `objects`
+    pub fn rebuild_bvh(&mut self) {
+        let bounds: Vec<_> = self.objects.iter().map(|o| o.bounds()).collect();
+        self.bvh = Bvh::build(&bounds);
+    }
+
+    pub fn set_objects(&mut self, objects: Vec<Box<dyn RayIntersect + Send + Sync>>) {
+        self.objects = objects;
+        self.rebuild_bvh();
+    }
+
+    /// Reemplaza un objeto (p.ej. uno que se está moviendo) actualizando el BVH sin reconstruirlo
+    pub fn replace_object(&mut self, index: usize, object: Box<dyn RayIntersect + Send + Sync>) {
+        self.bvh.update(index, object.bounds());
+        self.objects[index] = object;
+    }
+
+    /// Hit más cercano entre todos los objetos
+    pub fn closest_hit(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        self.closest_hit_indexed(ray_origin, ray_direction).map(|(_, hit)| hit)
+    }
+
+    /// Igual que closest_hit pero también devuelve el índice del objeto en `objects`
+    pub fn closest_hit_indexed(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(usize, HitInfo)> {
+        let mut closest_hit: Option<(usize, HitInfo)> = None;
+        self.bvh.traverse(ray_origin, ray_direction, f32::INFINITY, |index, t_max| {
+            if let Some(hit) = self.objects[index].ray_intersect(ray_origin, ray_direction) {
+                // a igual distancia gana el de menor índice, como si se recorrieran en orden
+                let closer = match &closest_hit {
+                    None => true,
+                    Some((best, best_hit)) => hit.distance < best_hit.distance || (hit.distance == best_hit.distance && index < *best),
+                };
+                if closer {
+                    *t_max = hit.distance;
+                    closest_hit = Some((index, hit));
+                }
+            }
+            false
+        });
+        closest_hit
+    }
+
+    // Util para comprobar si hay cualquier intersección entre origin y origin + dir*max_dist
+    pub fn intersects_any(&self, origin: &Vec3, direction: &Vec3, max_dist: f32) -> bool {
+        let mut found = false;
+        self.bvh.traverse(origin, direction, max_dist, |index, _| {
+            found = self.objects[index].ray_intersect(origin, direction).is_some_and(|hit| hit.distance < max_dist);
+            found
+        });
+        found
+    }
+
+    /// Fracción de luz que atraviesa los objetos entre origin y origin + dir*max_dist:
+    /// los opacos la bloquean y los transparentes (vidrio, agua) dejan pasar `transparency`.
+    pub fn shadow_transmittance(&self, origin: &Vec3, direction: &Vec3, max_dist: f32) -> f32 {
+        let mut transmittance = 1.0;
+        self.bvh.traverse(origin, direction, max_dist, |index, _| {
+            if let Some(hit) = self.objects[index].ray_intersect(origin, direction)
+                && hit.distance < max_dist
+            {
+                transmittance *= hit.material.transparency;
+            }
+            transmittance <= 0.0
+        });
+        transmittance.max(0.0)
+    }
+}
@@ -0,0 +1,1648 @@
+// Archivos de escena (.scene): un formato de texto simple para describir materiales, objetos,
+// luces, niebla, cámara y hora del día sin recompilar. Cada bloque es `tipo [nombre] {`, una
+// propiedad por línea (`clave valores...`) y `}`; `#` comenta hasta el final de la línea.
+//
+//     time 10.5
+//
+//     material ladrillo {
+//         diffuse 0.7 0.31 0.23
+//         specular 16
+//         texture assets/brick.png
+//     }
+//
+//     cube techo {
+//         center 0 4.1 0
+//         half_size 4 1 4
+//         rotation 0 0        # grados alrededor de x y de y
+//         material ladrillo
+//     }
+//
+// En lugar de un color o una imagen, el color base puede salir de una textura procedural (ver
+// src/procedural.rs) armada en postfijo con líneas `pattern`: los patrones (`noise`, `worley`,
+// `checker`, `wood`, `marble`, `gradient`, seguidos de `world`, `local` o `uv` y sus valores),
+// `color r g b` e `image ruta` se apilan, `ramp` colorea el de arriba y `multiply` y `mix [t]`
+// combinan los de arriba:
+//
+//     material marmol {
+//         pattern marble world 2 1.5     # escala y turbulencia
+//         pattern ramp 0.2 0.2 0.25 0.95 0.93 0.9
+//         pattern image assets/brick.png
+//         pattern mix 0.2                # 80 % mármol, 20 % ladrillo
+//     }
+//
+// Para más control un material puede armar un grafo de nodos (ver src/shader.rs): cada `node
+// nombre tipo valores` lee nodos anteriores por nombre (o números), y `output` conecta uno a
+// `base_color`, `roughness`, `emission` o `normal`; lo que no se conecta sale de las demás
+// propiedades. Los tipos son `attribute` (albedo, emission, uv, position, local o normal), los
+// patrones de arriba, `image ruta uv`, `uv_transform uv sx sy grados ox oy`, `add`, `subtract`,
+// `multiply`, `divide`, `power`, `min` y `max` (a b), `mix a b factor` y `fresnel ior`:
+//
+//     material azulejo {
+//         diffuse 0.2 0.4 0.7
+//         node color attribute albedo
+//         node uv attribute uv
+//         node girada uv_transform uv 4 4 45 0 0
+//         node juntas image assets/juntas.png girada
+//         node base multiply color juntas
+//         node brillo fresnel 1.5
+//         node rugosidad mix 0.6 0.1 brillo      # más pulido en los bordes
+//         output base_color base
+//         output roughness rugosidad
+//     }
+//
+// Las primitivas analíticas (src/primitives.rs) usan `center` y `rotation` como el cubo más sus
+// medidas: `plane` (ninguna), `disk` (radius), `quad` (half_width half_depth), `cylinder`, `cone`
+// y `capsule` (radius half_height) y `torus` (major_radius minor_radius), todas mayores que 0:
+//
+//     cylinder tronco {
+//         center 0 1 0
+//         radius 0.3
+//         half_height 1
+//         material madera
+//     }
+//
+// Un bloque `model` ubica un modelo de bloque de Minecraft (src/block_model.rs) en el cubo de
+// 1×1×1 alrededor de `center`; `texture` da el valor de las variables de textura del modelo:
+//
+//     model escalon {
+//         center 0.5 0.5 2.5
+//         model stairs              # incluido o ruta a un .json del juego
+//         rotation 0 90
+//         texture side assets/brick.png
+//         material ladrillo         # las caras sin textura propia usan la del material
+//     }
+//
+// Un bloque `csg` combina dos objetos con nombre definidos antes (ver src/csg.rs), que pasan a
+// ser parte de él y no se dibujan solos; cada parte conserva su material:
+//
+//     csg ventana {
+//         difference pared hueco    # union, intersection o difference
+//     }
+//
+// Un bloque `sdf` describe un campo de distancia (ver src/sdf.rs) en notación postfija: las
+// formas (`sphere r`, `box hx hy hz`, `round_box hx hy hz r`, `torus R r`, `capsule h r`) se
+// apilan, `move x y z`, `repeat sx sy sz nx ny nz` y `twist grados` cambian la de arriba y
+// `union`, `intersection`, `difference` y `smooth k` combinan las dos de arriba:
+//
+//     sdf nube {
+//         center 0 6 0
+//         sphere 1
+//         sphere 0.8
+//         move 1 0 0
+//         smooth 0.4                # unión suave de las dos esferas
+//         material nube
+//     }
+//
+// Un bloque `minecraft` importa una estructura .nbt o una caja de una región .mca como cubos
+// (ver src/minecraft.rs para la tabla de bloques):
+//
+//     minecraft casa {
+//         file scenes/casita.nbt
+//         offset -3 -4 -3           # dónde queda la esquina mínima de la estructura
+//         blocks assets/blocks.table
+//         block glass vidrio        # entradas sueltas, con prioridad sobre la tabla
+//         merge off                 # un cubo por bloque (por defecto se fusionan, ver src/merge.rs)
+//     }
+//
+// SceneFile guarda la descripción tal cual (los objetos referencian materiales por nombre) para
+// poder reconstruir la escena o volver a escribir el archivo.
+
+use crate::block_model::{ModelLibrary, ModelShape};
+use crate::bvh::Aabb;
+use crate::camera::Camera;
+use crate::csg::{Csg, CsgOp};
+use crate::cube::Cube;
+use crate::daycycle::DayCycle;
+use crate::fog::{FogShape, FogVolume};
+use crate::light::{Light, LightKind};
+use crate::material::{Material, Subsurface};
+use crate::math::{Color, Mat4, Vec2, Vec3};
+use crate::minecraft::WorldImport;
+use crate::primitives::Primitive;
+use crate::procedural::{Coords, Pattern, TextureNode};
+use crate::shader::{Attribute, Input, MathOp, ShaderGraph, ShaderNode};
+use crate::ray_intersect::RayIntersect;
+use crate::scene::Scene;
+use crate::sdf::{Sdf, SdfShape};
+use crate::sphere::Sphere;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+// Forma geométrica de un objeto del archivo
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    // caja con rotación en grados (x primero, luego y)
+    Cube { center: Vec3, half_size: Vec3, rotation: Vec2 },
+    Sphere { center: Vec3, radius: f32 },
+    // modelo de bloque (nombre incluido o .json) con las variables de textura del archivo
+    Model { center: Vec3, rotation: Vec2, model: String, textures: Vec<(String, String)> },
+    // plano, disco, quad, cilindro, cono, toro o cápsula
+    Primitive { center: Vec3, rotation: Vec2, primitive: Primitive },
+    // combinación de dos objetos; se mueve y se mide por el primero
+    Csg { op: CsgOp, a: Box<ObjectDesc>, b: Box<ObjectDesc> },
+    // campo de distancia dibujado con sphere tracing
+    Sdf { center: Vec3, rotation: Vec2, sdf: Sdf },
+}
+
+impl Shape {
+    pub fn center(&self) -> Vec3 {
+        match self {
+            Shape::Cube { center, .. }
+            | Shape::Sphere { center, .. }
+            | Shape::Model { center, .. }
+            | Shape::Primitive { center, .. }
+            | Shape::Sdf { center, .. } => *center,
+            Shape::Csg { a, .. } => a.shape.center(),
+        }
+    }
+
+    /// Mayor distancia del centro a una cara (cubo) o el radio (esfera)
+    pub fn extent(&self) -> f32 {
+        match self {
+            Shape::Cube { half_size, .. } => half_size.x.max(half_size.y).max(half_size.z),
+            Shape::Sphere { radius, .. } => *radius,
+            Shape::Model { .. } => 0.5,
+            // el plano no tiene límites: el gizmo usa un tamaño fijo
+            Shape::Primitive { primitive, .. } => primitive_bounds(primitive).map_or(1.0, |b| {
+                let far = (-b.min).max(b.max);
+                far.x.max(far.y).max(far.z)
+            }),
+            Shape::Csg { a, .. } => a.shape.extent(),
+            Shape::Sdf { sdf, .. } => {
+                let b = sdf.bounds();
+                let far = (-b.min).max(b.max);
+                far.x.max(far.y).max(far.z)
+            }
+        }
+    }
+
+    /// Rotación del objeto (identidad para la esfera), con el mismo orden que Cube: X y luego Y
+    pub fn rotation(&self) -> Mat4 {
+        match self {
+            Shape::Cube { rotation, .. }
+            | Shape::Model { rotation, .. }
+            | Shape::Primitive { rotation, .. }
+            | Shape::Sdf { rotation, .. } => {
+                Mat4::rotation_y(rotation.y.to_radians()) * Mat4::rotation_x(rotation.x.to_radians())
+            }
+            Shape::Sphere { .. } | Shape::Csg { .. } => Mat4::identity(),
+        }
+    }
+
+    /// Aristas en espacio mundo para dibujar el contorno del objeto seleccionado
+    pub fn outline(&self) -> Vec<(Vec3, Vec3)> {
+        match self {
+            Shape::Cube { center, .. } | Shape::Model { center, .. } | Shape::Primitive { center, .. } | Shape::Sdf { center, .. } => {
+                let (min, max) = match self {
+                    Shape::Cube { half_size, .. } => (-*half_size, *half_size),
+                    // un cuadrado de 10×10 marca el plano
+                    Shape::Primitive { primitive, .. } => match primitive_bounds(primitive) {
+                        Some(b) => (b.min, b.max),
+                        None => (Vec3::new(-5.0, 0.0, -5.0), Vec3::new(5.0, 0.0, 5.0)),
+                    },
+                    // la caja del campo no siempre está centrada
+                    Shape::Sdf { sdf, .. } => {
+                        let b = sdf.bounds();
+                        (b.min, b.max)
+                    }
+                    _ => (Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5)),
+                };
+                let to_world = Mat4::translation(*center) * self.rotation();
+                let corner = |i: usize| {
+                    let pick = |bit: usize, lo: f32, hi: f32| if i & bit != 0 { hi } else { lo };
+                    to_world.transform_point(Vec3::new(pick(1, min.x, max.x), pick(2, min.y, max.y), pick(4, min.z, max.z)))
+                };
+                // dos esquinas forman arista si difieren en un solo eje
+                let mut edges = Vec::with_capacity(12);
+                for a in 0..8 {
+                    for bit in [1, 2, 4] {
+                        if a & bit == 0 {
+                            edges.push((corner(a), corner(a | bit)));
+                        }
+                    }
+                }
+                edges
+            }
+            Shape::Sphere { center, radius } => {
+                // tres círculos máximos, uno por plano de ejes
+                const SEGMENTS: usize = 32;
+                let mut edges = Vec::with_capacity(3 * SEGMENTS);
+                let (x, y, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+                for (u, v) in [(x, y), (y, z), (x, z)] {
+                    let point = |i: usize| {
+                        let a = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+                        *center + (u * a.cos() + v * a.sin()) * *radius
+                    };
+                    edges.extend((0..SEGMENTS).map(|i| (point(i), point(i + 1))));
+                }
+                edges
+            }
+            Shape::Csg { a, b, .. } => [a.shape.outline(), b.shape.outline()].concat(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectDesc {
+    pub name: Option<String>,
+    pub shape: Shape,
+    pub material: String,
+}
+
+impl ObjectDesc {
+    /// Objetos simples que forman este (él mismo, o las partes de un csg)
+    pub fn parts(&self) -> Vec<&ObjectDesc> {
+        match &self.shape {
+            Shape::Csg { a, b, .. } => [a.parts(), b.parts()].concat(),
+            _ => vec![self],
+        }
+    }
+
+    /// Instancia el objeto con el material ya resuelto; `library` tiene los modelos de bloque .json
+    /// y las máscaras de transparencia (alcanza con la vacía si no hay modelos). Las partes de un
+    /// csg usan todas ese material; SceneFile::build_object les da el suyo a cada una.
+    pub fn build(&self, material: Material, library: &ModelLibrary) -> Box<dyn RayIntersect + Send + Sync> {
+        match &self.shape {
+            Shape::Cube { center, half_size, rotation } => Box::new(Cube::new(
+                *center,
+                *half_size,
+                rotation.x.to_radians(),
+                rotation.y.to_radians(),
+                material,
+            )),
+            Shape::Sphere { center, radius } => Box::new(Sphere { center: *center, radius: *radius, material }),
+            Shape::Model { center, rotation, model, textures } => {
+                // un modelo que no está en la biblioteca (no se resolvió el archivo) queda vacío
+                let model = library.model(model).cloned().unwrap_or_default().with_textures(textures);
+                Box::new(ModelShape::new(&model, *center, *rotation, &material, library))
+            }
+            Shape::Primitive { center, rotation, primitive } => primitive.build(*center, *rotation, material),
+            Shape::Csg { op, a, b } => Box::new(Csg::new(*op, a.build(material.clone(), library), b.build(material, library))),
+            Shape::Sdf { center, rotation, sdf } => Box::new(SdfShape::new(sdf.clone(), *center, *rotation, material)),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SceneFile {
+    pub time: Option<f32>,
+    pub camera: Option<Camera>,
+    pub materials: Vec<(String, Material)>,
+    pub objects: Vec<ObjectDesc>,
+    pub lights: Vec<Light>,
+    pub fog: Vec<FogVolume>,
+    // importaciones de Minecraft; sus cubos van en la escena después de `objects`
+    pub imports: Vec<WorldImport>,
+    // modelos .json y máscaras de transparencia leídos por resolve_files
+    pub library: ModelLibrary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "línea {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error<T>(line: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { line, message })
+}
+
+// Una línea con contenido: número (desde 1) y palabras
+struct Line<'a> {
+    number: usize,
+    words: Vec<&'a str>,
+}
+
+impl<'a> Line<'a> {
+    fn key(&self) -> &'a str {
+        self.words[0]
+    }
+
+    fn floats<const N: usize>(&self) -> Result<[f32; N], ParseError> {
+        let values = &self.words[1..];
+        if values.len() != N {
+            return error(self.number, format!("'{}' espera {} número(s), hay {}", self.key(), N, values.len()));
+        }
+        let mut out = [0.0; N];
+        for (o, v) in out.iter_mut().zip(values) {
+            *o = v.parse().or_else(|_| error(self.number, format!("'{}' no es un número", v)))?;
+        }
+        Ok(out)
+    }
+
+    fn float(&self) -> Result<f32, ParseError> {
+        Ok(self.floats::<1>()?[0])
+    }
+
+    // medida de una forma: con cero, negativa o no finita las normales salen NaN o infinitas
+    fn positive(&self) -> Result<f32, ParseError> {
+        let value = self.float()?;
+        if value <= 0.0 || !value.is_finite() {
+            return error(self.number, format!("'{}' tiene que ser mayor que 0", self.key()));
+        }
+        Ok(value)
+    }
+
+    // igual que positive, componente a componente (medidas del cubo)
+    fn positive_vec3(&self) -> Result<Vec3, ParseError> {
+        let v = self.vec3()?;
+        if [v.x, v.y, v.z].iter().any(|c| *c <= 0.0 || !c.is_finite()) {
+            return error(self.number, format!("'{}' tiene que ser mayor que 0 en cada eje", self.key()));
+        }
+        Ok(v)
+    }
+
+    fn vec3(&self) -> Result<Vec3, ParseError> {
+        let [x, y, z] = self.floats::<3>()?;
+        Ok(Vec3::new(x, y, z))
+    }
+
+    fn color(&self) -> Result<Color, ParseError> {
+        let [r, g, b] = self.floats::<3>()?;
+        Ok(Color::new(r, g, b))
+    }
+
+    fn word(&self) -> Result<&'a str, ParseError> {
+        match self.words[1..] {
+            [w] => Ok(w),
+            _ => error(self.number, format!("'{}' espera un solo valor", self.key())),
+        }
+    }
+
+    fn unknown<T>(&self, block: &str) -> Result<T, ParseError> {
+        error(self.number, format!("propiedad desconocida '{}' en {}", self.key(), block))
+    }
+}
+
+// Bloque `tipo [argumento] { ... }`
+struct Block<'a> {
+    kind: &'a str,
+    arg: Option<&'a str>,
+    line: usize,
+    props: Vec<Line<'a>>,
+}
+
+impl<'a> Block<'a> {
+    fn required<T>(&self, value: Option<T>, key: &str) -> Result<T, ParseError> {
+        value.ok_or_else(|| ParseError { line: self.line, message: format!("a {} le falta '{}'", self.kind, key) })
+    }
+}
+
+// Un paso del árbol procedural: `p` es la línea `pattern ...` sin la palabra `pattern`
+fn pattern_step(p: &Line, stack: &mut Vec<TextureNode>) -> Result<(), ParseError> {
+    let mut pop = |count: usize| {
+        if stack.len() < count {
+            return error(p.number, format!("'{}' necesita {} entrada(s) antes", p.key(), count));
+        }
+        Ok(stack.split_off(stack.len() - count).into_iter().map(Box::new).collect::<Vec<_>>())
+    };
+    let node = match p.key() {
+        "color" => TextureNode::Color(p.color()?),
+        "image" => TextureNode::Image(p.word()?.to_string()),
+        "ramp" => {
+            let [r0, g0, b0, r1, g1, b1] = p.floats::<6>()?;
+            let [input] = <[_; 1]>::try_from(pop(1)?).unwrap();
+            TextureNode::Ramp { input, low: Color::new(r0, g0, b0), high: Color::new(r1, g1, b1) }
+        }
+        "multiply" => {
+            if p.words.len() > 1 {
+                return error(p.number, "'multiply' no lleva valores".into());
+            }
+            let [a, b] = <[_; 2]>::try_from(pop(2)?).unwrap();
+            TextureNode::Multiply(a, b)
+        }
+        // `mix t` mezcla las dos de arriba con un factor fijo; `mix` solo usa la tercera de factor
+        "mix" => {
+            let factor = match p.words.len() {
+                1 => None,
+                _ => Some(Box::new(TextureNode::Color(Color::splat(p.float()?)))),
+            };
+            let mut inputs = pop(if factor.is_some() { 2 } else { 3 })?.into_iter();
+            let (a, b) = (inputs.next().unwrap(), inputs.next().unwrap());
+            TextureNode::Mix { a, b, factor: factor.or(inputs.next()).unwrap() }
+        }
+        kind @ ("noise" | "worley" | "checker" | "wood" | "marble" | "gradient") => {
+            let Some(coords) = p.words.get(1).and_then(|w| Coords::from_name(w)) else {
+                return error(p.number, format!("'{}' espera las coordenadas (world, local o uv) y sus valores", kind));
+            };
+            // los valores sin la palabra de las coordenadas
+            let values = Line { number: p.number, words: [&p.words[..1], &p.words[2..]].concat() };
+            let pattern = match kind {
+                "noise" => {
+                    let [scale, octaves] = values.floats::<2>()?;
+                    Pattern::Noise { scale, octaves: octaves.max(1.0) as u32 }
+                }
+                "worley" => Pattern::Worley { scale: values.float()? },
+                "checker" => Pattern::Checker { scale: values.float()? },
+                "wood" => {
+                    let [scale, turbulence] = values.floats::<2>()?;
+                    Pattern::Wood { scale, turbulence }
+                }
+                "marble" => {
+                    let [scale, turbulence] = values.floats::<2>()?;
+                    Pattern::Marble { scale, turbulence }
+                }
+                _ => Pattern::Gradient { direction: values.vec3()? },
+            };
+            TextureNode::Pattern { pattern, coords }
+        }
+        _ => return error(p.number, format!("paso de pattern desconocido '{}'", p.key())),
+    };
+    stack.push(node);
+    Ok(())
+}
+
+// Entrada de un nodo: el nombre de un nodo anterior o un número
+fn shader_input(graph: &ShaderGraph, number: usize, word: &str) -> Result<Input, ParseError> {
+    if let Ok(value) = word.parse() {
+        return Ok(Input::Value(value));
+    }
+    match graph.node(word) {
+        Some(index) => Ok(Input::Node(index)),
+        None => error(number, format!("nodo '{}' no definido (tiene que estar antes)", word)),
+    }
+}
+
+// `node nombre tipo valores...`: `p` es la línea sin `node nombre`
+fn shader_node(graph: &ShaderGraph, p: &Line) -> Result<ShaderNode, ParseError> {
+    let input = |word: &str| shader_input(graph, p.number, word);
+    let args = &p.words[1..];
+    let node = match (p.key(), args) {
+        ("attribute", [name]) => match Attribute::from_name(name) {
+            Some(attribute) => ShaderNode::Attribute(attribute),
+            None => return error(p.number, format!("atributo desconocido '{}' (albedo, emission, uv, position, local o normal)", name)),
+        },
+        ("noise" | "worley" | "checker" | "wood" | "marble" | "gradient", _) => {
+            let mut stack = Vec::new();
+            pattern_step(p, &mut stack)?;
+            let Some(TextureNode::Pattern { pattern, coords }) = stack.pop() else { unreachable!() };
+            ShaderNode::Pattern { pattern, coords }
+        }
+        ("image", [path, uv]) => ShaderNode::Image { path: path.to_string(), uv: input(uv)? },
+        ("uv_transform", [uv, ..]) => {
+            let values = Line { number: p.number, words: [&p.words[..1], &p.words[2..]].concat() };
+            let [sx, sy, rotation, ox, oy] = values.floats::<5>()?;
+            ShaderNode::UvTransform { uv: input(uv)?, scale: Vec2::new(sx, sy), rotation, offset: Vec2::new(ox, oy) }
+        }
+        ("mix", [a, b, factor]) => ShaderNode::Mix { a: input(a)?, b: input(b)?, factor: input(factor)? },
+        ("fresnel", _) => ShaderNode::Fresnel { ior: p.float()? },
+        (op, [a, b]) if let Some(op) = MathOp::from_name(op) => ShaderNode::Math { op, a: input(a)?, b: input(b)? },
+        (kind, _) => {
+            let usage = match kind {
+                "attribute" => "attribute nombre",
+                "image" => "image ruta uv",
+                "uv_transform" => "uv_transform uv sx sy grados ox oy",
+                "mix" => "mix a b factor",
+                _ if MathOp::from_name(kind).is_some() => "operación a b",
+                _ => return error(p.number, format!("tipo de nodo desconocido '{}'", kind)),
+            };
+            return error(p.number, format!("'{}' espera `{}`", kind, usage));
+        }
+    };
+    Ok(node)
+}
+
+fn material(block: &Block) -> Result<Material, ParseError> {
+    let mut m = Material::default();
+    // los nodos y salidas arman el grafo del material (si hay alguno)
+    let mut graph = ShaderGraph::default();
+    // los pasos `pattern` arman el árbol en postfijo, como las formas de un sdf
+    let mut pattern = Vec::new();
+    for p in &block.props {
+        match p.key() {
+            "pattern" => pattern_step(&Line { number: p.number, words: p.words[1..].to_vec() }, &mut pattern)?,
+            "node" => {
+                let [_, name, _, ..] = p.words[..] else {
+                    return error(p.number, "'node' espera `node nombre tipo valores...`".into());
+                };
+                if graph.node(name).is_some() {
+                    return error(p.number, format!("el nodo '{}' ya existe", name));
+                }
+                let node = shader_node(&graph, &Line { number: p.number, words: p.words[2..].to_vec() })?;
+                graph.nodes.push((name.to_string(), node));
+            }
+            "output" => {
+                let [_, slot, word] = p.words[..] else {
+                    return error(p.number, "'output' espera `output salida nodo`".into());
+                };
+                let value = Some(shader_input(&graph, p.number, word)?);
+                match slot {
+                    "base_color" => graph.base_color = value,
+                    "roughness" => graph.roughness = value,
+                    "emission" => graph.emission = value,
+                    "normal" => graph.normal = value,
+                    _ => return error(p.number, format!("salida desconocida '{}' (base_color, roughness, emission o normal)", slot)),
+                }
+            }
+            "diffuse" => m.diffuse = p.color()?,
+            "specular" => m.specular = p.float()?,
+            "reflectivity" => m.reflectivity = p.float()?,
+            "transparency" => m.transparency = p.float()?,
+            "refractive_index" => m.refractive_index = p.float()?,
+            "albedo" => m.albedo = p.floats::<2>()?,
+            "texture" => m.texture_path = Some(p.word()?.to_string()),
+            "emissive" => m.emissive = p.color()?,
+            "emission" => m.emission = p.float()?,
+            "subsurface" => {
+                let [r, g, b, mean_free_path, weight] = p.floats::<5>()?;
+                m.subsurface = Some(Subsurface { color: Color::new(r, g, b), mean_free_path, weight });
+            }
+            _ => return p.unknown("material"),
+        }
+    }
+    m.pattern = match pattern.len() {
+        0 | 1 => pattern.pop().map(Arc::new),
+        n => return error(block.line, format!("pattern termina con {} entradas sueltas (faltan multiply o mix)", n)),
+    };
+    if graph != ShaderGraph::default() {
+        m.shader = Some(Arc::new(graph));
+    }
+    Ok(m)
+}
+
+// caja local de una primitiva tal como la calcula la propia forma (None para el plano)
+fn primitive_bounds(primitive: &Primitive) -> Option<Aabb> {
+    primitive.build(Vec3::zero(), Vec2::zero(), Material::default()).bounds()
+}
+
+fn object(block: &Block) -> Result<ObjectDesc, ParseError> {
+    let (mut center, mut half_size, mut rotation, mut radius, mut material) = (None, None, Vec2::new(0.0, 0.0), None, None);
+    let (mut model, mut textures) = (None, Vec::new());
+    // medidas de las primitivas analíticas, en el orden de Primitive::params
+    let mut primitive = Primitive::from_kind(block.kind);
+    let mut params = vec![None; primitive.map_or(0, |p| p.values().len())];
+    for p in &block.props {
+        let param = primitive.and_then(|primitive| primitive.values().iter().position(|(key, _)| *key == p.key()));
+        if let Some(index) = param {
+            params[index] = Some(p.positive()?);
+            continue;
+        }
+        match (block.kind, p.key()) {
+            (_, "center") => center = Some(p.vec3()?),
+            (_, "material") => material = Some(p.word()?.to_string()),
+            ("cube", "half_size") => half_size = Some(p.positive_vec3()?),
+            (kind, "rotation") if matches!(kind, "cube" | "model") || primitive.is_some() => {
+                let [x, y] = p.floats::<2>()?;
+                rotation = Vec2::new(x, y);
+            }
+            ("sphere", "radius") => radius = Some(p.positive()?),
+            ("model", "model") => model = Some(p.word()?.to_string()),
+            ("model", "texture") => match p.words[1..] {
+                [variable, texture] => textures.push((variable.to_string(), texture.to_string())),
+                _ => return error(p.number, "'texture' espera la variable y la textura".into()),
+            },
+            _ => return p.unknown(block.kind),
+        }
+    }
+    let center = block.required(center, "center")?;
+    let shape = match block.kind {
+        "cube" => Shape::Cube { center, half_size: block.required(half_size, "half_size")?, rotation },
+        "model" => Shape::Model { center, rotation, model: block.required(model, "model")?, textures },
+        _ if let Some(primitive) = &mut primitive => {
+            for ((key, _, value), set) in primitive.params().into_iter().zip(params) {
+                *value = block.required(set, key)?;
+            }
+            Shape::Primitive { center, rotation, primitive: *primitive }
+        }
+        _ => Shape::Sphere { center, radius: block.required(radius, "radius")? },
+    };
+    Ok(ObjectDesc {
+        name: block.arg.map(str::to_string),
+        shape,
+        material: block.required(material, "material")?,
+    })
+}
+
+// `csg` saca de `objects` las dos partes, que tienen que estar definidas antes con nombre
+fn csg(block: &Block, objects: &mut Vec<ObjectDesc>, lines: &mut Vec<usize>) -> Result<ObjectDesc, ParseError> {
+    let [p] = &block.props[..] else {
+        return error(block.line, "csg espera una línea `operación objeto objeto`".into());
+    };
+    let (op, a, b) = match p.words[..] {
+        [op, a, b] => (CsgOp::from_name(op), a, b),
+        _ => return error(p.number, "csg espera `operación objeto objeto`".into()),
+    };
+    let Some(op) = op else {
+        return error(p.number, format!("operación desconocida '{}' (union, intersection o difference)", p.key()));
+    };
+    if a == b {
+        return error(p.number, format!("csg usa dos veces '{}'", a));
+    }
+    let mut take = |name: &str| {
+        let Some(index) = objects.iter().position(|o| o.name.as_deref() == Some(name)) else {
+            return error(p.number, format!("objeto '{}' no definido (tiene que estar antes del csg)", name));
+        };
+        lines.remove(index);
+        Ok(Box::new(objects.remove(index)))
+    };
+    let (a, b) = (take(a)?, take(b)?);
+    Ok(ObjectDesc { name: block.arg.map(str::to_string), material: a.material.clone(), shape: Shape::Csg { op, a, b } })
+}
+
+// las líneas de forma van a una pila (ver el comentario del principio); al final tiene que
+// quedar una sola
+fn sdf_object(block: &Block) -> Result<ObjectDesc, ParseError> {
+    let (mut center, mut rotation, mut material) = (None, Vec2::new(0.0, 0.0), None);
+    let mut stack: Vec<Sdf> = Vec::new();
+    for p in &block.props {
+        let top = |stack: &mut Vec<Sdf>| match stack.pop() {
+            Some(shape) => Ok(Box::new(shape)),
+            None => error(p.number, format!("'{}' necesita una forma antes", p.key())),
+        };
+        let pair = |stack: &mut Vec<Sdf>| {
+            if stack.len() < 2 {
+                return error(p.number, format!("'{}' necesita dos formas antes", p.key()));
+            }
+            let b = Box::new(stack.pop().unwrap());
+            Ok((Box::new(stack.pop().unwrap()), b))
+        };
+        let shape = match p.key() {
+            "center" => {
+                center = Some(p.vec3()?);
+                continue;
+            }
+            "rotation" => {
+                let [x, y] = p.floats::<2>()?;
+                rotation = Vec2::new(x, y);
+                continue;
+            }
+            "material" => {
+                material = Some(p.word()?.to_string());
+                continue;
+            }
+            "sphere" => Sdf::Sphere { radius: p.float()? },
+            "box" => Sdf::Box { half_size: p.vec3()? },
+            "round_box" => {
+                let [x, y, z, radius] = p.floats::<4>()?;
+                Sdf::RoundBox { half_size: Vec3::new(x, y, z), radius }
+            }
+            "torus" => {
+                let [major_radius, minor_radius] = p.floats::<2>()?;
+                Sdf::Torus { major_radius, minor_radius }
+            }
+            "capsule" => {
+                let [half_height, radius] = p.floats::<2>()?;
+                Sdf::Capsule { half_height, radius }
+            }
+            "move" => Sdf::Move { offset: p.vec3()?, shape: top(&mut stack)? },
+            "repeat" => {
+                let [sx, sy, sz, nx, ny, nz] = p.floats::<6>()?;
+                if [nx, ny, nz].iter().any(|n| *n < 1.0 || n.fract() != 0.0) {
+                    return error(p.number, "las copias de 'repeat' tienen que ser enteros de 1 en adelante".into());
+                }
+                Sdf::Repeat { spacing: Vec3::new(sx, sy, sz), count: [nx as u32, ny as u32, nz as u32], shape: top(&mut stack)? }
+            }
+            "twist" => Sdf::Twist { degrees_per_unit: p.float()?, shape: top(&mut stack)? },
+            "union" | "intersection" | "difference" => {
+                if p.words.len() > 1 {
+                    return error(p.number, format!("'{}' no lleva valores", p.key()));
+                }
+                let (a, b) = pair(&mut stack)?;
+                match p.key() {
+                    "union" => Sdf::Union(a, b),
+                    "intersection" => Sdf::Intersection(a, b),
+                    _ => Sdf::Difference(a, b),
+                }
+            }
+            "smooth" => {
+                let k = p.float()?;
+                let (a, b) = pair(&mut stack)?;
+                Sdf::SmoothUnion { a, b, k }
+            }
+            _ => return p.unknown("sdf"),
+        };
+        stack.push(shape);
+    }
+    let sdf = match stack.len() {
+        0 => return error(block.line, "sdf no tiene ninguna forma".into()),
+        1 => stack.pop().unwrap(),
+        n => return error(block.line, format!("sdf termina con {} formas sueltas (faltan operaciones que las combinen)", n)),
+    };
+    Ok(ObjectDesc {
+        name: block.arg.map(str::to_string),
+        shape: Shape::Sdf { center: block.required(center, "center")?, rotation, sdf },
+        material: block.required(material, "material")?,
+    })
+}
+
+fn light(block: &Block) -> Result<Light, ParseError> {
+    let (mut position, mut direction, mut color, mut intensity) = (None, None, Color::WHITE, 1.0);
+    for p in &block.props {
+        match (block.arg, p.key()) {
+            (Some("point"), "position") => position = Some(p.vec3()?),
+            (Some("directional"), "direction") => direction = Some(p.vec3()?),
+            (_, "color") => color = p.color()?,
+            (_, "intensity") => intensity = p.float()?,
+            _ => return p.unknown("light"),
+        }
+    }
+    match block.arg {
+        Some("point") => Ok(Light::point(block.required(position, "position")?, color, intensity)),
+        Some("directional") => Ok(Light::directional(block.required(direction, "direction")?, color, intensity)),
+        other => error(block.line, format!("tipo de luz desconocido {:?} (point o directional)", other.unwrap_or(""))),
+    }
+}
+
+fn fog(block: &Block) -> Result<FogVolume, ParseError> {
+    let mut volume = FogVolume::homogeneous(0.0, 0.0, 0.0, 0.0);
+    let (mut base, mut falloff) = (0.0, 1.0);
+    let (mut center, mut half_size, mut rotation) = (None, None, Vec2::new(0.0, 0.0));
+    for p in &block.props {
+        match (block.arg, p.key()) {
+            (_, "density") => volume.density = p.float()?,
+            (_, "absorption") => volume.absorption = p.float()?,
+            (_, "scattering") => volume.scattering = p.float()?,
+            (_, "g") => volume.g = p.float()?,
+            (_, "color") => volume.color = p.color()?,
+            (Some("height"), "base") => base = p.float()?,
+            (Some("height"), "falloff") => falloff = p.float()?,
+            (Some("box"), "center") => center = Some(p.vec3()?),
+            (Some("box"), "half_size") => half_size = Some(p.vec3()?),
+            (Some("box"), "rotation") => {
+                let [x, y] = p.floats::<2>()?;
+                rotation = Vec2::new(x, y);
+            }
+            _ => return p.unknown("fog"),
+        }
+    }
+    volume.shape = match block.arg {
+        Some("homogeneous") => FogShape::Homogeneous,
+        Some("height") => FogShape::Height { base, falloff },
+        Some("box") => FogShape::Bounded(Cube::new(
+            block.required(center, "center")?,
+            block.required(half_size, "half_size")?,
+            rotation.x.to_radians(),
+            rotation.y.to_radians(),
+            Material::default(),
+        )),
+        other => {
+            return error(block.line, format!("tipo de niebla desconocido {:?} (homogeneous, height o box)", other.unwrap_or("")));
+        }
+    };
+    Ok(volume)
+}
+
+fn ints3(p: &Line) -> Result<[i32; 3], ParseError> {
+    let [x, y, z] = p.floats::<3>()?;
+    if [x, y, z].iter().any(|v| v.fract() != 0.0) {
+        return error(p.number, format!("'{}' espera coordenadas de bloque enteras", p.key()));
+    }
+    Ok([x as i32, y as i32, z as i32])
+}
+
+fn world_import(block: &Block) -> Result<WorldImport, ParseError> {
+    let mut import = WorldImport { name: block.arg.map(str::to_string), merge: true, ..Default::default() };
+    let (mut file, mut min, mut max) = (None, None, None);
+    for p in &block.props {
+        match p.key() {
+            "file" => file = Some(p.word()?.to_string()),
+            "min" => min = Some(ints3(p)?),
+            "max" => max = Some(ints3(p)?),
+            "offset" => import.offset = p.vec3()?,
+            "blocks" => import.table = Some(p.word()?.to_string()),
+            "merge" => {
+                import.merge = match p.word()? {
+                    "on" => true,
+                    "off" => false,
+                    other => return error(p.number, format!("'merge' espera on u off, no '{}'", other)),
+                }
+            }
+            "block" => {
+                if p.words.len() < 3 {
+                    return error(p.number, "'block' espera el bloque y al menos un material o textura".into());
+                }
+                import.blocks.push((p.words[1].to_string(), p.words[2..].iter().map(|w| w.to_string()).collect()));
+            }
+            _ => return p.unknown("minecraft"),
+        }
+    }
+    import.file = block.required(file, "file")?;
+    import.region_box = match (min, max) {
+        (Some(min), Some(max)) => Some((min, max)),
+        (None, None) if import.file.ends_with(".mca") => return error(block.line, "una región .mca necesita 'min' y 'max'".into()),
+        (None, None) => None,
+        _ => return error(block.line, "'min' y 'max' van juntos".into()),
+    };
+    Ok(import)
+}
+
+fn camera(block: &Block) -> Result<Camera, ParseError> {
+    let (mut eye, mut center, mut up) = (None, Vec3::zero(), Vec3::new(0.0, 1.0, 0.0));
+    for p in &block.props {
+        match p.key() {
+            "eye" => eye = Some(p.vec3()?),
+            "center" => center = p.vec3()?,
+            "up" => up = p.vec3()?,
+            _ => return p.unknown("camera"),
+        }
+    }
+    Ok(Camera::new(block.required(eye, "eye")?, center, up))
+}
+
+impl SceneFile {
+    /// Lee y valida un archivo de escena; los errores de sintaxis llevan el archivo y la línea
+    /// También lee los archivos que referencia (ver resolve_files)
+    pub fn load(path: &str) -> io::Result<SceneFile> {
+        let text = fs::read_to_string(path)?;
+        let mut scene = SceneFile::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))?;
+        scene.resolve_files()?;
+        Ok(scene)
+    }
+
+    /// Lee las estructuras/regiones de los bloques `minecraft` (y genera sus cubos), los modelos
+    /// de bloque .json y la transparencia de las texturas de los modelos. `parse` no toca el
+    /// disco, así que hay que llamarlo si la escena no viene de `load`.
+    pub fn resolve_files(&mut self) -> io::Result<()> {
+        for import in &mut self.imports {
+            import.resolve(&self.materials)?;
+        }
+        let mut library = ModelLibrary::default();
+        let models: Vec<&String> = self
+            .objects
+            .iter()
+            .flat_map(ObjectDesc::parts)
+            .chain(self.imports.iter().flat_map(|i| &i.objects))
+            .filter_map(|o| if let Shape::Model { model, .. } = &o.shape { Some(model) } else { None })
+            .collect();
+        for model in &models {
+            library.load_model(model)?;
+        }
+        if !models.is_empty() {
+            // las texturas que no se pueden leer ya se informan al cargarlas para renderizar
+            for path in self.texture_paths() {
+                let _ = library.load_mask(&path);
+            }
+        }
+        self.library = library;
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> Result<SceneFile, ParseError> {
+        let mut lines = text.lines().enumerate().filter_map(|(i, raw)| {
+            let content = raw.split('#').next().unwrap_or("");
+            let words: Vec<&str> = content.split_whitespace().collect();
+            (!words.is_empty()).then_some(Line { number: i + 1, words })
+        });
+
+        let mut scene = SceneFile::default();
+        // línea de cada objeto, para reportar materiales que no existen
+        let mut object_lines = Vec::new();
+        while let Some(line) = lines.next() {
+            if line.key() == "time" {
+                scene.time = Some(line.float()?);
+                continue;
+            }
+            let block = match line.words[..] {
+                [kind, "{"] => Block { kind, arg: None, line: line.number, props: Vec::new() },
+                [kind, arg, "{"] => Block { kind, arg: Some(arg), line: line.number, props: Vec::new() },
+                _ => return error(line.number, format!("se esperaba `tipo [nombre] {{`, no '{}'", line.words.join(" "))),
+            };
+            let mut block = block;
+            loop {
+                match lines.next() {
+                    Some(p) if p.words == ["}"] => break,
+                    // las operaciones de un sdf (union, intersection, difference) van solas
+                    Some(p) if p.words.len() < 2 && block.kind != "sdf" => {
+                        return error(p.number, format!("propiedad '{}' sin valor", p.key()));
+                    }
+                    Some(p) => block.props.push(p),
+                    None => return error(block.line, format!("falta la '}}' que cierra {}", block.kind)),
+                }
+            }
+
+            match block.kind {
+                "material" => {
+                    let name = block.arg.ok_or_else(|| ParseError { line: block.line, message: "el material necesita un nombre".into() })?;
+                    if scene.material(name).is_some() {
+                        return error(block.line, format!("el material '{}' ya existe", name));
+                    }
+                    scene.materials.push((name.to_string(), material(&block)?));
+                }
+                kind if matches!(kind, "cube" | "sphere" | "model") || Primitive::from_kind(kind).is_some() => {
+                    scene.objects.push(object(&block)?);
+                    object_lines.push(block.line);
+                }
+                "sdf" => {
+                    scene.objects.push(sdf_object(&block)?);
+                    object_lines.push(block.line);
+                }
+                "csg" => {
+                    let object = csg(&block, &mut scene.objects, &mut object_lines)?;
+                    scene.objects.push(object);
+                    object_lines.push(block.line);
+                }
+                "light" => scene.lights.push(light(&block)?),
+                "fog" => scene.fog.push(fog(&block)?),
+                "camera" => scene.camera = Some(camera(&block)?),
+                "minecraft" => scene.imports.push(world_import(&block)?),
+                other => return error(block.line, format!("bloque desconocido '{}'", other)),
+            }
+        }
+
+        for (object, line) in scene.objects.iter().zip(object_lines) {
+            if let Some(part) = object.parts().into_iter().find(|part| scene.material(&part.material).is_none()) {
+                return error(line, format!("material '{}' no definido", part.material));
+            }
+        }
+        Ok(scene)
+    }
+
+    /// Material por nombre: los de la escena y luego los creados por las importaciones
+    pub fn material(&self, name: &str) -> Option<&Material> {
+        self.materials
+            .iter()
+            .chain(self.imports.iter().flat_map(|i| &i.materials))
+            .find(|(n, _)| n == name)
+            .map(|(_, m)| m)
+    }
+
+    /// Texturas que usan los materiales y los modelos de bloque (sin repetir), para cargarlas
+    /// antes de renderizar
+    pub fn texture_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = Vec::new();
+        let materials = self.materials.iter().chain(self.imports.iter().flat_map(|i| &i.materials));
+        let model_textures = self.objects.iter().flat_map(ObjectDesc::parts).flat_map(|o| match &o.shape {
+            Shape::Model { textures, .. } => textures.iter().map(|(_, t)| t).filter(|t| t.ends_with(".png")).collect(),
+            _ => Vec::new(),
+        });
+        let material_textures = materials.flat_map(|(_, m)| {
+            let patterns = m.pattern.iter().flat_map(|pattern| pattern.image_paths());
+            let nodes = m.shader.iter().flat_map(|graph| &graph.nodes).filter_map(|(_, node)| match node {
+                ShaderNode::Image { path, .. } => Some(path.as_str()),
+                _ => None,
+            });
+            m.texture_path.as_deref().into_iter().chain(patterns).chain(nodes)
+        });
+        for path in material_textures.chain(model_textures.map(String::as_str)) {
+            if !paths.iter().any(|p| p == path) {
+                paths.push(path.to_string());
+            }
+        }
+        paths
+    }
+
+    /// Archivos que lee la escena además del suyo (estructuras, regiones, tablas de bloques y
+    /// modelos .json), para vigilarlos
+    pub fn file_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for import in &self.imports {
+            paths.push(import.file.clone());
+            paths.extend(import.table.clone());
+        }
+        for object in self.objects.iter().flat_map(ObjectDesc::parts) {
+            if let Shape::Model { model, .. } = &object.shape
+                && model.ends_with(".json")
+                && !paths.contains(model)
+            {
+                paths.push(model.clone());
+            }
+        }
+        paths
+    }
+
+    /// Los objetos del archivo (mismos índices que `objects`) seguidos de los importados
+    pub fn build_objects(&self) -> Vec<Box<dyn RayIntersect + Send + Sync>> {
+        let mut built: Vec<_> = (0..self.objects.len()).map(|index| self.build_object(index)).collect();
+        // los bloques importados (sueltos o fusionados) repiten la textura una vez por bloque
+        for object in self.imports.iter().flat_map(|i| &i.objects) {
+            let material = self.material(&object.material).cloned().unwrap_or_default();
+            built.push(match object.shape {
+                Shape::Cube { center, half_size, .. } => Box::new(Cube::tiled(center, half_size, material)),
+                _ => object.build(material, &self.library),
+            });
+        }
+        built
+    }
+
+    /// Instancia solo el objeto `index` (p.ej. para reemplazarlo en la escena tras moverlo)
+    pub fn build_object(&self, index: usize) -> Box<dyn RayIntersect + Send + Sync> {
+        self.build_desc(&self.objects[index])
+    }
+
+    // las partes de un csg se construyen cada una con su material
+    fn build_desc(&self, object: &ObjectDesc) -> Box<dyn RayIntersect + Send + Sync> {
+        match &object.shape {
+            Shape::Csg { op, a, b } => Box::new(Csg::new(*op, self.build_desc(a), self.build_desc(b))),
+            _ => object.build(self.material(&object.material).cloned().unwrap_or_default(), &self.library),
+        }
+    }
+
+    /// Hora del archivo (o la de por defecto)
+    pub fn day(&self) -> DayCycle {
+        self.time.map(DayCycle::new).unwrap_or_default()
+    }
+
+    /// Arma la escena con el ciclo de día dado (normalmente `self.day()` o el de la escena anterior)
+    pub fn build(&self, day: DayCycle) -> Scene {
+        let mut scene = Scene::new(self.build_objects(), self.lights.clone(), day);
+        scene.fog = self.fog.clone();
+        scene
+    }
+}
+
+fn v3(v: Vec3) -> String {
+    format!("{} {} {}", v.x, v.y, v.z)
+}
+
+fn rgb(c: Color) -> String {
+    format!("{} {} {}", c.r, c.g, c.b)
+}
+
+fn write_fog_props(f: &mut fmt::Formatter, volume: &FogVolume) -> fmt::Result {
+    writeln!(f, "    density {}", volume.density)?;
+    writeln!(f, "    absorption {}", volume.absorption)?;
+    writeln!(f, "    scattering {}", volume.scattering)?;
+    writeln!(f, "    g {}", volume.g)?;
+    if volume.color != Color::WHITE {
+        writeln!(f, "    color {}", rgb(volume.color))?;
+    }
+    writeln!(f, "}}")
+}
+
+// las partes de un csg se escriben antes, como objetos sueltos con nombre
+fn write_object(f: &mut fmt::Formatter, object: &ObjectDesc) -> fmt::Result {
+    if let Shape::Csg { a, b, .. } = &object.shape {
+        write_object(f, a)?;
+        write_object(f, b)?;
+    }
+    let kind = match object.shape {
+        Shape::Cube { .. } => "cube",
+        Shape::Sphere { .. } => "sphere",
+        Shape::Model { .. } => "model",
+        Shape::Primitive { primitive, .. } => primitive.kind(),
+        Shape::Csg { .. } => "csg",
+        Shape::Sdf { .. } => "sdf",
+    };
+    match &object.name {
+        Some(name) => writeln!(f, "{} {} {{", kind, name)?,
+        None => writeln!(f, "{} {{", kind)?,
+    }
+    match &object.shape {
+        Shape::Cube { center, half_size, rotation } => {
+            writeln!(f, "    center {}", v3(*center))?;
+            writeln!(f, "    half_size {}", v3(*half_size))?;
+            writeln!(f, "    rotation {} {}", rotation.x, rotation.y)?;
+        }
+        Shape::Sphere { center, radius } => {
+            writeln!(f, "    center {}", v3(*center))?;
+            writeln!(f, "    radius {}", radius)?;
+        }
+        Shape::Model { center, rotation, model, textures } => {
+            writeln!(f, "    center {}", v3(*center))?;
+            writeln!(f, "    model {}", model)?;
+            writeln!(f, "    rotation {} {}", rotation.x, rotation.y)?;
+            for (variable, texture) in textures {
+                writeln!(f, "    texture {} {}", variable, texture)?;
+            }
+        }
+        Shape::Primitive { center, rotation, primitive } => {
+            writeln!(f, "    center {}", v3(*center))?;
+            for (key, value) in primitive.values() {
+                writeln!(f, "    {} {}", key, value)?;
+            }
+            writeln!(f, "    rotation {} {}", rotation.x, rotation.y)?;
+        }
+        // el material sale de las partes
+        Shape::Csg { op, a, b } => {
+            let name = |o: &ObjectDesc| o.name.clone().unwrap_or_default();
+            return writeln!(f, "    {} {} {}\n}}\n", op.name(), name(a), name(b));
+        }
+        Shape::Sdf { center, rotation, sdf } => {
+            writeln!(f, "    center {}", v3(*center))?;
+            writeln!(f, "    rotation {} {}", rotation.x, rotation.y)?;
+            write_sdf(f, sdf)?;
+        }
+    }
+    writeln!(f, "    material {}\n}}\n", object.material)
+}
+
+fn write_shader(f: &mut fmt::Formatter, graph: &ShaderGraph) -> fmt::Result {
+    let input = |input: Input| match input {
+        Input::Node(index) => graph.nodes[index].0.clone(),
+        Input::Value(value) => value.to_string(),
+    };
+    for (name, node) in &graph.nodes {
+        let body = match node {
+            ShaderNode::Attribute(attribute) => format!("attribute {}", attribute.name()),
+            ShaderNode::Pattern { pattern, coords } => pattern_words(pattern, *coords),
+            ShaderNode::Image { path, uv } => format!("image {} {}", path, input(*uv)),
+            ShaderNode::UvTransform { uv, scale, rotation, offset } => {
+                format!("uv_transform {} {} {} {} {} {}", input(*uv), scale.x, scale.y, rotation, offset.x, offset.y)
+            }
+            ShaderNode::Math { op, a, b } => format!("{} {} {}", op.name(), input(*a), input(*b)),
+            ShaderNode::Mix { a, b, factor } => format!("mix {} {} {}", input(*a), input(*b), input(*factor)),
+            ShaderNode::Fresnel { ior } => format!("fresnel {}", ior),
+        };
+        writeln!(f, "    node {} {}", name, body)?;
+    }
+    for (slot, value) in [("base_color", graph.base_color), ("roughness", graph.roughness), ("emission", graph.emission), ("normal", graph.normal)] {
+        if let Some(value) = value {
+            writeln!(f, "    output {} {}", slot, input(value))?;
+        }
+    }
+    Ok(())
+}
+
+fn pattern_words(pattern: &Pattern, coords: Coords) -> String {
+    let (kind, values) = match pattern {
+        Pattern::Noise { scale, octaves } => ("noise", format!("{} {}", scale, octaves)),
+        Pattern::Worley { scale } => ("worley", scale.to_string()),
+        Pattern::Checker { scale } => ("checker", scale.to_string()),
+        Pattern::Wood { scale, turbulence } => ("wood", format!("{} {}", scale, turbulence)),
+        Pattern::Marble { scale, turbulence } => ("marble", format!("{} {}", scale, turbulence)),
+        Pattern::Gradient { direction } => ("gradient", v3(*direction)),
+    };
+    format!("{} {} {}", kind, coords.name(), values)
+}
+
+// también en postfijo, una línea `pattern` por nodo
+fn write_pattern(f: &mut fmt::Formatter, node: &TextureNode) -> fmt::Result {
+    match node {
+        TextureNode::Color(color) => writeln!(f, "    pattern color {}", rgb(*color)),
+        TextureNode::Image(path) => writeln!(f, "    pattern image {}", path),
+        TextureNode::Pattern { pattern, coords } => writeln!(f, "    pattern {}", pattern_words(pattern, *coords)),
+        TextureNode::Ramp { input, low, high } => {
+            write_pattern(f, input)?;
+            writeln!(f, "    pattern ramp {} {}", rgb(*low), rgb(*high))
+        }
+        TextureNode::Multiply(a, b) => {
+            write_pattern(f, a)?;
+            write_pattern(f, b)?;
+            writeln!(f, "    pattern multiply")
+        }
+        TextureNode::Mix { a, b, factor } => {
+            write_pattern(f, a)?;
+            write_pattern(f, b)?;
+            write_pattern(f, factor)?;
+            writeln!(f, "    pattern mix")
+        }
+    }
+}
+
+// en postfijo, como se lee: primero los operandos y después la operación
+fn write_sdf(f: &mut fmt::Formatter, sdf: &Sdf) -> fmt::Result {
+    match sdf {
+        Sdf::Sphere { radius } => writeln!(f, "    sphere {}", radius),
+        Sdf::Box { half_size } => writeln!(f, "    box {}", v3(*half_size)),
+        Sdf::RoundBox { half_size, radius } => writeln!(f, "    round_box {} {}", v3(*half_size), radius),
+        Sdf::Torus { major_radius, minor_radius } => writeln!(f, "    torus {} {}", major_radius, minor_radius),
+        Sdf::Capsule { half_height, radius } => writeln!(f, "    capsule {} {}", half_height, radius),
+        Sdf::Move { offset, shape } => {
+            write_sdf(f, shape)?;
+            writeln!(f, "    move {}", v3(*offset))
+        }
+        Sdf::Union(a, b) | Sdf::Intersection(a, b) | Sdf::Difference(a, b) => {
+            write_sdf(f, a)?;
+            write_sdf(f, b)?;
+            let op = match sdf {
+                Sdf::Union(..) => "union",
+                Sdf::Intersection(..) => "intersection",
+                _ => "difference",
+            };
+            writeln!(f, "    {}", op)
+        }
+        Sdf::SmoothUnion { a, b, k } => {
+            write_sdf(f, a)?;
+            write_sdf(f, b)?;
+            writeln!(f, "    smooth {}", k)
+        }
+        Sdf::Repeat { spacing, count, shape } => {
+            write_sdf(f, shape)?;
+            writeln!(f, "    repeat {} {} {} {}", v3(*spacing), count[0], count[1], count[2])
+        }
+        Sdf::Twist { degrees_per_unit, shape } => {
+            write_sdf(f, shape)?;
+            writeln!(f, "    twist {}", degrees_per_unit)
+        }
+    }
+}
+
+impl fmt::Display for SceneFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(time) = self.time {
+            writeln!(f, "time {}\n", time)?;
+        }
+        if let Some(camera) = &self.camera {
+            writeln!(f, "camera {{\n    eye {}\n    center {}\n    up {}\n}}\n", v3(camera.eye), v3(camera.center), v3(camera.up))?;
+        }
+
+        for (name, m) in &self.materials {
+            writeln!(f, "material {} {{", name)?;
+            writeln!(f, "    diffuse {}", rgb(m.diffuse))?;
+            writeln!(f, "    specular {}", m.specular)?;
+            writeln!(f, "    reflectivity {}", m.reflectivity)?;
+            writeln!(f, "    transparency {}", m.transparency)?;
+            writeln!(f, "    refractive_index {}", m.refractive_index)?;
+            writeln!(f, "    albedo {} {}", m.albedo[0], m.albedo[1])?;
+            if let Some(path) = &m.texture_path {
+                writeln!(f, "    texture {}", path)?;
+            }
+            if let Some(pattern) = &m.pattern {
+                write_pattern(f, pattern)?;
+            }
+            if let Some(graph) = &m.shader {
+                write_shader(f, graph)?;
+            }
+            if m.emission != 0.0 {
+                writeln!(f, "    emissive {}", rgb(m.emissive))?;
+                writeln!(f, "    emission {}", m.emission)?;
+            }
+            if let Some(s) = &m.subsurface {
+                writeln!(f, "    subsurface {} {} {}", rgb(s.color), s.mean_free_path, s.weight)?;
+            }
+            writeln!(f, "}}\n")?;
+        }
+
+        for object in &self.objects {
+            write_object(f, object)?;
+        }
+
+        for light in &self.lights {
+            match light.kind {
+                LightKind::Point => writeln!(f, "light point {{\n    position {}", v3(light.position))?,
+                LightKind::Directional => writeln!(f, "light directional {{\n    direction {}", v3(light.direction))?,
+            }
+            writeln!(f, "    color {}\n    intensity {}\n}}\n", rgb(light.color), light.intensity)?;
+        }
+
+        for import in &self.imports {
+            match &import.name {
+                Some(name) => writeln!(f, "minecraft {} {{", name)?,
+                None => writeln!(f, "minecraft {{")?,
+            }
+            writeln!(f, "    file {}", import.file)?;
+            if let Some((min, max)) = import.region_box {
+                writeln!(f, "    min {} {} {}\n    max {} {} {}", min[0], min[1], min[2], max[0], max[1], max[2])?;
+            }
+            writeln!(f, "    offset {}", v3(import.offset))?;
+            if let Some(table) = &import.table {
+                writeln!(f, "    blocks {}", table)?;
+            }
+            for (block, values) in &import.blocks {
+                writeln!(f, "    block {} {}", block, values.join(" "))?;
+            }
+            if !import.merge {
+                writeln!(f, "    merge off")?;
+            }
+            writeln!(f, "}}\n")?;
+        }
+
+        for volume in &self.fog {
+            match &volume.shape {
+                FogShape::Homogeneous => writeln!(f, "fog homogeneous {{")?,
+                FogShape::Height { base, falloff } => {
+                    writeln!(f, "fog height {{\n    base {}\n    falloff {}", base, falloff)?;
+                }
+                FogShape::Bounded(cube) => {
+                    writeln!(f, "fog box {{\n    center {}\n    half_size {}", v3(cube.center), v3(cube.half_size))?;
+                    writeln!(f, "    rotation {} {}", cube.rot_x.to_degrees(), cube.rot_y.to_degrees())?;
+                }
+            }
+            write_fog_props(f, volume)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "
+        time 8   # mañana
+        material rojo {
+            diffuse 1 0 0
+            subsurface 0.5 0.5 0.5 0.2 0.3
+        }
+        cube caja {
+            center 0 1 0
+            half_size 1 1 1
+            rotation 30 45
+            material rojo
+        }
+        sphere {
+            center 2 0 0
+            radius 0.5
+            material rojo
+        }
+        light point {
+            position 0 5 0
+            color 1 0.5 0.25
+            intensity 3
+        }
+        fog height {
+            base -1
+            falloff 0.5
+            density 0.1
+        }
+    ";
+
+    #[test]
+    fn parses_blocks() {
+        let scene = SceneFile::parse(SMALL).unwrap();
+        assert_eq!(scene.time, Some(8.0));
+        assert_eq!(scene.materials.len(), 1);
+        assert_eq!(scene.material("rojo").unwrap().diffuse, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.objects[0].name.as_deref(), Some("caja"));
+        assert_eq!(
+            scene.objects[0].shape,
+            Shape::Cube { center: Vec3::new(0.0, 1.0, 0.0), half_size: Vec3::one(), rotation: Vec2::new(30.0, 45.0) }
+        );
+        assert_eq!(scene.lights.len(), 1);
+        assert!(matches!(scene.fog[0].shape, FogShape::Height { .. }));
+        assert_eq!(scene.build(scene.day()).objects.len(), 2);
+    }
+
+    #[test]
+    fn roundtrips_through_text() {
+        let scene = SceneFile::parse(SMALL).unwrap();
+        let text = scene.to_string();
+        let again = SceneFile::parse(&text).unwrap();
+        assert_eq!(again.objects, scene.objects);
+        assert_eq!(again.to_string(), text);
+    }
+
+    fn parse_error(text: &str) -> ParseError {
+        SceneFile::parse(text).err().expect("se esperaba un error")
+    }
+
+    #[test]
+    fn reports_line_of_errors() {
+        let err = parse_error("material a {\n  diffuse 1 x 0\n}");
+        assert_eq!(err.line, 2);
+        let err = parse_error("\n\nsphere {\n center 0 0 0\n radius 1\n material nada\n}");
+        assert_eq!((err.line, err.message.as_str()), (3, "material 'nada' no definido"));
+        let err = parse_error("cube {\n center 0 0 0");
+        assert_eq!(err.line, 1);
+        let err = parse_error("material a {\n brillo 3\n}");
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn parses_material_patterns() {
+        let text = "material marmol {\n pattern marble world 2 1.5\n pattern ramp 0.2 0.2 0.25 0.95 0.93 0.9\n\
+                    pattern image assets/vetas.png\n pattern mix 0.2\n pattern checker uv 8\n pattern multiply\n}";
+        let scene = SceneFile::parse(text).unwrap();
+        let pattern = scene.material("marmol").unwrap().pattern.as_ref().unwrap();
+        let TextureNode::Multiply(mix, checker) = &**pattern else { panic!("se esperaba multiply") };
+        assert_eq!(**checker, TextureNode::Pattern { pattern: Pattern::Checker { scale: 8.0 }, coords: Coords::Uv });
+        let TextureNode::Mix { a, b, factor } = &**mix else { panic!("se esperaba mix") };
+        assert!(matches!(**a, TextureNode::Ramp { .. }));
+        assert_eq!((&**b, &**factor), (&TextureNode::Image("assets/vetas.png".into()), &TextureNode::Color(Color::splat(0.2))));
+        assert_eq!(scene.texture_paths(), vec!["assets/vetas.png".to_string()]);
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.material("marmol").unwrap().pattern.as_ref(), Some(pattern));
+        let twice = SceneFile::parse(text).unwrap();
+        assert_eq!(twice.material("marmol").unwrap().id(), scene.material("marmol").unwrap().id());
+        assert_eq!(again.to_string(), scene.to_string());
+
+        let material = |body: &str| format!("material m {{\n diffuse 1 1 1\n{}}}", body);
+        let err = parse_error(&material(" pattern color 1 0 0\n pattern multiply\n"));
+        assert_eq!((err.line, err.message.as_str()), (4, "'multiply' necesita 2 entrada(s) antes"));
+        assert!(parse_error(&material(" pattern noise 3 4\n")).message.contains("world, local o uv"));
+        assert!(parse_error(&material(" pattern color 1 0 0\n pattern color 0 1 0\n")).message.contains("2 entradas sueltas"));
+        assert!(parse_error(&material(" pattern plasma world 1\n")).message.contains("'plasma'"));
+    }
+
+    #[test]
+    fn parses_material_node_graph() {
+        let text = "material azulejo {\n diffuse 0.2 0.4 0.7\n node color attribute albedo\n node uv attribute uv\n\
+                    node girada uv_transform uv 4 4 45 0 0\n node juntas image assets/juntas.png girada\n\
+                    node base multiply color juntas\n node brillo fresnel 1.5\n node rugosidad mix 0.6 0.1 brillo\n\
+                    node vetas noise local 3 4\n output base_color base\n output roughness rugosidad\n output emission 0\n}";
+        let scene = SceneFile::parse(text).unwrap();
+        let material = scene.material("azulejo").unwrap();
+        let graph = material.shader.as_ref().unwrap();
+        assert_eq!(graph.nodes.len(), 8);
+        assert_eq!(graph.nodes[4].1, ShaderNode::Math { op: MathOp::Multiply, a: Input::Node(0), b: Input::Node(3) });
+        assert_eq!(graph.nodes[7].1, ShaderNode::Pattern { pattern: Pattern::Noise { scale: 3.0, octaves: 4 }, coords: Coords::Local });
+        assert_eq!((graph.base_color, graph.roughness, graph.emission, graph.normal), (Some(Input::Node(4)), Some(Input::Node(6)), Some(Input::Value(0.0)), None));
+        assert!(scene.texture_paths().contains(&"assets/juntas.png".to_string()));
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.material("azulejo").unwrap().shader.as_ref(), Some(graph));
+        // el id del material sale del contenido: dos lecturas del mismo archivo dan el mismo
+        let twice = SceneFile::parse(text).unwrap();
+        assert_eq!(twice.material("azulejo").unwrap().id(), material.id());
+        assert_eq!(again.to_string(), scene.to_string());
+        // sin nodos no hay grafo: se usa el de siempre
+        assert!(SceneFile::parse(SMALL).unwrap().materials.iter().all(|(_, m)| m.shader.is_none()));
+
+        let material = |body: &str| format!("material m {{\n diffuse 1 1 1\n{}}}", body);
+        let err = parse_error(&material(" node a add b 1\n"));
+        assert_eq!((err.line, err.message.as_str()), (3, "nodo 'b' no definido (tiene que estar antes)"));
+        assert_eq!(parse_error(&material(" node a fresnel 1.5\n node a fresnel 1.3\n")).message, "el nodo 'a' ya existe");
+        assert_eq!(parse_error(&material(" node a mix 1 2\n")).message, "'mix' espera `mix a b factor`");
+        assert!(parse_error(&material(" node a attribute color\n")).message.contains("'color'"));
+        assert!(parse_error(&material(" output brillo 1\n")).message.contains("'brillo'"));
+        assert!(parse_error(&material(" node a blur 1\n")).message.contains("'blur'"));
+    }
+
+    #[test]
+    fn parses_minecraft_block() {
+        let text = "material piedra {\n diffuse 0.5 0.5 0.5\n}\n\
+                    minecraft casa {\n file mundo/r.0.0.mca\n min 0 60 0\n max 15 70 15\n offset 1 -2 3\n\
+                    blocks assets/blocks.table\n block stone piedra\n}";
+        let scene = SceneFile::parse(text).unwrap();
+        let import = &scene.imports[0];
+        assert_eq!(import.name.as_deref(), Some("casa"));
+        assert_eq!(import.region_box, Some(([0, 60, 0], [15, 70, 15])));
+        assert_eq!(import.offset, Vec3::new(1.0, -2.0, 3.0));
+        assert_eq!(import.blocks, vec![("stone".to_string(), vec!["piedra".to_string()])]);
+        assert!(import.merge);
+        // parse no lee el archivo: todavía no hay cubos
+        assert!(scene.build_objects().is_empty());
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.to_string(), scene.to_string());
+
+        assert_eq!(parse_error("minecraft {\n offset 0 0 0\n}").message, "a minecraft le falta 'file'");
+        assert!(parse_error("minecraft {\n file r.0.0.mca\n}").message.contains("'min' y 'max'"));
+        assert_eq!(parse_error("minecraft {\n file a.nbt\n min 0 0.5 0\n max 1 1 1\n}").line, 3);
+    }
+
+    #[test]
+    fn loads_imported_structure() {
+        use crate::nbt::{self, Tag};
+        let dir = std::env::temp_dir().join(format!("animal_rt_import_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ints = |v: &[i32]| Tag::List(v.iter().map(|&i| Tag::Int(i)).collect());
+        let block = |pos: &[i32], state| Tag::Compound(vec![("pos".to_string(), ints(pos)), ("state".to_string(), Tag::Int(state))]);
+        let name = |n: &str| Tag::Compound(vec![("Name".to_string(), Tag::String(n.to_string()))]);
+        let root = Tag::Compound(vec![
+            ("size".to_string(), ints(&[2, 1, 1])),
+            ("palette".to_string(), Tag::List(vec![name("minecraft:stone"), name("minecraft:glass")])),
+            ("blocks".to_string(), Tag::List(vec![block(&[0, 0, 0], 0), block(&[1, 0, 0], 1)])),
+        ]);
+        let structure = dir.join("casa.nbt");
+        fs::write(&structure, crate::zlib::gzip(&nbt::write("", &root))).unwrap();
+        let scene_path = dir.join("casa.scene");
+        let text = format!(
+            "material piedra {{\n diffuse 0.5 0.5 0.5\n}}\nsphere {{\n center 0 5 0\n radius 1\n material piedra\n}}\n\
+             minecraft {{\n file {}\n offset 0 0 0\n block stone piedra\n merge off\n}}\n",
+            structure.display()
+        );
+        fs::write(&scene_path, text).unwrap();
+
+        let scene = SceneFile::load(scene_path.to_str().unwrap()).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        let import = &scene.imports[0];
+        assert_eq!(import.objects.len(), 1);
+        assert!(!import.merge);
+        assert_eq!(import.unmapped, vec!["minecraft:glass".to_string()]);
+        // la esfera del archivo sigue primero, los cubos importados después
+        assert_eq!(scene.build_objects().len(), 2);
+        assert_eq!(scene.objects.len(), 1);
+    }
+
+    #[test]
+    fn parses_block_model_object() {
+        let text = "material ladrillo {\n diffuse 0.6 0.3 0.2\n}\n\
+                    model escalon {\n center 0.5 0.5 2.5\n model stairs\n rotation 0 90\n\
+                    texture side assets/brick.png\n material ladrillo\n}";
+        let mut scene = SceneFile::parse(text).unwrap();
+        assert_eq!(
+            scene.objects[0].shape,
+            Shape::Model {
+                center: Vec3::new(0.5, 0.5, 2.5),
+                rotation: Vec2::new(0.0, 90.0),
+                model: "stairs".to_string(),
+                textures: vec![("side".to_string(), "assets/brick.png".to_string())],
+            }
+        );
+        assert!(scene.texture_paths().contains(&"assets/brick.png".to_string()));
+        assert!(scene.file_paths().is_empty());
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.objects, scene.objects);
+
+        // resuelto, el modelo ocupa el bloque alrededor del centro
+        scene.resolve_files().unwrap();
+        let bounds = scene.build_objects()[0].bounds().unwrap();
+        assert!((bounds.min - Vec3::new(0.0, 0.0, 2.0)).length() < 1e-4);
+        assert!((bounds.max - Vec3::new(1.0, 1.0, 3.0)).length() < 1e-4);
+
+        assert_eq!(parse_error("model {\n center 0 0 0\n}").message, "a model le falta 'model'");
+        assert_eq!(parse_error("model {\n center 0 0 0\n model cube\n texture all\n}").line, 4);
+        let mut unknown = SceneFile::parse("material m {\n diffuse 1 1 1\n}\nmodel {\n center 0 0 0\n model nada\n material m\n}").unwrap();
+        assert!(unknown.resolve_files().is_err());
+    }
+
+    #[test]
+    fn parses_analytic_primitives() {
+        let text = "material m {\n diffuse 1 1 1\n}\n\
+                    plane suelo {\n center 0 -1 0\n material m\n}\n\
+                    cylinder tronco {\n center 0 1 0\n radius 0.3\n half_height 1\n rotation 0 45\n material m\n}\n\
+                    torus {\n center 3 0 0\n major_radius 1\n minor_radius 0.25\n material m\n}\n\
+                    quad {\n center 0 0 5\n half_width 2\n half_depth 1\n rotation 90 0\n material m\n}";
+        let scene = SceneFile::parse(text).unwrap();
+        assert_eq!(scene.objects.len(), 4);
+        assert_eq!(
+            scene.objects[1].shape,
+            Shape::Primitive {
+                center: Vec3::new(0.0, 1.0, 0.0),
+                rotation: Vec2::new(0.0, 45.0),
+                primitive: Primitive::Cylinder { radius: 0.3, half_height: 1.0 },
+            }
+        );
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.objects, scene.objects);
+        assert_eq!(again.to_string(), scene.to_string());
+
+        let built = scene.build_objects();
+        assert!(built[0].bounds().is_none());
+        let hit = built[0].ray_intersect(&Vec3::new(10.0, 5.0, 10.0), &Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((hit.distance - 6.0).abs() < 1e-5);
+        let b = built[2].bounds().unwrap();
+        assert!((b.max.x - 4.25).abs() < 1e-3);
+
+        assert_eq!(parse_error("disk {\n center 0 0 0\n material m\n}").message, "a disk le falta 'radius'");
+        assert_eq!(parse_error("plane {\n center 0 0 0\n radius 1\n}").line, 3);
+        let err = parse_error("material m {\n diffuse 1 1 1\n}\ncone {\n center 0 0 0\n radius 1\n half_height 0\n material m\n}");
+        assert_eq!((err.line, err.message.as_str()), (7, "'half_height' tiene que ser mayor que 0"));
+        assert_eq!(parse_error("torus {\n center 0 0 0\n major_radius 1\n minor_radius -0.5\n}").line, 4);
+        assert_eq!(parse_error("sphere {\n center 0 0 0\n radius 0\n}").line, 3);
+        let err = parse_error("cube {\n center 0 0 0\n half_size 1 0 1\n}");
+        assert_eq!((err.line, err.message.as_str()), (3, "'half_size' tiene que ser mayor que 0 en cada eje"));
+    }
+
+    #[test]
+    fn parses_csg_block() {
+        let text = "material pared {\n diffuse 1 1 1\n}\nmaterial marco {\n diffuse 0.5 0.3 0.1\n}\n\
+                    cube pared {\n center 0 0 0\n half_size 2 2 0.25\n material pared\n}\n\
+                    cube hueco {\n center 0 0 0\n half_size 0.5 0.5 1\n material marco\n}\n\
+                    sphere {\n center 5 0 0\n radius 1\n material pared\n}\n\
+                    csg ventana {\n difference pared hueco\n}";
+        let scene = SceneFile::parse(text).unwrap();
+        // las partes dejan de ser objetos sueltos
+        assert_eq!(scene.objects.len(), 2);
+        let ventana = &scene.objects[1];
+        let Shape::Csg { op, a, b } = &ventana.shape else { panic!("se esperaba un csg") };
+        assert_eq!((*op, a.name.as_deref(), b.name.as_deref()), (CsgOp::Difference, Some("pared"), Some("hueco")));
+        assert_eq!(ventana.parts().len(), 2);
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.objects, scene.objects);
+        assert_eq!(again.to_string(), scene.to_string());
+
+        // el borde de la ventana tiene el material del hueco
+        let built = scene.build_object(1);
+        assert!(built.ray_intersect(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_none());
+        let hit = built.ray_intersect(&Vec3::zero(), &Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert_eq!(hit.material.diffuse, Color::new(0.5, 0.3, 0.1));
+
+        let cube = |name: &str, material: &str| format!("cube {} {{\n center 0 0 0\n half_size 1 1 1\n material {}\n}}\n", name, material);
+        let materials = "material m {\n diffuse 1 1 1\n}\n";
+        let err = parse_error(&format!("{}{}csg {{\n union a nada\n}}", materials, cube("a", "m")));
+        assert_eq!((err.line, err.message.as_str()), (10, "objeto 'nada' no definido (tiene que estar antes del csg)"));
+        assert!(parse_error(&format!("{}{}{}csg {{\n xor a b\n}}", materials, cube("a", "m"), cube("b", "m"))).message.contains("'xor'"));
+        let err = parse_error(&format!("{}{}{}csg {{\n union a b\n}}", materials, cube("a", "m"), cube("b", "nada")));
+        assert_eq!(err.message, "material 'nada' no definido");
+    }
+
+    #[test]
+    fn parses_sdf_block() {
+        let text = "material nube {\n diffuse 1 1 1\n}\n\
+                    sdf nube {\n center 0 6 0\n rotation 0 45\n sphere 1\n sphere 0.8\n move 1 0 0\n smooth 0.4\n\
+                     box 0.2 0.2 0.2\n repeat 1 0 0 3 1 1\n twist 30\n difference\n material nube\n}";
+        let scene = SceneFile::parse(text).unwrap();
+        let Shape::Sdf { center, rotation, sdf } = &scene.objects[0].shape else { panic!("se esperaba un sdf") };
+        assert_eq!((*center, *rotation), (Vec3::new(0.0, 6.0, 0.0), Vec2::new(0.0, 45.0)));
+        let Sdf::Difference(cloud, holes) = sdf else { panic!("se esperaba una diferencia") };
+        assert!(matches!(**cloud, Sdf::SmoothUnion { k: 0.4, .. }));
+        let Sdf::Twist { shape, .. } = &**holes else { panic!("se esperaba una torsión") };
+        assert!(matches!(**shape, Sdf::Repeat { count: [3, 1, 1], .. }));
+        let again = SceneFile::parse(&scene.to_string()).unwrap();
+        assert_eq!(again.objects, scene.objects);
+
+        let hit = scene.build_object(0).ray_intersect(&Vec3::new(0.0, 10.0, 0.0), &Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((hit.point.y - 7.0).abs() < 0.05);
+
+        let sdf = |body: &str| format!("material m {{\n diffuse 1 1 1\n}}\nsdf {{\n center 0 0 0\n material m\n{}}}", body);
+        let err = parse_error(&sdf(" sphere 1\n union\n"));
+        assert_eq!((err.line, err.message.as_str()), (8, "'union' necesita dos formas antes"));
+        assert_eq!(parse_error(&sdf(" sphere 1\n sphere 2\n")).message, "sdf termina con 2 formas sueltas (faltan operaciones que las combinen)");
+        assert_eq!(parse_error(&sdf("")).message, "sdf no tiene ninguna forma");
+        assert!(parse_error(&sdf(" sphere 1\n repeat 1 1 1 0 1 1\n")).message.contains("'repeat'"));
+    }
+
+    #[test]
+    fn bundled_diorama_parses() {
+        let path = format!("{}/scenes/diorama.scene", env!("CARGO_MANIFEST_DIR"));
+        let scene = SceneFile::load(&path).unwrap();
+        assert!(scene.camera.is_some());
+        assert!(!scene.objects.is_empty());
+        assert!(scene.texture_paths().iter().all(|p| p.starts_with("assets/")));
+    }
+}
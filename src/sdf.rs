@@ -0,0 +1,368 @@
+// Campos de distancia con signo (SDF) para decoraciones orgánicas: nubes, rocas, piezas
+// fractales. Un árbol de Sdf combina formas básicas con uniones (también suaves), intersecciones,
+// restas, repetición del espacio y torsión; SdfShape lo ubica en la escena y lo dibuja con sphere
+// tracing: el rayo avanza de a la distancia al objeto (que nunca lo hace pasar de largo) dentro de
+// la caja del árbol, y la normal sale del gradiente del campo.
+//
+// La torsión deforma el espacio y el campo deja de ser una distancia exacta: los árboles que la
+// usan avanzan con pasos más cortos (ver Sdf::step_scale).
+
+use crate::bvh::Aabb;
+use crate::material::Material;
+use crate::math::{Vec2, Vec3};
+use crate::primitives::Placement;
+use crate::ray_intersect::{HitInfo, RayIntersect};
+
+// pasos máximos por rayo y distancia a la que se considera que el rayo tocó la superficie
+const MAX_STEPS: usize = 256;
+const HIT_EPSILON: f32 = 1e-4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sdf {
+    Sphere { radius: f32 },
+    Box { half_size: Vec3 },
+    // caja con las aristas redondeadas con `radius` (half_size incluye el redondeo)
+    RoundBox { half_size: Vec3, radius: f32 },
+    // alrededor del eje y, como primitives::Torus
+    Torus { major_radius: f32, minor_radius: f32 },
+    // vertical, de y = -half_height a half_height más las semiesferas
+    Capsule { half_height: f32, radius: f32 },
+    Move { offset: Vec3, shape: Box<Sdf> },
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    // el primero menos el segundo
+    Difference(Box<Sdf>, Box<Sdf>),
+    // unión que redondea el encuentro en un ancho `k`
+    SmoothUnion { a: Box<Sdf>, b: Box<Sdf>, k: f32 },
+    // `count` copias por eje separadas `spacing`, centradas en el origen
+    Repeat { spacing: Vec3, count: [u32; 3], shape: Box<Sdf> },
+    // gira el plano xz `degrees_per_unit` grados por cada unidad de altura
+    Twist { degrees_per_unit: f32, shape: Box<Sdf> },
+}
+
+fn abs(v: Vec3) -> Vec3 {
+    Vec3::new(v.x.abs(), v.y.abs(), v.z.abs())
+}
+
+fn box_distance(p: Vec3, half_size: Vec3) -> f32 {
+    let q = abs(p) - half_size;
+    q.max(Vec3::zero()).length() + q.x.max(q.y).max(q.z).min(0.0)
+}
+
+// primera copia y desplazamiento del centro de la repetición en un eje
+fn repeat_axis(p: f32, spacing: f32, count: u32) -> f32 {
+    if count <= 1 || spacing <= 0.0 {
+        return p;
+    }
+    let offset = (count - 1) as f32 * 0.5;
+    let index = (p / spacing + offset).round().clamp(0.0, (count - 1) as f32);
+    p - spacing * (index - offset)
+}
+
+impl Sdf {
+    /// Distancia con signo (negativa adentro) del punto local `p` a la superficie
+    pub fn distance(&self, p: Vec3) -> f32 {
+        match self {
+            Sdf::Sphere { radius } => p.length() - radius,
+            Sdf::Box { half_size } => box_distance(p, *half_size),
+            Sdf::RoundBox { half_size, radius } => box_distance(p, *half_size - *radius) - radius,
+            Sdf::Torus { major_radius, minor_radius } => {
+                let ring = Vec2::new(Vec2::new(p.x, p.z).length() - major_radius, p.y);
+                ring.length() - minor_radius
+            }
+            Sdf::Capsule { half_height, radius } => {
+                let y = p.y - p.y.clamp(-half_height, *half_height);
+                Vec3::new(p.x, y, p.z).length() - radius
+            }
+            Sdf::Move { offset, shape } => shape.distance(p - *offset),
+            Sdf::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Sdf::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Sdf::Difference(a, b) => a.distance(p).max(-b.distance(p)),
+            Sdf::SmoothUnion { a, b, k } => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                if *k <= 0.0 {
+                    return da.min(db);
+                }
+                // mínimo suave polinomial
+                let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h - k * h * (1.0 - h)
+            }
+            Sdf::Repeat { spacing, count, shape } => shape.distance(Vec3::new(
+                repeat_axis(p.x, spacing.x, count[0]),
+                repeat_axis(p.y, spacing.y, count[1]),
+                repeat_axis(p.z, spacing.z, count[2]),
+            )),
+            Sdf::Twist { degrees_per_unit, shape } => {
+                let (s, c) = (-degrees_per_unit.to_radians() * p.y).sin_cos();
+                shape.distance(Vec3::new(c * p.x - s * p.z, p.y, s * p.x + c * p.z))
+            }
+        }
+    }
+
+    /// Caja local que contiene la superficie
+    pub fn bounds(&self) -> Aabb {
+        let centered = |half: Vec3| Aabb::new(-half, half);
+        match self {
+            Sdf::Sphere { radius } => centered(Vec3::new(*radius, *radius, *radius)),
+            Sdf::Box { half_size } | Sdf::RoundBox { half_size, .. } => centered(*half_size),
+            Sdf::Torus { major_radius, minor_radius } => {
+                let outer = major_radius + minor_radius;
+                centered(Vec3::new(outer, *minor_radius, outer))
+            }
+            Sdf::Capsule { half_height, radius } => centered(Vec3::new(*radius, half_height + radius, *radius)),
+            Sdf::Move { offset, shape } => {
+                let b = shape.bounds();
+                Aabb::new(b.min + *offset, b.max + *offset)
+            }
+            Sdf::Union(a, b) => a.bounds().union(&b.bounds()),
+            // el redondeo solo agrega material cerca del encuentro
+            Sdf::SmoothUnion { a, b, k } => a.bounds().union(&b.bounds()).expanded(k.max(0.0)),
+            Sdf::Intersection(a, b) => {
+                let (a, b) = (a.bounds(), b.bounds());
+                Aabb::new(a.min.max(b.min), a.max.min(b.max))
+            }
+            Sdf::Difference(a, _) => a.bounds(),
+            Sdf::Repeat { spacing, count, shape } => {
+                let b = shape.bounds();
+                let reach = |s: f32, n: u32| if n <= 1 || s <= 0.0 { 0.0 } else { s * (n - 1) as f32 * 0.5 };
+                let reach = Vec3::new(reach(spacing.x, count[0]), reach(spacing.y, count[1]), reach(spacing.z, count[2]));
+                Aabb::new(b.min - reach, b.max + reach)
+            }
+            Sdf::Twist { shape, .. } => {
+                // al girar alrededor de y ocupa el cilindro que alcanza la esquina más lejana
+                let b = shape.bounds();
+                let r = [b.min.x.abs().max(b.max.x.abs()), b.min.z.abs().max(b.max.z.abs())];
+                let r = Vec2::new(r[0], r[1]).length();
+                Aabb::new(Vec3::new(-r, b.min.y, -r), Vec3::new(r, b.max.y, r))
+            }
+        }
+    }
+
+    /// Fracción de la distancia que se avanza por paso: 1 si el campo es una distancia exacta (o
+    /// menor), menos si una torsión la estira
+    pub fn step_scale(&self) -> f32 {
+        match self {
+            Sdf::Sphere { .. } | Sdf::Box { .. } | Sdf::RoundBox { .. } | Sdf::Torus { .. } | Sdf::Capsule { .. } => 1.0,
+            Sdf::Move { shape, .. } | Sdf::Repeat { shape, .. } => shape.step_scale(),
+            Sdf::Union(a, b) | Sdf::Intersection(a, b) | Sdf::Difference(a, b) | Sdf::SmoothUnion { a, b, .. } => {
+                a.step_scale().min(b.step_scale())
+            }
+            Sdf::Twist { degrees_per_unit, shape } => {
+                // un punto a distancia r del eje se mueve r·k por unidad de altura
+                let b = shape.bounds();
+                let r = b.min.x.abs().max(b.max.x.abs()).max(b.min.z.abs()).max(b.max.z.abs());
+                let stretch = degrees_per_unit.to_radians() * r;
+                shape.step_scale() / (1.0 + stretch * stretch).sqrt()
+            }
+        }
+    }
+
+    /// Normal (gradiente normalizado) en el punto local `p`, por diferencias con cuatro muestras
+    pub fn normal(&self, p: Vec3) -> Vec3 {
+        const H: f32 = 1e-3;
+        let k = [Vec3::new(1.0, -1.0, -1.0), Vec3::new(-1.0, -1.0, 1.0), Vec3::new(-1.0, 1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)];
+        let gradient = k.iter().fold(Vec3::zero(), |g, k| g + *k * self.distance(p + *k * H));
+        if gradient.length() > 0.0 { gradient.normalized() } else { Vec3::new(0.0, 1.0, 0.0) }
+    }
+}
+
+/// Un árbol Sdf ubicado en la escena con un centro y una rotación (como las primitivas)
+pub struct SdfShape {
+    pub sdf: Sdf,
+    pub placement: Placement,
+    pub material: Material,
+    local_bounds: Aabb,
+    step_scale: f32,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Sdf, center: Vec3, rotation: Vec2, material: Material) -> Self {
+        let local_bounds = sdf.bounds();
+        let step_scale = sdf.step_scale();
+        SdfShape { sdf, placement: Placement::new(center, rotation), material, local_bounds, step_scale }
+    }
+
+    // tramo del rayo local dentro de la caja (la entrada puede ser negativa)
+    fn box_interval(&self, o: Vec3, d: Vec3) -> Option<(f32, f32)> {
+        let b = self.local_bounds.expanded(HIT_EPSILON * 10.0);
+        let mut range = (f32::NEG_INFINITY, f32::INFINITY);
+        for (o, d, min, max) in [(o.x, d.x, b.min.x, b.max.x), (o.y, d.y, b.min.y, b.max.y), (o.z, d.z, b.min.z, b.max.z)] {
+            if d.abs() < 1e-12 {
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = ((min - o) / d, (max - o) / d);
+            range = (range.0.max(t0.min(t1)), range.1.min(t0.max(t1)));
+        }
+        (range.0 <= range.1 && range.1 >= 0.0).then_some(range)
+    }
+}
+
+impl RayIntersect for SdfShape {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        let (o, d) = self.placement.to_local(ray_origin, ray_direction);
+        let (t_enter, t_exit) = self.box_interval(o, d)?;
+        let mut t = t_enter.max(0.0);
+        // si el origen está adentro se marcha hacia la salida: se sigue |distancia|
+        for _ in 0..MAX_STEPS {
+            if t > t_exit {
+                return None;
+            }
+            let distance = self.sdf.distance(o + d * t).abs();
+            if distance < HIT_EPSILON {
+                break;
+            }
+            t += distance * self.step_scale;
+        }
+        if t > t_exit {
+            return None;
+        }
+        let p = o + d * t;
+        if self.sdf.distance(p).abs() > HIT_EPSILON * 10.0 {
+            // se acabaron los pasos sin llegar a la superficie (rozándola desde afuera o
+            // avanzando a pasitos por dentro de una forma delgada)
+            return None;
+        }
+
+        let local_normal = self.sdf.normal(p);
+        let center = self.local_bounds.centroid();
+        Some(HitInfo {
+            hit: true,
+            point: self.placement.point_to_world(p),
+            // la UV de cubo sobre la caja del árbol
+            local_point: p - center,
+            local_half_size: (self.local_bounds.max - self.local_bounds.min) * 0.5,
+            normal: self.placement.vector_to_world(local_normal).normalized(),
+            local_normal,
+            distance: t,
+            material: self.material.clone(),
+            texture_repeat: Vec2::new(1.0, 1.0),
+            uv: None,
+        })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let b = self.local_bounds;
+        let center = b.centroid();
+        let world = self.placement.bounds((b.max - b.min) * 0.5);
+        // la caja de Placement está centrada en el origen local: se corre al centro de la del árbol
+        let shift = self.placement.vector_to_world(center);
+        Some(Aabb::new(world.min + shift, world.max + shift))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(sdf: Sdf) -> Box<Sdf> {
+        Box::new(sdf)
+    }
+
+    fn shape(sdf: Sdf) -> SdfShape {
+        SdfShape::new(sdf, Vec3::zero(), Vec2::zero(), Material::default())
+    }
+
+    fn down() -> Vec3 {
+        Vec3::new(0.0, -1.0, 0.0)
+    }
+
+    #[test]
+    fn distances_of_basic_shapes() {
+        let sphere = Sdf::Sphere { radius: 1.0 };
+        assert!((sphere.distance(Vec3::new(3.0, 0.0, 0.0)) - 2.0).abs() < 1e-6);
+        assert!((sphere.distance(Vec3::zero()) + 1.0).abs() < 1e-6);
+        let cube = Sdf::Box { half_size: Vec3::new(1.0, 2.0, 1.0) };
+        assert!((cube.distance(Vec3::new(0.0, 4.0, 0.0)) - 2.0).abs() < 1e-6);
+        assert!((cube.distance(Vec3::new(2.0, 3.0, 0.0)) - 2f32.sqrt()).abs() < 1e-6);
+        let torus = Sdf::Torus { major_radius: 2.0, minor_radius: 0.5 };
+        assert!((torus.distance(Vec3::zero()) - 1.5).abs() < 1e-6);
+        let capsule = Sdf::Capsule { half_height: 1.0, radius: 0.5 };
+        assert!((capsule.distance(Vec3::new(0.0, 3.0, 0.0)) - 1.5).abs() < 1e-6);
+        let rounded = Sdf::RoundBox { half_size: Vec3::one(), radius: 0.25 };
+        assert!((rounded.distance(Vec3::new(3.0, 0.0, 0.0)) - 2.0).abs() < 1e-6);
+        assert!(rounded.distance(Vec3::new(1.0, 1.0, 1.0)) > 0.0);
+    }
+
+    #[test]
+    fn smooth_union_fills_the_gap() {
+        let a = Sdf::Move { offset: Vec3::new(-1.1, 0.0, 0.0), shape: boxed(Sdf::Sphere { radius: 1.0 }) };
+        let b = Sdf::Move { offset: Vec3::new(1.1, 0.0, 0.0), shape: boxed(Sdf::Sphere { radius: 1.0 }) };
+        let hard = Sdf::Union(boxed(a.clone()), boxed(b.clone()));
+        let smooth = Sdf::SmoothUnion { a: boxed(a), b: boxed(b), k: 0.5 };
+        // entre las dos esferas la unión dura deja un hueco y la suave lo rellena
+        assert!(hard.distance(Vec3::zero()) > 0.0);
+        assert!(smooth.distance(Vec3::zero()) < 0.0);
+        // lejos del encuentro son iguales
+        let far = Vec3::new(4.0, 0.0, 0.0);
+        assert!((hard.distance(far) - smooth.distance(far)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn repetition_and_twist_bounds() {
+        let row = Sdf::Repeat { spacing: Vec3::new(3.0, 0.0, 0.0), count: [3, 1, 1], shape: boxed(Sdf::Sphere { radius: 1.0 }) };
+        assert!(row.distance(Vec3::new(3.0, 0.0, 0.0)) < 0.0);
+        assert!(row.distance(Vec3::new(-3.0, 0.0, 0.0)) < 0.0);
+        // más allá de la última copia no hay otra
+        assert!((row.distance(Vec3::new(6.0, 0.0, 0.0)) - 2.0).abs() < 1e-6);
+        let b = row.bounds();
+        assert_eq!((b.min.x, b.max.x), (-4.0, 4.0));
+
+        let twisted = Sdf::Twist { degrees_per_unit: 90.0, shape: boxed(Sdf::Box { half_size: Vec3::new(1.0, 2.0, 0.2) }) };
+        // a y = 1 la tabla giró 90°: lo que estaba sobre x ahora está sobre z
+        assert!(twisted.distance(Vec3::new(0.0, 1.0, 0.9)) < 0.0);
+        assert!(twisted.distance(Vec3::new(0.9, 1.0, 0.0)) > 0.0);
+        assert!(twisted.step_scale() < 1.0);
+        let b = twisted.bounds();
+        assert!((b.max.x - 1.04f32.sqrt()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sphere_tracing_finds_surfaces() {
+        let ball = shape(Sdf::Sphere { radius: 1.0 });
+        let hit = ball.ray_intersect(&Vec3::new(0.0, 5.0, 0.0), &down()).unwrap();
+        assert!((hit.distance - 4.0).abs() < 1e-3);
+        assert!((hit.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-3);
+        assert!(ball.ray_intersect(&Vec3::new(1.5, 5.0, 0.0), &down()).is_none());
+        // desde adentro llega a la salida con la normal hacia afuera
+        let hit = ball.ray_intersect(&Vec3::zero(), &Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.distance - 1.0).abs() < 1e-3 && hit.normal.x > 0.99);
+
+        // la caja de la escena sigue al centro y a la forma movida
+        let moved = SdfShape::new(
+            Sdf::Move { offset: Vec3::new(2.0, 0.0, 0.0), shape: boxed(Sdf::Box { half_size: Vec3::one() }) },
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec2::zero(),
+            Material::default(),
+        );
+        let b = moved.bounds().unwrap();
+        assert!((b.min - Vec3::new(1.0, 0.0, -1.0)).length() < 1e-5 && (b.max - Vec3::new(3.0, 2.0, 1.0)).length() < 1e-5);
+        let hit = moved.ray_intersect(&Vec3::new(2.5, 5.0, 0.5), &down()).unwrap();
+        assert!((hit.point.y - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn unconverged_march_from_inside_misses() {
+        // varilla muy delgada y larga: desde adentro, a lo largo, cada paso avanza ~0.01 y los
+        // pasos se acaban mucho antes de la salida
+        let rod = shape(Sdf::Box { half_size: Vec3::new(0.01, 0.01, 50.0) });
+        assert!(rod.ray_intersect(&Vec3::zero(), &Vec3::new(0.0, 0.0, 1.0)).is_none());
+        // de través sí llega a la pared
+        let hit = rod.ray_intersect(&Vec3::zero(), &Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.distance - 0.01).abs() < 1e-3 && hit.normal.x > 0.99);
+    }
+
+    #[test]
+    fn twisted_shapes_do_not_overshoot() {
+        let twisted = shape(Sdf::Twist { degrees_per_unit: 120.0, shape: boxed(Sdf::Box { half_size: Vec3::new(1.0, 2.0, 0.2) }) });
+        // los impactos quedan sobre la superficie (sin atravesarla)
+        for i in 0..20 {
+            let y = -1.8 + i as f32 * 0.18;
+            if let Some(hit) = twisted.ray_intersect(&Vec3::new(0.0, y, 5.0), &Vec3::new(0.0, 0.0, -1.0)) {
+                assert!(twisted.sdf.distance(hit.local_point + twisted.local_bounds.centroid()).abs() < 1e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+// Grafo de nodos de material: en lugar de agregar un campo a Material y una rama a cast_ray por
+// cada efecto, un material puede declarar nodos (entradas de la superficie, texturas, transformaciones
+// de UV, operaciones, mezclas y Fresnel) y conectar sus resultados a las salidas que usa el
+// renderer: color base, rugosidad, emisión y normal. Cada nodo devuelve un Color (que también sirve
+// de vector o, por su luminancia, de número) y solo puede leer nodos anteriores, así que se evalúan
+// en orden una vez por impacto.
+//
+// Una salida sin conectar usa lo de siempre del material; los materiales sin grafo usan
+// ShaderGraph::legacy, que conecta el color base y la emisión de Material tal cual.
+
+use crate::math::{Color, Vec2, Vec3};
+use crate::procedural::{Coords, Pattern, SurfacePoint};
+use crate::ray_intersect::HitInfo;
+use crate::renderer;
+use crate::sampling::orthonormal_basis;
+use crate::textures::TextureManager;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+// Lo que el nodo lee de la superficie o del material
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    // el color base del material (difuso, textura o patrón procedural)
+    Albedo,
+    // emissive × emission
+    Emission,
+    // (u, v, 0) con la repetición de la textura
+    Uv,
+    Position,
+    Local,
+    Normal,
+}
+
+impl Attribute {
+    pub fn name(self) -> &'static str {
+        match self {
+            Attribute::Albedo => "albedo",
+            Attribute::Emission => "emission",
+            Attribute::Uv => "uv",
+            Attribute::Position => "position",
+            Attribute::Local => "local",
+            Attribute::Normal => "normal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Attribute> {
+        [Attribute::Albedo, Attribute::Emission, Attribute::Uv, Attribute::Position, Attribute::Local, Attribute::Normal]
+            .into_iter()
+            .find(|a| a.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MathOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Min,
+    Max,
+}
+
+impl MathOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            MathOp::Add => "add",
+            MathOp::Subtract => "subtract",
+            MathOp::Multiply => "multiply",
+            MathOp::Divide => "divide",
+            MathOp::Power => "power",
+            MathOp::Min => "min",
+            MathOp::Max => "max",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<MathOp> {
+        [MathOp::Add, MathOp::Subtract, MathOp::Multiply, MathOp::Divide, MathOp::Power, MathOp::Min, MathOp::Max]
+            .into_iter()
+            .find(|op| op.name() == name)
+    }
+
+    fn apply(self, a: f32, b: f32) -> f32 {
+        match self {
+            MathOp::Add => a + b,
+            MathOp::Subtract => a - b,
+            MathOp::Multiply => a * b,
+            // dividir por cero da 0 en vez de infinito
+            MathOp::Divide => if b == 0.0 { 0.0 } else { a / b },
+            MathOp::Power => a.max(0.0).powf(b),
+            MathOp::Min => a.min(b),
+            MathOp::Max => a.max(b),
+        }
+    }
+}
+
+// Entrada de un nodo o de una salida: un nodo anterior (por índice) o un número fijo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    Node(usize),
+    Value(f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderNode {
+    Attribute(Attribute),
+    // patrón de src/procedural.rs en el punto del impacto
+    Pattern { pattern: Pattern, coords: Coords },
+    // PNG muestreado en la UV que da `uv` (x, y)
+    Image { path: String, uv: Input },
+    // escala, gira (grados) y desplaza una UV, en ese orden
+    UvTransform { uv: Input, scale: Vec2, rotation: f32, offset: Vec2 },
+    // canal a canal
+    Math { op: MathOp, a: Input, b: Input },
+    // `a` donde el factor es 0, `b` donde es 1 (se usa su luminancia)
+    Mix { a: Input, b: Input, factor: Input },
+    // reflectancia de Schlick para el índice de refracción `ior`: crece hacia los bordes
+    Fresnel { ior: f32 },
+}
+
+// Input y ShaderNode se hashean a mano por los f32 (por bits, como Vec3); ShaderGraph lo
+// deriva encima, así el id de material sale del contenido del grafo
+impl Hash for Input {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match *self {
+            Input::Node(index) => index.hash(state),
+            Input::Value(value) => value.to_bits().hash(state),
+        }
+    }
+}
+
+impl Hash for ShaderNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            ShaderNode::Attribute(attribute) => attribute.hash(state),
+            ShaderNode::Pattern { pattern, coords } => (pattern, coords).hash(state),
+            ShaderNode::Image { path, uv } => (path, uv).hash(state),
+            ShaderNode::UvTransform { uv, scale, rotation, offset } => (uv, scale, rotation.to_bits(), offset).hash(state),
+            ShaderNode::Math { op, a, b } => (op, a, b).hash(state),
+            ShaderNode::Mix { a, b, factor } => (a, b, factor).hash(state),
+            ShaderNode::Fresnel { ior } => ior.to_bits().hash(state),
+        }
+    }
+}
+
+/// Grafo de un material: nodos en orden de evaluación y las salidas conectadas
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
+pub struct ShaderGraph {
+    pub nodes: Vec<(String, ShaderNode)>,
+    pub base_color: Option<Input>,
+    // 0 = espejo pulido, 1 = mate; reemplaza al exponente `specular`
+    pub roughness: Option<Input>,
+    pub emission: Option<Input>,
+    // normal en el espacio tangente, codificada como en un normal map (0.5 0.5 1 = sin cambio)
+    pub normal: Option<Input>,
+}
+
+/// Lo que el renderer necesita de la superficie en un impacto
+pub struct Shading {
+    pub base_color: Color,
+    // exponente de Blinn-Phong
+    pub specular: f32,
+    pub emission: Color,
+    pub normal: Vec3,
+}
+
+/// Exponente de Blinn-Phong equivalente a una rugosidad en [0, 1] (α = rugosidad²)
+pub fn roughness_to_specular(roughness: f32) -> f32 {
+    let alpha = roughness.clamp(0.02, 1.0).powi(2);
+    (2.0 / (alpha * alpha) - 2.0).max(1.0)
+}
+
+impl ShaderGraph {
+    /// El grafo de los materiales sin grafo propio: color base y emisión del material
+    pub fn legacy() -> &'static ShaderGraph {
+        static LEGACY: OnceLock<ShaderGraph> = OnceLock::new();
+        LEGACY.get_or_init(|| ShaderGraph {
+            nodes: vec![
+                ("albedo".to_string(), ShaderNode::Attribute(Attribute::Albedo)),
+                ("emission".to_string(), ShaderNode::Attribute(Attribute::Emission)),
+            ],
+            base_color: Some(Input::Node(0)),
+            emission: Some(Input::Node(1)),
+            ..ShaderGraph::default()
+        })
+    }
+
+    /// Índice del nodo `name`
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|(n, _)| n == name)
+    }
+
+    /// Evalúa el grafo en el impacto; `view_dir` va del punto hacia el ojo
+    pub fn evaluate(&self, hit: &HitInfo, view_dir: &Vec3, textures: &TextureManager) -> Shading {
+        let at = renderer::surface_point(hit);
+        let mut values: Vec<Color> = Vec::with_capacity(self.nodes.len());
+        for (_, node) in &self.nodes {
+            let value = evaluate_node(node, &values, hit, &at, view_dir, textures);
+            values.push(value);
+        }
+        let read = |input: Input| match input {
+            Input::Node(index) => values.get(index).copied().unwrap_or(Color::BLACK),
+            Input::Value(v) => Color::splat(v),
+        };
+
+        let m = &hit.material;
+        let normal = match self.normal {
+            Some(input) => {
+                let c = read(input) * 2.0 - 1.0;
+                let (t, b) = orthonormal_basis(&hit.normal);
+                let n = t * c.r + b * c.g + hit.normal * c.b;
+                // una normal que apunta hacia adentro dejaría la cara negra
+                if n.dot(hit.normal) > 0.0 { n.normalized() } else { hit.normal }
+            }
+            None => hit.normal,
+        };
+        Shading {
+            base_color: self.base_color.map_or_else(|| renderer::surface_albedo(hit, textures), read),
+            specular: self.roughness.map_or(m.specular, |input| roughness_to_specular(read(input).luminance())),
+            emission: self.emission.map_or(m.emissive * m.emission, read),
+            normal,
+        }
+    }
+}
+
+fn evaluate_node(node: &ShaderNode, values: &[Color], hit: &HitInfo, at: &SurfacePoint, view_dir: &Vec3, textures: &TextureManager) -> Color {
+    let read = |input: Input| match input {
+        Input::Node(index) => values.get(index).copied().unwrap_or(Color::BLACK),
+        Input::Value(v) => Color::splat(v),
+    };
+    let vector = |v: Vec3| Color::new(v.x, v.y, v.z);
+    match node {
+        ShaderNode::Attribute(attribute) => match attribute {
+            Attribute::Albedo => renderer::surface_albedo(hit, textures),
+            Attribute::Emission => hit.material.emissive * hit.material.emission,
+            Attribute::Uv => at.uv.map_or(Color::BLACK, |uv| Color::new(uv.x, uv.y, 0.0)),
+            Attribute::Position => vector(at.world),
+            Attribute::Local => vector(at.local),
+            Attribute::Normal => vector(hit.normal),
+        },
+        ShaderNode::Pattern { pattern, coords } => {
+            let p = match coords {
+                Coords::World => at.world,
+                Coords::Local => at.local,
+                Coords::Uv => at.uv.map_or(Vec3::zero(), |uv| Vec3::new(uv.x, uv.y, 0.0)),
+            };
+            Color::splat(pattern.value(p))
+        }
+        ShaderNode::Image { path, uv } => {
+            let uv = read(*uv);
+            textures.sample_uv(path, uv.r, uv.g)
+        }
+        ShaderNode::UvTransform { uv, scale, rotation, offset } => {
+            let uv = read(*uv);
+            let (u, v) = (uv.r * scale.x, uv.g * scale.y);
+            let (s, c) = rotation.to_radians().sin_cos();
+            Color::new(c * u - s * v + offset.x, s * u + c * v + offset.y, uv.b)
+        }
+        ShaderNode::Math { op, a, b } => {
+            let (a, b) = (read(*a), read(*b));
+            Color::new(op.apply(a.r, b.r), op.apply(a.g, b.g), op.apply(a.b, b.b))
+        }
+        ShaderNode::Mix { a, b, factor } => read(*a).lerp(read(*b), read(*factor).luminance().clamp(0.0, 1.0)),
+        ShaderNode::Fresnel { ior } => {
+            let r0 = ((1.0 - ior) / (1.0 + ior)).powi(2);
+            let cos = hit.normal.dot(*view_dir).abs().min(1.0);
+            Color::splat(r0 + (1.0 - r0) * (1.0 - cos).powi(5))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::ray_intersect::RayIntersect;
+
+    // impacto en la cara de arriba de un cubo unitario, mirando desde arriba
+    fn hit(material: Material) -> HitInfo {
+        let cube = Cube::new(Vec3::zero(), Vec3::new(0.5, 0.5, 0.5), 0.0, 0.0, material);
+        cube.ray_intersect(&Vec3::new(0.25, 5.0, 0.0), &Vec3::new(0.0, -1.0, 0.0)).unwrap()
+    }
+
+    fn up() -> Vec3 {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+
+    #[test]
+    fn legacy_graph_matches_material() {
+        let material = Material { diffuse: Color::new(0.3, 0.6, 0.9), specular: 40.0, emissive: Color::new(1.0, 0.5, 0.0), emission: 2.0, ..Material::default() };
+        let shading = ShaderGraph::legacy().evaluate(&hit(material), &up(), &TextureManager::new());
+        assert_eq!(shading.base_color, Color::new(0.3, 0.6, 0.9));
+        assert_eq!(shading.specular, 40.0);
+        assert_eq!(shading.emission, Color::new(2.0, 1.0, 0.0));
+        assert_eq!(shading.normal, up());
+    }
+
+    #[test]
+    fn nodes_feed_outputs() {
+        // color base = albedo × 0.5 oscurecido hacia +x por un degradé en el mundo
+        let graph = ShaderGraph {
+            nodes: vec![
+                ("albedo".into(), ShaderNode::Attribute(Attribute::Albedo)),
+                ("oscuro".into(), ShaderNode::Math { op: MathOp::Multiply, a: Input::Node(0), b: Input::Value(0.5) }),
+                ("negro".into(), ShaderNode::Math { op: MathOp::Max, a: Input::Value(0.0), b: Input::Value(0.0) }),
+                ("borde".into(), ShaderNode::Pattern { pattern: Pattern::Gradient { direction: Vec3::new(1.0, 0.0, 0.0) }, coords: Coords::World }),
+                ("base".into(), ShaderNode::Mix { a: Input::Node(1), b: Input::Node(2), factor: Input::Node(3) }),
+                ("brillo".into(), ShaderNode::Fresnel { ior: 1.5 }),
+            ],
+            base_color: Some(Input::Node(4)),
+            roughness: Some(Input::Value(1.0)),
+            emission: Some(Input::Node(5)),
+            normal: Some(Input::Value(0.5)),
+        };
+        let shading = graph.evaluate(&hit(Material::default()), &up(), &TextureManager::new());
+        // en x = 0.25 el factor es 0.25: 0.75 × 0.5
+        assert!((shading.base_color.g - 0.375).abs() < 1e-5);
+        assert_eq!(shading.specular, 1.0);
+        // mirando de frente Fresnel da r0 = 0.04
+        assert!((shading.emission.r - 0.04).abs() < 1e-5);
+        // 0.5 0.5 0.5 es (0, 0, 0) en el espacio tangente: queda la normal geométrica
+        assert_eq!(shading.normal, up());
+        // de costado Fresnel llega casi a 1
+        let grazing = graph.evaluate(&hit(Material::default()), &Vec3::new(1.0, 0.01, 0.0).normalized(), &TextureManager::new());
+        assert!(grazing.emission.r > 0.9);
+    }
+
+    #[test]
+    fn uv_transform_and_tangent_normal() {
+        let graph = ShaderGraph {
+            nodes: vec![
+                ("uv".into(), ShaderNode::Attribute(Attribute::Uv)),
+                ("giro".into(), ShaderNode::UvTransform { uv: Input::Node(0), scale: Vec2::new(2.0, 2.0), rotation: 90.0, offset: Vec2::new(1.0, 0.0) }),
+                ("inclinada".into(), ShaderNode::Math { op: MathOp::Add, a: Input::Value(0.5), b: Input::Value(0.0) }),
+            ],
+            base_color: Some(Input::Node(1)),
+            ..ShaderGraph::default()
+        };
+        let h = hit(Material::default());
+        let (u, v) = renderer::hit_uv(&h).unwrap();
+        let shading = graph.evaluate(&h, &up(), &TextureManager::new());
+        // (u, v) × 2 girado 90° es (-2v, 2u), más el desplazamiento
+        assert!((shading.base_color.r - (1.0 - 2.0 * v)).abs() < 1e-5);
+        assert!((shading.base_color.g - 2.0 * u).abs() < 1e-5);
+        assert!(roughness_to_specular(0.0) > 1000.0 && roughness_to_specular(1.0) == 1.0);
+
+        // una normal tangente inclinada gira la normal sin dejar de apuntar afuera
+        let mut tilted = ShaderGraph { normal: Some(Input::Node(3)), ..graph };
+        tilted.nodes.push(("n".into(), ShaderNode::Mix { a: Input::Value(1.0), b: Input::Value(0.5), factor: Input::Value(0.0) }));
+        let shading = tilted.evaluate(&h, &up(), &TextureManager::new());
+        assert!(shading.normal.y > 0.0 && shading.normal.y < 0.99);
+        assert!((shading.normal.length() - 1.0).abs() < 1e-5);
+    }
+}
@@ -1,15 +1,44 @@
-use raylib::prelude::*;
-use crate::ray_intersect::{RayIntersect, HitInfo};
+use crate::bvh::Aabb;
+use crate::math::{Vec2, Vec3};
+use crate::ray_intersect::{RayIntersect, HitInfo, Span};
 use crate::material::Material;
 
+#[derive(Clone)]
 pub struct Sphere {
-    pub center: Vector3,
+    pub center: Vec3,
     pub radius: f32,
     pub material: Material
 }
 
 impl RayIntersect for Sphere {
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<HitInfo> {
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.center - self.radius, self.center + self.radius))
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<HitInfo> {
+        let (t0, t1) = self.roots(ray_origin, ray_direction)?;
+
+        let t = if t0 < 0.0 { t1 } else { t0 };
+        if t < 0.0 {
+            return None;
+        }
+        Some(self.hit_at(ray_origin, ray_direction, t))
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        match self.roots(ray_origin, ray_direction) {
+            Some((t0, t1)) if t1 >= 0.0 => vec![Span {
+                enter: self.hit_at(ray_origin, ray_direction, t0),
+                exit: self.hit_at(ray_origin, ray_direction, t1),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Sphere {
+    // distancias de entrada y salida del rayo a la esfera (la entrada puede ser negativa)
+    fn roots(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
         let l = self.center - *ray_origin;
         let tca = l.dot(*ray_direction);
         let d2 = l.dot(l) - tca * tca;
@@ -20,27 +49,24 @@ impl RayIntersect for Sphere {
         }
 
         let thc = (radius2 - d2).sqrt();
-        let t0 = tca - thc;
-        let t1 = tca + thc;
-
-        let t = if t0 < 0.0 { t1 } else { t0 };
-        if t < 0.0 {
-            return None;
-        }
+        Some((tca - thc, tca + thc))
+    }
 
+    fn hit_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, t: f32) -> HitInfo {
         let hit_point = *ray_origin + *ray_direction * t;
         let normal = (hit_point - self.center).normalized();
 
-        Some(HitInfo {
+        HitInfo {
             hit: true,
             point: hit_point,
             local_point: hit_point - self.center, // local respecto al centro
-            local_half_size: Vector3::new(self.radius, self.radius, self.radius), // aproximación
+            local_half_size: Vec3::new(self.radius, self.radius, self.radius), // aproximación
             normal,
             local_normal: normal,
             distance: t,
             material: self.material.clone(),
-            texture_repeat: Vector2::new(1.0, 1.0),
-        })
+            texture_repeat: Vec2::new(1.0, 1.0),
+            uv: None,
+        }
     }
 }
@@ -0,0 +1,63 @@
+use crate::math::Color;
+use crate::material::Subsurface;
+use crate::ray_intersect::HitInfo;
+use crate::sampling::{self, Rng};
+use crate::scene::Scene;
+
+// caminatas por punto de impacto y máximo de eventos de dispersión por caminata
+const WALKS: u32 = 4;
+const MAX_EVENTS: u32 = 12;
+
+/// Aproxima la luz que entra por la superficie, viaja dentro del volumen del objeto y sale
+/// por otro punto (random walk). En cada evento la distancia recorrida se muestrea con
+/// exp(-d / mean_free_path) y el color se atenúa por `sss.color`; al salir se evalúa la luz
+/// directa en el punto de salida.
+pub fn random_walk(scene: &Scene, hit: &HitInfo, sss: &Subsurface) -> Color {
+    if sss.mean_free_path <= 0.0 {
+        return Color::BLACK;
+    }
+
+    let mut rng = Rng::from_point(&hit.point, 0x55);
+    let inward = -hit.normal;
+    let mut total = Color::BLACK;
+
+    for _ in 0..WALKS {
+        // entrada difusa hacia el interior
+        let mut dir = sampling::cosine_hemisphere(&inward, &mut rng);
+        let mut pos = hit.point - hit.normal * 1e-3;
+        let mut throughput = Color::WHITE;
+
+        for _ in 0..MAX_EVENTS {
+            let distance = -(1.0 - rng.next_f32()).ln() * sss.mean_free_path;
+
+            // desde adentro, el hit más cercano es la cara por la que saldría el camino
+            let Some(exit) = scene.closest_hit(&pos, &dir) else { break; };
+
+            if exit.distance <= distance {
+                total += throughput * exit_radiance(scene, &exit);
+                break;
+            }
+
+            // evento de dispersión isotrópico dentro del medio
+            pos += dir * distance;
+            throughput *= sss.color;
+            dir = sampling::uniform_sphere(&mut rng);
+        }
+    }
+
+    total / WALKS as f32
+}
+
+// luz directa que recibe la superficie en el punto por donde sale la caminata
+fn exit_radiance(scene: &Scene, exit: &HitInfo) -> Color {
+    let mut radiance = Color::BLACK;
+    let shadow_origin = exit.point + exit.normal * 5e-3;
+    for light in scene.lights.iter() {
+        let Some((light_dir, dist)) = light.incidence(&exit.point) else { continue; };
+        let ndotl = exit.normal.dot(light_dir).max(0.0);
+        if ndotl <= 0.0 { continue; }
+        if scene.intersects_any(&shadow_origin, &light_dir, dist - 1e-3) { continue; }
+        radiance += light.color * (ndotl * light.attenuation(dist));
+    }
+    radiance
+}
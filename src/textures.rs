@@ -1,13 +1,32 @@
-use raylib::prelude::*;
 use std::collections::HashMap;
+use crate::math::Color;
+use crate::png::{self, RgbaImage};
+use std::io;
+#[cfg(feature = "raylib")]
+use raylib::prelude::{Image, RaylibHandle, RaylibThread, Texture2D};
 
 pub struct CpuTexture {
     pub width: i32,
     pub height: i32,
-    pub pixels: Vec<Vector3>, // Normalized RGB values
+    pub pixels: Vec<Color>, // RGB lineal en [0, 1]
 }
 
 impl CpuTexture {
+    pub fn from_rgba(image: &RgbaImage) -> Self {
+        let pixels = image
+            .pixels
+            .iter()
+            .map(|c| Color::new(c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0))
+            .collect();
+
+        CpuTexture {
+            width: image.width,
+            height: image.height,
+            pixels,
+        }
+    }
+
+    #[cfg(feature = "raylib")]
     pub fn from_image(image: &Image) -> Self {
         // cuidado con la API exacta de raylib-rs: aquí asumimos que
         // image.get_image_data() -> Vec<Color> (o ajusta según tu versión)
@@ -15,7 +34,7 @@ impl CpuTexture {
         let pixels = colors
             .iter()
             .map(|c| {
-                Vector3::new(
+                Color::new(
                     c.r as f32 / 255.0,
                     c.g as f32 / 255.0,
                     c.b as f32 / 255.0,
@@ -31,41 +50,88 @@ impl CpuTexture {
     }
 }
 
+#[derive(Default)]
 pub struct TextureManager {
     cpu_textures: HashMap<String, CpuTexture>,
+    #[cfg(feature = "raylib")]
     textures: HashMap<String, Texture2D>, // GPU textures para rendering
 }
 
 impl TextureManager {
     pub fn new() -> Self { Self::default() }
 
+    /// Registra una textura ya decodificada (sin ventana ni GPU)
+    pub fn insert(&mut self, path: &str, texture: CpuTexture) {
+        self.cpu_textures.insert(path.to_string(), texture);
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.cpu_textures.contains_key(path)
+    }
+
+    /// Carga una textura PNG solo en CPU (sin ventana): basta para trazar rayos en modo headless
+    pub fn load_cpu_texture(&mut self, path: &str) -> io::Result<()> {
+        if !self.contains(path) {
+            let image = png::read(path)?;
+            self.insert(path, CpuTexture::from_rgba(&image));
+        }
+        Ok(())
+    }
+
+    /// Vuelve a leer del disco una textura en CPU (p.ej. porque el archivo cambió)
+    pub fn reload_cpu_texture(&mut self, path: &str) -> io::Result<()> {
+        let image = png::read(path)?;
+        self.insert(path, CpuTexture::from_rgba(&image));
+        Ok(())
+    }
+
+    /// Carga la textura en GPU y en CPU; no hace nada si ya estaba cargada
+    #[cfg(feature = "raylib")]
     pub fn load_texture(
         &mut self,
         rl: &mut RaylibHandle,
         thread: &RaylibThread,
         path: &str,
-    ) {
+    ) -> Result<(), String> {
         if self.textures.contains_key(path) {
-            return;
+            return Ok(());
         }
 
-        // Ajusta según la API de tu versión de raylib-rs si load_image devuelve Result
         let image = Image::load_image(path)
-            .unwrap_or_else(|_| panic!("Failed to load image {}", path));
+            .map_err(|e| format!("no se pudo cargar la imagen {}: {}", path, e))?;
 
         let texture = rl
             .load_texture_from_image(thread, &image)
-            .unwrap_or_else(|_| panic!("Failed to load texture {}", path));
-            // si tu API devuelve Result: .unwrap_or_else(...)
+            .map_err(|e| format!("no se pudo crear la textura {}: {}", path, e))?;
 
         let cpu_texture = CpuTexture::from_image(&image);
 
         self.cpu_textures.insert(path.to_string(), cpu_texture);
         self.textures.insert(path.to_string(), texture);
+        Ok(())
+    }
+
+    /// Descarta la copia cacheada (CPU y GPU) y vuelve a cargar la textura desde el disco. Si la
+    /// nueva imagen no se puede leer se conserva la anterior.
+    #[cfg(feature = "raylib")]
+    pub fn reload_texture(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        path: &str,
+    ) -> Result<(), String> {
+        let previous = self.textures.remove(path);
+        let result = self.load_texture(rl, thread, path);
+        if result.is_err()
+            && let Some(texture) = previous
+        {
+            self.textures.insert(path.to_string(), texture);
+        }
+        result
     }
 
     /// Muestra un texel dado (u,v). Aquí u,v pueden estar fuera de [0,1] — se envuelven (repeat).
-    pub fn sample_uv(&self, path: &str, u: f32, v: f32) -> Vector3 {
+    pub fn sample_uv(&self, path: &str, u: f32, v: f32) -> Color {
         if let Some(cpu_texture) = self.cpu_textures.get(path) {
             // wrap (repetir) coordenadas u,v incluso si están fuera de [0,1]
             let mut u_wrapped = u - u.floor(); // fract, pero funciona con negativos
@@ -85,24 +151,15 @@ impl TextureManager {
             if index < cpu_texture.pixels.len() {
                 cpu_texture.pixels[index]
             } else {
-                Vector3::one()
+                Color::WHITE
             }
         } else {
-            Vector3::one()
+            Color::WHITE
         }
     }
 
-
+    #[cfg(feature = "raylib")]
     pub fn get_texture(&self, path: &str) -> Option<&Texture2D> {
         self.textures.get(path)
     }
 }
-
-impl Default for TextureManager {
-    fn default() -> Self {
-        TextureManager {
-            cpu_textures: HashMap::new(),
-            textures: HashMap::new(),
-        }
-    }
-}
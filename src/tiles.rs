@@ -0,0 +1,210 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Instant;
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::textures::TextureManager;
+use crate::{trace_region, PassSettings, TracedPixel};
+
+pub const TILE_SIZE: i32 = 32;
+
+// Orden en el que se reparten los tiles: espiral desde el centro (lo importante primero)
+// o curva de Hilbert (tiles vecinos seguidos, mejor coherencia de caché)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TileOrder {
+    Spiral,
+    Hilbert,
+}
+
+// Rectángulo [x0, x1) × [y0, y1) en píxeles
+#[derive(Clone, Copy, Debug)]
+pub struct Tile {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+// d (posición en la curva) -> (x, y) en una curva de Hilbert de lado n (potencia de 2)
+fn hilbert_d2xy(n: i32, d: i32) -> (i32, i32) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+/// Divide la imagen en tiles de `tile_size` y los ordena según `order`
+pub fn make_tiles(width: i32, height: i32, tile_size: i32, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let cols = (width + tile_size - 1) / tile_size;
+    let rows = (height + tile_size - 1) / tile_size;
+    let tile_at = |cx: i32, cy: i32| Tile {
+        x0: cx * tile_size,
+        y0: cy * tile_size,
+        x1: ((cx + 1) * tile_size).min(width),
+        y1: ((cy + 1) * tile_size).min(height),
+    };
+
+    match order {
+        TileOrder::Spiral => {
+            let mut cells: Vec<(i32, i32)> = (0..rows).flat_map(|cy| (0..cols).map(move |cx| (cx, cy))).collect();
+            let center_x = (cols - 1) as f32 * 0.5;
+            let center_y = (rows - 1) as f32 * 0.5;
+            // anillo (distancia de Chebyshev al centro) y luego ángulo dentro del anillo
+            cells.sort_by(|a, b| {
+                let key = |c: &(i32, i32)| {
+                    let dx = c.0 as f32 - center_x;
+                    let dy = c.1 as f32 - center_y;
+                    (dx.abs().max(dy.abs()), dy.atan2(dx))
+                };
+                let (ra, aa) = key(a);
+                let (rb, ab) = key(b);
+                ra.total_cmp(&rb).then(aa.total_cmp(&ab))
+            });
+            cells.into_iter().map(|(cx, cy)| tile_at(cx, cy)).collect()
+        }
+        TileOrder::Hilbert => {
+            let mut n = 1;
+            while n < cols.max(rows) {
+                n *= 2;
+            }
+            (0..n * n)
+                .map(|d| hilbert_d2xy(n, d))
+                .filter(|(cx, cy)| *cx < cols && *cy < rows)
+                .map(|(cx, cy)| tile_at(cx, cy))
+                .collect()
+        }
+    }
+}
+
+// Token de cancelación compartido entre el hilo principal y los workers
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken(Arc::new(AtomicBool::new(false)))
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Un frame renderizándose en segundo plano. Los tiles terminados llegan por un canal y el hilo
+// principal los copia al framebuffer a medida que aparecen.
+pub struct RenderJob {
+    pub pass: PassSettings,
+    receiver: Receiver<Vec<TracedPixel>>,
+    total_tiles: usize,
+    received_tiles: usize,
+    cancel: CancelToken,
+    handle: Option<JoinHandle<()>>,
+    started: Instant,
+}
+
+impl RenderJob {
+    /// Lanza el render de `width`×`height` en un hilo que reparte los tiles en el pool de rayon.
+    /// Los workers toman tiles en el orden dado, así los primeros (el centro) salen antes.
+    pub fn spawn(
+        scene: Arc<Scene>,
+        texture_manager: Arc<TextureManager>,
+        camera: Camera,
+        size: (i32, i32),
+        pass: PassSettings,
+        order: TileOrder,
+    ) -> Self {
+        let (width, height) = size;
+        let tiles = make_tiles(width, height, TILE_SIZE, order);
+        let total_tiles = tiles.len();
+        let (sender, receiver) = mpsc::channel();
+        let cancel = CancelToken::new();
+        let worker_cancel = cancel.clone();
+
+        let handle = std::thread::spawn(move || {
+            let next = AtomicUsize::new(0);
+            rayon::scope(|s| {
+                for _ in 0..rayon::current_num_threads() {
+                    let sender = sender.clone();
+                    let (tiles, next, cancel) = (&tiles, &next, &worker_cancel);
+                    let (scene, texture_manager, camera) = (&scene, &texture_manager, &camera);
+                    s.spawn(move |_| loop {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(tile) = tiles.get(i) else { break; };
+                        let pixels = trace_region(width, height, tile, scene, camera, texture_manager, pass);
+                        if cancel.is_cancelled() || sender.send(pixels).is_err() {
+                            break;
+                        }
+                    });
+                }
+            });
+        });
+
+        RenderJob {
+            pass,
+            receiver,
+            total_tiles,
+            received_tiles: 0,
+            cancel,
+            handle: Some(handle),
+            started: Instant::now(),
+        }
+    }
+
+    /// Tiles terminados desde la última llamada (no bloquea)
+    pub fn take_finished(&mut self) -> Vec<Vec<TracedPixel>> {
+        let tiles: Vec<Vec<TracedPixel>> = self.receiver.try_iter().collect();
+        self.received_tiles += tiles.len();
+        tiles
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.total_tiles == 0 { 1.0 } else { self.received_tiles as f32 / self.total_tiles as f32 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.received_tiles >= self.total_tiles
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+
+    /// Aborta el frame y espera a que los workers suelten la escena
+    pub fn cancel(&mut self) {
+        self.cancel.cancel();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for RenderJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
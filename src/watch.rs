@@ -0,0 +1,141 @@
+// Detección de cambios en archivos por sondeo de la fecha de modificación (sin hilos ni APIs del
+// sistema operativo): suficiente para recargar escenas y texturas mientras la ventana está abierta.
+
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+pub struct FileWatcher {
+    files: Vec<String>,
+    // directorios vigilados con la extensión que interesa (p.ej. "png")
+    dirs: Vec<(String, String)>,
+    modified: HashMap<String, SystemTime>,
+    interval: Duration,
+    last_poll: Instant,
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl FileWatcher {
+    /// `interval`: tiempo mínimo entre dos revisiones del disco
+    pub fn new(interval: Duration) -> Self {
+        FileWatcher {
+            files: Vec::new(),
+            dirs: Vec::new(),
+            modified: HashMap::new(),
+            interval,
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn watch_file(&mut self, path: &str) {
+        if !self.files.iter().any(|f| f == path) {
+            self.files.push(path.to_string());
+            self.remember(path);
+        }
+    }
+
+    /// Vigila los archivos con extensión `extension` dentro de `dir` (incluidos los que se creen después)
+    pub fn watch_dir(&mut self, dir: &str, extension: &str) {
+        self.dirs.push((dir.to_string(), extension.to_string()));
+        for path in self.dir_entries(dir, extension) {
+            self.remember(&path);
+        }
+    }
+
+    /// Toma como vista la versión actual de `path`, p.ej. después de escribirlo uno mismo, para que
+    /// el próximo poll no lo informe como cambiado
+    pub fn acknowledge(&mut self, path: &str) {
+        self.remember(path);
+    }
+
+    fn remember(&mut self, path: &str) {
+        if let Some(time) = modified_time(path) {
+            self.modified.insert(path.to_string(), time);
+        }
+    }
+
+    // rutas como `dir/nombre.ext`, con el mismo formato con el que se referencian las texturas
+    fn dir_entries(&self, dir: &str, extension: &str) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+        let mut paths: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case(extension)))
+            .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(|n| format!("{}/{}", dir, n)))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Archivos creados o modificados desde la revisión anterior. No toca el disco si no pasó
+    /// `interval` desde la última vez.
+    pub fn poll(&mut self) -> Vec<String> {
+        if self.last_poll.elapsed() < self.interval {
+            return Vec::new();
+        }
+        self.poll_now()
+    }
+
+    /// Igual que poll pero sin esperar el intervalo
+    pub fn poll_now(&mut self) -> Vec<String> {
+        self.last_poll = Instant::now();
+        let mut candidates = self.files.clone();
+        for (dir, extension) in &self.dirs {
+            candidates.extend(self.dir_entries(dir, extension));
+        }
+
+        let mut changed = Vec::new();
+        for path in candidates {
+            // un archivo que desaparece (p.ej. mientras el editor lo reescribe) se ignora hasta que vuelva
+            let Some(time) = modified_time(&path) else { continue };
+            if self.modified.get(&path) != Some(&time) {
+                self.modified.insert(path.clone(), time);
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_modified_and_new_files() {
+        let dir = std::env::temp_dir().join(format!("animal_rt_watch_{}", std::process::id()));
+        let dir_str = dir.to_str().unwrap().to_string();
+        fs::create_dir_all(&dir).unwrap();
+        let scene = format!("{}/a.scene", dir_str);
+        fs::write(&scene, "time 1").unwrap();
+
+        let mut watcher = FileWatcher::new(Duration::from_secs(3600));
+        watcher.watch_file(&scene);
+        watcher.watch_dir(&dir_str, "png");
+        assert!(watcher.poll_now().is_empty());
+        // el intervalo todavía no pasó
+        assert!(watcher.poll().is_empty());
+
+        let texture = format!("{}/b.png", dir_str);
+        fs::write(&texture, "x").unwrap();
+        assert_eq!(watcher.poll_now(), vec![texture.clone()]);
+
+        // forzar una fecha distinta (la resolución del sistema de archivos puede ser gruesa)
+        let file = fs::File::options().write(true).open(&scene).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        assert_eq!(watcher.poll_now(), vec![scene.clone()]);
+        assert!(watcher.poll_now().is_empty());
+
+        let file = fs::File::options().write(true).open(&scene).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        watcher.acknowledge(&scene);
+        assert!(watcher.poll_now().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,490 @@
+// DEFLATE/zlib mínimo, sin dependencias: lo necesario para leer y escribir PNG y los formatos
+// comprimidos de Minecraft (gzip en las estructuras .nbt, zlib en las regiones .mca). El descompresor soporta los tres tipos de bloque; el compresor
+// usa un único bloque Huffman fijo con LZ77 por tabla hash (suficiente para imágenes pequeñas).
+
+use std::io;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// Tablas de la especificación (RFC 1951, 3.2.5)
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097,
+    6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// orden en que vienen las longitudes del código de longitudes en un bloque dinámico
+const CLEN_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// CRC-32 incremental (continúa a partir de un CRC ya calculado)
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    for &b in data {
+        c ^= b as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+    }
+    !c
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &x in chunk {
+            a += x as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// ---------------------------------------------------------------------------------------------
+// Descompresión
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0, bit_buf: 0, bit_count: 0 }
+    }
+
+    // `n` bits (n <= 16), el primero en el bit menos significativo
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.bit_count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("deflate: datos truncados"))?;
+            self.pos += 1;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let v = self.bit_buf & ((1u32 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        Ok(v)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+// Código de Huffman canónico: cuántos códigos hay de cada longitud y los símbolos ordenados
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offsets[l as usize] as usize] = symbol as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, br: &mut BitReader) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= br.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("deflate: código de Huffman inválido"))
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5u8; 30]))
+}
+
+fn dynamic_tables(br: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let hlit = br.bits(5)? as usize + 257;
+    let hdist = br.bits(5)? as usize + 1;
+    let hclen = br.bits(4)? as usize + 4;
+
+    let mut clen = [0u8; 19];
+    for &i in CLEN_ORDER.iter().take(hclen) {
+        clen[i] = br.bits(3)? as u8;
+    }
+    let clen_code = Huffman::new(&clen);
+
+    let mut lengths = vec![0u8; hlit + hdist];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = clen_code.decode(br)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let prev = *lengths[..i].last().ok_or_else(|| invalid("deflate: repetición sin longitud previa"))?;
+                (prev, 3 + br.bits(2)? as usize)
+            }
+            17 => (0, 3 + br.bits(3)? as usize),
+            _ => (0, 11 + br.bits(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(invalid("deflate: demasiadas longitudes"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    Ok((Huffman::new(&lengths[..hlit]), Huffman::new(&lengths[hlit..])))
+}
+
+fn inflate_block(br: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = lit.decode(br)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+        } else if symbol == 256 {
+            return Ok(());
+        } else {
+            let idx = symbol - 257;
+            if idx >= LEN_BASE.len() {
+                return Err(invalid("deflate: longitud inválida"));
+            }
+            let len = LEN_BASE[idx] as usize + br.bits(LEN_EXTRA[idx] as u32)? as usize;
+            let d = dist.decode(br)? as usize;
+            if d >= DIST_BASE.len() {
+                return Err(invalid("deflate: distancia inválida"));
+            }
+            let distance = DIST_BASE[d] as usize + br.bits(DIST_EXTRA[d] as u32)? as usize;
+            if distance > out.len() {
+                return Err(invalid("deflate: distancia fuera de la ventana"));
+            }
+            // byte a byte: la copia puede solaparse con lo que se está escribiendo
+            let start = out.len() - distance;
+            for k in 0..len {
+                out.push(out[start + k]);
+            }
+        }
+    }
+}
+
+/// Descomprime un flujo DEFLATE crudo (sin cabecera zlib)
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut br = BitReader::new(data);
+    let mut out = Vec::new();
+    loop {
+        let last = br.bits(1)? == 1;
+        match br.bits(2)? {
+            0 => {
+                br.align_to_byte();
+                let header = data.get(br.pos..br.pos + 4).ok_or_else(|| invalid("deflate: bloque truncado"))?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err(invalid("deflate: longitud de bloque sin comprimir inválida"));
+                }
+                br.pos += 4;
+                let bytes = data.get(br.pos..br.pos + len as usize).ok_or_else(|| invalid("deflate: bloque truncado"))?;
+                out.extend_from_slice(bytes);
+                br.pos += len as usize;
+            }
+            1 => {
+                let (lit, dist) = fixed_tables();
+                inflate_block(&mut br, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut br)?;
+                inflate_block(&mut br, &mut out, &lit, &dist)?;
+            }
+            _ => return Err(invalid("deflate: tipo de bloque reservado")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Descomprime un flujo zlib (cabecera de 2 bytes + DEFLATE + Adler-32)
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 6 || data[0] & 0x0f != 8 || !((data[0] as u16) << 8 | data[1] as u16).is_multiple_of(31) {
+        return Err(invalid("zlib: cabecera inválida"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(invalid("zlib: diccionario predefinido no soportado"));
+    }
+    let out = inflate(&data[2..])?;
+    let tail = &data[data.len() - 4..];
+    if adler32(&out) != u32::from_be_bytes([tail[0], tail[1], tail[2], tail[3]]) {
+        return Err(invalid("zlib: checksum incorrecto"));
+    }
+    Ok(out)
+}
+
+// banderas de la cabecera gzip (RFC 1952)
+const GZIP_FHCRC: u8 = 0x02;
+const GZIP_FEXTRA: u8 = 0x04;
+const GZIP_FNAME: u8 = 0x08;
+const GZIP_FCOMMENT: u8 = 0x10;
+
+/// Descomprime un archivo gzip de un solo miembro (cabecera + DEFLATE + CRC-32 y tamaño)
+pub fn gunzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+        return Err(invalid("gzip: cabecera inválida"));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    if flags & GZIP_FEXTRA != 0 {
+        let len = data.get(pos..pos + 2).ok_or_else(|| invalid("gzip: cabecera truncada"))?;
+        pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    // nombre y comentario terminan en 0
+    for flag in [GZIP_FNAME, GZIP_FCOMMENT] {
+        if flags & flag != 0 {
+            let end = data.get(pos..).and_then(|rest| rest.iter().position(|&b| b == 0));
+            pos += end.ok_or_else(|| invalid("gzip: cabecera truncada"))? + 1;
+        }
+    }
+    if flags & GZIP_FHCRC != 0 {
+        pos += 2;
+    }
+    let body = data.get(pos..data.len() - 8).ok_or_else(|| invalid("gzip: archivo truncado"))?;
+    let out = inflate(body)?;
+    let tail = &data[data.len() - 8..];
+    if crc32(&out) != u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]])
+        || out.len() as u32 != u32::from_le_bytes([tail[4], tail[5], tail[6], tail[7]])
+    {
+        return Err(invalid("gzip: checksum incorrecto"));
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------------------------
+// Compresión
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, n: u32) {
+        self.bit_buf |= value << self.bit_count;
+        self.bit_count += n;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    // los códigos de Huffman van con el bit más significativo primero
+    fn put_code(&mut self, code: u32, len: u32) {
+        self.put(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn flush(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buf as u8);
+        }
+        self.out
+    }
+}
+
+// código fijo de literales/longitudes (RFC 1951, 3.2.6)
+fn put_literal(bw: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => bw.put_code(0x30 + symbol, 8),
+        144..=255 => bw.put_code(0x190 + symbol - 144, 9),
+        256..=279 => bw.put_code(symbol - 256, 7),
+        _ => bw.put_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn put_match(bw: &mut BitWriter, len: usize, distance: usize) {
+    let l = LEN_BASE.iter().rposition(|&b| b as usize <= len).unwrap();
+    put_literal(bw, 257 + l as u32);
+    bw.put((len - LEN_BASE[l] as usize) as u32, LEN_EXTRA[l] as u32);
+    let d = DIST_BASE.iter().rposition(|&b| b as usize <= distance).unwrap();
+    bw.put_code(d as u32, 5);
+    bw.put((distance - DIST_BASE[d] as usize) as u32, DIST_EXTRA[d] as u32);
+}
+
+const WINDOW: usize = 32 * 1024;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 64;
+
+fn hash3(data: &[u8], i: usize) -> usize {
+    let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+    (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Comprime con DEFLATE crudo (un bloque Huffman fijo)
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut bw = BitWriter { out: Vec::new(), bit_buf: 0, bit_count: 0 };
+    bw.put(1, 1); // último bloque
+    bw.put(1, 2); // Huffman fijo
+
+    // head[h]: última posición con ese hash; prev[i % WINDOW]: posición anterior con el mismo hash
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + 2 < data.len() {
+            let h = hash3(data, i);
+            prev[i % WINDOW] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if i + 2 < data.len() {
+            let mut candidate = head[hash3(data, i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let max_len = (data.len() - i).min(258);
+                let len = (0..max_len).take_while(|&k| data[candidate + k] == data[i + k]).count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW];
+                // la tabla circular pudo reciclarse: solo se sigue hacia atrás
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= 3 {
+            put_match(&mut bw, best_len, best_dist);
+            for k in i..i + best_len {
+                insert(k, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            put_literal(&mut bw, data[i] as u32);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    put_literal(&mut bw, 256);
+    bw.flush()
+}
+
+/// Comprime en formato zlib (cabecera + DEFLATE + Adler-32)
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    out.extend(deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Comprime en formato gzip (cabecera mínima + DEFLATE + CRC-32 y tamaño)
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    // sin fecha ni nombre; 255 = sistema operativo desconocido
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    out.extend(deflate(data));
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc_and_adler_match_known_values() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn roundtrip_compress_decompress() {
+        let mut data = Vec::new();
+        for i in 0..20_000u32 {
+            data.push((i % 251) as u8);
+            if i % 7 == 0 {
+                data.extend_from_slice(b"abcabcabc");
+            }
+        }
+        let packed = compress(&data);
+        assert!(packed.len() < data.len());
+        assert_eq!(decompress(&packed).unwrap(), data);
+        assert_eq!(decompress(&compress(&[])).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn gzip_roundtrip_and_header_fields() {
+        let data = b"minecraft:stone minecraft:stone minecraft:dirt".repeat(20);
+        assert_eq!(gunzip(&gzip(&data)).unwrap(), data);
+
+        // cabecera con nombre de archivo (como la que escribe el juego o la herramienta gzip)
+        let mut named = vec![0x1f, 0x8b, 8, GZIP_FNAME, 0, 0, 0, 0, 0, 3];
+        named.extend_from_slice(b"casa.nbt\0");
+        named.extend(deflate(&data));
+        named.extend_from_slice(&crc32(&data).to_le_bytes());
+        named.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        assert_eq!(gunzip(&named).unwrap(), data);
+
+        let mut corrupt = gzip(&data);
+        let n = corrupt.len();
+        corrupt[n - 8] ^= 1;
+        assert!(gunzip(&corrupt).is_err());
+    }
+
+    #[test]
+    fn inflates_stored_block() {
+        // bloque sin comprimir: BFINAL=1, BTYPE=00, LEN=3, NLEN=!3, "abc"
+        let data = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(inflate(&data).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn rejects_corrupt_stream() {
+        let mut packed = compress(b"hola hola hola");
+        let n = packed.len();
+        packed[n - 1] ^= 0xff;
+        assert!(decompress(&packed).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// Tests de regresión por imagen: renderizan escenas pequeñas sin ventana y las comparan con las
+// referencias de tests/golden/*.png. Todo el muestreo del renderer es determinista (el Rng se
+// siembra con el punto de impacto) y la hora del día es fija, así que dos corridas dan la misma
+// imagen salvo diferencias de punto flotante entre máquinas; la tolerancia absorbe esas.
+//
+// Si un test falla se escriben en target/tmp/golden/ la imagen obtenida, una imagen de diferencias
+// y un reporte de texto. Para regenerar las referencias (a propósito):
+//
+//     GOLDEN_UPDATE=1 cargo test --test golden
+
+use animal_rt::cube::Cube;
+use animal_rt::daycycle::DayCycle;
+use animal_rt::math::{Color, Rgba8, Vec3};
+use animal_rt::png::{self, RgbaImage};
+use animal_rt::sphere::Sphere;
+use animal_rt::{Camera, Light, Material, RayIntersect, Renderer, Scene, TextureManager};
+use std::fmt::Write as _;
+use std::fs;
+use std::sync::Arc;
+
+const WIDTH: i32 = 64;
+const HEIGHT: i32 = 48;
+const TIME_OF_DAY: f32 = 10.5;
+
+// Tolerancias: error cuadrático medio (en unidades de 8 bits) y fracción de píxeles que se alejan
+// de la referencia más de BAD_PIXEL_DELTA en algún canal
+const MAX_RMSE: f64 = 1.5;
+const BAD_PIXEL_DELTA: u8 = 24;
+const MAX_BAD_FRACTION: f64 = 0.005;
+
+fn matte(diffuse: Color) -> Material {
+    Material { diffuse, ..Material::default() }
+}
+
+fn floor() -> Box<dyn RayIntersect + Send + Sync> {
+    Box::new(Cube::new(Vec3::new(0.0, -1.25, 0.0), Vec3::new(4.0, 0.25, 4.0), 0.0, 0.0, matte(Color::new(0.55, 0.55, 0.5))))
+}
+
+fn scene(objects: Vec<Box<dyn RayIntersect + Send + Sync>>) -> Scene {
+    let torch = Light::point(Vec3::new(-2.0, 2.0, 2.5), Color::new(1.0, 0.72, 0.35), 4.0);
+    Scene::new(objects, vec![torch], DayCycle::new(TIME_OF_DAY))
+}
+
+fn camera() -> Camera {
+    Camera::new(Vec3::new(0.0, 1.0, 4.5), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn render(scene: &Scene, textures: TextureManager) -> RgbaImage {
+    let renderer = Renderer::new(Arc::new(textures));
+    RgbaImage::new(WIDTH, HEIGHT, renderer.render_to_buffer(scene, &camera(), WIDTH, HEIGHT))
+}
+
+struct Comparison {
+    rmse: f64,
+    bad_pixels: usize,
+    max_delta: u8,
+    diff: RgbaImage,
+}
+
+fn compare(actual: &RgbaImage, reference: &RgbaImage) -> Comparison {
+    let mut sum = 0.0;
+    let mut bad_pixels = 0;
+    let mut max_delta = 0;
+    let mut diff = Vec::with_capacity(actual.pixels.len());
+    for (a, r) in actual.pixels.iter().zip(&reference.pixels) {
+        let deltas = [a.r.abs_diff(r.r), a.g.abs_diff(r.g), a.b.abs_diff(r.b)];
+        sum += deltas.iter().map(|&d| (d as f64).powi(2)).sum::<f64>();
+        let delta = deltas.into_iter().max().unwrap();
+        max_delta = max_delta.max(delta);
+        // diferencias pequeñas amplificadas en gris; las que superan el umbral, en rojo
+        diff.push(if delta > BAD_PIXEL_DELTA {
+            bad_pixels += 1;
+            Rgba8::new(255, 0, 0, 255)
+        } else {
+            let v = delta.saturating_mul(8);
+            Rgba8::new(v, v, v, 255)
+        });
+    }
+    let rmse = (sum / (actual.pixels.len() * 3).max(1) as f64).sqrt();
+    Comparison { rmse, bad_pixels, max_delta, diff: RgbaImage::new(actual.width, actual.height, diff) }
+}
+
+fn check_golden(name: &str, actual: &RgbaImage) {
+    let reference_path = format!("{}/tests/golden/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        png::write(&reference_path, actual).unwrap();
+        return;
+    }
+
+    let out_dir = format!("{}/golden", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(&out_dir).unwrap();
+    let actual_path = format!("{}/{}-actual.png", out_dir, name);
+
+    let reference = match png::read(&reference_path) {
+        Ok(reference) => reference,
+        Err(e) => {
+            png::write(&actual_path, actual).unwrap();
+            panic!("{}: no se pudo leer la referencia {} ({}); imagen obtenida en {}", name, reference_path, e, actual_path);
+        }
+    };
+    if (reference.width, reference.height) != (actual.width, actual.height) {
+        png::write(&actual_path, actual).unwrap();
+        panic!(
+            "{}: tamaño {}x{} distinto de la referencia {}x{}",
+            name, actual.width, actual.height, reference.width, reference.height
+        );
+    }
+
+    let result = compare(actual, &reference);
+    let bad_fraction = result.bad_pixels as f64 / actual.pixels.len() as f64;
+    if result.rmse <= MAX_RMSE && bad_fraction <= MAX_BAD_FRACTION {
+        return;
+    }
+
+    let diff_path = format!("{}/{}-diff.png", out_dir, name);
+    let report_path = format!("{}/{}.txt", out_dir, name);
+    png::write(&actual_path, actual).unwrap();
+    png::write(&diff_path, &result.diff).unwrap();
+
+    let mut report = String::new();
+    writeln!(report, "escena:      {}", name).unwrap();
+    writeln!(report, "referencia:  {}", reference_path).unwrap();
+    writeln!(report, "obtenida:    {}", actual_path).unwrap();
+    writeln!(report, "diferencias: {}", diff_path).unwrap();
+    writeln!(report, "rmse:        {:.3} (máximo {})", result.rmse, MAX_RMSE).unwrap();
+    writeln!(
+        report,
+        "píxeles > {}: {} de {} ({:.2}%, máximo {:.2}%)",
+        BAD_PIXEL_DELTA,
+        result.bad_pixels,
+        actual.pixels.len(),
+        bad_fraction * 100.0,
+        MAX_BAD_FRACTION * 100.0
+    )
+    .unwrap();
+    writeln!(report, "delta máx.:  {}", result.max_delta).unwrap();
+    fs::write(&report_path, &report).unwrap();
+    panic!("{}: la imagen no coincide con la referencia\n{}", name, report);
+}
+
+#[test]
+fn single_sphere() {
+    let sphere = Sphere { center: Vec3::new(0.0, 0.0, 0.0), radius: 1.0, material: matte(Color::new(0.8, 0.25, 0.2)) };
+    let scene = scene(vec![Box::new(sphere), floor()]);
+    check_golden("single_sphere", &render(&scene, TextureManager::new()));
+}
+
+#[test]
+fn rotated_cube() {
+    let cube = Cube::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.7, 0.7, 0.7), 0.5, 0.7, matte(Color::new(0.3, 0.5, 0.85)));
+    let scene = scene(vec![Box::new(cube), floor()]);
+    check_golden("rotated_cube", &render(&scene, TextureManager::new()));
+}
+
+#[test]
+fn glass_slab_over_floor() {
+    let glass = Material {
+        diffuse: Color::new(0.9, 0.95, 1.0),
+        specular: 125.0,
+        reflectivity: 0.08,
+        transparency: 0.85,
+        refractive_index: 1.5,
+        albedo: [0.1, 0.3],
+        ..matte(Color::WHITE)
+    };
+    let slab = Cube::new(Vec3::new(0.0, 0.0, 0.5), Vec3::new(1.2, 0.2, 1.0), 0.0, 0.3, glass);
+    let marker = Sphere { center: Vec3::new(0.3, -0.6, 0.3), radius: 0.4, material: matte(Color::new(0.2, 0.7, 0.3)) };
+    let scene = scene(vec![Box::new(slab), Box::new(marker), floor()]);
+    check_golden("glass_slab_over_floor", &render(&scene, TextureManager::new()));
+}
+
+#[test]
+fn textured_block() {
+    let mut textures = TextureManager::new();
+    textures.load_cpu_texture("assets/brick.png").unwrap();
+    let brick = Material { texture_path: Some("assets/brick.png".to_string()), ..matte(Color::WHITE) };
+    let block = Cube::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.8, 0.8, 0.8), 0.0, 0.6, brick);
+    let scene = scene(vec![Box::new(block), floor()]);
+    check_golden("textured_block", &render(&scene, textures));
+}
+
+#[test]
+fn comparison_flags_changed_pixels() {
+    let base = RgbaImage::new(4, 4, vec![Rgba8::new(100, 100, 100, 255); 16]);
+    let mut changed = base.clone();
+    changed.pixels[5] = Rgba8::new(200, 100, 100, 255);
+    let same = compare(&base, &base);
+    assert_eq!((same.rmse, same.bad_pixels), (0.0, 0));
+    let result = compare(&changed, &base);
+    assert_eq!(result.bad_pixels, 1);
+    assert_eq!(result.diff.get(1, 1), Rgba8::new(255, 0, 0, 255));
+    assert!(result.rmse > MAX_RMSE);
+}
@@ -0,0 +1,501 @@
+// Tests de propiedades para las intersecciones rayo–primitiva (Sphere, Cube, las analíticas de
+// src/primitives.rs y el sphere tracing de src/sdf.rs). Cada propiedad se
+// prueba con cientos de casos generados con el Rng del crate a partir de una semilla fija; si un
+// caso falla, el mensaje indica la semilla y el número de caso para reproducirlo.
+//
+// También se verifica que las cajas (`bounds`) que usa el BVH contengan los impactos y que los
+// tramos (`ray_spans`) que combina CSG coincidan con el impacto más cercano.
+//
+// Las distancias esperadas se calculan aparte en f64 (cuadrática para la esfera, slabs para la
+// caja) para no comparar la implementación consigo misma.
+
+use animal_rt::cube::Cube;
+use animal_rt::primitives::Primitive;
+use animal_rt::math::{Mat4, Vec2, Vec3};
+use animal_rt::sampling::{uniform_sphere, Rng};
+use animal_rt::sdf::{Sdf, SdfShape};
+use animal_rt::sphere::Sphere;
+use animal_rt::{HitInfo, Material, RayIntersect};
+
+const CASES: usize = 500;
+
+fn material() -> Material {
+    Material { specular: 0.0, albedo: [1.0, 0.0], ..Material::default() }
+}
+
+// Corre `property` con CASES casos; el closure devuelve Err con la descripción del fallo
+fn check(name: &str, seed: u64, mut property: impl FnMut(&mut Rng) -> Result<(), String>) {
+    for case in 0..CASES {
+        let mut rng = Rng::new(seed.wrapping_mul(1_000_003).wrapping_add(case as u64));
+        if let Err(msg) = property(&mut rng) {
+            panic!("{}: falla en el caso {} (semilla {}): {}", name, case, seed, msg);
+        }
+    }
+}
+
+fn range(rng: &mut Rng, lo: f32, hi: f32) -> f32 {
+    lo + (hi - lo) * rng.next_f32()
+}
+
+fn random_point(rng: &mut Rng, extent: f32) -> Vec3 {
+    Vec3::new(range(rng, -extent, extent), range(rng, -extent, extent), range(rng, -extent, extent))
+}
+
+fn ensure(cond: bool, msg: impl FnOnce() -> String) -> Result<(), String> {
+    if cond { Ok(()) } else { Err(msg()) }
+}
+
+fn near(a: f32, b: f32, tol: f32) -> bool {
+    (a - b).abs() <= tol
+}
+
+fn near_vec(a: Vec3, b: Vec3, tol: f32) -> bool {
+    (a - b).length() <= tol
+}
+
+// Propiedades comunes a cualquier hit: distancia positiva, punto sobre el rayo y normal unitaria
+fn check_hit_basics(hit: &HitInfo, origin: Vec3, dir: Vec3) -> Result<(), String> {
+    ensure(hit.distance > 0.0, || format!("distancia no positiva: {}", hit.distance))?;
+    let on_ray = origin + dir * hit.distance;
+    ensure(near_vec(hit.point, on_ray, 1e-3 * (1.0 + hit.distance)), || {
+        format!("el punto {:?} no está sobre el rayo (esperado {:?})", hit.point, on_ray)
+    })?;
+    ensure(near(hit.normal.length(), 1.0, 1e-4), || format!("normal no unitaria: {:?}", hit.normal))
+}
+
+// ---------------------------------------------------------------------------------------------
+// Esfera
+
+fn random_sphere(rng: &mut Rng) -> Sphere {
+    Sphere { center: random_point(rng, 5.0), radius: range(rng, 0.1, 3.0), material: material() }
+}
+
+// Raíces (t0 <= t1) de |o + t d - c| = r en f64, o None si el rayo no toca la esfera
+fn sphere_roots(sphere: &Sphere, origin: Vec3, dir: Vec3) -> Option<(f64, f64)> {
+    let l = [
+        (sphere.center.x - origin.x) as f64,
+        (sphere.center.y - origin.y) as f64,
+        (sphere.center.z - origin.z) as f64,
+    ];
+    let d = [dir.x as f64, dir.y as f64, dir.z as f64];
+    let tca = l[0] * d[0] + l[1] * d[1] + l[2] * d[2];
+    let d2 = l[0] * l[0] + l[1] * l[1] + l[2] * l[2] - tca * tca;
+    let r2 = (sphere.radius as f64).powi(2);
+    if d2 > r2 {
+        return None;
+    }
+    let thc = (r2 - d2).sqrt();
+    Some((tca - thc, tca + thc))
+}
+
+fn check_on_sphere(sphere: &Sphere, hit: &HitInfo) -> Result<(), String> {
+    let r = (hit.point - sphere.center).length();
+    ensure(near(r, sphere.radius, 1e-4 * (1.0 + hit.distance)), || {
+        format!("el punto está a {} del centro, radio {}", r, sphere.radius)
+    })?;
+    let outward = (hit.point - sphere.center) / r;
+    ensure(near_vec(hit.normal, outward, 1e-3), || format!("normal {:?} no es radial ({:?})", hit.normal, outward))
+}
+
+#[test]
+fn sphere_hits_from_outside_are_on_surface_and_nearest() {
+    check("sphere_hits_from_outside", 1, |rng| {
+        let sphere = random_sphere(rng);
+        let origin = sphere.center + uniform_sphere(rng) * sphere.radius * range(rng, 1.05, 10.0);
+        let target = sphere.center + random_point(rng, 1.5 * sphere.radius);
+        let dir = (target - origin).normalized();
+
+        match (sphere.ray_intersect(&origin, &dir), sphere_roots(&sphere, origin, dir)) {
+            (Some(hit), Some((t0, _))) => {
+                check_hit_basics(&hit, origin, dir)?;
+                check_on_sphere(&sphere, &hit)?;
+                ensure(hit.normal.dot(dir) <= 1e-3, || "desde afuera la normal debe oponerse al rayo".to_string())?;
+                ensure(near(hit.distance, t0 as f32, 1e-3 * (1.0 + t0 as f32)), || {
+                    format!("distancia {} pero la entrada está en {}", hit.distance, t0)
+                })
+            }
+            // solo se aceptan desacuerdos en rayos rasantes o con la esfera detrás
+            (None, Some((t0, _))) => ensure(t0 < 0.0, || format!("no hubo hit pero la esfera está en t = {}", t0)),
+            (Some(hit), None) => Err(format!("hit en t = {} pero el rayo no toca la esfera", hit.distance)),
+            (None, None) => Ok(()),
+        }
+    });
+}
+
+#[test]
+fn sphere_origin_inside_hits_exit_point() {
+    check("sphere_origin_inside", 2, |rng| {
+        let sphere = random_sphere(rng);
+        let origin = sphere.center + uniform_sphere(rng) * sphere.radius * range(rng, 0.0, 0.95);
+        let dir = uniform_sphere(rng);
+
+        let hit = sphere.ray_intersect(&origin, &dir).ok_or("desde adentro siempre hay salida")?;
+        check_hit_basics(&hit, origin, dir)?;
+        check_on_sphere(&sphere, &hit)?;
+        // la normal geométrica apunta hacia afuera, en el mismo sentido que el rayo que sale
+        ensure(hit.normal.dot(dir) >= -1e-3, || "la normal de salida debe apuntar hacia afuera".to_string())?;
+        let (_, t1) = sphere_roots(&sphere, origin, dir).ok_or("las raíces deben existir")?;
+        ensure(near(hit.distance, t1 as f32, 1e-3 * (1.0 + t1 as f32)), || {
+            format!("distancia {} pero la salida está en {}", hit.distance, t1)
+        })
+    });
+}
+
+#[test]
+fn sphere_grazing_rays() {
+    check("sphere_grazing", 3, |rng| {
+        let sphere = random_sphere(rng);
+        let dir = uniform_sphere(rng);
+        // dirección perpendicular a `dir` para desplazar el rayo respecto al centro
+        let side = dir.cross(uniform_sphere(rng)).normalized();
+        let start = sphere.center - dir * sphere.radius * 4.0;
+
+        let inside = start + side * sphere.radius * 0.999;
+        let hit = sphere.ray_intersect(&inside, &dir).ok_or("un rayo apenas dentro del borde debe pegar")?;
+        check_hit_basics(&hit, inside, dir)?;
+        check_on_sphere(&sphere, &hit)?;
+
+        let outside = start + side * sphere.radius * 1.001;
+        ensure(sphere.ray_intersect(&outside, &dir).is_none(), || "un rayo apenas fuera del borde no debe pegar".to_string())?;
+
+        // la esfera entera detrás del origen
+        let behind = sphere.center + dir * sphere.radius * 2.0;
+        ensure(sphere.ray_intersect(&behind, &dir).is_none(), || "una esfera detrás del origen no debe pegar".to_string())
+    });
+}
+
+// ---------------------------------------------------------------------------------------------
+// Cubo
+
+fn random_cube(rng: &mut Rng, rotated: bool) -> Cube {
+    let half = Vec3::new(range(rng, 0.1, 3.0), range(rng, 0.1, 3.0), range(rng, 0.1, 3.0));
+    let (rx, ry) = if rotated { (range(rng, -3.1, 3.1), range(rng, -3.1, 3.1)) } else { (0.0, 0.0) };
+    Cube::new(random_point(rng, 5.0), half, rx, ry, material())
+}
+
+// Transformación local -> mundo del cubo: primero Rx, luego Ry, luego la traslación
+fn cube_to_world(cube: &Cube) -> Mat4 {
+    Mat4::translation(cube.center) * Mat4::rotation_y(cube.rot_y) * Mat4::rotation_x(cube.rot_x)
+}
+
+fn half_axes(cube: &Cube) -> [f32; 3] {
+    [cube.half_size.x, cube.half_size.y, cube.half_size.z]
+}
+
+fn axes(v: Vec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+// Intervalo (t_near, t_far) en f64 del rayo local contra la caja [-h, h]
+fn slab_interval(half: [f32; 3], origin: Vec3, dir: Vec3) -> Option<(f64, f64)> {
+    let (mut t_near, mut t_far) = (f64::NEG_INFINITY, f64::INFINITY);
+    for ((h, o), d) in half.iter().zip(axes(origin)).zip(axes(dir)) {
+        let (h, o, d) = (*h as f64, o as f64, d as f64);
+        if d == 0.0 {
+            if o < -h || o > h {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (-h - o) / d;
+        let t2 = (h - o) / d;
+        t_near = t_near.max(t1.min(t2));
+        t_far = t_far.min(t1.max(t2));
+    }
+    (t_near <= t_far && t_far >= 0.0).then_some((t_near, t_far))
+}
+
+// El punto local está sobre una cara y la normal local es la de esa cara
+fn check_on_box(cube: &Cube, hit: &HitInfo) -> Result<(), String> {
+    let half = half_axes(cube);
+    let p = axes(hit.local_point);
+    let tol = 1e-4 * (1.0 + hit.distance);
+    for i in 0..3 {
+        ensure(p[i].abs() <= half[i] + tol, || format!("punto local {:?} fuera de la caja {:?}", p, half))?;
+    }
+
+    let n = axes(hit.local_normal);
+    let axis = (0..3).find(|&i| n[i].abs() == 1.0).ok_or_else(|| format!("normal local no alineada a un eje: {:?}", n))?;
+    ensure((0..3).all(|i| i == axis || n[i] == 0.0), || format!("normal local no alineada a un eje: {:?}", n))?;
+    ensure(near(p[axis] * n[axis], half[axis], tol), || {
+        format!("la normal {:?} no corresponde a la cara del punto {:?} (caja {:?})", n, p, half)
+    })?;
+
+    let world = cube_to_world(cube);
+    ensure(near_vec(world.transform_point(hit.local_point), hit.point, tol * 10.0), || {
+        "el punto local y el de mundo no coinciden".to_string()
+    })?;
+    ensure(near_vec(world.transform_vector(hit.local_normal), hit.normal, 1e-4), || {
+        "la normal local y la de mundo no coinciden".to_string()
+    })
+}
+
+#[test]
+fn cube_hits_from_outside_are_on_surface_and_nearest() {
+    check("cube_hits_from_outside", 4, |rng| {
+        let cube = random_cube(rng, true);
+        let world = cube_to_world(&cube);
+        let local = world.inverse().unwrap();
+        let origin = cube.center + uniform_sphere(rng) * (cube.half_size.length() * range(rng, 1.05, 6.0));
+        let target = world.transform_point(Vec3::new(
+            cube.half_size.x * range(rng, -1.3, 1.3),
+            cube.half_size.y * range(rng, -1.3, 1.3),
+            cube.half_size.z * range(rng, -1.3, 1.3),
+        ));
+        let dir = (target - origin).normalized();
+        let interval = slab_interval(half_axes(&cube), local.transform_point(origin), local.transform_vector(dir));
+
+        match (cube.ray_intersect(&origin, &dir), interval) {
+            (Some(hit), Some((t_near, _))) => {
+                check_hit_basics(&hit, origin, dir)?;
+                check_on_box(&cube, &hit)?;
+                ensure(hit.normal.dot(dir) <= 1e-4, || "desde afuera la normal debe oponerse al rayo".to_string())?;
+                ensure(near(hit.distance, t_near as f32, 1e-3 * (1.0 + t_near as f32)), || {
+                    format!("distancia {} pero la entrada está en {}", hit.distance, t_near)
+                })
+            }
+            (None, Some((t_near, t_far))) => ensure(t_far - t_near < 1e-4, || {
+                format!("no hubo hit pero el rayo cruza la caja en [{}, {}]", t_near, t_far)
+            }),
+            (Some(hit), None) => Err(format!("hit en t = {} pero el rayo no cruza la caja", hit.distance)),
+            (None, None) => Ok(()),
+        }
+    });
+}
+
+#[test]
+fn cube_origin_inside_hits_exit_face() {
+    // con el origen adentro t es t_far: la normal sale del respaldo por proximidad a las caras
+    check("cube_origin_inside", 5, |rng| {
+        let cube = random_cube(rng, true);
+        let world = cube_to_world(&cube);
+        let origin = world.transform_point(Vec3::new(
+            cube.half_size.x * range(rng, -0.95, 0.95),
+            cube.half_size.y * range(rng, -0.95, 0.95),
+            cube.half_size.z * range(rng, -0.95, 0.95),
+        ));
+        let dir = uniform_sphere(rng);
+
+        let hit = cube.ray_intersect(&origin, &dir).ok_or("desde adentro siempre hay salida")?;
+        check_hit_basics(&hit, origin, dir)?;
+        check_on_box(&cube, &hit)?;
+        ensure(hit.normal.dot(dir) >= -1e-4, || "la normal de salida debe apuntar hacia afuera".to_string())?;
+        let (_, t_far) = cube.ray_interval(&origin, &dir).ok_or("el intervalo debe existir")?;
+        ensure(near(hit.distance, t_far, 1e-4 * (1.0 + t_far)), || {
+            format!("distancia {} pero la salida está en {}", hit.distance, t_far)
+        })
+    });
+}
+
+#[test]
+fn cube_rays_parallel_to_slabs() {
+    check("cube_parallel", 6, |rng| {
+        let cube = random_cube(rng, false);
+        let h = cube.half_size;
+        let axis = (rng.next_u32() % 3) as usize;
+        let sign = if rng.next_u32() % 2 == 0 { 1.0 } else { -1.0 };
+        let mut d = [0.0; 3];
+        d[axis] = sign;
+        let dir = Vec3::new(d[0], d[1], d[2]);
+
+        // origen fuera de la caja sobre el eje del rayo, dentro de los otros dos slabs
+        let mut o = [
+            h.x * range(rng, -0.99, 0.99),
+            h.y * range(rng, -0.99, 0.99),
+            h.z * range(rng, -0.99, 0.99),
+        ];
+        o[axis] = -sign * (half_axes(&cube)[axis] + range(rng, 0.5, 5.0));
+        let origin = cube.center + Vec3::new(o[0], o[1], o[2]);
+
+        let hit = cube.ray_intersect(&origin, &dir).ok_or("un rayo paralelo dentro de los slabs debe pegar")?;
+        check_hit_basics(&hit, origin, dir)?;
+        check_on_box(&cube, &hit)?;
+        ensure(near_vec(hit.normal, -dir, 1e-6), || format!("normal {:?} para un rayo {:?}", hit.normal, dir))?;
+
+        // el mismo rayo corrido fuera de uno de los otros slabs no pega
+        let other = (axis + 1 + (rng.next_u32() % 2) as usize) % 3;
+        let mut shifted = o;
+        shifted[other] = half_axes(&cube)[other] * range(rng, 1.01, 3.0);
+        let shifted = cube.center + Vec3::new(shifted[0], shifted[1], shifted[2]);
+        ensure(cube.ray_intersect(&shifted, &dir).is_none(), || "un rayo paralelo fuera de un slab no debe pegar".to_string())
+    });
+}
+
+#[test]
+fn cube_grazing_rays_along_faces() {
+    check("cube_grazing", 7, |rng| {
+        // centrado en el origen para que el rayo quede exactamente en el plano de la cara
+        let half = random_cube(rng, false).half_size;
+        let cube = Cube::new(Vec3::zero(), half, 0.0, 0.0, material());
+        let h = half_axes(&cube);
+        // rayo en el plano de la cara +x, viajando en -z
+        let origin = cube.center + Vec3::new(h[0], h[1] * range(rng, -0.9, 0.9), h[2] + range(rng, 0.5, 5.0));
+        let dir = Vec3::new(0.0, 0.0, -1.0);
+        let hit = cube.ray_intersect(&origin, &dir).ok_or("un rayo sobre la cara debe pegar en la arista")?;
+        check_hit_basics(&hit, origin, dir)?;
+        check_on_box(&cube, &hit)?;
+
+        // apenas afuera de la cara no pega
+        let outside = origin + Vec3::new(h[0] * 1e-3, 0.0, 0.0);
+        ensure(cube.ray_intersect(&outside, &dir).is_none(), || "un rayo apenas fuera de la cara no debe pegar".to_string())
+    });
+}
+
+#[test]
+fn rotated_cube_agrees_with_axis_aligned() {
+    check("rotated_vs_aligned", 8, |rng| {
+        let rotated = random_cube(rng, true);
+        let aligned = Cube::new(Vec3::zero(), rotated.half_size, 0.0, 0.0, material());
+        let world = cube_to_world(&rotated);
+        let local = world.inverse().unwrap();
+
+        let origin = rotated.center + uniform_sphere(rng) * (rotated.half_size.length() * range(rng, 1.05, 6.0));
+        let target = rotated.center + random_point(rng, rotated.half_size.length());
+        let dir = (target - origin).normalized();
+        let local_origin = local.transform_point(origin);
+        let local_dir = local.transform_vector(dir);
+
+        match (rotated.ray_intersect(&origin, &dir), aligned.ray_intersect(&local_origin, &local_dir)) {
+            (Some(a), Some(b)) => {
+                ensure(near(a.distance, b.distance, 1e-3 * (1.0 + b.distance)), || {
+                    format!("distancias distintas: {} rotado, {} alineado", a.distance, b.distance)
+                })?;
+                ensure(near_vec(a.point, world.transform_point(b.point), 1e-3 * (1.0 + b.distance)), || {
+                    "los puntos de impacto no coinciden".to_string()
+                })?;
+                ensure(near_vec(a.normal, world.transform_vector(b.normal), 1e-4), || {
+                    format!("normales distintas: {:?} rotada, {:?} alineada", a.normal, world.transform_vector(b.normal))
+                })
+            }
+            (None, None) => Ok(()),
+            // solo pueden diferir rayos que rozan una arista
+            (a, b) => {
+                let interval = slab_interval(half_axes(&aligned), local_origin, local_dir);
+                ensure(interval.is_none_or(|(t_near, t_far)| t_far - t_near < 1e-3), || {
+                    format!("uno pega y el otro no (rotado {}, alineado {})", a.is_some(), b.is_some())
+                })
+            }
+        }
+    });
+}
+
+// Las cajas del BVH tienen que contener cada punto en el que un rayo puede pegarle al objeto
+#[test]
+fn hits_lie_inside_bounds() {
+    check("hits_inside_bounds", 8, |rng| {
+        let objects: [Box<dyn RayIntersect>; 2] = [Box::new(random_sphere(rng)), Box::new(random_cube(rng, true))];
+        let origin = random_point(rng, 12.0);
+        let dir = uniform_sphere(rng);
+        for object in &objects {
+            let bounds = object.bounds().ok_or("primitiva sin caja")?.expanded(1e-4);
+            if let Some(hit) = object.ray_intersect(&origin, &dir) {
+                let p = hit.point;
+                let inside = p.x >= bounds.min.x && p.y >= bounds.min.y && p.z >= bounds.min.z
+                    && p.x <= bounds.max.x && p.y <= bounds.max.y && p.z <= bounds.max.z;
+                ensure(inside, || format!("impacto {:?} fuera de la caja {:?}", p, bounds))?;
+            }
+        }
+        Ok(())
+    });
+}
+
+// Los tramos que usa CSG tienen que coincidir con ray_intersect: el choque es la entrada si está
+// delante del origen o la salida si no, y las dos normales miran hacia afuera del sólido
+#[test]
+fn spans_agree_with_nearest_hit() {
+    check("spans_agree", 9, |rng| {
+        let objects: [Box<dyn RayIntersect>; 2] = [Box::new(random_sphere(rng)), Box::new(random_cube(rng, true))];
+        let origin = random_point(rng, 12.0);
+        let dir = uniform_sphere(rng);
+        for object in &objects {
+            let spans = object.ray_spans(&origin, &dir);
+            let hit = object.ray_intersect(&origin, &dir);
+            ensure(spans.len() == usize::from(hit.is_some()), || format!("{} tramos para {}", spans.len(), hit.is_some()))?;
+            let (Some(span), Some(hit)) = (spans.first(), hit) else { continue };
+            let expected = if span.enter.distance >= 0.0 { &span.enter } else { &span.exit };
+            ensure((expected.distance - hit.distance).abs() < 1e-4, || {
+                format!("tramo {}..{} y choque en {}", span.enter.distance, span.exit.distance, hit.distance)
+            })?;
+            ensure(span.enter.normal.dot(dir) <= 1e-4 && span.exit.normal.dot(dir) >= -1e-4, || {
+                format!("normales {:?} y {:?} para la dirección {:?}", span.enter.normal, span.exit.normal, dir)
+            })?;
+        }
+        Ok(())
+    });
+}
+
+// ---------------------------------------------------------------------------------------------
+// Primitivas analíticas
+
+fn random_primitive(rng: &mut Rng) -> Box<dyn RayIntersect> {
+    let kind = rng.next_u32() % 6;
+    let mut size = || range(rng, 0.1, 3.0);
+    let primitive = match kind {
+        0 => Primitive::Disk { radius: size() },
+        1 => Primitive::Quad { half_width: size(), half_depth: size() },
+        2 => Primitive::Cylinder { radius: size(), half_height: size() },
+        3 => Primitive::Cone { radius: size(), half_height: size() },
+        4 => Primitive::Torus { major_radius: size() + 0.5, minor_radius: size() * 0.15 },
+        _ => Primitive::Capsule { radius: size(), half_height: size() },
+    };
+    let rotation = Vec2::new(range(rng, -180.0, 180.0), range(rng, -180.0, 180.0));
+    primitive.build(random_point(rng, 5.0), rotation, material())
+}
+
+// Los impactos de las primitivas analíticas están sobre el rayo, dentro de su caja y, en las
+// cerradas, coinciden con el primer borde de sus tramos
+#[test]
+fn analytic_primitives_agree_with_bounds_and_spans() {
+    check("analytic_primitives", 10, |rng| {
+        let object = random_primitive(rng);
+        let origin = random_point(rng, 12.0);
+        let dir = uniform_sphere(rng);
+        let Some(hit) = object.ray_intersect(&origin, &dir) else { return Ok(()) };
+        check_hit_basics(&hit, origin, dir)?;
+        let bounds = object.bounds().ok_or("primitiva sin caja")?.expanded(1e-3);
+        let p = hit.point;
+        let inside = p.x >= bounds.min.x && p.y >= bounds.min.y && p.z >= bounds.min.z
+            && p.x <= bounds.max.x && p.y <= bounds.max.y && p.z <= bounds.max.z;
+        ensure(inside, || format!("impacto {:?} fuera de la caja {:?}", p, bounds))?;
+        ensure(hit.uv.is_some(), || "sin uv".to_string())?;
+        if let Some(span) = object.ray_spans(&origin, &dir).first() {
+            let expected = if span.enter.distance >= 0.0 { &span.enter } else { &span.exit };
+            ensure((expected.distance - hit.distance).abs() < 1e-3, || {
+                format!("tramo {}..{} y choque en {}", span.enter.distance, span.exit.distance, hit.distance)
+            })?;
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn sdf_sphere_matches_analytic_sphere() {
+    // la misma esfera por sphere tracing y por la cuadrática, movida dentro del campo y rotada
+    check("sdf_sphere", 11, |rng| {
+        let radius = 0.3 + rng.next_f32() * 2.0;
+        let offset = random_point(rng, 2.0);
+        let center = random_point(rng, 3.0);
+        let rotation = Vec2::new(rng.next_f32() * 360.0, rng.next_f32() * 360.0);
+        let sdf = Sdf::Move { offset, shape: Box::new(Sdf::Sphere { radius }) };
+        let traced = SdfShape::new(sdf, center, rotation, material());
+        let rotate = Mat4::rotation_y(rotation.y.to_radians()) * Mat4::rotation_x(rotation.x.to_radians());
+        let world_center = center + rotate.transform_vector(offset);
+        let sphere = Sphere { center: world_center, radius, material: material() };
+
+        let origin = random_point(rng, 10.0);
+        let dir = uniform_sphere(rng);
+        match (traced.ray_intersect(&origin, &dir), sphere.ray_intersect(&origin, &dir)) {
+            (Some(a), Some(b)) => {
+                check_hit_basics(&a, origin, dir)?;
+                ensure((a.distance - b.distance).abs() < 1e-3, || format!("distancia {} y {}", a.distance, b.distance))?;
+                ensure((a.normal - b.normal).length() < 1e-2, || format!("normal {:?} y {:?}", a.normal, b.normal))
+            }
+            (None, None) => Ok(()),
+            // rozando el borde uno puede tocar y el otro no
+            (a, b) => {
+                let grazing = a.or(b).map(|hit| hit.normal.dot(dir).abs() < 0.05).unwrap_or(false);
+                ensure(grazing, || "uno choca y el otro no".to_string())
+            }
+        }
+    });
+}
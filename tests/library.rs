@@ -0,0 +1,49 @@
+// Uso del crate como biblioteca, sin ventana: lo mismo que haría un programa que embebe el
+// renderer (leer una escena de texto, armarla y pedir la imagen en memoria). Solo se usa lo que
+// lib.rs reexporta en la raíz del crate.
+
+use animal_rt::{to_pixel_color, Renderer, SceneFile, TextureManager};
+use std::sync::Arc;
+
+const SCENE: &str = "
+time 12
+
+material rojo {
+    diffuse 0.8 0.1 0.1
+}
+
+sphere bola {
+    center 0 0 0
+    radius 1
+    material rojo
+}
+
+camera {
+    eye 0 0 5
+    center 0 0 0
+}
+";
+
+#[test]
+fn renders_a_scene_file_in_memory() {
+    let scene_file = SceneFile::parse(SCENE).unwrap();
+    let scene = scene_file.build(scene_file.day());
+    let camera = scene_file.camera.clone().unwrap();
+    let renderer = Renderer::new(Arc::new(TextureManager::new()));
+
+    let (width, height) = (32, 24);
+    let pixels = renderer.render_to_buffer(&scene, &camera, width, height);
+    assert_eq!(pixels.len(), (width * height) as usize);
+
+    // la bola roja queda en el centro; la esquina es cielo
+    let center = pixels[(height / 2 * width + width / 2) as usize];
+    let corner = pixels[0];
+    assert!(center.r > center.g && center.r > center.b, "centro {:?}", center);
+    assert_ne!(center, corner);
+
+    // la imagen RGBA8 es la lineal convertida, y trace da el mismo color que el píxel
+    let linear = renderer.render_linear(&scene, &camera, width, height);
+    assert!(linear.iter().zip(&pixels).all(|(c, p)| to_pixel_color(*c) == *p));
+    let ray = camera.primary_ray((width / 2) as f32, (height / 2) as f32, width as f32, height as f32);
+    assert_eq!(to_pixel_color(renderer.trace(&ray.origin, &ray.direction, &scene)), center);
+}